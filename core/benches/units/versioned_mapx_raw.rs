@@ -189,6 +189,86 @@ fn branch_version_random_read_write(c: &mut Criterion) {
     group.finish();
 }
 
+fn branch_contains_version(c: &mut Criterion) {
+    let mut group = c.benchmark_group("** vsdb::versioned::mapx_raw::MapxRawVs **");
+    group
+        .measurement_time(Duration::from_secs(9))
+        .sample_size(10);
+
+    let mut db = MapxRawVs::new();
+    (0..100usize).for_each(|n| {
+        db.version_create(VersionName(&n.to_be_bytes())).unwrap();
+    });
+    let br = db.branch_get_default();
+
+    // 1M membership checks in each iteration
+    group.bench_function(" version exists on branch ", |b| {
+        b.iter(|| {
+            (0..1_000_000usize).for_each(|n| {
+                db.version_exists_on_branch(
+                    VersionName(&(n % 200).to_be_bytes()),
+                    br.as_deref(),
+                );
+            })
+        })
+    });
+
+    group.bench_function(" branch contains version fast ", |b| {
+        b.iter(|| {
+            (0..1_000_000usize).for_each(|n| {
+                db.branch_contains_version_fast(
+                    br.as_deref(),
+                    VersionName(&(n % 200).to_be_bytes()),
+                );
+            })
+        })
+    });
+    group.finish();
+}
+
+// Run with and without the `compressed-values` feature to compare,
+// 10k 1KB values across 5 versions,
+// check the size of `vsdb_get_base_dir()` for the storage usage.
+fn compressed_values(c: &mut Criterion) {
+    let mut group = c.benchmark_group("** vsdb::versioned::mapx_raw::MapxRawVs **");
+    group
+        .measurement_time(Duration::from_secs(9))
+        .sample_size(10);
+
+    let value = |n: usize, ver: usize| {
+        let mut v = format!("{{\"id\":{},\"version\":{},\"data\":\"", n, ver);
+        while v.len() < 1024 {
+            v.push_str("0123456789abcdef");
+        }
+        v.truncate(1024);
+        v.into_bytes()
+    };
+
+    let mut db = MapxRawVs::new();
+    let ver = AtomicUsize::new(0);
+
+    group.bench_function(" compressed values write ", |b| {
+        b.iter(|| {
+            let v = ver.fetch_add(1, Ordering::SeqCst);
+            db.version_create(VersionName(&v.to_be_bytes())).unwrap();
+            (0..10_000usize).for_each(|n| {
+                db.insert(&n.to_be_bytes(), &value(n, v % 5)).unwrap();
+            });
+            // keep about 5 versions alive
+            db.prune(Some(4)).unwrap();
+        })
+    });
+
+    group.bench_function(" compressed values read ", |b| {
+        b.iter(|| {
+            (0..10_000usize).for_each(|n| {
+                db.get(&n.to_be_bytes()).unwrap();
+            });
+        })
+    });
+    group.finish();
+}
+
 criterion_group!(
     benches,
     read_write,
@@ -196,5 +276,7 @@ criterion_group!(
     version_read_write,
     version_random_read_write,
     branch_version_read_write,
-    branch_version_random_read_write
+    branch_version_random_read_write,
+    branch_contains_version,
+    compressed_values
 );
@@ -16,12 +16,18 @@ pub(crate) use rocks_backend::RocksEngine as RocksDB;
 #[cfg(feature = "rocks_backend")]
 type EngineIter = rocks_backend::RocksIter;
 
+#[cfg(feature = "rocks_backend")]
+pub(crate) use rocks_backend::STORE_MARKER;
+
 #[cfg(feature = "parity_backend")]
 pub(crate) use parity_backend::ParityEngine as ParityDB;
 
 #[cfg(feature = "parity_backend")]
 type EngineIter = parity_backend::ParityIter;
 
+#[cfg(feature = "parity_backend")]
+pub(crate) use parity_backend::STORE_MARKER;
+
 /////////////////////////////////////////////////////////////////////////////
 /////////////////////////////////////////////////////////////////////////////
 
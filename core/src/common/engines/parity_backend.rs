@@ -26,6 +26,9 @@ const META_KEY_VERSION_ID: [u8; 1] = [u8::MAX - 2];
 const META_KEY_PREFIX_ALLOCATOR: [u8; 1] = [u8::MIN];
 const META_KEY_NULL: [u8; 0] = [0; 0];
 
+// A file that always exists in the directory of an opened DB
+pub(crate) const STORE_MARKER: &str = "metadata";
+
 static HDR: Lazy<DB> = Lazy::new(|| paritydb_open().unwrap());
 
 pub struct ParityEngine {
@@ -76,6 +76,9 @@ pub const RESERVED_VERSION_NUM_DEFAULT: usize = 10;
 
 const BASE_DIR_VAR: &str = "VSDB_BASE_DIR";
 
+// The max depth of sub-directories checked by `vsdb_list_instances`
+const INSTANCE_SEARCH_DEPTH: usize = 4;
+
 static VSDB_BASE_DIR: Lazy<Mutex<PathBuf>> = Lazy::new(|| Mutex::new(gen_data_dir()));
 
 static VSDB_CUSTOM_DIR: Lazy<PathBuf> = Lazy::new(|| {
@@ -192,6 +195,38 @@ pub fn vsdb_flush() {
     VSDB.flush();
 }
 
+/// List the paths of all backing-store directories within ${VSDB_BASE_DIR},
+/// the base directory itself is the first one after the engine has been opened.
+///
+/// A directory is regarded as a backing store only if it contains
+/// the metadata file of the storage engine, symlinks are not followed,
+/// and at most `INSTANCE_SEARCH_DEPTH` levels of sub-directories are checked.
+///
+/// NOTE: all `MapxRaw` instances share the engine located in ${VSDB_BASE_DIR},
+/// other stores are created by the users, e.g. under ${VSDB_CUSTOM_DIR}.
+pub fn vsdb_list_instances() -> Vec<PathBuf> {
+    let mut ret = vec![];
+    let mut dirs = vec![(vsdb_get_base_dir(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+        if dir.join(engines::STORE_MARKER).is_file() {
+            ret.push(dir.clone());
+        }
+        if INSTANCE_SEARCH_DEPTH == depth {
+            continue;
+        }
+        if let Ok(entries) = fs::read_dir(&dir) {
+            entries
+                .flatten()
+                // `file_type` does not follow symlinks, so no loops
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .for_each(|e| dirs.push((e.path(), 1 + depth)));
+        }
+    }
+    ret.sort();
+
+    ret
+}
+
 macro_rules! impl_from_for_name {
     ($target: tt) => {
         impl<'a> From<&'a [u8]> for $target<'a> {
@@ -27,8 +27,8 @@ pub use versioned::VsMgmt;
 pub use vsdb_derive::Vs;
 
 pub use common::{
-    vsdb_flush, vsdb_get_base_dir, vsdb_get_custom_dir, vsdb_set_base_dir, RawBytes,
-    RawKey, RawValue, GB, KB, MB, NULL,
+    vsdb_flush, vsdb_get_base_dir, vsdb_get_custom_dir, vsdb_list_instances,
+    vsdb_set_base_dir, RawBytes, RawKey, RawValue, GB, KB, MB, NULL,
 };
 
 #[cfg(feature = "vs")]
@@ -13,13 +13,19 @@ use crate::{
         VER_ID_MAX, VSDB,
     },
 };
+#[cfg(feature = "lru-cache")]
+use lru::LruCache;
+#[cfg(any(feature = "lru-cache", feature = "access-log"))]
+use parking_lot::Mutex;
 use parking_lot::RwLock;
 use ruc::{crypto::trie_root, *};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "access-log")]
+use std::time::SystemTime;
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     mem::size_of,
     ops::{Bound, RangeBounds},
     result::Result as StdResult,
@@ -44,6 +50,13 @@ pub(super) struct MapxRawVs {
     // - 'key -> multi-version(globally unique) -> multi-value'
     layered_kv: MapxRaw, // <RawKey, MapxRaw<VersionID, RawValue>>,
 
+    // the base branch of each branch, the initial branch has no parent
+    br_to_parent: MapxRaw, // MapxOrd<BranchID, BranchID>,
+
+    // whether the values are stored in the `lz4` compressed form,
+    // decided by the `compressed-values` feature when the instance is created
+    compressed: bool,
+
     // derived from `br_name_to_br_id` during starting
     br_id_to_br_name: Arc<RwLock<HashMap<BranchID, RawValue>>>,
 
@@ -53,6 +66,60 @@ pub(super) struct MapxRawVs {
     // Globally ever changed keys within each version
     // derived from `layered_kv` during the starting process.
     ver_to_change_set: Arc<RwLock<BTreeMap<VersionID, BTreeSet<RawKey>>>>,
+
+    // The number of items of the head of each branch,
+    // derived from `layered_kv` during the starting process,
+    // and will be re-derived lazily after being invalidated.
+    br_to_len: Arc<RwLock<HashMap<BranchID, usize>>>,
+
+    // The version set of each branch, for O(1) membership checks,
+    // derived from `br_to_its_vers` lazily,
+    // and will be re-derived after being invalidated.
+    br_to_ver_set: Arc<RwLock<HashMap<BranchID, HashSet<VersionID>>>>,
+
+    // An optional cache of the values on the head of each branch,
+    // entries are tagged with the branch and the head version,
+    // and will not be persisted.
+    #[cfg(feature = "lru-cache")]
+    cache: Option<Arc<Mutex<LruCache<RawKey, CachedValue>>>>,
+
+    // The recent read/write events of each branch, in chronological order,
+    // at most `ACCESS_LOG_CAP` entries per branch, will not be persisted.
+    #[cfg(feature = "access-log")]
+    access_log: Arc<Mutex<HashMap<BranchID, VecDeque<(AccessType, SystemTime)>>>>,
+
+    // Pending writes on the head of the default branch,
+    // `None` means no batch is active, will not be persisted.
+    batch: Option<Batch>,
+}
+
+// The writes buffered by `begin_batch`, last write wins,
+// bound to the head of the default branch when the batch began.
+#[derive(Clone, Debug)]
+struct Batch {
+    br_id: BranchID,
+    ver_id: VersionID,
+    // `None` means the key has been removed
+    writes: HashMap<RawKey, Option<RawValue>>,
+}
+
+// (branch, head version of the branch, value)
+#[cfg(feature = "lru-cache")]
+type CachedValue = (BranchID, VersionID, Option<RawValue>);
+
+// The max number of the logged events of each branch,
+// the oldest one will be dropped when the limit is reached.
+#[cfg(feature = "access-log")]
+const ACCESS_LOG_CAP: usize = 10_000;
+
+/// The type of an event recorded in the access log of a branch.
+#[cfg(feature = "access-log")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AccessType {
+    /// Recorded by `get_by_branch`
+    Read,
+    /// Recorded by a successful `insert_by_branch`
+    Write,
 }
 
 // !^~^! 撸猫 !^~^!
@@ -67,6 +134,8 @@ impl Clone for MapxRawVs {
             ver_name_to_ver_id: self.ver_name_to_ver_id.clone(),
             br_to_its_vers: self.br_to_its_vers.clone(),
             layered_kv: self.layered_kv.clone(),
+            br_to_parent: self.br_to_parent.clone(),
+            compressed: self.compressed,
             br_id_to_br_name: Arc::new(RwLock::new(
                 self.br_id_to_br_name.read().clone(),
             )),
@@ -76,6 +145,16 @@ impl Clone for MapxRawVs {
             ver_to_change_set: Arc::new(RwLock::new(
                 self.ver_to_change_set.read().clone(),
             )),
+            br_to_len: Arc::new(RwLock::new(self.br_to_len.read().clone())),
+            br_to_ver_set: Arc::new(RwLock::new(self.br_to_ver_set.read().clone())),
+            #[cfg(feature = "lru-cache")]
+            cache: self
+                .cache
+                .as_ref()
+                .map(|c| Arc::new(Mutex::new(LruCache::new(c.lock().cap())))),
+            #[cfg(feature = "access-log")]
+            access_log: Arc::new(Mutex::new(Default::default())),
+            batch: self.batch.clone(),
         }
     }
 }
@@ -111,6 +190,15 @@ pub(super) struct MapxRawVsWithoutDerivedFields {
     br_to_its_vers: MapxRaw, // MapxOrd<BranchID, MapxOrd<VersionID, ()>>,
 
     layered_kv: MapxRaw, // <RawKey, MapxRaw<VersionID, RawValue>>
+
+    // the base branch of each branch, the initial branch has no parent,
+    // appended to the end to keep the data of old versions decodable
+    #[serde(default)]
+    br_to_parent: MapxRaw, // MapxOrd<BranchID, BranchID>,
+
+    // the values of old instances are not compressed
+    #[serde(default)]
+    compressed: bool,
 }
 
 impl From<MapxRawVsWithoutDerivedFields> for MapxRawVs {
@@ -145,16 +233,35 @@ impl From<MapxRawVsWithoutDerivedFields> for MapxRawVs {
                     }
                     acc
                 });
-        Self {
+        let ret = Self {
             default_branch: m.default_branch,
             br_name_to_br_id: m.br_name_to_br_id,
             ver_name_to_ver_id: m.ver_name_to_ver_id,
             br_to_its_vers: m.br_to_its_vers,
             layered_kv: m.layered_kv,
+            br_to_parent: m.br_to_parent,
+            compressed: m.compressed,
             br_id_to_br_name: Arc::new(RwLock::new(br_id_to_br_name)),
             ver_id_to_ver_name: Arc::new(RwLock::new(ver_id_to_ver_name)),
             ver_to_change_set: Arc::new(RwLock::new(ver_to_change_set)),
-        }
+            br_to_len: Arc::new(RwLock::new(Default::default())),
+            br_to_ver_set: Arc::new(RwLock::new(Default::default())),
+            #[cfg(feature = "lru-cache")]
+            cache: None,
+            #[cfg(feature = "access-log")]
+            access_log: Arc::new(Mutex::new(Default::default())),
+            batch: None,
+        };
+
+        let br_to_len = ret
+            .br_id_to_br_name
+            .read()
+            .keys()
+            .map(|br_id| (*br_id, ret.iter_by_branch(*br_id).count()))
+            .collect::<HashMap<_, _>>();
+        *ret.br_to_len.write() = br_to_len;
+
+        ret
     }
 }
 
@@ -167,6 +274,8 @@ impl From<&MapxRawVs> for MapxRawVsWithoutDerivedFields {
                 ver_name_to_ver_id: m.ver_name_to_ver_id.shadow(),
                 br_to_its_vers: m.br_to_its_vers.shadow(),
                 layered_kv: m.layered_kv.shadow(),
+                br_to_parent: m.br_to_parent.shadow(),
+                compressed: m.compressed,
             }
         }
     }
@@ -184,14 +293,28 @@ impl MapxRawVs {
             ver_name_to_ver_id: self.ver_name_to_ver_id.shadow(),
             br_to_its_vers: self.br_to_its_vers.shadow(),
             layered_kv: self.layered_kv.shadow(),
+            br_to_parent: self.br_to_parent.shadow(),
+            compressed: self.compressed,
             br_id_to_br_name: Arc::clone(&self.br_id_to_br_name),
             ver_id_to_ver_name: Arc::clone(&self.ver_id_to_ver_name),
             ver_to_change_set: Arc::clone(&self.ver_to_change_set),
+            br_to_len: Arc::clone(&self.br_to_len),
+            br_to_ver_set: Arc::clone(&self.br_to_ver_set),
+            #[cfg(feature = "lru-cache")]
+            cache: self.cache.as_ref().map(Arc::clone),
+            #[cfg(feature = "access-log")]
+            access_log: Arc::clone(&self.access_log),
+            batch: None,
         }
     }
 
     #[inline(always)]
     pub(super) fn new() -> Self {
+        Self::with_compression(cfg!(feature = "compressed-values"))
+    }
+
+    #[inline(always)]
+    pub(super) fn with_compression(compressed: bool) -> Self {
         let mut ret = Self {
             default_branch: BranchID::default(),
 
@@ -199,16 +322,183 @@ impl MapxRawVs {
             ver_name_to_ver_id: MapxRaw::new(),
             br_to_its_vers: MapxRaw::new(),
             layered_kv: MapxRaw::new(),
+            br_to_parent: MapxRaw::new(),
+            compressed,
 
             br_id_to_br_name: Arc::new(RwLock::new(Default::default())),
             ver_id_to_ver_name: Arc::new(RwLock::new(Default::default())),
             ver_to_change_set: Arc::new(RwLock::new(Default::default())),
+            br_to_len: Arc::new(RwLock::new(Default::default())),
+            br_to_ver_set: Arc::new(RwLock::new(Default::default())),
+            #[cfg(feature = "lru-cache")]
+            cache: None,
+            #[cfg(feature = "access-log")]
+            access_log: Arc::new(Mutex::new(Default::default())),
+            batch: None,
         };
 
         ret.init();
         ret
     }
 
+    #[cfg(feature = "lru-cache")]
+    #[inline(always)]
+    pub(super) fn with_cache(capacity: std::num::NonZeroUsize) -> Self {
+        let mut ret = Self::new();
+        ret.cache = Some(Arc::new(Mutex::new(LruCache::new(capacity))));
+        ret
+    }
+
+    // Convert a value to its stored form, the 'NULL' value is always kept as it is,
+    // so an empty stored value still means 'not exist'.
+    #[inline(always)]
+    fn value_to_stored<'a>(&self, v: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.compressed && !v.is_empty() {
+            return Cow::Owned(lz4_flex::compress_prepend_size(v));
+        }
+        Cow::Borrowed(v)
+    }
+
+    // The reverse of `value_to_stored`, `None` means 'not exist'.
+    #[inline(always)]
+    fn stored_to_value(&self, v: RawValue) -> Option<RawValue> {
+        if v.is_empty() {
+            return None;
+        }
+        if self.compressed {
+            return Some(pnk!(lz4_flex::decompress_size_prepended(&v)));
+        }
+        Some(v)
+    }
+
+    // Should be called on every write of the `key`,
+    // the other branches may share the same version with the written one.
+    #[inline(always)]
+    fn cache_remove(&self, key: &[u8]) {
+        #[cfg(feature = "lru-cache")]
+        if let Some(cache) = self.cache.as_ref() {
+            cache.lock().pop(key);
+        }
+    }
+
+    // Should be called by operations that rewrite the data of existing versions,
+    // eg. `rebase`, `prune`, etc.
+    #[inline(always)]
+    fn cache_clear(&self) {
+        #[cfg(feature = "lru-cache")]
+        if let Some(cache) = self.cache.as_ref() {
+            cache.lock().clear();
+        }
+    }
+
+    #[cfg(feature = "access-log")]
+    #[inline(always)]
+    pub(super) fn access_log_push(&self, br_id: BranchID, t: AccessType) {
+        let mut log = self.access_log.lock();
+        let events = log.entry(br_id).or_default();
+        if ACCESS_LOG_CAP <= events.len() {
+            events.pop_front();
+        }
+        events.push_back((t, SystemTime::now()));
+    }
+
+    #[cfg(feature = "access-log")]
+    #[inline(always)]
+    pub(super) fn branch_access_log(
+        &self,
+        br_id: BranchID,
+    ) -> Vec<(AccessType, SystemTime)> {
+        self.access_log
+            .lock()
+            .get(&br_id)
+            .map(|events| events.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "access-log")]
+    #[inline(always)]
+    pub(super) fn branch_clear_access_log(&self, br_id: BranchID) {
+        self.access_log.lock().remove(&br_id);
+    }
+
+    #[inline(always)]
+    pub(super) fn begin_batch(&mut self) -> Result<()> {
+        if self.batch.is_some() {
+            return Err(eg!("a batch is already active"));
+        }
+        let br_id = self.branch_get_default();
+        let ver_id = self
+            .branch_get_head(br_id)
+            .c(d!("no version on the default branch"))?;
+        self.batch = Some(Batch {
+            br_id,
+            ver_id,
+            writes: HashMap::new(),
+        });
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(super) fn flush_batch(&mut self) -> Result<()> {
+        let batch = self.batch.take().c(d!("no active batch"))?;
+
+        // versions and branches can not be changed while the batch is active,
+        // so the target version still exists and the writes below can not fail,
+        // anyway, the batch is dropped only after all writes have been applied
+        let ret = batch.writes.iter().try_for_each(|(k, v)| {
+            self.write_by_branch_version(k, v.as_deref(), batch.br_id, batch.ver_id)
+                .map(|_| ())
+        });
+        if ret.is_err() {
+            self.batch = Some(batch);
+        }
+
+        ret.c(d!())
+    }
+
+    #[inline(always)]
+    pub(super) fn rollback_batch(&mut self) {
+        self.batch = None;
+    }
+
+    // The buffered writes are bound to the head of the default branch,
+    // so changes of versions and branches are refused during a batch.
+    #[inline(always)]
+    fn batch_check(&self) -> Result<()> {
+        if self.batch.is_some() {
+            return Err(eg!("a batch is active, flush or roll it back first"));
+        }
+        Ok(())
+    }
+
+    // Buffer a write if a batch is active on the branch,
+    // return the previous value(buffered writes are visible) on success.
+    #[inline(always)]
+    fn batch_write(
+        &mut self,
+        key: &[u8],
+        value: Option<&[u8]>,
+        br_id: BranchID,
+    ) -> Option<Option<RawValue>> {
+        if br_id != self.batch.as_ref()?.br_id {
+            return None;
+        }
+        let ret = self.get_by_branch(key, br_id);
+        self.batch.as_mut().map(|b| {
+            b.writes.insert(key.to_vec(), value.map(|v| v.to_vec()));
+            ret
+        })
+    }
+
+    // The buffered value of a key, if a batch is active on the branch.
+    #[inline(always)]
+    fn batch_read(&self, key: &[u8], br_id: BranchID) -> Option<Option<RawValue>> {
+        self.batch
+            .as_ref()
+            .filter(|b| b.br_id == br_id)
+            .and_then(|b| b.writes.get(key).cloned())
+    }
+
     #[inline(always)]
     fn init(&mut self) {
         let initial_brid = INITIAL_BRANCH_ID.to_be_bytes();
@@ -240,6 +530,9 @@ impl MapxRawVs {
         value: &[u8],
         br_id: BranchID,
     ) -> Result<Option<RawValue>> {
+        if let Some(ret) = self.batch_write(key, Some(value), br_id) {
+            return Ok(ret);
+        }
         decode_map(
             self.br_to_its_vers
                 .get(&br_id[..])
@@ -281,6 +574,9 @@ impl MapxRawVs {
         key: &[u8],
         br_id: BranchID,
     ) -> Result<Option<RawValue>> {
+        if let Some(ret) = self.batch_write(key, None, br_id) {
+            return Ok(ret);
+        }
         decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?)
             .last()
             .c(d!("no version on this branch, create a version first"))
@@ -306,6 +602,44 @@ impl MapxRawVs {
             .c(d!())
     }
 
+    #[inline(always)]
+    pub(super) fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<bool> {
+        self.compare_and_swap_by_branch(
+            key,
+            expected,
+            new_value,
+            self.branch_get_default(),
+        )
+        .c(d!())
+    }
+
+    // The read and the write happen within the same `&mut self` borrow,
+    // so no other writers can get in between them.
+    pub(super) fn compare_and_swap_by_branch(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+        br_id: BranchID,
+    ) -> Result<bool> {
+        if self.get_by_branch(key, br_id).as_deref() != expected {
+            return Ok(false);
+        }
+
+        if let Some(v) = new_value {
+            self.insert_by_branch(key, v, br_id).c(d!())?;
+        } else {
+            self.remove_by_branch(key, br_id).c(d!())?;
+        }
+
+        Ok(true)
+    }
+
     // This function should **NOT** be public,
     // `write`-like operations should only be applied
     // on the latest version of every branch,
@@ -326,6 +660,7 @@ impl MapxRawVs {
         }
 
         let value = value.unwrap_or(NULL);
+        let stored = self.value_to_stored(value);
 
         decode_map(
             &*self
@@ -333,7 +668,9 @@ impl MapxRawVs {
                 .entry(key)
                 .or_insert(encode_map(&MapxRaw::new())),
         )
-        .insert(ver_id, value);
+        .insert(ver_id, stored);
+
+        self.cache_remove(key);
 
         self.ver_to_change_set
             .write()
@@ -341,6 +678,34 @@ impl MapxRawVs {
             .c(d!())?
             .insert(key.to_vec());
 
+        // A 'NULL' value means 'not exist'.
+        let existed = ret.is_some();
+        let exists = !value.is_empty();
+        if existed != exists {
+            let mut len_hdr = self.br_to_len.write();
+
+            // other branches may share this version with this one,
+            // as their head or as an inherited older version
+            let affected = len_hdr
+                .keys()
+                .copied()
+                .filter(|id| *id != br_id && self.version_exists_on_branch(ver_id, *id))
+                .collect::<Vec<_>>();
+            affected.iter().for_each(|id| {
+                len_hdr.remove(id);
+            });
+
+            // re-derive it lazily if the counter is out of sync
+            let n = len_hdr
+                .get(&br_id)
+                .and_then(|n| alt!(exists, n.checked_add(1), n.checked_sub(1)));
+            if let Some(n) = n {
+                len_hdr.insert(br_id, n);
+            } else {
+                len_hdr.remove(&br_id);
+            }
+        }
+
         Ok(ret)
     }
 
@@ -351,9 +716,28 @@ impl MapxRawVs {
 
     #[inline(always)]
     pub(super) fn get_by_branch(&self, key: &[u8], br_id: BranchID) -> Option<RawValue> {
+        if let Some(v) = self.batch_read(key, br_id) {
+            return v;
+        }
         if let Some(vers) = self.br_to_its_vers.get(br_id) {
             if let Some(ver_id) = decode_map(vers).last().map(|(id, _)| id) {
-                return self.get_by_branch_version(key, br_id, to_verid(&ver_id));
+                let ver_id = to_verid(&ver_id);
+
+                #[cfg(feature = "lru-cache")]
+                if let Some(cache) = self.cache.as_ref() {
+                    if let Some((b, v, value)) = cache.lock().get(key) {
+                        if br_id == *b && ver_id == *v {
+                            return value.clone();
+                        }
+                    }
+                    let value = self.get_by_branch_version(key, br_id, ver_id);
+                    cache
+                        .lock()
+                        .put(key.to_vec(), (br_id, ver_id, value.clone()));
+                    return value;
+                }
+
+                return self.get_by_branch_version(key, br_id, ver_id);
             }
         }
         None
@@ -367,12 +751,75 @@ impl MapxRawVs {
         ver_id: VersionID,
     ) -> Option<RawValue> {
         let vers = decode_map(self.br_to_its_vers.get(br_id)?);
+        self.get_by_vers(key, &vers, ver_id)
+    }
+
+    #[inline(always)]
+    pub(super) fn get_batch(&self, keys: &[impl AsRef<[u8]>]) -> Vec<Option<RawValue>> {
+        self.get_batch_by_branch(keys, self.branch_get_default())
+    }
+
+    #[inline(always)]
+    pub(super) fn get_batch_by_branch(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        br_id: BranchID,
+    ) -> Vec<Option<RawValue>> {
+        if let Some(vers) = self.br_to_its_vers.get(br_id) {
+            let vers = decode_map(vers);
+            if let Some(ver_id) = vers.last().map(|(id, _)| id) {
+                let mut ret = self.get_batch_by_vers(keys, &vers, to_verid(&ver_id));
+                ret.iter_mut().zip(keys.iter()).for_each(|(v, k)| {
+                    if let Some(bv) = self.batch_read(k.as_ref(), br_id) {
+                        *v = bv;
+                    }
+                });
+                return ret;
+            }
+        }
+        vec![None; keys.len()]
+    }
+
+    #[inline(always)]
+    pub(super) fn get_batch_by_branch_version(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        br_id: BranchID,
+        ver_id: VersionID,
+    ) -> Vec<Option<RawValue>> {
+        if let Some(vers) = self.br_to_its_vers.get(br_id) {
+            return self.get_batch_by_vers(keys, &decode_map(vers), ver_id);
+        }
+        vec![None; keys.len()]
+    }
+
+    // The version list of the branch is fetched only once for all keys.
+    //
+    // TODO: sort the keys and sweep `layered_kv` only once.
+    fn get_batch_by_vers(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        vers: &MapxRaw,
+        ver_id: VersionID,
+    ) -> Vec<Option<RawValue>> {
+        keys.iter()
+            .map(|k| self.get_by_vers(k.as_ref(), vers, ver_id))
+            .collect()
+    }
 
+    // `vers` is the version list of the target branch.
+    #[inline(always)]
+    fn get_by_vers(
+        &self,
+        key: &[u8],
+        vers: &MapxRaw,
+        ver_id: VersionID,
+    ) -> Option<RawValue> {
         decode_map(self.layered_kv.get(key)?)
             .range(..=Cow::Borrowed(&ver_id[..]))
             .rev()
             .find(|(ver, _)| vers.contains_key(ver))
-            .and_then(|(_, value)| alt!(value.is_empty(), None, Some(value)))
+            .and_then(|(_, value)| self.stored_to_value(value))
     }
 
     #[inline(always)]
@@ -517,26 +964,261 @@ impl MapxRawVs {
         }
     }
 
-    // NOTE: just a stupid O(n) counter, very slow!
+    // All keys ever written, regardless of branches and versions,
+    // including the keys that have been removed.
     #[inline(always)]
-    pub(super) fn len(&self) -> usize {
-        self.iter().count()
+    pub(super) fn iter_all_keys_globally(&self) -> impl Iterator<Item = RawKey> + '_ {
+        self.layered_kv.iter().map(|(k, _)| k)
     }
 
-    // NOTE: just a stupid O(n) counter, very slow!
     #[inline(always)]
+    pub(super) fn len(&self) -> usize {
+        self.len_by_branch(self.branch_get_default())
+    }
+
+    // O(1) in most cases, the counter will be
+    // re-derived in O(n) after being invalidated.
     pub(super) fn len_by_branch(&self, br_id: BranchID) -> usize {
-        self.iter_by_branch(br_id).count()
+        let cached = self.br_to_len.read().get(&br_id).copied();
+        if let Some(n) = cached {
+            return n;
+        }
+
+        let n = self.iter_by_branch(br_id).count();
+        if self.branch_exists(br_id) {
+            self.br_to_len.write().insert(br_id, n);
+        }
+        n
     }
 
-    // NOTE: just a stupid O(n) counter, very slow!
+    // NOTE: just a stupid O(n) counter for historical versions, very slow!
     #[inline(always)]
     pub(super) fn len_by_branch_version(
         &self,
         br_id: BranchID,
         ver_id: VersionID,
     ) -> usize {
-        self.iter_by_branch_version(br_id, ver_id).count()
+        if self.branch_get_head(br_id) == Some(ver_id) {
+            self.len_by_branch(br_id)
+        } else {
+            self.iter_by_branch_version(br_id, ver_id).count()
+        }
+    }
+
+    // The newest version of a branch.
+    #[inline(always)]
+    fn branch_get_head(&self, br_id: BranchID) -> Option<VersionID> {
+        self.br_to_its_vers
+            .get(br_id)
+            .and_then(|vers| decode_map(vers).last())
+            .map(|(ver, _)| to_verid(&ver))
+    }
+
+    // Should be called after any operations
+    // that may change the data view of branch heads.
+    #[inline(always)]
+    fn len_cache_invalidate(&self) {
+        self.br_to_len.write().clear();
+    }
+
+    // Should be called before any operations
+    // that may change the version list of a branch.
+    #[inline(always)]
+    fn ver_set_cache_invalidate(&self) {
+        self.br_to_ver_set.write().clear();
+    }
+
+    // Net number of keys added(positive) or removed(negative)
+    // by the change set of a specified version on a specified branch.
+    pub(super) fn version_key_count_delta(
+        &self,
+        ver_id: VersionID,
+        br_id: BranchID,
+    ) -> Result<i64> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !vers.contains_key(ver_id) {
+            return Err(eg!("version is not on this branch"));
+        }
+
+        let chgset_hdr = self.ver_to_change_set.read();
+        let chgset = chgset_hdr.get(&ver_id).c(d!())?;
+
+        let mut delta = 0;
+        for k in chgset.iter() {
+            let kvers = decode_map(self.layered_kv.get(k).c(d!())?);
+            let existed = kvers
+                .range(..Cow::Borrowed(&ver_id[..]))
+                .rev()
+                .find(|(ver, _)| vers.contains_key(ver))
+                .map(|(_, v)| !v.is_empty())
+                .unwrap_or(false);
+            let exists = !kvers.get(ver_id).c(d!())?.is_empty();
+            delta += exists as i64 - existed as i64;
+        }
+
+        Ok(delta)
+    }
+
+    // Keys whose values differ between two versions of a specified branch,
+    // only the change sets of the versions between them need to be checked.
+    pub(super) fn diff_versions(
+        &self,
+        br_id: BranchID,
+        ver_a: VersionID,
+        ver_b: VersionID,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !vers.contains_key(ver_a) || !vers.contains_key(ver_b) {
+            return Err(eg!("version is not on this branch"));
+        }
+
+        let (lo, hi) = alt!(ver_a <= ver_b, (ver_a, ver_b), (ver_b, ver_a));
+
+        let chgset_hdr = self.ver_to_change_set.read();
+        let keys = vers
+            .range((
+                Bound::Excluded(Cow::Borrowed(&lo[..])),
+                Bound::Included(Cow::Borrowed(&hi[..])),
+            ))
+            .filter_map(|(ver, _)| chgset_hdr.get(&to_verid(&ver)))
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        drop(chgset_hdr);
+
+        Ok(keys
+            .into_iter()
+            .filter_map(|k| {
+                let a = self.get_by_branch_version(&k, br_id, ver_a);
+                let b = self.get_by_branch_version(&k, br_id, ver_b);
+                alt!(a == b, None, Some((k, a, b)))
+            })
+            .collect())
+    }
+
+    // The history of a key on a specified branch,
+    // a removal is recorded as an empty value in `layered_kv`.
+    pub(super) fn versions_touching_key(
+        &self,
+        key: &[u8],
+        br_id: BranchID,
+    ) -> Result<Vec<(VersionNameOwned, Option<RawValue>)>> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        let kvers = if let Some(kvers) = self.layered_kv.get(key) {
+            decode_map(kvers)
+        } else {
+            return Ok(vec![]);
+        };
+
+        let ver_hdr = self.ver_id_to_ver_name.read();
+        kvers
+            .iter()
+            .filter(|(ver, _)| vers.contains_key(ver))
+            .map(|(ver, value)| {
+                ver_hdr
+                    .get(&to_verid(&ver))
+                    .cloned()
+                    .c(d!("version not found"))
+                    .map(|name| (VersionNameOwned(name), self.stored_to_value(value)))
+            })
+            .collect()
+    }
+
+    // If the key is not defined at `from`,
+    // then the first non-removal write within `(from, to]` is what we want.
+    pub(super) fn get_by_version_range(
+        &self,
+        key: &[u8],
+        br_id: BranchID,
+        from: VersionID,
+        to: VersionID,
+    ) -> Option<RawValue> {
+        alt!(from > to, return None);
+
+        let vers = decode_map(self.br_to_its_vers.get(br_id)?);
+        if !vers.contains_key(from) || !vers.contains_key(to) {
+            return None;
+        }
+
+        if let Some(v) = self.get_by_vers(key, &vers, from) {
+            return Some(v);
+        }
+
+        decode_map(self.layered_kv.get(key)?)
+            .range((
+                Bound::Excluded(Cow::Borrowed(&from[..])),
+                Bound::Included(Cow::Borrowed(&to[..])),
+            ))
+            .filter(|(ver, _)| vers.contains_key(ver))
+            .find_map(|(_, value)| self.stored_to_value(value))
+    }
+
+    // Walk backward from the head version for each key,
+    // the cost is O(total_keys * avg_version_depth).
+    pub(super) fn version_blame(
+        &self,
+        br_id: BranchID,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        let head = if let Some((ver, _)) = vers.last() {
+            ver
+        } else {
+            return Ok(vec![]);
+        };
+
+        let ver_hdr = self.ver_id_to_ver_name.read();
+        let mut ret = vec![];
+        for (k, kvers) in self.layered_kv.iter() {
+            let last_write = decode_map(kvers)
+                .range(..=Cow::Borrowed(&head[..]))
+                .rev()
+                .find(|(ver, _)| vers.contains_key(ver));
+            if let Some((ver, value)) = last_write {
+                // removed on this branch
+                let value = if let Some(v) = self.stored_to_value(value) {
+                    v
+                } else {
+                    continue;
+                };
+                let name = ver_hdr
+                    .get(&to_verid(&ver))
+                    .cloned()
+                    .c(d!("version not found"))?;
+                ret.push((k, VersionNameOwned(name), Some(value)));
+            }
+        }
+
+        Ok(ret)
+    }
+
+    // Versions of the branch are collected at the beginning,
+    // the change set of each version is loaded lazily during iterating.
+    pub(super) fn change_stream(
+        &self,
+        br_id: BranchID,
+        from_ver: Option<VersionID>,
+    ) -> MapxRawVsChangeStream {
+        let vers = self
+            .br_to_its_vers
+            .get(br_id)
+            .map(|vers| {
+                let vers = decode_map(vers);
+                match from_ver {
+                    Some(from) if !vers.contains_key(from) => vec![],
+                    Some(from) => vers
+                        .range(Cow::Borrowed(&from[..])..)
+                        .map(|(ver, _)| to_verid(&ver))
+                        .collect(),
+                    None => vers.iter().map(|(ver, _)| to_verid(&ver)).collect(),
+                }
+            })
+            .unwrap_or_default();
+
+        MapxRawVsChangeStream {
+            hdr: self,
+            vers: vers.into_iter(),
+            changes: VecDeque::new(),
+        }
     }
 
     // Clear all data, for testing purpose.
@@ -545,11 +1227,16 @@ impl MapxRawVs {
         self.br_name_to_br_id.clear();
         self.ver_name_to_ver_id.clear();
         self.br_to_its_vers.clear();
+        self.br_to_parent.clear();
         self.layered_kv.clear();
 
         self.br_id_to_br_name.write().clear();
         self.ver_id_to_ver_name.write().clear();
         self.ver_to_change_set.write().clear();
+        self.br_to_len.write().clear();
+        self.br_to_ver_set.write().clear();
+        self.cache_clear();
+        self.batch = None;
 
         self.init();
     }
@@ -565,6 +1252,9 @@ impl MapxRawVs {
         ver_name: &[u8],
         br_id: BranchID,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         if self.ver_name_to_ver_id.get(ver_name).is_some() {
             return Err(eg!("version already exists"));
         }
@@ -590,6 +1280,60 @@ impl MapxRawVs {
         Ok(())
     }
 
+    // Give a new name to an existing version,
+    // the new name must not be used by any other version.
+    fn version_rename(&mut self, ver_id: VersionID, new_name: &[u8]) -> Result<()> {
+        if self.ver_name_to_ver_id.contains_key(new_name) {
+            return Err(eg!("version already exists"));
+        }
+
+        let old_name = self
+            .ver_id_to_ver_name
+            .write()
+            .insert(ver_id, new_name.to_vec())
+            .c(d!("version not found"))?;
+        self.ver_name_to_ver_id.remove(&old_name);
+        self.ver_name_to_ver_id.insert(new_name, ver_id);
+
+        Ok(())
+    }
+
+    // Create a new version on the default branch,
+    // and copy all changes of the source version into it.
+    pub(super) fn version_copy(
+        &mut self,
+        src_ver_id: VersionID,
+        dst_ver_name: &[u8],
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let chgset = self
+            .ver_to_change_set
+            .read()
+            .get(&src_ver_id)
+            .c(d!("source version not found"))?
+            .clone();
+
+        self.version_create(dst_ver_name).c(d!())?;
+        let dst_ver_id = self
+            .version_get_id_by_name(VersionName(dst_ver_name))
+            .c(d!())?;
+
+        let mut chgset_hdr = self.ver_to_change_set.write();
+        let dst_chgset = chgset_hdr.get_mut(&dst_ver_id).c(d!())?;
+        for k in chgset.into_iter() {
+            let mut kvers = decode_map(self.layered_kv.get(&k).c(d!())?);
+            // tombstones are copied too
+            let v = kvers.get(src_ver_id).c(d!())?;
+            kvers.insert(dst_ver_id, v);
+            dst_chgset.insert(k);
+        }
+        drop(chgset_hdr);
+
+        self.len_cache_invalidate();
+
+        Ok(())
+    }
+
     // Check if a verison exists on the default branch
     #[inline(always)]
     pub(super) fn version_exists(&self, ver_id: BranchID) -> bool {
@@ -615,6 +1359,31 @@ impl MapxRawVs {
             .unwrap_or(false)
     }
 
+    // Same as `version_exists_on_branch`, but the version set of the branch
+    // is loaded into memory at the first time, and then reused.
+    pub(super) fn branch_contains_version_fast(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+    ) -> bool {
+        if let Some(vers) = self.br_to_ver_set.read().get(&br_id) {
+            return vers.contains(&ver_id);
+        }
+
+        let vers = if let Some(vers) = self.br_to_its_vers.get(br_id) {
+            decode_map(vers)
+                .iter()
+                .map(|(ver, _)| to_verid(&ver))
+                .collect::<HashSet<_>>()
+        } else {
+            return false;
+        };
+
+        let ret = vers.contains(&ver_id);
+        self.br_to_ver_set.write().insert(br_id, vers);
+        ret
+    }
+
     // 'Write'-like operations on branches and versions are different from operations on data.
     //
     // 'Write'-like operations on data require recursive tracing of all parent nodes,
@@ -633,12 +1402,16 @@ impl MapxRawVs {
     // and should not do any tracing.
     #[inline(always)]
     pub(super) fn version_pop_by_branch(&mut self, br_id: BranchID) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         let mut vers =
             decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
 
         if let Some((ver_id, _)) = vers.last() {
             vers.remove(&ver_id)
                 .c(d!("BUG: version is not on this branch"))?;
+            self.br_to_len.write().remove(&br_id);
         }
 
         Ok(())
@@ -670,7 +1443,11 @@ impl MapxRawVs {
         base_version: VersionID,
         br_id: BranchID,
     ) -> Result<()> {
-        let mut brvers_hdr =
+        self.batch_check().c(d!())?;
+        self.cache_clear();
+        self.ver_set_cache_invalidate();
+
+        let brvers_hdr =
             decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
         let mut brvers = brvers_hdr
             .range(Cow::Borrowed(&base_version[..])..)
@@ -686,10 +1463,31 @@ impl MapxRawVs {
 
         let vers_to_be_merged = brvers.collect::<Vec<_>>();
 
+        self.merge_versions_into(br_id, base_version, &vers_to_be_merged)
+            .c(d!())?;
+
+        self.len_cache_invalidate();
+
+        Ok(())
+    }
+
+    // Move the changes of `vers` into `base_version`,
+    // and then remove `vers` from the branch and the global version list.
+    //
+    // The `vers` should be newer than the `base_version`,
+    // the newer ones are applied later, so their values take precedence.
+    fn merge_versions_into(
+        &mut self,
+        br_id: BranchID,
+        base_version: VersionID,
+        vers: &[VersionID],
+    ) -> Result<()> {
+        let mut brvers_hdr =
+            decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
         let mut ver_hdr = self.ver_id_to_ver_name.write();
         let mut chgset_hdr = self.ver_to_change_set.write();
 
-        for verid in vers_to_be_merged.iter() {
+        for verid in vers.iter() {
             let chgset = chgset_hdr.remove(verid).c(d!())?;
             for k in chgset.iter() {
                 chgset_hdr.get_mut(&base_version).c(d!())?.insert(k.clone());
@@ -737,64 +1535,433 @@ impl MapxRawVs {
         })
     }
 
+    // Version IDs are allocated incrementally,
+    // so the versions of a branch are ordered by their IDs.
+    pub(super) fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionID,
+        descendant: VersionID,
+        br_id: BranchID,
+    ) -> Result<bool> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !vers.contains_key(ancestor) || !vers.contains_key(descendant) {
+            return Err(eg!("version is not on this branch"));
+        }
+        Ok(ancestor < descendant)
+    }
+
+    pub(super) fn versions_between(
+        &self,
+        from: VersionID,
+        to: VersionID,
+        br_id: BranchID,
+    ) -> Result<Vec<VersionNameOwned>> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !vers.contains_key(from) || !vers.contains_key(to) {
+            return Err(eg!("version is not on this branch"));
+        }
+        if from > to {
+            return Err(eg!("`from` is newer than `to`"));
+        }
+
+        let ver_hdr = self.ver_id_to_ver_name.read();
+        let ret = vers
+            .range(Cow::Borrowed(&from[..])..Cow::Borrowed(&to[..]))
+            .map(|(ver, _)| ver_hdr.get(&to_verid(&ver)).unwrap().to_vec())
+            .map(VersionNameOwned)
+            .collect();
+        Ok(ret)
+    }
+
     #[inline(always)]
     pub(super) fn version_list_globally(&self) -> Vec<VersionNameOwned> {
         let ver_hdr = self.ver_id_to_ver_name.read();
         self.ver_to_change_set
             .read()
             .iter()
-            .map(|(ver, _)| ver_hdr.get(ver).unwrap().to_vec())
-            .map(VersionNameOwned)
-            .collect()
-    }
+            .map(|(ver, _)| ver_hdr.get(ver).unwrap().to_vec())
+            .map(VersionNameOwned)
+            .collect()
+    }
+
+    #[inline(always)]
+    pub(super) fn version_has_change_set(&self, ver_id: VersionID) -> Result<bool> {
+        self.ver_to_change_set
+            .read()
+            .get(&ver_id)
+            .c(d!())
+            .map(|chgset| !chgset.is_empty())
+    }
+
+    #[inline(always)]
+    pub(super) fn version_list_changed_keys(
+        &self,
+        ver_id: VersionID,
+    ) -> Result<Vec<RawKey>> {
+        self.ver_to_change_set
+            .read()
+            .get(&ver_id)
+            .c(d!())
+            .map(|chgset| chgset.iter().cloned().collect())
+    }
+
+    #[inline(always)]
+    pub(super) fn version_changed_key_count(&self, ver_id: VersionID) -> Result<usize> {
+        self.ver_to_change_set
+            .read()
+            .get(&ver_id)
+            .c(d!())
+            .map(|chgset| chgset.len())
+    }
+
+    #[inline(always)]
+    pub(super) fn version_chgset_size_by_branch(
+        &self,
+        br_id: BranchID,
+    ) -> Result<usize> {
+        self.branch_get_head(br_id)
+            .c(d!("no version on this branch"))
+            .and_then(|ver_id| self.version_changed_key_count(ver_id).c(d!()))
+    }
+
+    pub(super) fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionID,
+        ver_b: VersionID,
+    ) -> Result<bool> {
+        let chgset_hdr = self.ver_to_change_set.read();
+        let a = chgset_hdr.get(&ver_a).c(d!())?;
+        let b = chgset_hdr.get(&ver_b).c(d!())?;
+        Ok(!a.is_disjoint(b))
+    }
+
+    pub(super) fn version_conflict_keys(
+        &self,
+        ver_a: VersionID,
+        ver_b: VersionID,
+    ) -> Result<Vec<RawKey>> {
+        let chgset_hdr = self.ver_to_change_set.read();
+        let a = chgset_hdr.get(&ver_a).c(d!())?;
+        let b = chgset_hdr.get(&ver_b).c(d!())?;
+        Ok(a.intersection(b).cloned().collect())
+    }
+
+    /***
+     * Clean up orphan instances globally.
+     */
+    #[inline(always)]
+    pub(super) fn version_clean_up_globally(&mut self) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.do_prune(None, true).c(d!())
+    }
+
+    // # Safety
+    //
+    // Version itself and its corresponding changes will be completely purged from all branches
+    pub(super) unsafe fn version_revert_globally(
+        &mut self,
+        ver_id: VersionID,
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.cache_clear();
+        self.ver_set_cache_invalidate();
+
+        let mut ver_hdr = self.ver_id_to_ver_name.write();
+
+        for key in self
+            .ver_to_change_set
+            .write()
+            .remove(&ver_id)
+            .c(d!())?
+            .iter()
+        {
+            decode_map(&*self.layered_kv.get_mut(key).c(d!())?)
+                .remove(ver_id)
+                .c(d!())?;
+        }
+
+        self.br_to_its_vers.iter().for_each(|(_, vers)| {
+            decode_map(vers).remove(ver_id);
+        });
+
+        self.len_cache_invalidate();
+
+        ver_hdr
+            .remove(&ver_id)
+            .c(d!())
+            .and_then(|vername| self.ver_name_to_ver_id.remove(&vername).c(d!()))
+            .map(|_| ())
+    }
+
+    // Apply all changes of the source version onto the head of the target branch,
+    // a new version named `dst_ver_name` will be created to hold them.
+    //
+    // A conflict occurs when the current value on the target branch is neither
+    // the value before the source version nor the value in the source version.
+    pub(super) fn version_cherry_pick(
+        &mut self,
+        src_ver_id: VersionID,
+        src_br_id: BranchID,
+        dst_br_id: BranchID,
+        dst_ver_name: &[u8],
+        force: bool,
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let src_vers = decode_map(
+            self.br_to_its_vers
+                .get(src_br_id)
+                .c(d!("source branch not found"))?,
+        );
+        if !src_vers.contains_key(src_ver_id) {
+            return Err(eg!("version not found on the source branch"));
+        }
+        if !self.branch_exists(dst_br_id) {
+            return Err(eg!("target branch not found"));
+        }
+
+        let chgset = self
+            .ver_to_change_set
+            .read()
+            .get(&src_ver_id)
+            .c(d!("source version not found"))?
+            .clone();
+
+        let mut changes = Vec::with_capacity(chgset.len());
+        for k in chgset.into_iter() {
+            let kvers = decode_map(self.layered_kv.get(&k).c(d!())?);
+            // an empty value means the key was removed in the source version
+            let new = kvers
+                .get(src_ver_id)
+                .c(d!())
+                .map(|v| self.stored_to_value(v))?;
+
+            if !force {
+                let base = kvers
+                    .range(..Cow::Borrowed(&src_ver_id[..]))
+                    .rev()
+                    .find(|(ver, _)| src_vers.contains_key(ver))
+                    .and_then(|(_, v)| self.stored_to_value(v));
+                let cur = self.get_by_branch(&k, dst_br_id);
+                if cur != base && cur != new {
+                    return Err(eg!(format!(
+                        "conflict on key: {:?}, use `force` to overwrite it",
+                        k
+                    )));
+                }
+            }
+
+            changes.push((k, new));
+        }
+
+        self.version_create_by_branch(dst_ver_name, dst_br_id)
+            .c(d!())?;
+
+        for (k, v) in changes.iter() {
+            if let Some(v) = v {
+                self.insert_by_branch(k, v, dst_br_id).c(d!())?;
+            } else {
+                self.remove_by_branch(k, dst_br_id).c(d!())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Merge the newest `count` versions of a branch into the oldest one of them,
+    // and then rename the merged version to the name of the head version.
+    //
+    // # Safety
+    //
+    // If `force` is true and some of the versions are shared with other branches,
+    // the data records of other branches may be corrupted.
+    pub(super) unsafe fn version_squash(
+        &mut self,
+        br_id: BranchID,
+        count: usize,
+        force: bool,
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+
+        if 2 > count {
+            return Ok(());
+        }
+
+        let squashed = vers
+            .iter()
+            .rev()
+            .take(count)
+            .map(|(ver, _)| to_verid(&ver))
+            .collect::<Vec<_>>();
+        if squashed.len() < count {
+            return Err(eg!("not enough versions on this branch"));
+        }
+
+        if !force {
+            let shared = self
+                .br_to_its_vers
+                .iter()
+                .filter(|(id, _)| id[..] != br_id[..])
+                .map(|(_, other_vers)| decode_map(other_vers))
+                .any(|other_vers| {
+                    squashed.iter().any(|ver| other_vers.contains_key(ver))
+                });
+            if shared {
+                return Err(eg!(
+                    "some versions are shared with other branches, use `force` to squash them"
+                ));
+            }
+        }
+
+        let head = squashed[0];
+        let base = squashed[count - 1];
+        let head_name = self
+            .ver_id_to_ver_name
+            .read()
+            .get(&head)
+            .c(d!("version not found"))?
+            .clone();
+
+        self.version_rebase_by_branch(base, br_id).c(d!())?;
+
+        let base_name = self
+            .ver_id_to_ver_name
+            .write()
+            .insert(base, head_name.clone())
+            .c(d!("version not found"))?;
+        self.ver_name_to_ver_id.remove(&base_name);
+        self.ver_name_to_ver_id.insert(&head_name, base);
+
+        Ok(())
+    }
+
+    // Merge all versions in `[from_ver, to_ver]` of the branch into `from_ver`,
+    // and then rename it to `target_ver_name`.
+    //
+    // The newer versions are applied later, so the data visible
+    // at `to_ver` and all its descendants will keep unchanged.
+    pub(super) fn version_compact_between(
+        &mut self,
+        br_id: BranchID,
+        from_ver: VersionID,
+        to_ver: VersionID,
+        target_ver_name: &[u8],
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let brvers_hdr =
+            decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !brvers_hdr.contains_key(from_ver) || !brvers_hdr.contains_key(to_ver) {
+            return Err(eg!("version not found on the branch"));
+        }
+        if from_ver > to_ver {
+            return Err(eg!("`from_ver` is newer than `to_ver`"));
+        }
+
+        let vers = brvers_hdr
+            .range(Cow::Borrowed(&from_ver[..])..=Cow::Borrowed(&to_ver[..]))
+            .map(|(ver, _)| to_verid(&ver))
+            .collect::<Vec<_>>();
+
+        let shared = self
+            .br_to_its_vers
+            .iter()
+            .filter(|(id, _)| id[..] != br_id[..])
+            .map(|(_, other_vers)| decode_map(other_vers))
+            .any(|other_vers| vers.iter().any(|ver| other_vers.contains_key(ver)));
+        if shared {
+            return Err(eg!("some versions are shared with other branches"));
+        }
 
-    #[inline(always)]
-    pub(super) fn version_has_change_set(&self, ver_id: VersionID) -> Result<bool> {
-        self.ver_to_change_set
+        let from_ver_name = self
+            .ver_id_to_ver_name
             .read()
-            .get(&ver_id)
-            .c(d!())
-            .map(|chgset| !chgset.is_empty())
-    }
+            .get(&from_ver)
+            .c(d!("version not found"))?
+            .clone();
+        // the names of the merged versions can be reused
+        if let Some(id) = self.ver_name_to_ver_id.get(target_ver_name) {
+            if !vers.contains(&to_verid(&id)) {
+                return Err(eg!("version already exists"));
+            }
+        }
 
-    /***
-     * Clean up orphan instances globally.
-     */
-    #[inline(always)]
-    pub(super) fn version_clean_up_globally(&mut self) -> Result<()> {
-        self.do_prune(None, true).c(d!())
+        self.cache_clear();
+        self.ver_set_cache_invalidate();
+
+        self.merge_versions_into(br_id, from_ver, &vers[1..])
+            .c(d!())?;
+
+        self.ver_name_to_ver_id.remove(&from_ver_name);
+        self.ver_name_to_ver_id.insert(target_ver_name, from_ver);
+        self.ver_id_to_ver_name
+            .write()
+            .insert(from_ver, target_ver_name.to_vec());
+
+        self.len_cache_invalidate();
+
+        Ok(())
     }
 
-    // # Safety
-    //
-    // Version itself and its corresponding changes will be completely purged from all branches
-    pub(super) unsafe fn version_revert_globally(
+    // Remove all other branches and versions,
+    // create an empty version on the head of the branch,
+    // and then squash all versions of the branch into it.
+    pub(super) fn compact_to_snapshot_by_branch(
         &mut self,
-        ver_id: VersionID,
+        br_id: BranchID,
+        snapshot_ver_name: &[u8],
+        force: bool,
     ) -> Result<()> {
-        let mut ver_hdr = self.ver_id_to_ver_name.write();
+        self.batch_check().c(d!())?;
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if !force && 1 < self.br_to_its_vers.len() {
+            return Err(eg!("other branches exist, use `force` to remove them"));
+        }
 
-        for key in self
-            .ver_to_change_set
-            .write()
-            .remove(&ver_id)
-            .c(d!())?
-            .iter()
-        {
-            decode_map(&*self.layered_kv.get_mut(key).c(d!())?)
-                .remove(ver_id)
-                .c(d!())?;
+        // Check everything before the first change to the store,
+        // a failure after `branch_keep_only` can not be rolled back
+        if self.ver_name_to_ver_id.get(snapshot_ver_name).is_some() {
+            return Err(eg!("version already exists"));
         }
 
-        self.br_to_its_vers.iter().for_each(|(_, vers)| {
-            decode_map(vers).remove(ver_id);
-        });
+        // The versions out of this branch are also cleaned up
+        self.branch_keep_only(&[br_id]).c(d!())?;
+        self.branch_set_default(br_id).c(d!())?;
 
-        ver_hdr
-            .remove(&ver_id)
-            .c(d!())
-            .and_then(|vername| self.ver_name_to_ver_id.remove(&vername).c(d!()))
-            .map(|_| ())
+        self.version_create_by_branch(snapshot_ver_name, br_id)
+            .c(d!())?;
+
+        // `vers` is a live handle, the new version has been included,
+        // and all the versions are not shared, so it is safe
+        unsafe { self.version_squash(br_id, vers.len(), false).c(d!())? };
+
+        // Nothing is before the snapshot version,
+        // so the removed keys can be dropped entirely
+        let snapshot = vers.iter().next().map(|(ver, _)| to_verid(&ver)).c(d!())?;
+        let mut chgset_hdr = self.ver_to_change_set.write();
+        let chgset = chgset_hdr.get_mut(&snapshot).c(d!())?;
+        let removed = chgset
+            .iter()
+            .filter(|k| {
+                self.layered_kv
+                    .get(k)
+                    .and_then(|kvers| decode_map(kvers).get(snapshot))
+                    .map(|v| v.is_empty())
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for k in removed.iter() {
+            chgset.remove(k);
+            let mut kvers = decode_map(self.layered_kv.get(k).c(d!())?);
+            kvers.remove(snapshot);
+            if kvers.is_empty() {
+                self.layered_kv.remove(k);
+            }
+        }
+
+        Ok(())
     }
 
     pub(super) fn version_chgset_trie_root(
@@ -821,7 +1988,9 @@ impl MapxRawVs {
             .iter()
             .map(|k| {
                 let kvers = decode_map(pnk!(self.layered_kv.get(k)));
-                let v = pnk!(kvers.get(ver));
+                let v = self
+                    .stored_to_value(pnk!(kvers.get(ver)))
+                    .unwrap_or_default();
                 (k.clone(), v)
             })
             .collect::<Vec<_>>();
@@ -853,6 +2022,9 @@ impl MapxRawVs {
         base_br_id: BranchID,
         force: bool,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         if self.ver_name_to_ver_id.contains_key(ver_name) {
             return Err(eg!("this version already exists"));
         }
@@ -886,6 +2058,7 @@ impl MapxRawVs {
         base_ver_id: VersionID,
         force: bool,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
         if self.ver_name_to_ver_id.contains_key(ver_name) {
             return Err(eg!("this version already exists"));
         }
@@ -923,6 +2096,9 @@ impl MapxRawVs {
         base_br_id: BranchID,
         force: bool,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         let base_ver_id = decode_map(
             self.br_to_its_vers
                 .get(base_br_id)
@@ -949,6 +2125,7 @@ impl MapxRawVs {
         base_ver_id: VersionID,
         force: bool,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
         self.do_branch_create_by_base_branch_version(
             br_name,
             None,
@@ -969,6 +2146,8 @@ impl MapxRawVs {
         base_ver_id: Option<VersionID>,
         force: bool,
     ) -> Result<()> {
+        self.ver_set_cache_invalidate();
+
         if force {
             if let Some(brid) = self.br_name_to_br_id.get(br_name) {
                 self.branch_remove(to_brid(&brid)).c(d!())?;
@@ -1007,6 +2186,7 @@ impl MapxRawVs {
             .write()
             .insert(br_id, br_name.to_vec());
         self.br_to_its_vers.insert(br_id, encode_map(&vers_copied));
+        self.br_to_parent.insert(br_id, base_br_id);
 
         if let Some(vername) = ver_name {
             // create the first version of the new branch
@@ -1033,6 +2213,120 @@ impl MapxRawVs {
                 .unwrap_or(false)
     }
 
+    // Check if a branch has any versions that are not shared with any other branch,
+    // if not, removing this branch will not lose any data.
+    pub(super) fn branch_has_unique_versions(&self, br_id: BranchID) -> bool {
+        let vers = if let Some(vers) = self.br_to_its_vers.get(br_id) {
+            decode_map(vers)
+        } else {
+            return false;
+        };
+
+        let other_brvers = self
+            .br_to_its_vers
+            .iter()
+            .filter(|(id, _)| id[..] != br_id[..])
+            .map(|(_, vers)| decode_map(vers))
+            .collect::<Vec<_>>();
+
+        vers.iter().any(|(ver, _)| {
+            other_brvers
+                .iter()
+                .all(|other_vers| !other_vers.contains_key(&ver))
+        })
+    }
+
+    // Compare the heads of two branches, keys with identical values are skipped.
+    pub(super) fn branch_diff(
+        &self,
+        br_a: BranchID,
+        br_b: BranchID,
+    ) -> Vec<(RawKey, Option<RawValue>, Option<RawValue>)> {
+        self.iter_all_keys_globally()
+            .filter_map(|k| {
+                let a = self.get_by_branch(&k, br_a);
+                let b = self.get_by_branch(&k, br_b);
+                alt!(a == b, None, Some((k, a, b)))
+            })
+            .collect()
+    }
+
+    // Ordered from the immediate parent to the initial branch.
+    pub(super) fn branch_ancestors(&self, br_id: BranchID) -> Vec<BranchNameOwned> {
+        let br_hdr = self.br_id_to_br_name.read();
+
+        let mut ret = vec![];
+        let mut id = br_id;
+        while let Some(p) = self.br_to_parent.get(id) {
+            id = to_brid(&p);
+            if let Some(name) = br_hdr.get(&id) {
+                ret.push(BranchNameOwned(name.clone()));
+            } else {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    // Ordered by the creation time.
+    pub(super) fn branch_children(&self, br_id: BranchID) -> Vec<BranchNameOwned> {
+        let br_hdr = self.br_id_to_br_name.read();
+        self.br_to_parent
+            .iter()
+            .filter(|(_, p)| p[..] == br_id[..])
+            .filter_map(|(c, _)| br_hdr.get(&to_brid(&c)).cloned())
+            .map(BranchNameOwned)
+            .collect()
+    }
+
+    #[inline(always)]
+    pub(super) fn branch_is_leaf(&self, br_id: BranchID) -> bool {
+        !self.br_to_parent.iter().any(|(_, p)| p[..] == br_id[..])
+    }
+
+    pub(super) fn branch_fork_point(
+        &self,
+        br_a: BranchID,
+        br_b: BranchID,
+    ) -> Result<Option<VersionNameOwned>> {
+        let vers_a =
+            decode_map(self.br_to_its_vers.get(br_a).c(d!("branch not found"))?);
+        let vers_b =
+            decode_map(self.br_to_its_vers.get(br_b).c(d!("branch not found"))?);
+
+        vers_a
+            .iter()
+            .rev()
+            .find(|(ver, _)| vers_b.contains_key(ver))
+            .map(|(ver, _)| {
+                self.ver_id_to_ver_name
+                    .read()
+                    .get(&to_verid(&ver))
+                    .cloned()
+                    .map(VersionNameOwned)
+                    .c(d!("version not found"))
+            })
+            .transpose()
+    }
+
+    // Compare with the version list of the parent branch,
+    // all children are re-attached when their parent is removed,
+    // so the parent recorded in `br_to_parent` always exists.
+    pub(super) fn branch_is_dirty(&self, br_id: BranchID) -> Result<bool> {
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        if let Some(p) = self.br_to_parent.get(br_id) {
+            let parent_vers = decode_map(
+                self.br_to_its_vers
+                    .get(&p)
+                    .c(d!("parent branch not found"))?,
+            );
+            Ok(vers.iter().any(|(ver, _)| !parent_vers.contains_key(ver)))
+        } else {
+            Ok(!vers.is_empty())
+        }
+    }
+
     // Remove all changes directly made by this branch, and delete the branch itself.
     //
     // 'Write'-like operations on branches and versions are different from operations on data.
@@ -1042,6 +2336,11 @@ impl MapxRawVs {
     // and should not do any tracing.
     #[inline(always)]
     pub(super) fn branch_remove(&mut self, br_id: BranchID) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+        #[cfg(feature = "access-log")]
+        self.branch_clear_access_log(br_id);
+
         self.branch_truncate(br_id).c(d!())?;
 
         self.br_id_to_br_name
@@ -1051,6 +2350,23 @@ impl MapxRawVs {
             .and_then(|brname| self.br_name_to_br_id.remove(&brname).c(d!()))?;
 
         let vers = self.br_to_its_vers.remove(br_id).c(d!())?;
+        self.br_to_len.write().remove(&br_id);
+
+        // the children of this branch are attached to its parent
+        let parent = self.br_to_parent.remove(br_id);
+        let children = self
+            .br_to_parent
+            .iter()
+            .filter(|(_, p)| p[..] == br_id[..])
+            .map(|(c, _)| c)
+            .collect::<Vec<_>>();
+        for c in children.iter() {
+            if let Some(p) = parent.as_ref() {
+                self.br_to_parent.insert(c, p);
+            } else {
+                self.br_to_parent.remove(c);
+            }
+        }
 
         TRASH_CLEANER.lock().execute(move || {
             decode_map(vers).clear();
@@ -1061,6 +2377,7 @@ impl MapxRawVs {
 
     #[inline(always)]
     pub(super) fn branch_keep_only(&mut self, br_ids: &[BranchID]) -> Result<()> {
+        self.batch_check().c(d!())?;
         let brs = self
             .br_id_to_br_name
             .read()
@@ -1086,8 +2403,12 @@ impl MapxRawVs {
     // and should not do any tracing.
     #[inline(always)]
     pub(super) fn branch_truncate(&mut self, br_id: BranchID) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         if let Some(vers) = self.br_to_its_vers.get(br_id) {
             decode_map(vers).clear();
+            self.br_to_len.write().remove(&br_id);
             Ok(())
         } else {
             Err(eg!(
@@ -1109,6 +2430,9 @@ impl MapxRawVs {
         br_id: BranchID,
         last_ver_id: VersionID,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        self.ver_set_cache_invalidate();
+
         if let Some(vers) = self.br_to_its_vers.get(br_id) {
             // version id must be in descending order
             let mut vers = decode_map(vers);
@@ -1126,6 +2450,7 @@ impl MapxRawVs {
                 vers.remove(&ver_id)
                     .c(d!("version is not on this branch"))?;
             }
+            self.br_to_len.write().remove(&br_id);
             Ok(())
         } else {
             Err(eg!(
@@ -1145,15 +2470,105 @@ impl MapxRawVs {
         self.version_pop_by_branch(br_id).c(d!())
     }
 
+    // Copy the head state of the branch into a new version,
+    // and replace all versions of the branch with it.
+    //
+    // The replaced versions are only dereferenced here,
+    // the child branches still hold their own references,
+    // and the data will be dropped by `version_clean_up_globally`.
+    pub(super) fn branch_detach(&mut self, br_id: BranchID) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let mut vers =
+            decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        let ver_name = detached_ver_name(
+            &self.ver_name_to_ver_id,
+            self.br_id_to_br_name.read().get(&br_id).c(d!())?,
+        );
+
+        let entries = self.iter_by_branch(br_id).collect::<Vec<_>>();
+
+        self.cache_clear();
+        self.ver_set_cache_invalidate();
+
+        vers.clear();
+        self.br_to_parent.remove(br_id);
+        self.br_to_len.write().remove(&br_id);
+
+        self.version_create_by_branch(&ver_name, br_id).c(d!())?;
+        let ver_id = self.branch_get_head(br_id).c(d!())?;
+        for (k, v) in entries.iter() {
+            self.insert_by_branch_version(k, v, br_id, ver_id).c(d!())?;
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub(super) fn branch_merge_to(
         &mut self,
         br_id: BranchID,
         target_br_id: BranchID,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
         unsafe { self.do_branch_merge_to(br_id, target_br_id, false) }
     }
 
+    // Apply the net changes of `br_id` since its fork point with `target_br_id`
+    // onto the head of the target branch, as one new version.
+    //
+    // NOTE: the source branch wins if a key has also been changed on the target branch.
+    pub(super) fn branch_merge_squash(
+        &mut self,
+        br_id: BranchID,
+        target_br_id: BranchID,
+        squash_ver_name: &[u8],
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let fork_point = self
+            .branch_fork_point(br_id, target_br_id)
+            .c(d!())?
+            .map(|ver_name| {
+                self.version_get_id_by_name(VersionName(&ver_name.0))
+                    .c(d!("version not found"))
+            })
+            .transpose()?;
+
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        let keys = {
+            let chgset_hdr = self.ver_to_change_set.read();
+            vers.iter()
+                .map(|(ver, _)| to_verid(&ver))
+                .filter(|ver| fork_point.map(|fp| *ver > fp).unwrap_or(true))
+                .filter_map(|ver| chgset_hdr.get(&ver))
+                .flat_map(|chgset| chgset.iter().cloned())
+                .collect::<BTreeSet<_>>()
+        };
+
+        let changes = keys
+            .into_iter()
+            .map(|k| {
+                let v = self.get_by_branch(&k, br_id);
+                let base =
+                    fork_point.and_then(|fp| self.get_by_branch_version(&k, br_id, fp));
+                (k, v, base)
+            })
+            .filter(|(_, v, base)| v != base)
+            .map(|(k, v, _)| (k, v))
+            .collect::<Vec<_>>();
+
+        self.version_create_by_branch(squash_ver_name, target_br_id)
+            .c(d!())?;
+        for (k, v) in changes.iter() {
+            if let Some(v) = v {
+                self.insert_by_branch(k, v, target_br_id).c(d!())?;
+            } else {
+                self.remove_by_branch(k, target_br_id).c(d!())?;
+            }
+        }
+
+        Ok(())
+    }
+
     // # Safety
     //
     // If new different versions have been created on the target branch,
@@ -1164,9 +2579,140 @@ impl MapxRawVs {
         br_id: BranchID,
         target_br_id: BranchID,
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
         self.do_branch_merge_to(br_id, target_br_id, true)
     }
 
+    // Replay the versions of `br_id` since the fork point on the head of the target
+    // branch by `version_cherry_pick`, and then fast-forward both branches to the new tip.
+    //
+    // The replay is done on a temporary branch forked from the head of the target,
+    // if it fails, the temporary branch and versions are dropped,
+    // and the two branches are left unchanged.
+    // After a successful replay, the original versions of `br_id` are dropped,
+    // and the replayed ones take over their names.
+    pub(super) fn branch_merge_to_rebase(
+        &mut self,
+        br_id: BranchID,
+        target_br_id: BranchID,
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
+        let target_vers = decode_map(
+            self.br_to_its_vers
+                .get(target_br_id)
+                .c(d!("target branch not found"))?,
+        );
+
+        // No new versions on the target branch, a normal merge is enough
+        if target_vers
+            .last()
+            .map(|(ver, _)| vers.contains_key(ver))
+            .unwrap_or(true)
+        {
+            return self.branch_merge_to(br_id, target_br_id).c(d!());
+        }
+
+        let fork_point = vers
+            .iter()
+            .rev()
+            .find(|(ver, _)| target_vers.contains_key(ver))
+            .map(|(ver, _)| to_verid(&ver));
+        let replay_vers = vers
+            .iter()
+            .map(|(ver, _)| to_verid(&ver))
+            .filter(|ver| fork_point.map(|fp| *ver > fp).unwrap_or(true))
+            .collect::<Vec<_>>();
+
+        let shared = self
+            .br_to_its_vers
+            .iter()
+            .filter(|(br, _)| br[..] != br_id[..])
+            .any(|(_, vs)| {
+                let vs = decode_map(vs);
+                replay_vers.iter().any(|ver| vs.contains_key(ver))
+            });
+        if shared {
+            return Err(eg!(
+                "some versions to be replayed are used by other branches"
+            ));
+        }
+
+        let names = replay_vers
+            .iter()
+            .map(|ver| {
+                self.ver_id_to_ver_name
+                    .read()
+                    .get(ver)
+                    .cloned()
+                    .c(d!("version not found"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let tmp_br_name = unused_name(&self.br_name_to_br_id, b"rebase");
+        unsafe {
+            self.branch_create_by_base_branch_without_new_version(
+                &tmp_br_name,
+                target_br_id,
+                false,
+            )
+            .c(d!())?;
+        }
+        let tmp_br_id = self
+            .br_name_to_br_id
+            .get(&tmp_br_name)
+            .map(|id| to_brid(&id))
+            .c(d!())?;
+
+        let mut replayed = Vec::with_capacity(replay_vers.len());
+        let mut failed = None;
+        for (ver, name) in replay_vers.iter().zip(names.iter()) {
+            let tmp_ver_name = unused_name(&self.ver_name_to_ver_id, name);
+            match self
+                .version_cherry_pick(*ver, br_id, tmp_br_id, &tmp_ver_name, false)
+                .and_then(|_| self.branch_get_head(tmp_br_id).c(d!()))
+            {
+                Ok(new_ver) => replayed.push(new_ver),
+                Err(e) => {
+                    failed = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(e) = failed {
+            // a failed cherry-pick may have created its version
+            let created = decode_map(self.br_to_its_vers.get(tmp_br_id).c(d!())?)
+                .iter()
+                .map(|(ver, _)| to_verid(&ver))
+                .filter(|ver| !target_vers.contains_key(ver))
+                .collect::<Vec<_>>();
+            for ver in created.into_iter() {
+                unsafe { self.version_revert_globally(ver).c(d!())? };
+            }
+            self.branch_remove(tmp_br_id).c(d!())?;
+            return Err(e).c(d!());
+        }
+
+        // fast-forward the target branch to the new tip
+        self.branch_merge_to(tmp_br_id, target_br_id).c(d!())?;
+        self.branch_remove(tmp_br_id).c(d!())?;
+
+        // the original versions are only used by `br_id`, as checked above
+        if let Some(fp) = fork_point {
+            self.branch_truncate_to(br_id, fp).c(d!())?;
+        } else {
+            self.branch_truncate(br_id).c(d!())?;
+        }
+        for ((old, name), new) in replay_vers.iter().zip(names.iter()).zip(replayed) {
+            unsafe { self.version_revert_globally(*old).c(d!())? };
+            self.version_rename(new, name).c(d!())?;
+        }
+
+        // fast-forward the source branch to the new tip
+        self.branch_merge_to(target_br_id, br_id).c(d!())
+    }
+
     // Merge a branch into another,
     // even if new different versions have been created on the target branch.
     //
@@ -1180,6 +2726,8 @@ impl MapxRawVs {
         target_br_id: BranchID,
         force: bool,
     ) -> Result<()> {
+        self.ver_set_cache_invalidate();
+
         let vers = decode_map(self.br_to_its_vers.get(br_id).c(d!("branch not found"))?);
         let mut target_vers = decode_map(
             self.br_to_its_vers
@@ -1239,11 +2787,14 @@ impl MapxRawVs {
             return Ok(());
         };
 
+        self.br_to_len.write().remove(&target_br_id);
+
         Ok(())
     }
 
     #[inline(always)]
     pub(super) fn branch_set_default(&mut self, br_id: BranchID) -> Result<()> {
+        self.batch_check().c(d!())?;
         if !self.branch_exists(br_id) {
             return Err(eg!("branch not found"));
         }
@@ -1296,6 +2847,7 @@ impl MapxRawVs {
         branch_1: &[u8],
         branch_2: &[u8],
     ) -> Result<()> {
+        self.batch_check().c(d!())?;
         let mut br_hdr = self.br_id_to_br_name.write();
 
         let brid_1 = to_brid(&self.br_name_to_br_id.get(branch_1).c(d!())?);
@@ -1307,6 +2859,25 @@ impl MapxRawVs {
         br_hdr.insert(brid_1, branch_2.to_vec()).c(d!())?;
         br_hdr.insert(brid_2, branch_1.to_vec()).c(d!())?;
 
+        // keep the relationships between the branch names unchanged
+        let swap_id = |id: BranchID| match id {
+            _ if id == brid_1 => brid_2,
+            _ if id == brid_2 => brid_1,
+            _ => id,
+        };
+        let relations = self
+            .br_to_parent
+            .iter()
+            .map(|(c, p)| (to_brid(&c), to_brid(&p)))
+            .filter(|(c, p)| [brid_1, brid_2].iter().any(|id| id == c || id == p))
+            .collect::<Vec<_>>();
+        relations.iter().for_each(|(c, _)| {
+            self.br_to_parent.remove(c);
+        });
+        relations.into_iter().for_each(|(c, p)| {
+            self.br_to_parent.insert(swap_id(c), swap_id(p));
+        });
+
         if self.default_branch == brid_1 {
             self.default_branch = brid_2;
         } else if self.default_branch == brid_2 {
@@ -1316,6 +2887,27 @@ impl MapxRawVs {
         Ok(())
     }
 
+    // The default branch is recorded by ID, so it needs not to be changed.
+    pub(super) fn branch_rename(
+        &mut self,
+        br_id: BranchID,
+        new_name: &[u8],
+    ) -> Result<()> {
+        self.batch_check().c(d!())?;
+        if self.br_name_to_br_id.contains_key(new_name) {
+            return Err(eg!("branch already exists"));
+        }
+
+        let mut br_hdr = self.br_id_to_br_name.write();
+        let old_name = br_hdr.get(&br_id).c(d!("branch not found"))?.clone();
+
+        self.br_name_to_br_id.remove(&old_name);
+        self.br_name_to_br_id.insert(new_name, br_id);
+        br_hdr.insert(br_id, new_name.to_vec());
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub(super) fn branch_get_id_by_name(&self, br_name: BranchName) -> Option<BranchID> {
         self.br_name_to_br_id
@@ -1325,6 +2917,7 @@ impl MapxRawVs {
 
     #[inline(always)]
     pub(super) fn prune(&mut self, reserved_ver_num: Option<usize>) -> Result<()> {
+        self.batch_check().c(d!())?;
         self.do_prune(reserved_ver_num, false).c(d!())
     }
 
@@ -1339,6 +2932,9 @@ impl MapxRawVs {
         reserved_ver_num: Option<usize>,
         clean_only: bool,
     ) -> Result<()> {
+        self.cache_clear();
+        self.ver_set_cache_invalidate();
+
         /////////////////////////////////////////////////////////////////////
         let (mut ver_hdr, mut chgset_hdr) = if 0 == rand::random::<u32>() % 16 {
             (
@@ -1478,6 +3074,8 @@ impl MapxRawVs {
             self.layered_kv.remove(k).c(d!())?;
         }
 
+        self.len_cache_invalidate();
+
         Ok(())
     }
 }
@@ -1547,6 +3145,60 @@ impl DoubleEndedIterator for MapxRawVsIter<'_> {
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
+/// Changes of a branch in version order,
+/// each item is `(version, key, new_value)`, `None` means deleted.
+pub struct MapxRawVsChangeStream<'a> {
+    hdr: &'a MapxRawVs,
+    vers: std::vec::IntoIter<VersionID>,
+    changes: VecDeque<(VersionNameOwned, RawKey, Option<RawValue>)>,
+}
+
+impl<'a> MapxRawVsChangeStream<'a> {
+    // Load the change set of a version, keys are in ascending order.
+    fn load(&mut self, ver_id: VersionID) {
+        let name = if let Some(name) = self.hdr.ver_id_to_ver_name.read().get(&ver_id) {
+            VersionNameOwned(name.clone())
+        } else {
+            return;
+        };
+
+        let chgset_hdr = self.hdr.ver_to_change_set.read();
+        if let Some(chgset) = chgset_hdr.get(&ver_id) {
+            chgset.iter().for_each(|k| {
+                if let Some(v) = self
+                    .hdr
+                    .layered_kv
+                    .get(k)
+                    .and_then(|kvers| decode_map(kvers).get(ver_id))
+                {
+                    self.changes.push_back((
+                        name.clone(),
+                        k.clone(),
+                        self.hdr.stored_to_value(v),
+                    ));
+                }
+            });
+        }
+    }
+}
+
+impl<'a> Iterator for MapxRawVsChangeStream<'a> {
+    type Item = (VersionNameOwned, RawKey, Option<RawValue>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(change) = self.changes.pop_front() {
+                return Some(change);
+            }
+            let ver_id = self.vers.next()?;
+            self.load(ver_id);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////////
+
 #[inline(always)]
 fn encode_map(m: &MapxRaw) -> &[u8] {
     m.as_prefix_slice()
@@ -1557,6 +3209,25 @@ fn decode_map(v: impl AsRef<[u8]>) -> MapxRaw {
     unsafe { MapxRaw::from_prefix_slice(v.as_ref()) }
 }
 
+// A name derived from `base` that is not used in `names`,
+// for the temporary branches and versions.
+#[inline(always)]
+fn unused_name(names: &MapxRaw, base: &[u8]) -> RawValue {
+    (0u64..)
+        .map(|i| [base, &b"@tmp"[..], &i.to_be_bytes()[..]].concat())
+        .find(|n| !names.contains_key(n))
+        .unwrap()
+}
+
+// The name of the single version of a detached branch.
+#[inline(always)]
+fn detached_ver_name(names: &MapxRaw, br_name: &[u8]) -> RawValue {
+    (0u64..)
+        .map(|i| [br_name, &b"@detached"[..], &i.to_be_bytes()[..]].concat())
+        .find(|n| !names.contains_key(n))
+        .unwrap()
+}
+
 #[inline(always)]
 fn to_brid(bytes: &[u8]) -> BranchID {
     <[u8; size_of::<BranchID>()]>::try_from(bytes).unwrap()
@@ -31,6 +31,18 @@
 //! assert_eq!(l.len(), 0);
 //! ```
 //!
+//! # Value compression
+//!
+//! If the `compressed-values` feature is enabled,
+//! every non-empty value of the newly created instances will be compressed
+//! by `lz4` before being stored, and will be decompressed when being read,
+//! all public APIs keep unchanged.
+//!
+//! Whether an instance compresses its values is decided when it is created,
+//! and is persisted along with the instance, so toggling this feature
+//! does not change the existing instances: the compressed ones are still
+//! readable without this feature, and the old ones are never compressed.
+//!
 
 mod backend;
 
@@ -50,7 +62,12 @@ use std::{
     ops::{Deref, DerefMut, RangeBounds},
 };
 
-pub use backend::MapxRawVsIter;
+pub use backend::{MapxRawVsChangeStream, MapxRawVsIter};
+
+#[cfg(feature = "access-log")]
+pub use backend::AccessType;
+#[cfg(feature = "access-log")]
+use std::time::SystemTime;
 
 /// Advanced `MapxRaw`, with versioned feature.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -84,6 +101,91 @@ impl MapxRawVs {
         }
     }
 
+    /// Create an instance with an in-memory LRU cache,
+    /// which holds the values of the most recently read keys
+    /// on the head of each branch.
+    ///
+    /// NOTE:
+    /// - the cache is not persisted, a decoded instance has no cache
+    /// - a `0` capacity means no cache, same as `new`
+    #[cfg(feature = "lru-cache")]
+    #[inline(always)]
+    pub fn with_cache(capacity: usize) -> Self {
+        if let Some(capacity) = std::num::NonZeroUsize::new(capacity) {
+            Self {
+                inner: backend::MapxRawVs::with_cache(capacity),
+            }
+        } else {
+            Self::new()
+        }
+    }
+
+    /// Get the read/write events of a branch, in chronological order,
+    /// only the events of `get_by_branch` and `insert_by_branch` are recorded.
+    ///
+    /// NOTE:
+    /// - at most 10_000 events are kept for each branch, older ones are dropped
+    /// - the log is not persisted, a decoded instance has an empty log
+    #[cfg(feature = "access-log")]
+    #[inline(always)]
+    pub fn branch_access_log(
+        &self,
+        br_name: BranchName,
+    ) -> Result<Vec<(AccessType, SystemTime)>> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        Ok(self.inner.branch_access_log(br_id))
+    }
+
+    /// Clear all the recorded events of a branch.
+    #[cfg(feature = "access-log")]
+    #[inline(always)]
+    pub fn branch_clear_access_log(&mut self, br_name: BranchName) -> Result<()> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        self.inner.branch_clear_access_log(br_id);
+        Ok(())
+    }
+
+    /// Start buffering the writes to the default branch in memory,
+    /// they will be applied to its current head version by `flush_batch`.
+    ///
+    /// NOTE:
+    /// - the target version is fixed here, so the default branch must have a version
+    /// - all the writes to the default branch are buffered,
+    ///   including the ones of `insert_by_branch`, `compare_and_swap`, `get_mut`, etc.
+    /// - the point reads on the head of the default branch can see the buffered writes,
+    ///   they are `get`, `get_by_branch`, `get_batch`, `get_batch_by_branch`,
+    ///   `contains_key` and `contains_key_by_branch`
+    /// - other reads ignore the buffer, and see the data as it was when the batch began,
+    ///   they are `len*`, `iter*`, `range*`, `first*`, `last*`, `get_ge*`, `get_le*`
+    ///   and all the `*_by_branch_version` functions
+    /// - changes of versions and branches(`version_create`, `branch_set_default`, etc.)
+    ///   are refused until the batch is flushed or rolled back
+    /// - the buffer is not persisted, an unflushed batch is lost on restarting
+    /// - nested batches are not allowed
+    #[inline(always)]
+    pub fn begin_batch(&mut self) -> Result<()> {
+        self.inner.begin_batch().c(d!())
+    }
+
+    /// Apply all the buffered writes to the head of the default branch,
+    /// and end the current batch.
+    #[inline(always)]
+    pub fn flush_batch(&mut self) -> Result<()> {
+        self.inner.flush_batch().c(d!())
+    }
+
+    /// Discard all the buffered writes, and end the current batch.
+    #[inline(always)]
+    pub fn rollback_batch(&mut self) {
+        self.inner.rollback_batch()
+    }
+
     /// Insert a KV to the head version of the default branch.
     #[inline(always)]
     pub fn insert(
@@ -103,9 +205,13 @@ impl MapxRawVs {
         br_name: BranchName,
     ) -> Result<Option<RawValue>> {
         let br_id = self.inner.branch_get_id_by_name(br_name).c(d!())?;
-        self.inner
+        let ret = self
+            .inner
             .insert_by_branch(key.as_ref(), value.as_ref(), br_id)
-            .c(d!())
+            .c(d!())?;
+        #[cfg(feature = "access-log")]
+        self.inner.access_log_push(br_id, AccessType::Write);
+        Ok(ret)
     }
 
     /// Remove a KV from the head version of the default branch.
@@ -125,6 +231,40 @@ impl MapxRawVs {
         self.inner.remove_by_branch(key.as_ref(), br_id).c(d!())
     }
 
+    /// Update the value of a key on the head version of the default branch,
+    /// only if its current value equals to `expected`.
+    ///
+    /// - `expected == None` means the key must not exist
+    /// - `new_value == None` means the key will be removed
+    ///
+    /// Return `true` if the swap happened, or `false` if nothing has been done.
+    #[inline(always)]
+    pub fn compare_and_swap(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        expected: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<bool> {
+        self.inner
+            .compare_and_swap(key.as_ref(), expected, new_value)
+            .c(d!())
+    }
+
+    /// Same as `compare_and_swap`, but on a specified branch.
+    #[inline(always)]
+    pub fn compare_and_swap_by_branch(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        expected: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+        br_name: BranchName,
+    ) -> Result<bool> {
+        let br_id = self.inner.branch_get_id_by_name(br_name).c(d!())?;
+        self.inner
+            .compare_and_swap_by_branch(key.as_ref(), expected, new_value, br_id)
+            .c(d!())
+    }
+
     /// Get the value of a key from the default branch.
     #[inline(always)]
     pub fn get(&self, key: impl AsRef<[u8]>) -> Option<RawValue> {
@@ -162,6 +302,8 @@ impl MapxRawVs {
         br_name: BranchName,
     ) -> Option<RawValue> {
         let br_id = self.inner.branch_get_id_by_name(br_name)?;
+        #[cfg(feature = "access-log")]
+        self.inner.access_log_push(br_id, AccessType::Read);
         self.inner.get_by_branch(key.as_ref(), br_id)
     }
 
@@ -179,6 +321,46 @@ impl MapxRawVs {
             .get_by_branch_version(key.as_ref(), br_id, ver_id)
     }
 
+    /// Get the values of a batch of keys from the default branch,
+    /// the results are in the same order as the keys.
+    #[inline(always)]
+    pub fn get_batch(&self, keys: &[impl AsRef<[u8]>]) -> Vec<Option<RawValue>> {
+        self.inner.get_batch(keys)
+    }
+
+    /// Get the values of a batch of keys from the head of a specified branch,
+    /// the results are in the same order as the keys.
+    #[inline(always)]
+    pub fn get_batch_by_branch(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        br_name: BranchName,
+    ) -> Vec<Option<RawValue>> {
+        if let Some(br_id) = self.inner.branch_get_id_by_name(br_name) {
+            self.inner.get_batch_by_branch(keys, br_id)
+        } else {
+            vec![None; keys.len()]
+        }
+    }
+
+    /// Get the values of a batch of keys from a specified version of a specified branch,
+    /// the results are in the same order as the keys.
+    #[inline(always)]
+    pub fn get_batch_by_branch_version(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Vec<Option<RawValue>> {
+        let br_id = self.inner.branch_get_id_by_name(br_name);
+        let ver_id = self.inner.version_get_id_by_name(ver_name);
+        if let (Some(br_id), Some(ver_id)) = (br_id, ver_id) {
+            self.inner.get_batch_by_branch_version(keys, br_id, ver_id)
+        } else {
+            vec![None; keys.len()]
+        }
+    }
+
     /// Get the value of a key from the default branch,
     /// if the target key does not exist, will try to
     /// search a closest value bigger than the target key.
@@ -296,6 +478,73 @@ impl MapxRawVs {
         self.inner.iter_by_branch_version(br_id, ver_id)
     }
 
+    /// Get the first KV of the default branch.
+    ///
+    /// NOTE: this is an O(k) scan until O(1) optimizations are added,
+    /// `k` is the number of leading keys removed on the branch.
+    #[inline(always)]
+    pub fn first(&self) -> Option<(RawKey, RawValue)> {
+        self.iter().next()
+    }
+
+    /// Get the first KV of the head of a specified branch.
+    ///
+    /// NOTE: the same O(k) cost as [`first`](Self::first).
+    #[inline(always)]
+    pub fn first_by_branch(&self, br_name: BranchName) -> Option<(RawKey, RawValue)> {
+        self.iter_by_branch(br_name).next()
+    }
+
+    /// Get the first KV of a specified version of a specified branch.
+    ///
+    /// NOTE: the same O(k) cost as [`first`](Self::first).
+    #[inline(always)]
+    pub fn first_by_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Option<(RawKey, RawValue)> {
+        self.iter_by_branch_version(br_name, ver_name).next()
+    }
+
+    /// Get the last KV of the default branch.
+    ///
+    /// NOTE: this is an O(k) scan until O(1) optimizations are added,
+    /// `k` is the number of trailing keys removed on the branch.
+    #[inline(always)]
+    pub fn last(&self) -> Option<(RawKey, RawValue)> {
+        self.iter().next_back()
+    }
+
+    /// Get the last KV of the head of a specified branch.
+    ///
+    /// NOTE: the same O(k) cost as [`last`](Self::last).
+    #[inline(always)]
+    pub fn last_by_branch(&self, br_name: BranchName) -> Option<(RawKey, RawValue)> {
+        self.iter_by_branch(br_name).next_back()
+    }
+
+    /// Get the last KV of a specified version of a specified branch.
+    ///
+    /// NOTE: the same O(k) cost as [`last`](Self::last).
+    #[inline(always)]
+    pub fn last_by_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Option<(RawKey, RawValue)> {
+        self.iter_by_branch_version(br_name, ver_name).next_back()
+    }
+
+    /// Iterate all keys ever written, regardless of branches and versions,
+    /// the removed keys are also included if they have not been pruned.
+    ///
+    /// NOTE: this is a raw storage scan, mainly used by GC-like tools.
+    #[inline(always)]
+    pub fn iter_all_keys_globally(&self) -> impl Iterator<Item = RawKey> + '_ {
+        self.inner.iter_all_keys_globally()
+    }
+
     /// Create a range iterator over the default branch.
     #[inline(always)]
     pub fn range_mut<'a, R: RangeBounds<Cow<'a, [u8]>>>(
@@ -344,6 +593,61 @@ impl MapxRawVs {
         self.inner.range_by_branch_version(br_id, ver_id, bounds)
     }
 
+    /// Visit the KVs of the default branch in key order,
+    /// the scan stops once `f` returns `Ok(false)` or an error.
+    #[inline(always)]
+    pub fn scan<F>(&self, f: F) -> Result<()>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        do_scan(self.inner.iter(), f).c(d!())
+    }
+
+    /// Visit the KVs of a specified branch in key order,
+    /// the scan stops once `f` returns `Ok(false)` or an error.
+    #[inline(always)]
+    pub fn scan_by_branch<F>(&self, br_name: BranchName, f: F) -> Result<()>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        do_scan(self.inner.iter_by_branch(br_id), f).c(d!())
+    }
+
+    /// Visit the KVs within the `bounds` of the default branch in key order,
+    /// the scan stops once `f` returns `Ok(false)` or an error.
+    #[inline(always)]
+    pub fn scan_range<'a, R, F>(&'a self, bounds: R, f: F) -> Result<()>
+    where
+        R: RangeBounds<Cow<'a, [u8]>>,
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        do_scan(self.inner.range(bounds), f).c(d!())
+    }
+
+    /// Visit the KVs within the `bounds` of a specified branch in key order,
+    /// the scan stops once `f` returns `Ok(false)` or an error.
+    #[inline(always)]
+    pub fn scan_range_by_branch<'a, R, F>(
+        &'a self,
+        br_name: BranchName,
+        bounds: R,
+        f: F,
+    ) -> Result<()>
+    where
+        R: RangeBounds<Cow<'a, [u8]>>,
+        F: FnMut(&[u8], &[u8]) -> Result<bool>,
+    {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        do_scan(self.inner.range_by_branch(br_id, bounds), f).c(d!())
+    }
+
     /// Check if a key exist on the default branch.
     #[inline(always)]
     pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
@@ -372,17 +676,19 @@ impl MapxRawVs {
             .is_some()
     }
 
-    /// NOTE: just a stupid O(n) counter, very slow!
-    ///
     /// Get the total number of items of the default branch.
+    ///
+    /// NOTE: O(1) in most cases, the cached counter will be
+    /// re-derived in O(n) after some heavy operations, e.g. `prune`.
     #[inline(always)]
     pub fn len(&self) -> usize {
         self.inner.len()
     }
 
-    /// NOTE: just a stupid O(n) counter, very slow!
-    ///
     /// Get the total number of items of the head of a specified branch.
+    ///
+    /// NOTE: O(1) in most cases, the cached counter will be
+    /// re-derived in O(n) after some heavy operations, e.g. `prune`.
     #[inline(always)]
     pub fn len_by_branch(&self, br_name: BranchName) -> usize {
         self.inner
@@ -391,7 +697,7 @@ impl MapxRawVs {
             .unwrap_or(0)
     }
 
-    /// NOTE: just a stupid O(n) counter, very slow!
+    /// NOTE: just a stupid O(n) counter for non-head versions, very slow!
     ///
     /// Get the total number of items of a specified version of a specified branch.
     #[inline(always)]
@@ -434,6 +740,228 @@ impl MapxRawVs {
             .is_none()
     }
 
+    /// Get the net number of keys changed by a specified version of a specified branch,
+    /// a positive value means keys added, a negative value means keys removed.
+    #[inline(always)]
+    pub fn version_key_count_delta(
+        &self,
+        ver_name: VersionName,
+        br_name: BranchName,
+    ) -> Result<i64> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let ver_id = self
+            .inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))?;
+        self.inner.version_key_count_delta(ver_id, br_id).c(d!())
+    }
+
+    /// Get all keys whose values differ between two versions of a specified branch,
+    /// each entry is `(key, value_at_ver_a, value_at_ver_b)`.
+    #[inline(always)]
+    pub fn diff_versions(
+        &self,
+        br_name: BranchName,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let ver_a = self
+            .inner
+            .version_get_id_by_name(ver_a)
+            .c(d!("version not found"))?;
+        let ver_b = self
+            .inner
+            .version_get_id_by_name(ver_b)
+            .c(d!("version not found"))?;
+        self.inner.diff_versions(br_id, ver_a, ver_b).c(d!())
+    }
+
+    /// Stream the changes of a specified branch in version order,
+    /// and in key order within each version, starting from `from_ver`(inclusive),
+    /// or from the first version if `None`.
+    ///
+    /// An empty stream will be returned if the branch or `from_ver` does not exist.
+    #[inline(always)]
+    pub fn change_stream<'a>(
+        &'a self,
+        br_name: BranchName,
+        from_ver: Option<VersionName>,
+    ) -> MapxRawVsChangeStream<'a> {
+        let br_id = self.inner.branch_get_id_by_name(br_name).unwrap_or(NULL_ID);
+        let ver_id =
+            from_ver.map(|v| self.inner.version_get_id_by_name(v).unwrap_or(NULL_ID));
+        self.inner.change_stream(br_id, ver_id)
+    }
+
+    /// List all versions of a specified branch that have changed the key,
+    /// ordered by the creation time, each entry is `(version, value_after_change)`,
+    /// a `None` value means the key was removed in that version.
+    #[inline(always)]
+    pub fn versions_touching_key(
+        &self,
+        key: impl AsRef<[u8]>,
+        br_name: BranchName,
+    ) -> Result<Vec<(VersionNameOwned, Option<RawValue>)>> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        self.inner
+            .versions_touching_key(key.as_ref(), br_id)
+            .c(d!())
+    }
+
+    /// Get the value of the key at the earliest version within `[from_ver, to_ver]`
+    /// of a specified branch at which the key is defined,
+    /// `None` will be returned if the key is not defined in the whole range,
+    /// or any of the branch and versions does not exist.
+    #[inline(always)]
+    pub fn get_by_version_range(
+        &self,
+        key: impl AsRef<[u8]>,
+        br_name: BranchName,
+        from_ver: VersionName,
+        to_ver: VersionName,
+    ) -> Option<RawValue> {
+        let br_id = self.inner.branch_get_id_by_name(br_name)?;
+        let from = self.inner.version_get_id_by_name(from_ver)?;
+        let to = self.inner.version_get_id_by_name(to_ver)?;
+        self.inner
+            .get_by_version_range(key.as_ref(), br_id, from, to)
+    }
+
+    /// Check if a branch has any versions that are not shared with any other branch,
+    /// if not, removing this branch will not lose any data.
+    #[inline(always)]
+    pub fn branch_has_unique_versions(&self, br_name: BranchName) -> bool {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .map(|br_id| self.inner.branch_has_unique_versions(br_id))
+            .unwrap_or(false)
+    }
+
+    /// Create a new version on the default branch,
+    /// and copy all changes of the source version into it.
+    #[inline(always)]
+    pub fn version_copy(
+        &mut self,
+        src_ver: VersionName,
+        dst_ver: VersionName,
+    ) -> Result<()> {
+        self.inner
+            .version_get_id_by_name(src_ver)
+            .c(d!("source version not found"))
+            .and_then(|src_ver_id| {
+                self.inner.version_copy(src_ver_id, dst_ver.0).c(d!())
+            })
+    }
+
+    /// Collapse all versions of the default branch into one version,
+    /// which holds the current state and is named `snapshot_ver_name`,
+    /// all the history of the overwritten or removed values will be dropped,
+    /// the result is a map with only one branch and one version.
+    ///
+    /// Fail if other branches exist, unless `force` is true,
+    /// in which case all other branches will be removed.
+    #[inline(always)]
+    pub fn compact_to_snapshot(
+        &mut self,
+        snapshot_ver_name: VersionName,
+        force: bool,
+    ) -> Result<()> {
+        let br_id = self.inner.branch_get_default();
+        self.inner
+            .compact_to_snapshot_by_branch(br_id, snapshot_ver_name.0, force)
+            .c(d!())
+    }
+
+    /// Same as [`compact_to_snapshot`](Self::compact_to_snapshot),
+    /// but on a specified branch, which will be the new default branch.
+    #[inline(always)]
+    pub fn compact_to_snapshot_by_branch(
+        &mut self,
+        br_name: BranchName,
+        snapshot_ver_name: VersionName,
+        force: bool,
+    ) -> Result<()> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        self.inner
+            .compact_to_snapshot_by_branch(br_id, snapshot_ver_name.0, force)
+            .c(d!())
+    }
+
+    /// Export all KVs of the head version of a specified branch,
+    /// or the default branch if `None` is given, as a portable byte blob.
+    ///
+    /// The blob can be imported into another `MapxRawVs`,
+    /// even in a different process, by the `import_snapshot` function.
+    pub fn export_snapshot(&self, br_name: Option<BranchName>) -> Result<Vec<u8>> {
+        let iter = if let Some(br_name) = br_name {
+            self.inner
+                .branch_get_id_by_name(br_name)
+                .c(d!("branch not found"))
+                .map(|br_id| self.inner.iter_by_branch(br_id))?
+        } else {
+            self.iter()
+        };
+
+        let mut cnt = 0u64;
+        let mut ret = cnt.to_be_bytes().to_vec();
+        for (k, v) in iter {
+            snapshot_encode_field(&mut ret, &k);
+            snapshot_encode_field(&mut ret, &v);
+            cnt += 1;
+        }
+        ret[..SNAPSHOT_LEN_SIZE].copy_from_slice(&cnt.to_be_bytes());
+
+        Ok(ret)
+    }
+
+    /// Create a new version on the default branch,
+    /// and insert all KVs of a snapshot exported by `export_snapshot` into it.
+    ///
+    /// Fail if the default branch already has data, unless `force` is `true`,
+    /// in which case the existing KVs that are not in the snapshot are kept.
+    pub fn import_snapshot(
+        &mut self,
+        ver_name: VersionName,
+        snapshot: &[u8],
+        force: bool,
+    ) -> Result<()> {
+        if !force && !self.is_empty() {
+            return Err(eg!("the default branch is not empty"));
+        }
+
+        let mut buf = snapshot;
+        let cnt = snapshot_decode_len(&mut buf).c(d!())?;
+        let mut kvs = vec![];
+        for _ in 0..cnt {
+            let k = snapshot_decode_field(&mut buf).c(d!())?;
+            let v = snapshot_decode_field(&mut buf).c(d!())?;
+            kvs.push((k, v));
+        }
+        if !buf.is_empty() {
+            return Err(eg!("invalid snapshot: trailing bytes"));
+        }
+
+        self.version_create(ver_name).c(d!())?;
+        for (k, v) in kvs.iter() {
+            self.insert(k, v).c(d!())?;
+        }
+
+        Ok(())
+    }
+
     /// Clear all data, mainly for testing purpose.
     #[inline(always)]
     pub fn clear(&mut self) {
@@ -441,6 +969,46 @@ impl MapxRawVs {
     }
 }
 
+fn do_scan<F>(iter: MapxRawVsIter, mut f: F) -> Result<()>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<bool>,
+{
+    for (k, v) in iter {
+        if !f(&k, &v).c(d!())? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+const SNAPSHOT_LEN_SIZE: usize = std::mem::size_of::<u64>();
+
+// Format: `[ length(u64, big endian) | bytes ]`
+fn snapshot_encode_field(buf: &mut Vec<u8>, field: &[u8]) {
+    buf.extend_from_slice(&(field.len() as u64).to_be_bytes());
+    buf.extend_from_slice(field);
+}
+
+fn snapshot_decode_len(buf: &mut &[u8]) -> Result<usize> {
+    if buf.len() < SNAPSHOT_LEN_SIZE {
+        return Err(eg!("invalid snapshot: truncated length"));
+    }
+    let (len, rest) = buf.split_at(SNAPSHOT_LEN_SIZE);
+    *buf = rest;
+    let len = u64::from_be_bytes(<[u8; SNAPSHOT_LEN_SIZE]>::try_from(len).unwrap());
+    usize::try_from(len).c(d!())
+}
+
+fn snapshot_decode_field(buf: &mut &[u8]) -> Result<Vec<u8>> {
+    let len = snapshot_decode_len(buf).c(d!())?;
+    if buf.len() < len {
+        return Err(eg!("invalid snapshot: truncated field"));
+    }
+    let (field, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(field.to_vec())
+}
+
 impl VsMgmt for MapxRawVs {
     /// Create a new version on the default branch.
     #[inline(always)]
@@ -500,6 +1068,23 @@ impl VsMgmt for MapxRawVs {
             .unwrap_or(false)
     }
 
+    /// Same as `version_exists_on_branch`, but O(1) in most cases.
+    #[inline(always)]
+    fn branch_contains_version_fast(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> bool {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .and_then(|br_id| {
+                self.inner
+                    .version_get_id_by_name(ver_name)
+                    .map(|ver_id| self.inner.branch_contains_version_fast(br_id, ver_id))
+            })
+            .unwrap_or(false)
+    }
+
     /// Remove the newest version on the default branch.
     ///
     /// 'Write'-like operations on branches and versions are different from operations on data.
@@ -578,6 +1163,52 @@ impl VsMgmt for MapxRawVs {
             .and_then(|brid| self.inner.version_list_by_branch(brid).c(d!()))
     }
 
+    #[inline(always)]
+    fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionName,
+        descendant: VersionName,
+        br_name: BranchName,
+    ) -> Result<bool> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let ancestor = self
+            .inner
+            .version_get_id_by_name(ancestor)
+            .c(d!("version not found"))?;
+        let descendant = self
+            .inner
+            .version_get_id_by_name(descendant)
+            .c(d!("version not found"))?;
+        self.inner
+            .version_is_ancestor_of(ancestor, descendant, br_id)
+            .c(d!())
+    }
+
+    #[inline(always)]
+    fn versions_between(
+        &self,
+        from: VersionName,
+        to: VersionName,
+        br_name: BranchName,
+    ) -> Result<Vec<VersionNameOwned>> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let from = self
+            .inner
+            .version_get_id_by_name(from)
+            .c(d!("version not found"))?;
+        let to = self
+            .inner
+            .version_get_id_by_name(to)
+            .c(d!("version not found"))?;
+        self.inner.versions_between(from, to, br_id).c(d!())
+    }
+
     #[inline(always)]
     fn version_list_globally(&self) -> Vec<VersionNameOwned> {
         self.inner.version_list_globally()
@@ -591,6 +1222,83 @@ impl VsMgmt for MapxRawVs {
             .and_then(|verid| self.inner.version_has_change_set(verid).c(d!()))
     }
 
+    #[inline(always)]
+    fn version_list_changed_keys(&self, ver_name: VersionName) -> Result<Vec<RawKey>> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_list_changed_keys(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_changed_key_count(&self, ver_name: VersionName) -> Result<usize> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_changed_key_count(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_chgset_size(&self, ver_name: VersionName) -> Result<usize> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_changed_key_count(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_chgset_size_by_branch(&self, br_name: BranchName) -> Result<usize> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_chgset_size_by_branch(brid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<bool> {
+        let ver_a = self
+            .inner
+            .version_get_id_by_name(ver_a)
+            .c(d!("version not found"))?;
+        let ver_b = self
+            .inner
+            .version_get_id_by_name(ver_b)
+            .c(d!("version not found"))?;
+        self.inner.version_has_conflicts_with(ver_a, ver_b).c(d!())
+    }
+
+    #[inline(always)]
+    fn version_conflict_keys(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<Vec<RawKey>> {
+        let ver_a = self
+            .inner
+            .version_get_id_by_name(ver_a)
+            .c(d!("version not found"))?;
+        let ver_b = self
+            .inner
+            .version_get_id_by_name(ver_b)
+            .c(d!("version not found"))?;
+        self.inner.version_conflict_keys(ver_a, ver_b).c(d!())
+    }
+
+    #[inline(always)]
+    fn version_blame(
+        &self,
+        br_name: BranchName,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_blame(brid).c(d!()))
+    }
+
     #[inline(always)]
     fn version_clean_up_globally(&mut self) -> Result<()> {
         self.inner.version_clean_up_globally().c(d!())
@@ -604,6 +1312,124 @@ impl VsMgmt for MapxRawVs {
             .and_then(|verid| self.inner.version_revert_globally(verid).c(d!()))
     }
 
+    #[inline(always)]
+    fn version_rollback_to(
+        &mut self,
+        br_name: BranchName,
+        last_ver: VersionName,
+    ) -> Result<()> {
+        if !self.version_exists_on_branch(last_ver, br_name) {
+            return Err(eg!("version not found on the branch"));
+        }
+        self.branch_truncate_to(br_name, last_ver).c(d!())?;
+        self.version_clean_up_globally().c(d!())
+    }
+
+    /// Apply the changes made by `src_ver` on `src_br` onto the head of `dst_br`,
+    /// as a new version named `dst_ver`.
+    #[inline(always)]
+    fn version_cherry_pick(
+        &mut self,
+        src_ver: VersionName,
+        src_br: BranchName,
+        dst_br: BranchName,
+        dst_ver: VersionName,
+        force: bool,
+    ) -> Result<()> {
+        let src_verid = self
+            .inner
+            .version_get_id_by_name(src_ver)
+            .c(d!("version not found"))?;
+        let src_brid = self
+            .inner
+            .branch_get_id_by_name(src_br)
+            .c(d!("branch not found"))?;
+        let dst_brid = self
+            .inner
+            .branch_get_id_by_name(dst_br)
+            .c(d!("branch not found"))?;
+        self.inner
+            .version_cherry_pick(src_verid, src_brid, dst_brid, dst_ver.0, force)
+            .c(d!())
+    }
+
+    /// The version is created by `version_create_by_branch`,
+    /// so an existing version name will be rejected.
+    fn version_apply_patch(
+        &mut self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        changes: &[(RawKey, Option<RawValue>)],
+    ) -> Result<()> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        self.inner
+            .version_create_by_branch(ver_name.0, br_id)
+            .c(d!())?;
+        for (k, v) in changes.iter() {
+            if let Some(v) = v {
+                self.inner.insert_by_branch(k, v, br_id).c(d!())?;
+            } else {
+                self.inner.remove_by_branch(k, br_id).c(d!())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the newest `count` versions of a branch into one version,
+    /// the merged version retains the name of the head version.
+    #[inline(always)]
+    fn version_squash(&mut self, br_name: BranchName, count: usize) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| unsafe {
+                self.inner.version_squash(brid, count, false).c(d!())
+            })
+    }
+
+    /// Merge the newest `count` versions of a branch into one version,
+    /// even if some of them are shared with other branches.
+    #[inline(always)]
+    unsafe fn version_squash_force(
+        &mut self,
+        br_name: BranchName,
+        count: usize,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_squash(brid, count, true).c(d!()))
+    }
+
+    /// Merge the versions between `from_ver` and `to_ver`(both inclusive)
+    /// of a branch into one version named `target_ver_name`.
+    fn version_compact_between(
+        &mut self,
+        br_name: BranchName,
+        from_ver: VersionName,
+        to_ver: VersionName,
+        target_ver_name: VersionName,
+    ) -> Result<()> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let from_ver_id = self
+            .inner
+            .version_get_id_by_name(from_ver)
+            .c(d!("version not found"))?;
+        let to_ver_id = self
+            .inner
+            .version_get_id_by_name(to_ver)
+            .c(d!("version not found"))?;
+        self.inner
+            .version_compact_between(br_id, from_ver_id, to_ver_id, target_ver_name.0)
+            .c(d!())
+    }
+
     #[inline(always)]
     fn version_chgset_trie_root(
         &self,
@@ -874,6 +1700,15 @@ impl VsMgmt for MapxRawVs {
             .and_then(|id| self.inner.branch_pop_version(id).c(d!()))
     }
 
+    /// Make a branch independent of the history of its parent.
+    #[inline(always)]
+    fn branch_detach(&mut self, br_name: BranchName) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|id| self.inner.branch_detach(id).c(d!()))
+    }
+
     /// Merge a branch into another.
     #[inline(always)]
     fn branch_merge_to(
@@ -918,6 +1753,55 @@ impl VsMgmt for MapxRawVs {
             })
     }
 
+    #[inline(always)]
+    fn branch_merge_to_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| {
+                let target_brid = self
+                    .inner
+                    .branch_get_id_by_name(target_br_name)
+                    .c(d!("target branch not found"))?;
+                self.inner.branch_merge_to_rebase(brid, target_brid).c(d!())
+            })
+    }
+
+    // `branch_merge_to_rebase` falls back to a fast-forward merge
+    // if the target branch has not advanced
+    #[inline(always)]
+    fn branch_merge_to_base_with_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        self.branch_merge_to_rebase(br_name, target_br_name).c(d!())
+    }
+
+    #[inline(always)]
+    fn branch_merge_squash(
+        &mut self,
+        src_br: BranchName,
+        dst_br: BranchName,
+        squash_ver_name: VersionName,
+    ) -> Result<()> {
+        let src_brid = self
+            .inner
+            .branch_get_id_by_name(src_br)
+            .c(d!("branch not found"))?;
+        let dst_brid = self
+            .inner
+            .branch_get_id_by_name(dst_br)
+            .c(d!("target branch not found"))?;
+        self.inner
+            .branch_merge_squash(src_brid, dst_brid, squash_ver_name.0)
+            .c(d!())
+    }
+
     /// Make a branch to be default,
     /// all default operations will be applied to it.
     #[inline(always)]
@@ -946,6 +1830,72 @@ impl VsMgmt for MapxRawVs {
         self.inner.branch_get_default_name()
     }
 
+    #[inline(always)]
+    fn branch_diff(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>> {
+        let br_a = self
+            .inner
+            .branch_get_id_by_name(br_a)
+            .c(d!("branch not found"))?;
+        let br_b = self
+            .inner
+            .branch_get_id_by_name(br_b)
+            .c(d!("branch not found"))?;
+        Ok(self.inner.branch_diff(br_a, br_b))
+    }
+
+    #[inline(always)]
+    fn branch_ancestors(&self, br_name: BranchName) -> Result<Vec<BranchNameOwned>> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .map(|br_id| self.inner.branch_ancestors(br_id))
+    }
+
+    #[inline(always)]
+    fn branch_children(&self, br_name: BranchName) -> Vec<BranchNameOwned> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .map(|br_id| self.inner.branch_children(br_id))
+            .unwrap_or_default()
+    }
+
+    #[inline(always)]
+    fn branch_is_leaf(&self, br_name: BranchName) -> bool {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .map(|br_id| self.inner.branch_is_leaf(br_id))
+            .unwrap_or(false)
+    }
+
+    #[inline(always)]
+    fn branch_fork_point(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Option<VersionNameOwned>> {
+        let br_a = self
+            .inner
+            .branch_get_id_by_name(br_a)
+            .c(d!("branch not found"))?;
+        let br_b = self
+            .inner
+            .branch_get_id_by_name(br_b)
+            .c(d!("branch not found"))?;
+        self.inner.branch_fork_point(br_a, br_b).c(d!())
+    }
+
+    #[inline(always)]
+    fn branch_is_dirty(&self, br_name: BranchName) -> Result<bool> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.branch_is_dirty(brid).c(d!()))
+    }
+
     #[inline(always)]
     unsafe fn branch_swap(
         &mut self,
@@ -955,6 +1905,18 @@ impl VsMgmt for MapxRawVs {
         self.inner.branch_swap(branch_1.0, branch_2.0).c(d!())
     }
 
+    #[inline(always)]
+    fn branch_rename(
+        &mut self,
+        old_name: BranchName,
+        new_name: BranchName,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(old_name)
+            .c(d!("branch not found"))
+            .and_then(|br_id| self.inner.branch_rename(br_id, new_name.0).c(d!()))
+    }
+
     /// Clean outdated versions out of the default reserved number.
     #[inline(always)]
     fn prune(&mut self, reserved_ver_num: Option<usize>) -> Result<()> {
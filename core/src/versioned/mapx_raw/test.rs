@@ -397,3 +397,2197 @@ fn test_prune() {
     assert!(!hdr.version_exists(VersionName(b"b")));
     assert!(hdr.version_exists(VersionName(b"c")));
 }
+
+#[test]
+fn test_version_key_count_delta() {
+    let mut hdr = MapxRawVs::new();
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.insert([0], [9]));
+    pnk!(hdr.remove([1]));
+    pnk!(hdr.remove([2]));
+
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.insert([3], [3]));
+    pnk!(hdr.insert([4], [4]));
+    pnk!(hdr.remove([4]));
+
+    assert_eq!(
+        3,
+        pnk!(hdr.version_key_count_delta(vn0, INITIAL_BRANCH_NAME))
+    );
+    assert_eq!(
+        -2,
+        pnk!(hdr.version_key_count_delta(vn1, INITIAL_BRANCH_NAME))
+    );
+    assert_eq!(
+        1,
+        pnk!(hdr.version_key_count_delta(vn2, INITIAL_BRANCH_NAME))
+    );
+
+    assert!(
+        hdr.version_key_count_delta(VersionName(b"v3"), INITIAL_BRANCH_NAME)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_branch_has_unique_versions() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+
+    let bn = BranchName(b"test");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+
+    assert!(!hdr.branch_has_unique_versions(INITIAL_BRANCH_NAME));
+    assert!(!hdr.branch_has_unique_versions(bn));
+
+    pnk!(hdr.version_create_by_branch(VersionName(b"v1"), bn));
+    pnk!(hdr.insert_by_branch([1], [1], bn));
+
+    assert!(!hdr.branch_has_unique_versions(INITIAL_BRANCH_NAME));
+    assert!(hdr.branch_has_unique_versions(bn));
+
+    pnk!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME));
+    assert!(!hdr.branch_has_unique_versions(bn));
+
+    assert!(!hdr.branch_has_unique_versions(BranchName(b"nope")));
+}
+
+#[test]
+fn test_iter_all_keys_globally() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+
+    let bn = BranchName(b"test");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch([2], [2], bn));
+    pnk!(hdr.remove([1]));
+
+    assert_eq!(1, hdr.iter().count());
+    assert_eq!(
+        vec![vec![0], vec![1], vec![2]],
+        hdr.iter_all_keys_globally().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_version_copy() {
+    let mut hdr = MapxRawVs::new();
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([0], [9]));
+    pnk!(hdr.remove([1]));
+    pnk!(hdr.insert([2], [2]));
+
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_copy(vn0, vn2));
+    assert!(hdr.version_exists(vn2));
+    assert_eq!(
+        pnk!(hdr.version_chgset_trie_root(None, Some(vn0))),
+        pnk!(hdr.version_chgset_trie_root(None, Some(vn2)))
+    );
+
+    assert_eq!(vec![0], pnk!(hdr.get([0])));
+    assert_eq!(vec![1], pnk!(hdr.get([1])));
+    assert_eq!(vec![2], pnk!(hdr.get([2])));
+
+    assert!(
+        hdr.version_copy(VersionName(b"nope"), VersionName(b"v3"))
+            .is_err()
+    );
+    assert!(hdr.version_copy(vn0, vn2).is_err());
+}
+
+#[test]
+fn test_len_cache() {
+    let mut hdr = MapxRawVs::new();
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..100_000u32).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), i.to_be_bytes()));
+    });
+    assert_eq!(100_000, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    (0..100_000u32).step_by(2).for_each(|i| {
+        pnk!(hdr.remove(i.to_be_bytes()));
+    });
+    assert_eq!(50_000, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v2"), false));
+    pnk!(hdr.insert_by_branch(0u32.to_be_bytes(), [0], bn));
+    assert_eq!(50_001, hdr.len_by_branch(bn));
+    assert_eq!(50_000, hdr.len());
+    pnk!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME));
+    assert_eq!(50_001, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+
+    pnk!(hdr.prune(Some(1)));
+    assert_eq!(50_001, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+
+    pnk!(hdr.prune(None));
+    assert_eq!(50_001, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+}
+
+#[test]
+fn test_len_cache_after_fork() {
+    let mut hdr = MapxRawVs::new();
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..10u32).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), i.to_be_bytes()));
+    });
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert_eq!(10, hdr.len_by_branch(bn));
+    assert_eq!(10, hdr.len());
+
+    // `v0` is the head of the parent, and an inherited version of `b`
+    pnk!(hdr.insert(100u32.to_be_bytes(), [0]));
+    assert_eq!(11, hdr.len());
+    assert_eq!(11, hdr.len_by_branch(bn));
+    assert_eq!(hdr.len_by_branch(bn), hdr.iter_by_branch(bn).count());
+
+    pnk!(hdr.remove(0u32.to_be_bytes()));
+    pnk!(hdr.remove(1u32.to_be_bytes()));
+    assert_eq!(9, hdr.len());
+    assert_eq!(9, hdr.len_by_branch(bn));
+    assert_eq!(hdr.len_by_branch(bn), hdr.iter_by_branch(bn).count());
+
+    // changes of the child are invisible to the parent
+    pnk!(hdr.remove_by_branch(2u32.to_be_bytes(), bn));
+    assert_eq!(8, hdr.len_by_branch(bn));
+    assert_eq!(9, hdr.len());
+    assert_eq!(hdr.len(), hdr.iter().count());
+}
+
+#[test]
+fn test_branch_diff() {
+    let mut hdr = MapxRawVs::new();
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert!(pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn)).is_empty());
+
+    pnk!(hdr.remove_by_branch([0], bn));
+    pnk!(hdr.insert_by_branch([2], [2], bn));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.insert([1], [9]));
+    pnk!(hdr.insert([3], [3]));
+
+    assert_eq!(
+        vec![
+            (vec![0], Some(vec![0]), None),
+            (vec![1], Some(vec![9]), Some(vec![1])),
+            (vec![2], None, Some(vec![2])),
+            (vec![3], Some(vec![3]), None),
+        ],
+        pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn))
+    );
+    assert_eq!(
+        vec![
+            (vec![0], None, Some(vec![0])),
+            (vec![1], Some(vec![1]), Some(vec![9])),
+            (vec![2], Some(vec![2]), None),
+            (vec![3], None, Some(vec![3])),
+        ],
+        pnk!(hdr.branch_diff(bn, INITIAL_BRANCH_NAME))
+    );
+
+    assert!(pnk!(hdr.branch_diff(bn, bn)).is_empty());
+    assert!(hdr.branch_diff(bn, BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_version_cherry_pick() {
+    let mut hdr = MapxRawVs::new();
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch([1], [7], bn));
+
+    // `v2` is not in the ancestry of the branch `b`
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.remove([0]));
+    pnk!(hdr.insert([1], [8]));
+    pnk!(hdr.insert([2], [2]));
+
+    // the version must exist on the source branch
+    assert!(
+        hdr.version_cherry_pick(
+            VersionName(b"v1"),
+            INITIAL_BRANCH_NAME,
+            bn,
+            VersionName(b"v3"),
+            false
+        )
+        .is_err()
+    );
+
+    // `[1]` has been changed on `b` in a different way
+    assert!(
+        hdr.version_cherry_pick(
+            VersionName(b"v2"),
+            INITIAL_BRANCH_NAME,
+            bn,
+            VersionName(b"v3"),
+            false
+        )
+        .is_err()
+    );
+    assert!(!hdr.version_exists(VersionName(b"v3")));
+    assert_eq!(pnk!(hdr.get_by_branch(&[1], bn)), vec![7]);
+
+    pnk!(hdr.version_cherry_pick(
+        VersionName(b"v2"),
+        INITIAL_BRANCH_NAME,
+        bn,
+        VersionName(b"v3"),
+        true
+    ));
+    assert!(hdr.version_exists_on_branch(VersionName(b"v3"), bn));
+    assert!(hdr.get_by_branch(&[0], bn).is_none());
+    assert_eq!(pnk!(hdr.get_by_branch(&[1], bn)), vec![8]);
+    assert_eq!(pnk!(hdr.get_by_branch(&[2], bn)), vec![2]);
+
+    // the source version is left untouched
+    assert!(!hdr.version_exists_on_branch(VersionName(b"v3"), INITIAL_BRANCH_NAME));
+    assert_eq!(pnk!(hdr.get(&[1])), vec![8]);
+
+    // no conflicts
+    pnk!(hdr.version_create_by_branch(VersionName(b"v4"), bn));
+    pnk!(hdr.insert_by_branch([5], [5], bn));
+    pnk!(hdr.insert_by_branch([1], [1], bn));
+    pnk!(hdr.version_cherry_pick(
+        VersionName(b"v4"),
+        bn,
+        INITIAL_BRANCH_NAME,
+        VersionName(b"v5"),
+        false
+    ));
+    assert_eq!(pnk!(hdr.get(&[5])), vec![5]);
+    assert_eq!(pnk!(hdr.get(&[1])), vec![1]);
+    assert_eq!(3, hdr.len());
+}
+
+#[test]
+fn test_branch_ancestors() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let [ba, bb, bc] = [BranchName(b"a"), BranchName(b"b"), BranchName(b"c")];
+    pnk!(hdr.branch_create(ba, VersionName(b"v1"), false));
+    pnk!(hdr.branch_create_by_base_branch(
+        bb,
+        VersionName(b"v2"),
+        ParentBranchName(b"a"),
+        false
+    ));
+    pnk!(hdr.branch_create_by_base_branch(
+        bc,
+        VersionName(b"v3"),
+        ParentBranchName(b"a"),
+        false
+    ));
+
+    assert!(pnk!(hdr.branch_ancestors(INITIAL_BRANCH_NAME)).is_empty());
+    assert_eq!(
+        vec![BranchNameOwned::from(INITIAL_BRANCH_NAME)],
+        pnk!(hdr.branch_ancestors(ba))
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(ba), INITIAL_BRANCH_NAME.into()],
+        pnk!(hdr.branch_ancestors(bb))
+    );
+    assert!(hdr.branch_ancestors(BranchName(b"nope")).is_err());
+
+    assert_eq!(
+        vec![BranchNameOwned::from(ba)],
+        hdr.branch_children(INITIAL_BRANCH_NAME)
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(bb), bc.into()],
+        hdr.branch_children(ba)
+    );
+    assert!(hdr.branch_children(bb).is_empty());
+    assert!(hdr.branch_children(BranchName(b"nope")).is_empty());
+
+    // the relationships follow the names
+    unsafe { pnk!(hdr.branch_swap(ba, bb)) };
+    assert_eq!(
+        vec![BranchNameOwned::from(ba), INITIAL_BRANCH_NAME.into()],
+        pnk!(hdr.branch_ancestors(bb))
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(bb), bc.into()],
+        hdr.branch_children(ba)
+    );
+
+    // the children are attached to the parent of the removed branch
+    pnk!(hdr.branch_remove(ba));
+    assert_eq!(
+        vec![BranchNameOwned::from(INITIAL_BRANCH_NAME)],
+        pnk!(hdr.branch_ancestors(bc))
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(bb), bc.into()],
+        hdr.branch_children(INITIAL_BRANCH_NAME)
+    );
+}
+
+#[test]
+fn test_branch_is_leaf() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let [ba, bb, bc, bd] = [
+        BranchName(b"a"),
+        BranchName(b"b"),
+        BranchName(b"c"),
+        BranchName(b"d"),
+    ];
+    pnk!(hdr.branch_create(ba, VersionName(b"va"), false));
+    pnk!(hdr.branch_create(bb, VersionName(b"vb"), false));
+    pnk!(hdr.branch_create_by_base_branch(
+        bc,
+        VersionName(b"vc"),
+        ParentBranchName(b"a"),
+        false
+    ));
+    pnk!(hdr.branch_create_by_base_branch(
+        bd,
+        VersionName(b"vd"),
+        ParentBranchName(b"a"),
+        false
+    ));
+
+    assert_eq!(
+        vec![BranchNameOwned::from(ba), bb.into()],
+        hdr.branch_children(INITIAL_BRANCH_NAME)
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(bc), bd.into()],
+        hdr.branch_children(ba)
+    );
+
+    assert!(!hdr.branch_is_leaf(INITIAL_BRANCH_NAME));
+    assert!(!hdr.branch_is_leaf(ba));
+    assert!(hdr.branch_is_leaf(bb));
+    assert!(hdr.branch_is_leaf(bc));
+    assert!(hdr.branch_is_leaf(bd));
+    assert!(!hdr.branch_is_leaf(BranchName(b"nope")));
+
+    pnk!(hdr.branch_remove(bc));
+    pnk!(hdr.branch_remove(bd));
+    assert!(hdr.branch_is_leaf(ba));
+}
+
+#[test]
+fn test_compare_and_swap() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let [v0, v1, v2]: [&[u8]; 3] = [&[0], &[1], &[2]];
+
+    // the key must be absent
+    assert!(pnk!(hdr.compare_and_swap([0], None, Some(v0))));
+    assert!(!pnk!(hdr.compare_and_swap([0], None, Some(v1))));
+    assert_eq!(v0, pnk!(hdr.get([0])));
+
+    // optimistic locking, only the first one wins
+    let expected = pnk!(hdr.get([0]));
+    let expected = Some(expected.as_slice());
+    assert!(pnk!(hdr.compare_and_swap([0], expected, Some(v1))));
+    assert!(!pnk!(hdr.compare_and_swap([0], expected, Some(v2))));
+    assert_eq!(v1, pnk!(hdr.get([0])));
+
+    // remove on success
+    assert!(!pnk!(hdr.compare_and_swap([0], Some(v0), None)));
+    assert!(pnk!(hdr.compare_and_swap([0], Some(v1), None)));
+    assert!(hdr.get([0]).is_none());
+    assert!(pnk!(hdr.compare_and_swap([0], None, None)));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert!(pnk!(hdr.compare_and_swap_by_branch(
+        [0],
+        None,
+        Some(v2),
+        bn
+    )));
+    assert!(!pnk!(hdr.compare_and_swap_by_branch(
+        [0],
+        None,
+        Some(v1),
+        bn
+    )));
+    assert_eq!(v2, pnk!(hdr.get_by_branch([0], bn)));
+    assert!(hdr.get([0]).is_none());
+    assert!(
+        hdr.compare_and_swap_by_branch([0], None, None, BranchName(b"nope"))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_snapshot() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..1000u64).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), (i * 10).to_be_bytes()));
+    });
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.remove_by_branch(0u64.to_be_bytes(), bn));
+
+    let snapshot = pnk!(hdr.export_snapshot(None));
+    assert_eq!(
+        snapshot,
+        pnk!(hdr.export_snapshot(Some(INITIAL_BRANCH_NAME)))
+    );
+    assert!(hdr.export_snapshot(Some(BranchName(b"nope"))).is_err());
+
+    let mut new_hdr = MapxRawVs::new();
+    pnk!(new_hdr.import_snapshot(VersionName(b"x"), &snapshot, false));
+    assert_eq!(1000, new_hdr.len());
+    (0..1000u64).for_each(|i| {
+        assert_eq!(
+            (i * 10).to_be_bytes().to_vec(),
+            pnk!(new_hdr.get(i.to_be_bytes()))
+        );
+    });
+    assert!(new_hdr.version_exists(VersionName(b"x")));
+
+    // the default branch is not empty
+    assert!(
+        new_hdr
+            .import_snapshot(VersionName(b"y"), &snapshot, false)
+            .is_err()
+    );
+
+    let br_snapshot = pnk!(hdr.export_snapshot(Some(bn)));
+    pnk!(new_hdr.import_snapshot(VersionName(b"y"), &br_snapshot, true));
+    assert_eq!(1000, new_hdr.len());
+
+    let mut new_hdr = MapxRawVs::new();
+    pnk!(new_hdr.import_snapshot(VersionName(b"x"), &br_snapshot, false));
+    assert_eq!(999, new_hdr.len());
+    assert!(new_hdr.get(0u64.to_be_bytes()).is_none());
+
+    // broken snapshots
+    let mut new_hdr = MapxRawVs::new();
+    assert!(
+        new_hdr
+            .import_snapshot(VersionName(b"x"), &snapshot[1..], false)
+            .is_err()
+    );
+    assert!(
+        new_hdr
+            .import_snapshot(
+                VersionName(b"x"),
+                &snapshot[..snapshot.len() - 1],
+                false
+            )
+            .is_err()
+    );
+    assert!(new_hdr.is_empty());
+}
+
+#[test]
+fn test_version_squash() {
+    let mut hdr = MapxRawVs::new();
+    (0u8..5).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&[i])));
+        pnk!(hdr.insert([i], [i]));
+        pnk!(hdr.insert([9], [i]));
+    });
+    pnk!(hdr.remove([0]));
+
+    pnk!(hdr.version_squash(INITIAL_BRANCH_NAME, 3));
+    assert_eq!(
+        vec![vec![0], vec![1], vec![4]],
+        pnk!(hdr.version_list())
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<Vec<_>>()
+    );
+    assert!(!hdr.version_exists(VersionName(&[2])));
+    assert!(!hdr.version_exists(VersionName(&[3])));
+
+    // data of the head are not changed
+    assert_eq!(4, hdr.len());
+    assert!(hdr.get([0]).is_none());
+    (1u8..5).for_each(|i| {
+        assert_eq!(vec![i], pnk!(hdr.get([i])));
+    });
+    assert_eq!(vec![4], pnk!(hdr.get([9])));
+    assert_eq!(
+        vec![1],
+        pnk!(hdr.get_by_branch_version([9], INITIAL_BRANCH_NAME, VersionName(&[1])))
+    );
+
+    // nothing to do
+    pnk!(hdr.version_squash(INITIAL_BRANCH_NAME, 1));
+    assert_eq!(3, pnk!(hdr.version_list()).len());
+    assert!(hdr.version_squash(INITIAL_BRANCH_NAME, 4).is_err());
+    assert!(hdr.version_squash(BranchName(b"nope"), 2).is_err());
+
+    // versions shared with other branches can not be squashed safely
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert!(hdr.version_squash(INITIAL_BRANCH_NAME, 2).is_err());
+    assert!(hdr.version_squash(bn, 2).is_err());
+    assert_eq!(3, pnk!(hdr.version_list()).len());
+
+    pnk!(hdr.version_create(VersionName(b"v5")));
+    pnk!(hdr.insert([5], [5]));
+    pnk!(hdr.version_create(VersionName(b"v6")));
+    pnk!(hdr.insert([6], [6]));
+    pnk!(hdr.version_squash(INITIAL_BRANCH_NAME, 2));
+    assert_eq!(
+        vec![vec![0], vec![1], vec![4], b"v6".to_vec()],
+        pnk!(hdr.version_list())
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(vec![5], pnk!(hdr.get([5])));
+    assert_eq!(vec![6], pnk!(hdr.get([6])));
+    assert!(hdr.get_by_branch([5], bn).is_none());
+
+    unsafe {
+        pnk!(hdr.version_squash_force(INITIAL_BRANCH_NAME, 2));
+    }
+    assert_eq!(3, pnk!(hdr.version_list()).len());
+    assert!(hdr.version_exists(VersionName(b"v6")));
+    assert!(!hdr.version_exists(VersionName(&[4])));
+    assert_eq!(vec![6], pnk!(hdr.get([6])));
+}
+
+#[test]
+fn test_version_compact_between() {
+    let mut hdr = MapxRawVs::new();
+    let vn = |i: u8| format!("v{}", i).into_bytes();
+
+    (1u8..=10).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&vn(i))));
+        pnk!(hdr.insert([i], [i]));
+        pnk!(hdr.insert([0], [i]));
+        if 4 == i {
+            pnk!(hdr.remove([3]));
+        }
+    });
+
+    let head = hdr.iter().collect::<Vec<_>>();
+    let at_v7 = hdr
+        .iter_by_branch_version(INITIAL_BRANCH_NAME, VersionName(b"v7"))
+        .collect::<Vec<_>>();
+
+    pnk!(hdr.version_compact_between(
+        INITIAL_BRANCH_NAME,
+        VersionName(b"v3"),
+        VersionName(b"v7"),
+        VersionName(b"v3_to_v7"),
+    ));
+
+    assert_eq!(
+        vec![vn(1), vn(2), b"v3_to_v7".to_vec(), vn(8), vn(9), vn(10)],
+        pnk!(hdr.version_list())
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<Vec<_>>()
+    );
+    (3u8..=7).for_each(|i| {
+        assert!(!hdr.version_exists_globally(VersionName(&vn(i))));
+    });
+
+    assert_eq!(head, hdr.iter().collect::<Vec<_>>());
+    assert_eq!(
+        at_v7,
+        hdr.iter_by_branch_version(INITIAL_BRANCH_NAME, VersionName(b"v3_to_v7"))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![2],
+        pnk!(hdr.get_by_branch_version([0], INITIAL_BRANCH_NAME, VersionName(b"v2")))
+    );
+
+    // invalid ranges
+    assert!(
+        hdr.version_compact_between(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v9"),
+            VersionName(b"v8"),
+            VersionName(b"x"),
+        )
+        .is_err()
+    );
+    assert!(
+        hdr.version_compact_between(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v8"),
+            VersionName(b"v9"),
+            VersionName(b"v1"),
+        )
+        .is_err()
+    );
+
+    // versions shared with other branches can not be merged
+    pnk!(hdr.branch_create(BranchName(b"b"), VersionName(b"b0"), false));
+    assert!(
+        hdr.version_compact_between(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v8"),
+            VersionName(b"v9"),
+            VersionName(b"x"),
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_scan() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..50_000u64).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), i.to_be_bytes()));
+    });
+
+    let mut cnt = 0;
+    pnk!(hdr.scan(|k, v| {
+        assert_eq!(k, v);
+        assert_eq!(k, (cnt as u64).to_be_bytes());
+        cnt += 1;
+        Ok(cnt < 100)
+    }));
+    assert_eq!(100, cnt);
+
+    // errors are propagated, and the scan stops at once
+    let mut cnt = 0;
+    assert!(
+        hdr.scan(|_, _| {
+            cnt += 1;
+            Err(eg!())
+        })
+        .is_err()
+    );
+    assert_eq!(1, cnt);
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.remove_by_branch(0u64.to_be_bytes(), bn));
+
+    let mut keys = vec![];
+    pnk!(hdr.scan_by_branch(bn, |k, _| {
+        keys.push(k.to_vec());
+        Ok(keys.len() < 3)
+    }));
+    assert_eq!(
+        (1..4u64)
+            .map(|i| i.to_be_bytes().to_vec())
+            .collect::<Vec<_>>(),
+        keys
+    );
+    assert!(
+        hdr.scan_by_branch(BranchName(b"nope"), |_, _| Ok(true))
+            .is_err()
+    );
+
+    let lo = 100u64.to_be_bytes();
+    let hi = 200u64.to_be_bytes();
+    let mut cnt = 0;
+    pnk!(
+        hdr.scan_range(Cow::Borrowed(&lo[..])..Cow::Borrowed(&hi[..]), |_, _| {
+            cnt += 1;
+            Ok(true)
+        })
+    );
+    assert_eq!(100, cnt);
+
+    let mut keys = vec![];
+    pnk!(
+        hdr.scan_range_by_branch(bn, ..Cow::Borrowed(&hi[..]), |k, _| {
+            keys.push(k.to_vec());
+            Ok(true)
+        })
+    );
+    assert_eq!(199, keys.len());
+    assert_eq!(1u64.to_be_bytes().to_vec(), keys[0]);
+}
+
+#[test]
+fn test_branch_rename() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch([1], [1], bn));
+
+    let new_master = BranchName(b"new_master");
+    pnk!(hdr.branch_rename(INITIAL_BRANCH_NAME, new_master));
+    assert!(!hdr.branch_exists(INITIAL_BRANCH_NAME));
+    assert!(hdr.branch_exists(new_master));
+    assert_eq!(new_master.0, &hdr.branch_get_default().0[..]);
+    assert_eq!(
+        vec![new_master.0.to_vec()],
+        pnk!(hdr.branch_ancestors(bn))
+            .into_iter()
+            .map(|b| b.0)
+            .collect::<Vec<_>>()
+    );
+
+    // data and versions remain intact
+    pnk!(hdr.insert([2], [2]));
+    assert_eq!(vec![0], pnk!(hdr.get_by_branch([0], new_master)));
+    assert_eq!(vec![2], pnk!(hdr.get_by_branch([2], new_master)));
+    assert!(hdr.get_by_branch([1], new_master).is_none());
+    assert_eq!(vec![1], pnk!(hdr.get_by_branch([1], bn)));
+    assert!(hdr.version_exists_on_branch(VersionName(b"v0"), new_master));
+
+    // the old name is not found any more
+    assert!(hdr.get_by_branch([0], INITIAL_BRANCH_NAME).is_none());
+    assert!(hdr.branch_remove(INITIAL_BRANCH_NAME).is_err());
+    assert!(
+        hdr.branch_rename(INITIAL_BRANCH_NAME, BranchName(b"x"))
+            .is_err()
+    );
+
+    // the new name must not exist
+    assert!(hdr.branch_rename(bn, new_master).is_err());
+    assert_eq!(vec![1], pnk!(hdr.get_by_branch([1], bn)));
+
+    pnk!(hdr.branch_rename(bn, INITIAL_BRANCH_NAME));
+    assert!(!hdr.branch_exists(bn));
+    assert_eq!(vec![1], pnk!(hdr.get_by_branch([1], INITIAL_BRANCH_NAME)));
+}
+
+#[test]
+fn test_diff_versions() {
+    let mut hdr = MapxRawVs::new();
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert([0], [0]));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.insert([2], [9]));
+
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.insert([2], [2]));
+    pnk!(hdr.insert([3], [3]));
+    pnk!(hdr.remove([1]));
+
+    assert!(pnk!(hdr.diff_versions(INITIAL_BRANCH_NAME, vn1, vn1)).is_empty());
+
+    let diff = pnk!(hdr.diff_versions(INITIAL_BRANCH_NAME, vn0, vn2));
+    assert_eq!(
+        diff,
+        vec![
+            (vec![1], Some(vec![1]), None),
+            (vec![3], None, Some(vec![3])),
+        ]
+    );
+
+    let diff = pnk!(hdr.diff_versions(INITIAL_BRANCH_NAME, vn2, vn0));
+    assert_eq!(
+        diff,
+        vec![
+            (vec![1], None, Some(vec![1])),
+            (vec![3], Some(vec![3]), None),
+        ]
+    );
+
+    let diff = pnk!(hdr.diff_versions(INITIAL_BRANCH_NAME, vn0, vn1));
+    assert_eq!(diff, vec![(vec![2], Some(vec![2]), Some(vec![9]))]);
+
+    assert!(
+        hdr.diff_versions(INITIAL_BRANCH_NAME, vn0, VersionName(b"v3"))
+            .is_err()
+    );
+    assert!(hdr.diff_versions(BranchName(b"b"), vn0, vn1).is_err());
+}
+
+#[test]
+fn test_first_last() {
+    let mut hdr = MapxRawVs::new();
+    assert!(hdr.first().is_none());
+    assert!(hdr.last().is_none());
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    assert!(hdr.first().is_none());
+    assert!(hdr.last().is_none());
+
+    pnk!(hdr.insert([5], [5]));
+    assert_eq!((vec![5], vec![5]), pnk!(hdr.first()));
+    assert_eq!((vec![5], vec![5]), pnk!(hdr.last()));
+
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.remove([0]));
+    pnk!(hdr.remove([9]));
+    assert_eq!((vec![1], vec![1]), pnk!(hdr.first()));
+    assert_eq!((vec![8], vec![8]), pnk!(hdr.last()));
+
+    // other branches and older versions are not affected
+    assert_eq!((vec![0], vec![0]), pnk!(hdr.first_by_branch(bn)));
+    assert_eq!((vec![9], vec![9]), pnk!(hdr.last_by_branch(bn)));
+    assert_eq!(
+        (vec![0], vec![0]),
+        pnk!(hdr.first_by_branch_version(INITIAL_BRANCH_NAME, vn0))
+    );
+    assert_eq!(
+        (vec![9], vec![9]),
+        pnk!(hdr.last_by_branch_version(INITIAL_BRANCH_NAME, vn0))
+    );
+    assert_eq!(
+        (vec![1], vec![1]),
+        pnk!(hdr.first_by_branch_version(INITIAL_BRANCH_NAME, vn2))
+    );
+    assert_eq!(
+        (vec![8], vec![8]),
+        pnk!(hdr.last_by_branch_version(INITIAL_BRANCH_NAME, vn2))
+    );
+
+    (1..9u8).for_each(|i| {
+        pnk!(hdr.remove([i]));
+    });
+    assert!(hdr.first().is_none());
+    assert!(hdr.last().is_none());
+    assert!(hdr.first_by_branch(BranchName(b"nope")).is_none());
+    assert!(hdr.last_by_branch(BranchName(b"nope")).is_none());
+}
+
+#[test]
+fn test_version_list_changed_keys() {
+    let mut hdr = MapxRawVs::new();
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert([200], [0]));
+    pnk!(hdr.insert([201], [0]));
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    assert!(pnk!(hdr.version_list_changed_keys(vn1)).is_empty());
+    assert_eq!(0, pnk!(hdr.version_changed_key_count(vn1)));
+
+    let mut keys = (0..50u32)
+        .map(|i| vec![((i * 37) % 50) as u8])
+        .collect::<Vec<_>>();
+    keys.iter().for_each(|k| {
+        pnk!(hdr.insert(k, k));
+    });
+    // a removal is also a change
+    pnk!(hdr.remove([200]));
+    keys.push(vec![200]);
+    keys.sort();
+
+    assert_eq!(keys, pnk!(hdr.version_list_changed_keys(vn1)));
+    assert_eq!(51, pnk!(hdr.version_changed_key_count(vn1)));
+
+    assert_eq!(
+        vec![vec![200], vec![201]],
+        pnk!(hdr.version_list_changed_keys(vn0))
+    );
+    assert_eq!(2, pnk!(hdr.version_changed_key_count(vn0)));
+
+    assert!(hdr.version_list_changed_keys(VersionName(b"v2")).is_err());
+    assert!(hdr.version_changed_key_count(VersionName(b"v2")).is_err());
+}
+
+#[test]
+fn test_version_conflict_keys() {
+    let mut hdr = MapxRawVs::new();
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    (0u8..10).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+
+    // disjoint
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    (10u8..20).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+    assert!(!pnk!(hdr.version_has_conflicts_with(vn0, vn1)));
+    assert!(pnk!(hdr.version_conflict_keys(vn0, vn1)).is_empty());
+
+    // partially overlapping, the same writes are also conflicts
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_create(vn2));
+    (5u8..15).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+    assert!(pnk!(hdr.version_has_conflicts_with(vn0, vn2)));
+    assert!(pnk!(hdr.version_has_conflicts_with(vn2, vn0)));
+    assert_eq!(
+        (5u8..10).map(|i| vec![i]).collect::<Vec<_>>(),
+        pnk!(hdr.version_conflict_keys(vn0, vn2))
+    );
+    assert_eq!(
+        (10u8..15).map(|i| vec![i]).collect::<Vec<_>>(),
+        pnk!(hdr.version_conflict_keys(vn2, vn1))
+    );
+
+    // fully overlapping
+    let vn3 = VersionName(b"v3");
+    pnk!(hdr.version_create(vn3));
+    (0u8..10).for_each(|i| {
+        pnk!(hdr.insert([i], [0]));
+    });
+    assert!(pnk!(hdr.version_has_conflicts_with(vn0, vn3)));
+    assert_eq!(
+        pnk!(hdr.version_list_changed_keys(vn0)),
+        pnk!(hdr.version_conflict_keys(vn0, vn3))
+    );
+    assert!(pnk!(hdr.version_has_conflicts_with(vn3, vn3)));
+
+    // empty versions
+    let vn4 = VersionName(b"v4");
+    pnk!(hdr.version_create(vn4));
+    assert!(!pnk!(hdr.version_has_conflicts_with(vn0, vn4)));
+    assert!(!pnk!(hdr.version_has_conflicts_with(vn4, vn4)));
+    assert!(pnk!(hdr.version_conflict_keys(vn4, vn3)).is_empty());
+
+    assert!(
+        hdr.version_has_conflicts_with(vn0, VersionName(b"v5"))
+            .is_err()
+    );
+    assert!(hdr.version_conflict_keys(VersionName(b"v5"), vn0).is_err());
+}
+
+#[test]
+fn test_versions_touching_key() {
+    let mut hdr = MapxRawVs::new();
+
+    (0..5u8).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&[i])));
+        if 0 == i % 2 {
+            pnk!(hdr.insert([0], [i]));
+        } else {
+            pnk!(hdr.remove([0]));
+        }
+        pnk!(hdr.insert([1], [i]));
+    });
+
+    // a version that does not touch the key
+    pnk!(hdr.version_create(VersionName(&[5])));
+    pnk!(hdr.insert([1], [5]));
+
+    let history = pnk!(hdr.versions_touching_key([0], INITIAL_BRANCH_NAME));
+    assert_eq!(5, history.len());
+    history.iter().enumerate().for_each(|(i, (ver, value))| {
+        let i = i as u8;
+        assert_eq!(&[i][..], &ver.0[..]);
+        assert_eq!(value, &alt!(0 == i % 2, Some(vec![i]), None));
+    });
+
+    // versions of other branches are not included
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create_by_base_branch_version(
+        bn,
+        VersionName(&[6]),
+        ParentBranchName(INITIAL_BRANCH_NAME.0),
+        VersionName(&[2]),
+        false
+    ));
+    pnk!(hdr.insert_by_branch([0], [9], bn));
+    let history = pnk!(hdr.versions_touching_key([0], bn));
+    assert_eq!(4, history.len());
+    assert_eq!(
+        (VersionNameOwned(vec![6]), Some(vec![9])),
+        history[3].clone()
+    );
+    assert_eq!(
+        5,
+        pnk!(hdr.versions_touching_key([0], INITIAL_BRANCH_NAME)).len()
+    );
+
+    assert!(pnk!(hdr.versions_touching_key([9], bn)).is_empty());
+    assert!(hdr.versions_touching_key([0], BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_branch_fork_point() {
+    let mut hdr = MapxRawVs::new();
+
+    // no common history
+    let [ba, bb] = [BranchName(b"a"), BranchName(b"b")];
+    pnk!(hdr.branch_create(ba, VersionName(b"a0"), false));
+    assert!(pnk!(hdr.branch_fork_point(INITIAL_BRANCH_NAME, ba)).is_none());
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.branch_create_by_base_branch_version(
+        bb,
+        VersionName(b"b0"),
+        ParentBranchName(INITIAL_BRANCH_NAME.0),
+        VersionName(b"v1"),
+        false
+    ));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bb));
+    pnk!(hdr.version_create(VersionName(b"v3")));
+
+    let fp = pnk!(hdr.branch_fork_point(INITIAL_BRANCH_NAME, bb));
+    assert_eq!(Some(VersionNameOwned(b"v1".to_vec())), fp);
+    assert_eq!(fp, pnk!(hdr.branch_fork_point(bb, INITIAL_BRANCH_NAME)));
+
+    assert_eq!(
+        Some(VersionNameOwned(b"v3".to_vec())),
+        pnk!(hdr.branch_fork_point(INITIAL_BRANCH_NAME, INITIAL_BRANCH_NAME))
+    );
+    assert!(pnk!(hdr.branch_fork_point(ba, bb)).is_none());
+    assert!(hdr.branch_fork_point(ba, BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_get_batch() {
+    let mut hdr = MapxRawVs::new();
+    let keys = (0..20u8).map(|i| [i]).collect::<Vec<_>>();
+    assert_eq!(vec![None; 20], hdr.get_batch(&keys));
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.remove([0]));
+    pnk!(hdr.insert([1], [99]));
+
+    let res = hdr.get_batch(&keys);
+    assert_eq!(keys.len(), res.len());
+    assert!(res[0].is_none());
+    assert_eq!(Some(vec![99]), res[1]);
+    (2..10).for_each(|i| {
+        assert_eq!(Some(vec![i as u8]), res[i]);
+    });
+    assert!(res[10..].iter().all(|v| v.is_none()));
+
+    // the order of the input is kept
+    let res = hdr.get_batch(&[[5], [250], [1], [5]]);
+    assert_eq!(
+        vec![Some(vec![5]), None, Some(vec![99]), Some(vec![5])],
+        res
+    );
+
+    let res = hdr.get_batch_by_branch_version(&keys, INITIAL_BRANCH_NAME, vn0);
+    assert_eq!(keys.len(), res.len());
+    assert_eq!(Some(vec![0]), res[0]);
+    assert_eq!(Some(vec![1]), res[1]);
+
+    assert_eq!(
+        hdr.get_batch(&keys),
+        hdr.get_batch_by_branch(&keys, INITIAL_BRANCH_NAME)
+    );
+    assert_eq!(
+        vec![None; 20],
+        hdr.get_batch_by_branch(&keys, BranchName(b"nope"))
+    );
+    assert_eq!(
+        vec![None; 20],
+        hdr.get_batch_by_branch_version(
+            &keys,
+            INITIAL_BRANCH_NAME,
+            VersionName(b"nope")
+        )
+    );
+    let empty: &[[u8; 1]] = &[];
+    assert!(hdr.get_batch(empty).is_empty());
+}
+
+#[test]
+fn test_version_apply_patch() {
+    let mut hdr = MapxRawVs::new();
+    let bn = BranchName(b"b");
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    let changes: Vec<(RawKey, Option<RawValue>)> = vec![
+        (vec![1], None),
+        (vec![2], Some(vec![22])),
+        (vec![3], Some(vec![3])),
+        (vec![4], None),
+    ];
+    pnk!(hdr.version_apply_patch(bn, VersionName(b"p0"), &changes));
+
+    assert!(hdr.version_exists_on_branch(VersionName(b"p0"), bn));
+    assert!(hdr.get_by_branch([1], bn).is_none());
+    assert_eq!(Some(vec![22]), hdr.get_by_branch([2], bn));
+    assert_eq!(Some(vec![3]), hdr.get_by_branch([3], bn));
+    assert!(hdr.get_by_branch([4], bn).is_none());
+    assert_eq!(3, pnk!(hdr.version_changed_key_count(VersionName(b"p0"))));
+
+    // the base branch is untouched
+    assert_eq!(Some(vec![1]), hdr.get([1]));
+    assert_eq!(Some(vec![2]), hdr.get([2]));
+    assert!(hdr.get([3]).is_none());
+
+    assert!(
+        hdr.version_apply_patch(bn, VersionName(b"p0"), &changes)
+            .is_err()
+    );
+    assert!(
+        hdr.version_apply_patch(BranchName(b"nope"), VersionName(b"p1"), &changes)
+            .is_err()
+    );
+}
+
+#[cfg(feature = "lru-cache")]
+#[test]
+fn test_with_cache() {
+    let mut hdr = MapxRawVs::with_cache(8);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+
+    // the second read is a cache hit
+    (0..2).for_each(|_| {
+        assert_eq!(Some(vec![1]), hdr.get([1]));
+        assert!(hdr.get([9]).is_none());
+    });
+
+    pnk!(hdr.insert([1], [11]));
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    pnk!(hdr.insert([9], [9]));
+    assert_eq!(Some(vec![9]), hdr.get([9]));
+    pnk!(hdr.remove([1]));
+    assert!(hdr.get([1]).is_none());
+
+    // the branch shares the head version with the default branch
+    let bn = BranchName(b"b");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+    assert_eq!(Some(vec![2]), hdr.get_by_branch([2], bn));
+    pnk!(hdr.insert([2], [22]));
+    assert_eq!(Some(vec![22]), hdr.get_by_branch([2], bn));
+
+    // the head version changes
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([2], [222]));
+    assert_eq!(Some(vec![222]), hdr.get([2]));
+    pnk!(hdr.version_pop());
+    assert_eq!(Some(vec![22]), hdr.get([2]));
+
+    // more keys than the capacity
+    (0..32u8).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+    (0..32u8).rev().for_each(|i| {
+        assert_eq!(Some(vec![i]), hdr.get([i]));
+    });
+
+    assert_eq!(Some(vec![2]), hdr.get_by_branch([2], bn));
+    hdr.clear();
+    assert!(hdr.get([2]).is_none());
+}
+
+#[cfg(feature = "access-log")]
+#[test]
+fn test_branch_access_log() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([0], [0]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert!(pnk!(hdr.branch_access_log(bn)).is_empty());
+
+    (0..5u8).for_each(|i| {
+        hdr.get_by_branch([i], bn);
+    });
+    (0..3u8).for_each(|i| {
+        pnk!(hdr.insert_by_branch([i], [i], bn));
+    });
+
+    let log = pnk!(hdr.branch_access_log(bn));
+    assert_eq!(8, log.len());
+    assert!(log.windows(2).all(|w| w[0].1 <= w[1].1));
+    assert!(log[..5].iter().all(|(t, _)| AccessType::Read == *t));
+    assert!(log[5..].iter().all(|(t, _)| AccessType::Write == *t));
+
+    // events of other branches are not mixed in
+    assert!(pnk!(hdr.branch_access_log(INITIAL_BRANCH_NAME)).is_empty());
+
+    pnk!(hdr.branch_clear_access_log(bn));
+    assert!(pnk!(hdr.branch_access_log(bn)).is_empty());
+
+    assert!(hdr.branch_access_log(BranchName(b"x")).is_err());
+}
+
+#[test]
+fn test_branch_merge_squash() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert([i], [i]));
+    });
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch([1], [11], bn));
+    pnk!(hdr.remove_by_branch([2], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bn));
+    pnk!(hdr.insert_by_branch([1], [111], bn));
+    pnk!(hdr.insert_by_branch([20], [20], bn));
+    // changed and then restored, not a net change
+    pnk!(hdr.insert_by_branch([3], [33], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b2"), bn));
+    pnk!(hdr.insert_by_branch([3], [3], bn));
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([5], [55]));
+
+    let src_vers = pnk!(hdr.version_list_by_branch(bn));
+    let dst_ver_cnt = pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len();
+
+    let sv = VersionName(b"squashed");
+    pnk!(hdr.branch_merge_squash(bn, INITIAL_BRANCH_NAME, sv));
+
+    assert_eq!(
+        1 + dst_ver_cnt,
+        pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len()
+    );
+    assert_eq!(src_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(
+        vec![vec![1], vec![2], vec![20]],
+        pnk!(hdr.version_list_changed_keys(sv))
+    );
+
+    assert_eq!(Some(vec![111]), hdr.get([1]));
+    assert!(hdr.get([2]).is_none());
+    assert_eq!(Some(vec![3]), hdr.get([3]));
+    assert_eq!(Some(vec![55]), hdr.get([5]));
+    assert_eq!(Some(vec![20]), hdr.get([20]));
+
+    // the source branch is unmodified
+    assert_eq!(Some(vec![5]), hdr.get_by_branch([5], bn));
+    assert_eq!(10, hdr.len_by_branch(bn));
+
+    assert!(
+        hdr.branch_merge_squash(bn, INITIAL_BRANCH_NAME, sv)
+            .is_err()
+    );
+    assert!(
+        hdr.branch_merge_squash(BranchName(b"nope"), INITIAL_BRANCH_NAME, sv)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_batch() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+
+    // flushed writes are visible
+    pnk!(hdr.begin_batch());
+    assert!(hdr.begin_batch().is_err());
+    assert_eq!(Some(vec![1]), pnk!(hdr.insert([1], [10])));
+    assert_eq!(Some(vec![10]), pnk!(hdr.insert([1], [11])));
+    assert_eq!(Some(vec![2]), pnk!(hdr.remove([2])));
+    assert!(pnk!(hdr.insert([3], [3])).is_none());
+
+    // point reads inside the batch see the latest buffered values
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    assert!(hdr.get([2]).is_none());
+    assert!(hdr.contains_key([3]));
+    assert_eq!(Some(vec![11]), hdr.get_by_branch([1], INITIAL_BRANCH_NAME));
+    assert!(hdr.contains_key_by_branch([3], INITIAL_BRANCH_NAME));
+    assert_eq!(
+        vec![Some(vec![11]), None, Some(vec![3])],
+        hdr.get_batch(&[[1], [2], [3]])
+    );
+
+    // other reads see the data as it was when the batch began
+    assert_eq!(2, hdr.len());
+    assert_eq!(
+        vec![(vec![1], vec![1]), (vec![2], vec![2])],
+        hdr.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(Some((vec![2], vec![2])), hdr.last());
+    assert_eq!(
+        Some(vec![1]),
+        hdr.get_by_branch_version([1], INITIAL_BRANCH_NAME, VersionName(b"v0"))
+    );
+
+    pnk!(hdr.flush_batch());
+    assert!(hdr.flush_batch().is_err());
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    assert!(hdr.get([2]).is_none());
+    assert_eq!(Some(vec![3]), hdr.get([3]));
+    assert_eq!(2, hdr.len());
+
+    // rolled-back writes are not visible
+    pnk!(hdr.begin_batch());
+    pnk!(hdr.insert([1], [111]));
+    pnk!(hdr.remove([3]));
+    pnk!(hdr.insert([4], [4]));
+    assert_eq!(Some(vec![111]), hdr.get([1]));
+    hdr.rollback_batch();
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    assert_eq!(Some(vec![3]), hdr.get([3]));
+    assert!(hdr.get([4]).is_none());
+    assert_eq!(2, hdr.len());
+}
+
+#[test]
+fn test_batch_bound_to_head() {
+    let mut hdr = MapxRawVs::new();
+    let bn = BranchName(b"b");
+
+    // no version on the default branch
+    assert!(hdr.begin_batch().is_err());
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.begin_batch());
+
+    // writes on the default branch are buffered,
+    // no matter which function is used
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert_by_branch([2], [2], INITIAL_BRANCH_NAME));
+    assert!(pnk!(hdr.compare_and_swap([3], None, Some(&[3][..]))));
+    assert!(pnk!(hdr.remove_by_branch([1], INITIAL_BRANCH_NAME)).is_some());
+    assert_eq!(0, hdr.len());
+
+    // writes on other branches are applied directly
+    pnk!(hdr.insert_by_branch([9], [9], bn));
+    assert_eq!(Some(vec![9]), hdr.get_by_branch([9], bn));
+    assert_eq!(1, hdr.len_by_branch(bn));
+
+    // versions and branches can not be changed
+    assert!(hdr.version_create(VersionName(b"v1")).is_err());
+    assert!(
+        hdr.version_create_by_branch(VersionName(b"b1"), bn)
+            .is_err()
+    );
+    assert!(hdr.version_pop().is_err());
+    assert!(hdr.branch_set_default(bn).is_err());
+    assert!(
+        hdr.branch_create(BranchName(b"c"), VersionName(b"c0"), false)
+            .is_err()
+    );
+    assert!(hdr.branch_remove(bn).is_err());
+    assert!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME).is_err());
+    assert!(hdr.prune(None).is_err());
+    assert!(!hdr.version_exists_globally(VersionName(b"v1")));
+    assert!(!hdr.branch_exists(BranchName(b"c")));
+    assert_eq!(INITIAL_BRANCH_NAME.0, &hdr.branch_get_default().0[..]);
+
+    // the buffered writes land on the version that was the head at `begin_batch`
+    pnk!(hdr.flush_batch());
+    assert!(hdr.get([1]).is_none());
+    assert_eq!(Some(vec![2]), hdr.get([2]));
+    assert_eq!(Some(vec![3]), hdr.get([3]));
+    assert_eq!(2, hdr.len());
+    assert_eq!(
+        Some(vec![3]),
+        hdr.get_by_branch_version([3], INITIAL_BRANCH_NAME, VersionName(b"v0"))
+    );
+    assert!(hdr.get_by_branch([3], bn).is_none());
+
+    // all are allowed again
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.branch_set_default(bn));
+}
+
+#[test]
+fn test_version_blame() {
+    let mut hdr = MapxRawVs::new();
+    let vn = |i: u8| VersionNameOwned(format!("v{i}").into_bytes());
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+    pnk!(hdr.insert([3], [3]));
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([2], [22]));
+    pnk!(hdr.insert([4], [4]));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.remove([3]));
+
+    pnk!(hdr.version_create(VersionName(b"v3")));
+    pnk!(hdr.insert([1], [11]));
+    pnk!(hdr.insert([5], [5]));
+
+    pnk!(hdr.version_create(VersionName(b"v4")));
+    pnk!(hdr.insert([3], [33]));
+    pnk!(hdr.remove([4]));
+
+    let blame = pnk!(hdr.version_blame(INITIAL_BRANCH_NAME));
+    assert_eq!(
+        blame,
+        vec![
+            (vec![1], vn(3), Some(vec![11])),
+            (vec![2], vn(1), Some(vec![22])),
+            (vec![3], vn(4), Some(vec![33])),
+            (vec![5], vn(3), Some(vec![5])),
+        ]
+    );
+
+    // changes on other branches are invisible
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch([2], [222], bn));
+    assert_eq!(blame, pnk!(hdr.version_blame(INITIAL_BRANCH_NAME)));
+    assert_eq!(
+        (vec![2], VersionNameOwned(b"b0".to_vec()), Some(vec![222])),
+        pnk!(hdr.version_blame(bn))[1]
+    );
+
+    assert!(hdr.version_blame(BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_version_rollback_to() {
+    let mut hdr = MapxRawVs::new();
+    let (vn0, vn1, vn2) = (VersionName(b"v0"), VersionName(b"v1"), VersionName(b"v2"));
+
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.insert([1], [11]));
+
+    // `v1` is still referenced by this branch after the rollback
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.insert([3], [3]));
+    pnk!(hdr.remove([2]));
+
+    assert!(
+        hdr.version_rollback_to(INITIAL_BRANCH_NAME, VersionName(b"nope"))
+            .is_err()
+    );
+    assert!(hdr.version_rollback_to(bn, vn2).is_err());
+    assert!(hdr.version_rollback_to(BranchName(b"nope"), vn0).is_err());
+
+    pnk!(hdr.version_rollback_to(INITIAL_BRANCH_NAME, vn0));
+    assert_eq!(vec![1], pnk!(hdr.get([1])));
+    assert_eq!(vec![2], pnk!(hdr.get([2])));
+    assert!(hdr.get([3]).is_none());
+    assert_eq!(2, hdr.len());
+    assert_eq!(
+        vec![VersionNameOwned(b"v0".to_vec())],
+        pnk!(hdr.version_list())
+    );
+
+    // the data of the removed versions are cleaned up
+    assert!(!hdr.version_exists_globally(vn2));
+    assert!(hdr.version_exists_globally(vn1));
+    assert_eq!(vec![11], pnk!(hdr.get_by_branch([1], bn)));
+
+    // new versions can be created on the rolled-back branch
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.insert([1], [111]));
+    assert_eq!(vec![111], pnk!(hdr.get([1])));
+    assert_eq!(vec![11], pnk!(hdr.get_by_branch([1], bn)));
+}
+
+#[test]
+fn test_branch_contains_version_fast() {
+    let mut hdr = MapxRawVs::new();
+    let (vn0, vn1, vn2) = (VersionName(b"v0"), VersionName(b"v1"), VersionName(b"v2"));
+    let bn = BranchName(b"b");
+
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    let check = |hdr: &MapxRawVs, br: BranchName, ver: VersionName| {
+        let ret = hdr.branch_contains_version_fast(br, ver);
+        assert_eq!(hdr.version_exists_on_branch(ver, br), ret);
+        ret
+    };
+
+    // the second check hits the in-memory version set
+    (0..2).for_each(|_| {
+        assert!(check(&hdr, INITIAL_BRANCH_NAME, vn1));
+        assert!(!check(&hdr, INITIAL_BRANCH_NAME, VersionName(b"b0")));
+        assert!(check(&hdr, bn, VersionName(b"b0")));
+        assert!(check(&hdr, bn, vn0));
+    });
+
+    // the version set is re-derived after changes
+    pnk!(hdr.version_create(vn2));
+    assert!(check(&hdr, INITIAL_BRANCH_NAME, vn2));
+    assert!(!check(&hdr, bn, vn2));
+    pnk!(hdr.version_pop());
+    assert!(!check(&hdr, INITIAL_BRANCH_NAME, vn2));
+    pnk!(hdr.branch_truncate_to(bn, vn0));
+    assert!(!check(&hdr, bn, vn1));
+    assert!(check(&hdr, bn, vn0));
+    pnk!(hdr.branch_remove(bn));
+    assert!(!check(&hdr, bn, vn0));
+
+    assert!(!check(&hdr, INITIAL_BRANCH_NAME, VersionName(b"nope")));
+    assert!(!check(&hdr, BranchName(b"nope"), vn0));
+}
+
+#[test]
+fn test_change_stream() {
+    let mut hdr = MapxRawVs::new();
+    let vn = |i: u8| VersionNameOwned(format!("v{i}").into_bytes());
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([2], [2]));
+    pnk!(hdr.insert([1], [1]));
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([3], [3]));
+    pnk!(hdr.insert([1], [11]));
+    pnk!(hdr.remove([2]));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.remove([1]));
+    pnk!(hdr.insert([2], [22]));
+
+    let expected = vec![
+        (vn(0), vec![1], Some(vec![1])),
+        (vn(0), vec![2], Some(vec![2])),
+        (vn(1), vec![1], Some(vec![11])),
+        (vn(1), vec![2], None),
+        (vn(1), vec![3], Some(vec![3])),
+        (vn(2), vec![1], None),
+        (vn(2), vec![2], Some(vec![22])),
+    ];
+    assert_eq!(
+        expected,
+        hdr.change_stream(INITIAL_BRANCH_NAME, None)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        expected[2..],
+        hdr.change_stream(INITIAL_BRANCH_NAME, Some(VersionName(b"v1")))
+            .collect::<Vec<_>>()
+    );
+
+    // changes on other branches are invisible
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch([4], [4], bn));
+    assert_eq!(
+        expected,
+        hdr.change_stream(INITIAL_BRANCH_NAME, None)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![(VersionNameOwned(b"b0".to_vec()), vec![4], Some(vec![4]))],
+        hdr.change_stream(bn, Some(VersionName(b"b0")))
+            .collect::<Vec<_>>()
+    );
+
+    // absent branch or version
+    assert_eq!(0, hdr.change_stream(BranchName(b"nope"), None).count());
+    assert_eq!(
+        0,
+        hdr.change_stream(INITIAL_BRANCH_NAME, Some(VersionName(b"b0")))
+            .count()
+    );
+}
+
+#[test]
+fn test_branch_is_dirty() {
+    let mut hdr = MapxRawVs::new();
+    assert!(!pnk!(hdr.branch_is_dirty(INITIAL_BRANCH_NAME)));
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    assert!(pnk!(hdr.branch_is_dirty(INITIAL_BRANCH_NAME)));
+
+    let bn = BranchName(b"b");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+    assert!(!pnk!(hdr.branch_is_dirty(bn)));
+
+    pnk!(hdr.version_create_by_branch(VersionName(b"b0"), bn));
+    pnk!(hdr.insert_by_branch([2], [2], bn));
+    assert!(pnk!(hdr.branch_is_dirty(bn)));
+
+    pnk!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME));
+    assert!(!pnk!(hdr.branch_is_dirty(bn)));
+
+    assert!(hdr.branch_is_dirty(BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_branch_merge_to_rebase() {
+    let mut hdr = MapxRawVs::new();
+    let vns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| VersionNameOwned(n.as_bytes().to_vec()))
+            .collect::<Vec<_>>()
+    };
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert([1], [1]));
+    pnk!(hdr.insert([2], [2]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch([1], [11], bn));
+
+    // nothing new on the target branch
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    assert_eq!(
+        vns(&["v0", "b0"]),
+        pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME))
+    );
+
+    // parallel edits on different keys
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bn));
+    pnk!(hdr.insert_by_branch([3], [3], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b2"), bn));
+    pnk!(hdr.remove_by_branch([2], bn));
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert([4], [4]));
+
+    assert!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME).is_err());
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+
+    let vers = vns(&["v0", "b0", "v1", "b1", "b2"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    for br in [INITIAL_BRANCH_NAME, bn] {
+        assert_eq!(Some(vec![11]), hdr.get_by_branch([1], br));
+        assert!(hdr.get_by_branch([2], br).is_none());
+        assert_eq!(Some(vec![3]), hdr.get_by_branch([3], br));
+        assert_eq!(Some(vec![4]), hdr.get_by_branch([4], br));
+    }
+
+    // an orphan version, not used by any branch
+    let bc = BranchName(b"c");
+    pnk!(hdr.branch_create(bc, VersionName(b"c0"), false));
+    pnk!(hdr.branch_remove(bc));
+    assert!(hdr.version_exists_globally(VersionName(b"c0")));
+
+    // conflicts, the first version can be replayed, but the second one can not
+    pnk!(hdr.version_create_by_branch(VersionName(b"b3"), bn));
+    pnk!(hdr.insert_by_branch([5], [5], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b4"), bn));
+    pnk!(hdr.insert_by_branch([1], [111], bn));
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.insert([1], [1111]));
+
+    let brs = hdr.branch_list();
+    let all_vers = hdr.version_list_globally();
+    let br_vers = pnk!(hdr.version_list_by_branch(bn));
+    let main_vers = pnk!(hdr.version_list());
+
+    assert!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME).is_err());
+    assert_eq!(Some(vec![111]), hdr.get_by_branch([1], bn));
+    assert_eq!(Some(vec![5]), hdr.get_by_branch([5], bn));
+    assert_eq!(Some(vec![1111]), hdr.get([1]));
+    assert!(hdr.get([5]).is_none());
+    assert!(hdr.version_exists_on_branch(VersionName(b"b3"), bn));
+    assert!(!hdr.version_exists(VersionName(b"b3")));
+
+    // nothing is changed by the failed rebase
+    assert_eq!(brs, hdr.branch_list());
+    assert_eq!(all_vers, hdr.version_list_globally());
+    assert_eq!(br_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(main_vers, pnk!(hdr.version_list()));
+
+    // resolve the conflict on the branch, and try again
+    pnk!(hdr.insert_by_branch([1], [1111], bn));
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+    let vers = vns(&["v0", "b0", "v1", "b1", "b2", "v2", "b3", "b4"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![1111]), hdr.get([1]));
+    assert_eq!(Some(vec![5]), hdr.get([5]));
+    assert_eq!(brs, hdr.branch_list());
+
+    // versions of other branches are not cleaned up
+    assert!(hdr.version_exists_globally(VersionName(b"c0")));
+}
+
+#[test]
+fn test_branch_merge_to_base_with_rebase() {
+    let mut hdr = MapxRawVs::new();
+    let vns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| VersionNameOwned(n.as_bytes().to_vec()))
+            .collect::<Vec<_>>()
+    };
+
+    (0..=5u8).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(format!("v{i}").as_bytes())));
+        pnk!(hdr.insert([i], [i]));
+    });
+
+    // nothing new on the base branch, a fast-forward merge is enough
+    let bs = BranchName(b"staging");
+    pnk!(hdr.branch_create(bs, VersionName(b"s0"), false));
+    pnk!(hdr.insert_by_branch([0], [10], bs));
+    pnk!(hdr.branch_merge_to_base_with_rebase(bs, INITIAL_BRANCH_NAME));
+    assert_eq!(Some(vec![10]), hdr.get([0]));
+    pnk!(hdr.branch_remove(bs));
+
+    // fork from the main branch at `v5`
+    let bn = BranchName(b"b");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+    pnk!(hdr.version_create(VersionName(b"v6")));
+    pnk!(hdr.insert([6], [6]));
+    pnk!(hdr.version_create_by_branch(VersionName(b"v6'"), bn));
+    pnk!(hdr.insert_by_branch([7], [7], bn));
+
+    assert!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME).is_err());
+    pnk!(hdr.branch_merge_to_base_with_rebase(bn, INITIAL_BRANCH_NAME));
+
+    let vers = vns(&["v0", "v1", "v2", "v3", "v4", "v5", "s0", "v6", "v6'"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![6]), hdr.get([6]));
+    assert_eq!(Some(vec![7]), hdr.get([7]));
+
+    // a conflict leaves both branches unchanged
+    pnk!(hdr.version_create(VersionName(b"v7")));
+    pnk!(hdr.insert([1], [11]));
+    pnk!(hdr.version_create_by_branch(VersionName(b"v7'"), bn));
+    pnk!(hdr.insert_by_branch([1], [111], bn));
+
+    let brs = hdr.branch_list();
+    let main_vers = pnk!(hdr.version_list());
+    let br_vers = pnk!(hdr.version_list_by_branch(bn));
+    assert!(
+        hdr.branch_merge_to_base_with_rebase(bn, INITIAL_BRANCH_NAME)
+            .is_err()
+    );
+    assert_eq!(brs, hdr.branch_list());
+    assert_eq!(main_vers, pnk!(hdr.version_list()));
+    assert_eq!(br_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![11]), hdr.get([1]));
+    assert_eq!(Some(vec![111]), hdr.get_by_branch([1], bn));
+}
+
+#[test]
+fn test_compact_to_snapshot() {
+    let mut hdr = MapxRawVs::new();
+
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&[i])));
+        (0..=i).for_each(|j| {
+            pnk!(hdr.insert([j], [i]));
+        });
+        if 0 < i {
+            pnk!(hdr.remove([i - 1]));
+        }
+    });
+    let expected = hdr.iter().collect::<Vec<_>>();
+    assert_eq!(10, pnk!(hdr.version_list()).len());
+
+    pnk!(hdr.compact_to_snapshot(VersionName(b"snapshot"), false));
+    assert_eq!(
+        vec![VersionNameOwned(b"snapshot".to_vec())],
+        pnk!(hdr.version_list())
+    );
+    assert_eq!(expected, hdr.iter().collect::<Vec<_>>());
+    (0..10u8).for_each(|i| {
+        assert!(!hdr.version_exists_globally(VersionName(&[i])));
+    });
+    assert_eq!(expected.len(), hdr.len());
+
+    // new changes are still working
+    pnk!(hdr.version_create(VersionName(b"v")));
+    pnk!(hdr.insert([0], [0]));
+    assert_eq!(Some(vec![0]), hdr.get([0]));
+
+    // other branches exist
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch([1], [1], bn));
+    assert!(
+        hdr.compact_to_snapshot(VersionName(b"snapshot2"), false)
+            .is_err()
+    );
+    assert!(
+        hdr.compact_to_snapshot_by_branch(bn, VersionName(b"snapshot2"), false)
+            .is_err()
+    );
+    assert!(!hdr.version_exists_globally(VersionName(b"snapshot2")));
+    assert!(hdr.branch_exists(bn));
+    assert_eq!(3, hdr.version_list_globally().len());
+    assert!(
+        hdr.compact_to_snapshot_by_branch(BranchName(b"nope"), VersionName(b"s"), true)
+            .is_err()
+    );
+
+    // a duplicate name is rejected before any branch is removed
+    assert!(
+        hdr.compact_to_snapshot_by_branch(bn, VersionName(b"v"), true)
+            .is_err()
+    );
+    assert!(hdr.branch_exists(INITIAL_BRANCH_NAME));
+    assert!(hdr.branch_exists(bn));
+    assert_eq!(INITIAL_BRANCH_NAME.0, &hdr.branch_get_default().0[..]);
+    assert_eq!(3, hdr.version_list_globally().len());
+    assert_eq!(Some(vec![0]), hdr.get([0]));
+    assert_eq!(Some(vec![1]), hdr.get_by_branch([1], bn));
+
+    // remove other branches by force
+    let expected = hdr.iter_by_branch(bn).collect::<Vec<_>>();
+    pnk!(hdr.compact_to_snapshot_by_branch(bn, VersionName(b"snapshot2"), true));
+    assert_eq!(vec![BranchNameOwned(b"b".to_vec())], hdr.branch_list());
+    assert_eq!(
+        vec![VersionNameOwned(b"snapshot2".to_vec())],
+        hdr.version_list_globally()
+    );
+    assert_eq!(expected, hdr.iter().collect::<Vec<_>>());
+    assert_eq!(expected.len(), hdr.len());
+}
+
+#[test]
+fn test_branch_detach() {
+    let mut hdr = MapxRawVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0u8..10).for_each(|i| {
+        pnk!(hdr.insert(&[i], &[i]));
+    });
+
+    let ba = BranchName(b"a");
+    pnk!(hdr.branch_create(ba, VersionName(b"va0"), false));
+    pnk!(hdr.insert_by_branch(&[10], &[10], ba));
+    pnk!(hdr.remove_by_branch(&[0], ba));
+    pnk!(hdr.version_create_by_branch(VersionName(b"va1"), ba));
+    pnk!(hdr.insert_by_branch(&[1], &[111], ba));
+
+    let bc = BranchName(b"c");
+    pnk!(hdr.branch_create_by_base_branch(
+        bc,
+        VersionName(b"vc"),
+        ParentBranchName(b"a"),
+        false
+    ));
+    let child = hdr.iter_by_branch(bc).collect::<Vec<_>>();
+
+    assert!(hdr.branch_detach(BranchName(b"nope")).is_err());
+
+    let before = hdr.iter_by_branch(ba).collect::<Vec<_>>();
+    assert_eq!(10, before.len());
+    assert_eq!(3, pnk!(hdr.version_list_by_branch(ba)).len());
+    assert_eq!(1, pnk!(hdr.branch_ancestors(ba)).len());
+
+    let detached =
+        |i: u64| VersionNameOwned([&b"a@detached"[..], &i.to_be_bytes()[..]].concat());
+
+    pnk!(hdr.branch_detach(ba));
+    assert_eq!(vec![detached(0)], pnk!(hdr.version_list_by_branch(ba)));
+    assert!(pnk!(hdr.branch_ancestors(ba)).is_empty());
+    assert!(hdr.branch_is_leaf(INITIAL_BRANCH_NAME));
+    assert_eq!(before, hdr.iter_by_branch(ba).collect::<Vec<_>>());
+    assert_eq!(10, hdr.len_by_branch(ba));
+    assert!(hdr.get_by_branch(&[0], ba).is_none());
+    assert_eq!(vec![111], pnk!(hdr.get_by_branch(&[1], ba)));
+
+    // the parent is not affected
+    assert_eq!(
+        1,
+        pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len()
+    );
+    assert_eq!(10, hdr.len());
+    assert_eq!(vec![1], pnk!(hdr.get(&[1])));
+
+    // the child is not affected
+    assert!(!hdr.branch_is_leaf(ba));
+    assert_eq!(4, pnk!(hdr.version_list_by_branch(bc)).len());
+    assert_eq!(child, hdr.iter_by_branch(bc).collect::<Vec<_>>());
+
+    // detach again
+    pnk!(hdr.branch_detach(ba));
+    assert_eq!(vec![detached(1)], pnk!(hdr.version_list_by_branch(ba)));
+    assert_eq!(before, hdr.iter_by_branch(ba).collect::<Vec<_>>());
+
+    pnk!(hdr.version_clean_up_globally());
+    assert_eq!(before, hdr.iter_by_branch(ba).collect::<Vec<_>>());
+    assert_eq!(child, hdr.iter_by_branch(bc).collect::<Vec<_>>());
+    assert!(!hdr.version_exists_globally(VersionName(&detached(0).0)));
+    assert!(hdr.version_exists_globally(VersionName(b"va1")));
+    assert!(hdr.version_exists_globally(VersionName(b"v0")));
+
+    pnk!(hdr.branch_remove(bc));
+    pnk!(hdr.version_clean_up_globally());
+    assert_eq!(before, hdr.iter_by_branch(ba).collect::<Vec<_>>());
+    assert!(!hdr.version_exists_globally(VersionName(b"va1")));
+    assert!(hdr.version_exists_globally(VersionName(b"v0")));
+}
+
+// Values are stored in compressed form with the `compressed-values` feature,
+// all read paths should get the original values.
+#[test]
+fn test_large_values() {
+    let mut hdr = MapxRawVs::new();
+    let bn = INITIAL_BRANCH_NAME;
+    let value = |i: u8, ver: u8| vec![i ^ ver; 1024];
+
+    for ver in 0u8..5 {
+        pnk!(hdr.version_create(VersionName(&[ver])));
+        (0u8..100).for_each(|i| {
+            pnk!(hdr.insert([i], value(i, ver)));
+        });
+    }
+    pnk!(hdr.remove([0]));
+
+    assert_eq!(99, hdr.len());
+    assert!(hdr.get([0]).is_none());
+    assert_eq!(value(1, 4), pnk!(hdr.get([1])));
+    assert_eq!(
+        value(1, 2),
+        pnk!(hdr.get_by_branch_version([1], bn, VersionName(&[2])))
+    );
+    assert!(hdr.iter().all(|(k, v)| v == value(k[0], 4)));
+    assert_eq!(
+        Some((vec![99], value(99, 4))),
+        hdr.range(Cow::Borrowed(&[50][..])..).next_back()
+    );
+
+    let diff = pnk!(hdr.diff_versions(bn, VersionName(&[1]), VersionName(&[4])));
+    assert_eq!(100, diff.len());
+    assert_eq!((vec![0], Some(value(0, 1)), None), diff[0]);
+    assert_eq!((vec![1], Some(value(1, 1)), Some(value(1, 4))), diff[1]);
+
+    let changes = hdr
+        .change_stream(bn, Some(VersionName(&[4])))
+        .collect::<Vec<_>>();
+    assert_eq!(100, changes.len());
+    assert_eq!((VersionNameOwned(vec![4]), vec![0], None), changes[0]);
+    assert_eq!(
+        (VersionNameOwned(vec![4]), vec![1], Some(value(1, 4))),
+        changes[1]
+    );
+
+    let history = pnk!(hdr.versions_touching_key([2], bn));
+    assert_eq!(5, history.len());
+    assert_eq!((VersionNameOwned(vec![3]), Some(value(2, 3))), history[3]);
+}
+
+// The values of an uncompressed instance are read as they are,
+// even if they look like the compressed ones.
+#[test]
+fn test_legacy_uncompressed_values() {
+    let mut hdr = MapxRawVs {
+        inner: backend::MapxRawVs::with_compression(false),
+    };
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let values = [
+        vec![9; 1024],
+        vec![0xff],
+        vec![0xff, 1, 2, 3],
+        vec![0xff, 0xff, 0xff, 0xff, 0xff, 0],
+        vec![0xff, 16, 0, 0, 0, 1, 2, 3],
+        lz4_flex::compress_prepend_size(&[7; 64]),
+    ];
+    values.iter().enumerate().for_each(|(i, v)| {
+        pnk!(hdr.insert([i as u8], v));
+    });
+
+    let shadow = unsafe { hdr.shadow() };
+    values.iter().enumerate().for_each(|(i, v)| {
+        assert_eq!(Some(v), hdr.get([i as u8]).as_ref());
+        assert_eq!(Some(v), shadow.get([i as u8]).as_ref());
+    });
+    assert!(hdr.iter().all(|(k, v)| v == values[k[0] as usize]));
+}
+
+// The compression flag belongs to each instance,
+// a compressed instance is readable whether the feature is enabled or not.
+#[test]
+fn test_compressed_values() {
+    let mut hdr = MapxRawVs {
+        inner: backend::MapxRawVs::with_compression(true),
+    };
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let values = [vec![9; 1024], vec![0xff], vec![0xff, 1, 2, 3]];
+    values.iter().enumerate().for_each(|(i, v)| {
+        pnk!(hdr.insert([i as u8], v));
+    });
+    pnk!(hdr.insert([9], NULL));
+
+    let shadow = unsafe { hdr.shadow() };
+    values.iter().enumerate().for_each(|(i, v)| {
+        assert_eq!(Some(v), hdr.get([i as u8]).as_ref());
+        assert_eq!(Some(v), shadow.get([i as u8]).as_ref());
+    });
+    assert!(hdr.get([9]).is_none());
+    assert!(hdr.iter().all(|(k, v)| v == values[k[0] as usize]));
+}
+
+#[test]
+fn test_version_is_ancestor_of() {
+    let mut hdr = MapxRawVs::new();
+    let (v0, v1, v2) = (VersionName(b"v0"), VersionName(b"v1"), VersionName(b"v2"));
+    pnk!(hdr.version_create(v0));
+    pnk!(hdr.version_create(v1));
+    pnk!(hdr.version_create(v2));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    let m = INITIAL_BRANCH_NAME;
+    assert!(pnk!(hdr.version_is_ancestor_of(v0, v2, m)));
+    assert!(pnk!(hdr.version_is_ancestor_of(v1, v2, m)));
+    assert!(!pnk!(hdr.version_is_ancestor_of(v1, v1, m)));
+    assert!(!pnk!(hdr.version_is_ancestor_of(v2, v0, m)));
+    assert!(pnk!(hdr.version_is_ancestor_of(v0, VersionName(b"b0"), bn)));
+    assert!(
+        hdr.version_is_ancestor_of(v0, VersionName(b"b0"), m)
+            .is_err()
+    );
+    assert!(
+        hdr.version_is_ancestor_of(v0, v1, BranchName(b"x"))
+            .is_err()
+    );
+
+    let names = |l: &[&[u8]]| {
+        l.iter()
+            .map(|n| VersionNameOwned(n.to_vec()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(&[b"v0", b"v1"]),
+        pnk!(hdr.versions_between(v0, v2, m))
+    );
+    assert!(pnk!(hdr.versions_between(v1, v1, m)).is_empty());
+    assert!(hdr.versions_between(v2, v0, m).is_err());
+    assert_eq!(
+        names(&[b"v1", b"v2"]),
+        pnk!(hdr.versions_between(v1, VersionName(b"b0"), bn))
+    );
+    assert!(hdr.versions_between(v0, VersionName(b"b0"), m).is_err());
+}
+
+#[test]
+fn test_get_by_version_range() {
+    let mut hdr = MapxRawVs::new();
+    let m = INITIAL_BRANCH_NAME;
+
+    (1u8..=10).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&[i])));
+        match i {
+            3 => assert!(pnk!(hdr.insert([0], [3])).is_none()),
+            5 => assert!(pnk!(hdr.insert([0], [5])).is_some()),
+            6 => assert!(pnk!(hdr.remove([0])).is_some()),
+            8 => assert!(pnk!(hdr.insert([0], [8])).is_none()),
+            _ => {}
+        }
+    });
+
+    let get = |from: u8, to: u8| {
+        hdr.get_by_version_range([0], m, VersionName(&[from]), VersionName(&[to]))
+    };
+    assert_eq!(Some(vec![3]), get(1, 3));
+    assert_eq!(None, get(1, 2));
+    assert_eq!(Some(vec![3]), get(3, 10));
+    assert_eq!(Some(vec![5]), get(5, 10));
+    assert_eq!(None, get(6, 7));
+    assert_eq!(Some(vec![8]), get(6, 10));
+    assert_eq!(Some(vec![8]), get(9, 9));
+    assert_eq!(None, get(10, 1));
+    assert_eq!(None, get(1, 11));
+    let x = BranchName(b"x");
+    assert!(
+        hdr.get_by_version_range([0], x, VersionName(&[1]), VersionName(&[3]))
+            .is_none()
+    );
+}
+
+#[test]
+fn test_version_chgset_size() {
+    let mut hdr = MapxRawVs::new();
+    let m = INITIAL_BRANCH_NAME;
+    assert!(hdr.version_chgset_size_by_branch(m).is_err());
+
+    let vn = VersionName(b"v0");
+    pnk!(hdr.version_create(vn));
+    assert_eq!(0, pnk!(hdr.version_chgset_size_by_branch(m)));
+    (0..500u64).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), i.to_be_bytes()));
+    });
+    // overwriting does not change the size
+    (0..100u64).for_each(|i| {
+        pnk!(hdr.insert(i.to_be_bytes(), [0]));
+    });
+    assert_eq!(500, pnk!(hdr.version_chgset_size(vn)));
+    assert_eq!(500, pnk!(hdr.version_chgset_size_by_branch(m)));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.remove_by_branch(0u64.to_be_bytes(), bn));
+    assert_eq!(1, pnk!(hdr.version_chgset_size(VersionName(b"b0"))));
+    assert_eq!(1, pnk!(hdr.version_chgset_size_by_branch(bn)));
+    assert_eq!(500, pnk!(hdr.version_chgset_size_by_branch(m)));
+
+    assert!(hdr.version_chgset_size(VersionName(b"x")).is_err());
+    assert!(hdr.version_chgset_size_by_branch(BranchName(b"x")).is_err());
+}
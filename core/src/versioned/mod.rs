@@ -5,8 +5,8 @@
 pub mod mapx_raw;
 
 use crate::{
-    basic::mapx_raw::MapxRaw, BranchName, BranchNameOwned, ParentBranchName,
-    VersionName, VersionNameOwned,
+    basic::mapx_raw::MapxRaw, BranchName, BranchNameOwned, ParentBranchName, RawKey,
+    RawValue, VersionName, VersionNameOwned,
 };
 use ruc::*;
 use std::{
@@ -43,6 +43,23 @@ pub trait VsMgmt {
         br_name: BranchName,
     ) -> bool;
 
+    /// Same as `version_exists_on_branch`, but for hot paths
+    /// that check lots of versions.
+    ///
+    /// The versions of a branch are kept in an on-disk sub-map,
+    /// whose `contains_key` is already O(log n), but every check needs disk IO.
+    /// Here they are loaded into an in-memory `HashSet` at the first check,
+    /// then each check is O(1) until the next change of the versions.
+    ///
+    /// NOTE:
+    /// - the name-to-id lookups of the branch and the version are still needed
+    /// - multi-key structures fall back to `version_exists_on_branch`
+    fn branch_contains_version_fast(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> bool;
+
     /// Remove the newest version on the default branch.
     ///
     /// 'Write'-like operations on branches and versions are different from operations on data.
@@ -123,6 +140,26 @@ pub trait VsMgmt {
         br_name: BranchName,
     ) -> Result<Vec<VersionNameOwned>>;
 
+    /// Check if `ancestor` is created before `descendant` on the branch,
+    /// both of them must exist on the branch.
+    ///
+    /// NOTE: `false` is returned if they are the same version.
+    fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionName,
+        descendant: VersionName,
+        br_name: BranchName,
+    ) -> Result<bool>;
+
+    /// List the versions of the branch within `[from, to)`,
+    /// from the oldest to the newest.
+    fn versions_between(
+        &self,
+        from: VersionName,
+        to: VersionName,
+        br_name: BranchName,
+    ) -> Result<Vec<VersionNameOwned>>;
+
     /// # NOTE
     ///
     /// The result can only be used as hints, they are unreliable!
@@ -143,6 +180,51 @@ pub trait VsMgmt {
     /// Check if some changes have been make on the version.
     fn version_has_change_set(&self, ver_name: VersionName) -> Result<bool>;
 
+    /// List all keys changed by the version.
+    ///
+    /// NOTE: this may be expensive on a large change set,
+    /// use `version_changed_key_count` if only the number is needed.
+    fn version_list_changed_keys(&self, ver_name: VersionName) -> Result<Vec<RawKey>>;
+
+    /// Get the number of keys changed by the version.
+    fn version_changed_key_count(&self, ver_name: VersionName) -> Result<usize>;
+
+    /// Get the number of keys in the change set of the version,
+    /// the size of the change set is read directly, no iterating.
+    fn version_chgset_size(&self, ver_name: VersionName) -> Result<usize>;
+
+    /// Same as `version_chgset_size`, on the head version of the branch.
+    fn version_chgset_size_by_branch(&self, br_name: BranchName) -> Result<usize>;
+
+    /// Check if the two versions have changed some common keys.
+    ///
+    /// NOTE: it's a conservative check, a key is treated as a conflict
+    /// even if both versions wrote the same value to it,
+    /// use `version_conflict_keys` and inspect the values if necessary.
+    fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<bool>;
+
+    /// List all keys changed by both of the two versions.
+    fn version_conflict_keys(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<Vec<RawKey>>;
+
+    /// For every visible key on the branch,
+    /// find the version that produced its current value, like the `blame` of git,
+    /// removed keys are excluded.
+    ///
+    /// NOTE: this walks backward from the head version for each key,
+    /// the cost is O(total_keys * avg_version_depth).
+    fn version_blame(
+        &self,
+        br_name: BranchName,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>>;
+
     /// Clean up all orphan versions, versions not belong to any branch.
     fn version_clean_up_globally(&mut self) -> Result<()>;
 
@@ -151,6 +233,87 @@ pub trait VsMgmt {
     /// Version itself and its corresponding changes will be completely purged from all branches
     unsafe fn version_revert_globally(&mut self, ver_name: VersionName) -> Result<()>;
 
+    /// Roll back a branch to `last_ver`, all newer versions of the branch
+    /// are removed, and then their data are cleaned up
+    /// by `version_clean_up_globally`.
+    ///
+    /// NOTE: versions still referenced by other branches are kept,
+    /// and all other orphan versions will also be cleaned up.
+    fn version_rollback_to(
+        &mut self,
+        br_name: BranchName,
+        last_ver: VersionName,
+    ) -> Result<()>;
+
+    /// Apply the changes made by `src_ver` on `src_br` onto the head of `dst_br`,
+    /// as a new version named `dst_ver`, like the `cherry-pick` of git.
+    ///
+    /// If some of the keys have been changed on `dst_br` in a different way,
+    /// an error will be returned, unless `force` is true.
+    fn version_cherry_pick(
+        &mut self,
+        src_ver: VersionName,
+        src_br: BranchName,
+        dst_br: BranchName,
+        dst_ver: VersionName,
+        force: bool,
+    ) -> Result<()>;
+
+    /// Create a new version on the head of `br_name`,
+    /// and apply an explicit change set to it,
+    /// a `None` value means the key should be removed.
+    ///
+    /// This is useful when the changes are computed externally,
+    /// eg. syncing data from a remote peer.
+    ///
+    /// The keys and values are in the form being stored:
+    /// - the encoded ones for the typed structures
+    /// - the keys of the multi-key structures are built by `MapxRawMkVs::encode_key`
+    /// - the keys of a derived structure are prefixed by the name of the field
+    ///   and a `.`, eg. `b"field.key"`, the index is the name of an unnamed field
+    ///
+    /// The non-versioned structures only accept an empty patch.
+    fn version_apply_patch(
+        &mut self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        changes: &[(RawKey, Option<RawValue>)],
+    ) -> Result<()>;
+
+    /// Merge the newest `count` versions of a branch into one version,
+    /// the merged version retains the name of the head version.
+    ///
+    /// Unlike `prune`, the versions do not need to exist on all branches,
+    /// but versions shared with other branches(eg. inherited by a fork)
+    /// can not be squashed, use `version_squash_force` in that case.
+    fn version_squash(&mut self, br_name: BranchName, count: usize) -> Result<()>;
+
+    /// Merge the newest `count` versions of a branch into one version,
+    /// even if some of them are shared with other branches.
+    ///
+    /// # Safety
+    ///
+    /// If some of the versions are shared with other branches,
+    /// the data records of other branches may be corrupted.
+    unsafe fn version_squash_force(
+        &mut self,
+        br_name: BranchName,
+        count: usize,
+    ) -> Result<()>;
+
+    /// Merge the versions between `from_ver` and `to_ver`(both inclusive)
+    /// of a branch into one version named `target_ver_name`,
+    /// the data visible at `to_ver` and its descendants will not be changed.
+    ///
+    /// Versions shared with other branches can not be merged.
+    fn version_compact_between(
+        &mut self,
+        br_name: BranchName,
+        from_ver: VersionName,
+        to_ver: VersionName,
+        target_ver_name: VersionName,
+    ) -> Result<()>;
+
     /// Generate a trie root of the changes **directly** made by the target version,
     /// if no target version specified, the header version of the target branch will be used,
     /// if not target branch specified, then use the default branch.
@@ -272,6 +435,15 @@ pub trait VsMgmt {
     /// and should not do any tracing.
     fn branch_pop_version(&mut self, br_name: BranchName) -> Result<()>;
 
+    /// Make a branch independent of the history of its parent,
+    /// the head state of the branch will be copied into a new version,
+    /// which is named after the branch(`<br_name>@detached<index>`),
+    /// and all the other versions will be removed from the branch.
+    ///
+    /// NOTE:
+    /// the data of unreferenced versions will be dropped by `version_clean_up_globally`
+    fn branch_detach(&mut self, br_name: BranchName) -> Result<()>;
+
     /// Merge a branch into another.
     fn branch_merge_to(
         &mut self,
@@ -292,6 +464,54 @@ pub trait VsMgmt {
         target_br_name: BranchName,
     ) -> Result<()>;
 
+    /// Merge a branch into another safely,
+    /// even if new different versions have been created on the target branch.
+    ///
+    /// Like the `rebase` of git, the versions of `br_name` since the fork point
+    /// are replayed on the head of `target_br_name` by `version_cherry_pick`,
+    /// and then both branches are fast-forwarded to the new tip,
+    /// the replayed versions take over the names of the original ones.
+    ///
+    /// If `target_br_name` has not advanced since the fork point,
+    /// this is the same as `branch_merge_to`.
+    ///
+    /// Conflicts are detected in the same way as `version_cherry_pick`,
+    /// an error naming the conflicting key will be returned,
+    /// and both branches are left unchanged.
+    /// It also fails if any version to be replayed is shared with other branches.
+    fn branch_merge_to_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()>;
+
+    /// Merge a branch into its base branch, the typical usage is
+    /// merging a staging branch into the main branch periodically.
+    ///
+    /// - if `target_br_name` has advanced since the fork point,
+    ///   `br_name` is rebased onto its head first, like `branch_merge_to_rebase`
+    /// - then `target_br_name` is fast-forwarded to the head of `br_name`
+    ///
+    /// Either fully succeeds, or leaves both branches unchanged.
+    fn branch_merge_to_base_with_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()>;
+
+    /// Apply the net changes of `src_br` since its fork point with `dst_br`
+    /// onto the head of `dst_br`, as one new version named `squash_ver_name`.
+    ///
+    /// Unlike `branch_merge_to`, the version list of `dst_br` grows by exactly one,
+    /// and `src_br` is left unmodified.
+    /// If a key has also been changed on `dst_br`, the value of `src_br` wins.
+    fn branch_merge_squash(
+        &mut self,
+        src_br: BranchName,
+        dst_br: BranchName,
+        squash_ver_name: VersionName,
+    ) -> Result<()>;
+
     /// Make a branch to be default,
     /// all default operations will be applied to it.
     fn branch_set_default(&mut self, br_name: BranchName) -> Result<()>;
@@ -319,6 +539,42 @@ pub trait VsMgmt {
     /// Get the default branch name.
     fn branch_get_default(&self) -> BranchNameOwned;
 
+    /// Compare the heads of two branches,
+    /// return all keys that have different values on them.
+    ///
+    /// The items are in the form of `(key, value_in_a, value_in_b)`,
+    /// `None` means the key does not exist on that branch.
+    fn branch_diff(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>>;
+
+    /// Walk the chain from a branch back to the initial branch,
+    /// the result is ordered from the immediate parent to the root.
+    fn branch_ancestors(&self, br_name: BranchName) -> Result<Vec<BranchNameOwned>>;
+
+    /// Get the branches that were created directly on the given one.
+    fn branch_children(&self, br_name: BranchName) -> Vec<BranchNameOwned>;
+
+    /// Check if a branch exists and no branches were created on it,
+    /// see `branch_children` for listing the children.
+    fn branch_is_leaf(&self, br_name: BranchName) -> bool;
+
+    /// Find the latest version shared by two branches, that is where they diverged,
+    /// `None` means the two branches have no common history.
+    fn branch_fork_point(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Option<VersionNameOwned>>;
+
+    /// Check if a branch has any versions that are not on its parent branch,
+    /// that is, a merge back to the parent is needed.
+    ///
+    /// The initial branch has no parent, it is dirty as long as it has versions.
+    fn branch_is_dirty(&self, br_name: BranchName) -> Result<bool>;
+
     /// Logically similar to `std::ptr::swap`
     ///
     /// For example: If you have a master branch and a test branch, the data is always trial-run on the test branch, and then periodically merged back into the master branch. Rather than merging the test branch into the master branch, and then recreating the new test branch, it is more efficient to just swap the two branches, and then recreating the new test branch.
@@ -333,6 +589,14 @@ pub trait VsMgmt {
         branch_2: BranchName,
     ) -> Result<()>;
 
+    /// Change the name of a branch, all versions and data on it remain intact,
+    /// fail if the new name has been used by another branch.
+    fn branch_rename(
+        &mut self,
+        old_name: BranchName,
+        new_name: BranchName,
+    ) -> Result<()>;
+
     /// Clean outdated versions out of the default branch.
     fn prune(&mut self, reserved_ver_num: Option<usize>) -> Result<()>;
 }
@@ -375,6 +639,15 @@ macro_rules! impl_vs_methods {
             self.inner.version_exists_on_branch(ver_name, br_name)
         }
 
+        #[inline(always)]
+        fn branch_contains_version_fast(
+            &self,
+            br_name: $crate::BranchName,
+            ver_name: $crate::VersionName,
+        ) -> bool {
+            self.inner.branch_contains_version_fast(br_name, ver_name)
+        }
+
         /// Remove the newest version on the default branch.
         ///
         /// 'Write'-like operations on branches and versions are different from operations on data.
@@ -453,6 +726,28 @@ macro_rules! impl_vs_methods {
             self.inner.version_list_by_branch(br_name).c(d!())
         }
 
+        #[inline(always)]
+        fn version_is_ancestor_of(
+            &self,
+            ancestor: $crate::VersionName,
+            descendant: $crate::VersionName,
+            br_name: $crate::BranchName,
+        ) -> ruc::Result<bool> {
+            self.inner
+                .version_is_ancestor_of(ancestor, descendant, br_name)
+                .c(d!())
+        }
+
+        #[inline(always)]
+        fn versions_between(
+            &self,
+            from: $crate::VersionName,
+            to: $crate::VersionName,
+            br_name: $crate::BranchName,
+        ) -> ruc::Result<Vec<$crate::VersionNameOwned>> {
+            self.inner.versions_between(from, to, br_name).c(d!())
+        }
+
         #[inline(always)]
         fn version_list_globally(&self) -> Vec<$crate::VersionNameOwned> {
             self.inner.version_list_globally()
@@ -466,6 +761,70 @@ macro_rules! impl_vs_methods {
             self.inner.version_has_change_set(ver_name).c(d!())
         }
 
+        #[inline(always)]
+        fn version_list_changed_keys(
+            &self,
+            ver_name: $crate::VersionName,
+        ) -> ruc::Result<Vec<$crate::RawKey>> {
+            self.inner.version_list_changed_keys(ver_name).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_changed_key_count(
+            &self,
+            ver_name: $crate::VersionName,
+        ) -> ruc::Result<usize> {
+            self.inner.version_changed_key_count(ver_name).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_chgset_size(
+            &self,
+            ver_name: $crate::VersionName,
+        ) -> ruc::Result<usize> {
+            self.inner.version_chgset_size(ver_name).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_chgset_size_by_branch(
+            &self,
+            br_name: $crate::BranchName,
+        ) -> ruc::Result<usize> {
+            self.inner.version_chgset_size_by_branch(br_name).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_has_conflicts_with(
+            &self,
+            ver_a: $crate::VersionName,
+            ver_b: $crate::VersionName,
+        ) -> ruc::Result<bool> {
+            self.inner.version_has_conflicts_with(ver_a, ver_b).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_conflict_keys(
+            &self,
+            ver_a: $crate::VersionName,
+            ver_b: $crate::VersionName,
+        ) -> ruc::Result<Vec<$crate::RawKey>> {
+            self.inner.version_conflict_keys(ver_a, ver_b).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_blame(
+            &self,
+            br_name: $crate::BranchName,
+        ) -> ruc::Result<
+            Vec<(
+                $crate::RawKey,
+                $crate::VersionNameOwned,
+                Option<$crate::RawValue>,
+            )>,
+        > {
+            self.inner.version_blame(br_name).c(d!())
+        }
+
         #[inline(always)]
         fn version_clean_up_globally(&mut self) -> ruc::Result<()> {
             self.inner.version_clean_up_globally().c(d!())
@@ -479,6 +838,72 @@ macro_rules! impl_vs_methods {
             self.inner.version_revert_globally(ver_name).c(d!())
         }
 
+        #[inline(always)]
+        fn version_rollback_to(
+            &mut self,
+            br_name: $crate::BranchName,
+            last_ver: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            self.inner.version_rollback_to(br_name, last_ver).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_cherry_pick(
+            &mut self,
+            src_ver: $crate::VersionName,
+            src_br: $crate::BranchName,
+            dst_br: $crate::BranchName,
+            dst_ver: $crate::VersionName,
+            force: bool,
+        ) -> ruc::Result<()> {
+            self.inner
+                .version_cherry_pick(src_ver, src_br, dst_br, dst_ver, force)
+                .c(d!())
+        }
+
+        #[inline(always)]
+        fn version_apply_patch(
+            &mut self,
+            br_name: $crate::BranchName,
+            ver_name: $crate::VersionName,
+            changes: &[($crate::RawKey, Option<$crate::RawValue>)],
+        ) -> ruc::Result<()> {
+            self.inner
+                .version_apply_patch(br_name, ver_name, changes)
+                .c(d!())
+        }
+
+        #[inline(always)]
+        fn version_squash(
+            &mut self,
+            br_name: $crate::BranchName,
+            count: usize,
+        ) -> ruc::Result<()> {
+            self.inner.version_squash(br_name, count).c(d!())
+        }
+
+        #[inline(always)]
+        unsafe fn version_squash_force(
+            &mut self,
+            br_name: $crate::BranchName,
+            count: usize,
+        ) -> ruc::Result<()> {
+            self.inner.version_squash_force(br_name, count).c(d!())
+        }
+
+        #[inline(always)]
+        fn version_compact_between(
+            &mut self,
+            br_name: $crate::BranchName,
+            from_ver: $crate::VersionName,
+            to_ver: $crate::VersionName,
+            target_ver_name: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            self.inner
+                .version_compact_between(br_name, from_ver, to_ver, target_ver_name)
+                .c(d!())
+        }
+
         #[inline(always)]
         fn version_chgset_trie_root(
             &self,
@@ -670,6 +1095,12 @@ macro_rules! impl_vs_methods {
             self.inner.branch_pop_version(br_name).c(d!())
         }
 
+        /// Make a branch independent of the history of its parent.
+        #[inline(always)]
+        fn branch_detach(&mut self, br_name: $crate::BranchName) -> ruc::Result<()> {
+            self.inner.branch_detach(br_name).c(d!())
+        }
+
         /// Merge a branch into another
         #[inline(always)]
         fn branch_merge_to(
@@ -697,6 +1128,40 @@ macro_rules! impl_vs_methods {
                 .c(d!())
         }
 
+        #[inline(always)]
+        fn branch_merge_to_rebase(
+            &mut self,
+            br_name: $crate::BranchName,
+            target_br_name: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            self.inner
+                .branch_merge_to_rebase(br_name, target_br_name)
+                .c(d!())
+        }
+
+        #[inline(always)]
+        fn branch_merge_to_base_with_rebase(
+            &mut self,
+            br_name: $crate::BranchName,
+            target_br_name: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            self.inner
+                .branch_merge_to_base_with_rebase(br_name, target_br_name)
+                .c(d!())
+        }
+
+        #[inline(always)]
+        fn branch_merge_squash(
+            &mut self,
+            src_br: $crate::BranchName,
+            dst_br: $crate::BranchName,
+            squash_ver_name: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            self.inner
+                .branch_merge_squash(src_br, dst_br, squash_ver_name)
+                .c(d!())
+        }
+
         /// Make a branch to be default,
         /// all default operations will be applied to it.
         #[inline(always)]
@@ -719,6 +1184,55 @@ macro_rules! impl_vs_methods {
             self.inner.branch_get_default()
         }
 
+        fn branch_diff(
+            &self,
+            br_a: $crate::BranchName,
+            br_b: $crate::BranchName,
+        ) -> ruc::Result<
+            Vec<(
+                $crate::RawKey,
+                Option<$crate::RawValue>,
+                Option<$crate::RawValue>,
+            )>,
+        > {
+            self.inner.branch_diff(br_a, br_b).c(d!())
+        }
+
+        #[inline(always)]
+        fn branch_ancestors(
+            &self,
+            br_name: $crate::BranchName,
+        ) -> ruc::Result<Vec<$crate::BranchNameOwned>> {
+            self.inner.branch_ancestors(br_name).c(d!())
+        }
+
+        #[inline(always)]
+        fn branch_children(
+            &self,
+            br_name: $crate::BranchName,
+        ) -> Vec<$crate::BranchNameOwned> {
+            self.inner.branch_children(br_name)
+        }
+
+        #[inline(always)]
+        fn branch_is_leaf(&self, br_name: $crate::BranchName) -> bool {
+            self.inner.branch_is_leaf(br_name)
+        }
+
+        #[inline(always)]
+        fn branch_fork_point(
+            &self,
+            br_a: $crate::BranchName,
+            br_b: $crate::BranchName,
+        ) -> ruc::Result<Option<$crate::VersionNameOwned>> {
+            self.inner.branch_fork_point(br_a, br_b).c(d!())
+        }
+
+        #[inline(always)]
+        fn branch_is_dirty(&self, br_name: $crate::BranchName) -> ruc::Result<bool> {
+            self.inner.branch_is_dirty(br_name).c(d!())
+        }
+
         unsafe fn branch_swap(
             &mut self,
             branch_1: $crate::BranchName,
@@ -727,6 +1241,15 @@ macro_rules! impl_vs_methods {
             self.inner.branch_swap(branch_1, branch_2).c(d!())
         }
 
+        #[inline(always)]
+        fn branch_rename(
+            &mut self,
+            old_name: $crate::BranchName,
+            new_name: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            self.inner.branch_rename(old_name, new_name).c(d!())
+        }
+
         /// Clean outdated versions out of the default reserved number.
         #[inline(always)]
         fn prune(&mut self, reserved_ver_num: Option<usize>) -> ruc::Result<()> {
@@ -769,8 +1292,17 @@ macro_rules! impl_vs_methods_nope {
         }
 
         #[inline(always)]
-        fn version_pop(&mut self) -> ruc::Result<()> {
-            Ok(())
+        fn branch_contains_version_fast(
+            &self,
+            _: $crate::BranchName,
+            __: $crate::VersionName,
+        ) -> bool {
+            true
+        }
+
+        #[inline(always)]
+        fn version_pop(&mut self) -> ruc::Result<()> {
+            Ok(())
         }
 
         #[inline(always)]
@@ -807,6 +1339,24 @@ macro_rules! impl_vs_methods_nope {
             Ok(Default::default())
         }
 
+        fn version_is_ancestor_of(
+            &self,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<bool> {
+            Ok(false)
+        }
+
+        fn versions_between(
+            &self,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<Vec<$crate::VersionNameOwned>> {
+            Ok(Default::default())
+        }
+
         fn version_list_globally(&self) -> Vec<$crate::VersionNameOwned> {
             Default::default()
         }
@@ -815,6 +1365,60 @@ macro_rules! impl_vs_methods_nope {
             Ok(true)
         }
 
+        fn version_list_changed_keys(
+            &self,
+            _: $crate::VersionName,
+        ) -> ruc::Result<Vec<$crate::RawKey>> {
+            Ok(Default::default())
+        }
+
+        fn version_changed_key_count(
+            &self,
+            _: $crate::VersionName,
+        ) -> ruc::Result<usize> {
+            Ok(0)
+        }
+
+        fn version_chgset_size(&self, _: $crate::VersionName) -> ruc::Result<usize> {
+            Ok(0)
+        }
+
+        fn version_chgset_size_by_branch(
+            &self,
+            _: $crate::BranchName,
+        ) -> ruc::Result<usize> {
+            Ok(0)
+        }
+
+        fn version_has_conflicts_with(
+            &self,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+        ) -> ruc::Result<bool> {
+            Ok(false)
+        }
+
+        fn version_conflict_keys(
+            &self,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+        ) -> ruc::Result<Vec<$crate::RawKey>> {
+            Ok(Default::default())
+        }
+
+        fn version_blame(
+            &self,
+            _: $crate::BranchName,
+        ) -> ruc::Result<
+            Vec<(
+                $crate::RawKey,
+                $crate::VersionNameOwned,
+                Option<$crate::RawValue>,
+            )>,
+        > {
+            Ok(Default::default())
+        }
+
         fn version_clean_up_globally(&mut self) -> ruc::Result<()> {
             Ok(())
         }
@@ -826,6 +1430,69 @@ macro_rules! impl_vs_methods_nope {
             Ok(())
         }
 
+        fn version_rollback_to(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn version_cherry_pick(
+            &mut self,
+            _: $crate::VersionName,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+            _: $crate::VersionName,
+            _: bool,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn version_apply_patch(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::VersionName,
+            changes: &[($crate::RawKey, Option<$crate::RawValue>)],
+        ) -> ruc::Result<()> {
+            if changes.is_empty() {
+                Ok(())
+            } else {
+                Err(ruc::eg!("not a versioned structure"))
+            }
+        }
+
+        #[inline(always)]
+        fn version_squash(
+            &mut self,
+            _: $crate::BranchName,
+            _: usize,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        #[inline(always)]
+        unsafe fn version_squash_force(
+            &mut self,
+            _: $crate::BranchName,
+            _: usize,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn version_compact_between(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+            _: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
         #[inline(always)]
         fn version_chgset_trie_root(
             &self,
@@ -934,6 +1601,11 @@ macro_rules! impl_vs_methods_nope {
             Ok(())
         }
 
+        #[inline(always)]
+        fn branch_detach(&mut self, _: $crate::BranchName) -> ruc::Result<()> {
+            Ok(())
+        }
+
         #[inline(always)]
         fn branch_merge_to(
             &mut self,
@@ -951,6 +1623,33 @@ macro_rules! impl_vs_methods_nope {
             Ok(())
         }
 
+        #[inline(always)]
+        fn branch_merge_to_rebase(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn branch_merge_to_base_with_rebase(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
+        fn branch_merge_squash(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+            _: $crate::VersionName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
         #[inline(always)]
         fn branch_set_default(&mut self, _: $crate::BranchName) -> ruc::Result<()> {
             Ok(())
@@ -968,6 +1667,55 @@ macro_rules! impl_vs_methods_nope {
             Default::default()
         }
 
+        fn branch_diff(
+            &self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<
+            Vec<(
+                $crate::RawKey,
+                Option<$crate::RawValue>,
+                Option<$crate::RawValue>,
+            )>,
+        > {
+            Ok(Default::default())
+        }
+
+        #[inline(always)]
+        fn branch_ancestors(
+            &self,
+            _: $crate::BranchName,
+        ) -> ruc::Result<Vec<$crate::BranchNameOwned>> {
+            Ok(Default::default())
+        }
+
+        #[inline(always)]
+        fn branch_children(
+            &self,
+            _: $crate::BranchName,
+        ) -> Vec<$crate::BranchNameOwned> {
+            Default::default()
+        }
+
+        #[inline(always)]
+        fn branch_is_leaf(&self, _: $crate::BranchName) -> bool {
+            true
+        }
+
+        #[inline(always)]
+        fn branch_fork_point(
+            &self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<Option<$crate::VersionNameOwned>> {
+            Ok(None)
+        }
+
+        #[inline(always)]
+        fn branch_is_dirty(&self, _: $crate::BranchName) -> ruc::Result<bool> {
+            Ok(false)
+        }
+
         unsafe fn branch_swap(
             &mut self,
             _: $crate::BranchName,
@@ -976,6 +1724,15 @@ macro_rules! impl_vs_methods_nope {
             Ok(())
         }
 
+        #[inline(always)]
+        fn branch_rename(
+            &mut self,
+            _: $crate::BranchName,
+            _: $crate::BranchName,
+        ) -> ruc::Result<()> {
+            Ok(())
+        }
+
         #[inline(always)]
         fn prune(&mut self, _: Option<usize>) -> ruc::Result<()> {
             Ok(())
@@ -1123,6 +1880,18 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         true
     }
 
+    #[inline(always)]
+    fn branch_contains_version_fast(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> bool {
+        if let Some(i) = self.as_ref() {
+            return i.branch_contains_version_fast(br_name, ver_name);
+        }
+        true
+    }
+
     #[inline(always)]
     fn version_pop(&mut self) -> Result<()> {
         if let Some(i) = self.as_mut() {
@@ -1186,6 +1955,34 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(Default::default())
     }
 
+    #[inline(always)]
+    fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionName,
+        descendant: VersionName,
+        br_name: BranchName,
+    ) -> Result<bool> {
+        if let Some(i) = self.as_ref() {
+            return i
+                .version_is_ancestor_of(ancestor, descendant, br_name)
+                .c(d!());
+        }
+        Ok(false)
+    }
+
+    #[inline(always)]
+    fn versions_between(
+        &self,
+        from: VersionName,
+        to: VersionName,
+        br_name: BranchName,
+    ) -> Result<Vec<VersionNameOwned>> {
+        if let Some(i) = self.as_ref() {
+            return i.versions_between(from, to, br_name).c(d!());
+        }
+        Ok(Default::default())
+    }
+
     #[inline(always)]
     fn version_list_globally(&self) -> Vec<VersionNameOwned> {
         if let Some(i) = self.as_ref() {
@@ -1202,6 +1999,73 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(true)
     }
 
+    #[inline(always)]
+    fn version_list_changed_keys(&self, ver_name: VersionName) -> Result<Vec<RawKey>> {
+        if let Some(i) = self.as_ref() {
+            return i.version_list_changed_keys(ver_name).c(d!());
+        }
+        Ok(Default::default())
+    }
+
+    #[inline(always)]
+    fn version_changed_key_count(&self, ver_name: VersionName) -> Result<usize> {
+        if let Some(i) = self.as_ref() {
+            return i.version_changed_key_count(ver_name).c(d!());
+        }
+        Ok(0)
+    }
+
+    #[inline(always)]
+    fn version_chgset_size(&self, ver_name: VersionName) -> Result<usize> {
+        if let Some(i) = self.as_ref() {
+            return i.version_chgset_size(ver_name).c(d!());
+        }
+        Ok(0)
+    }
+
+    #[inline(always)]
+    fn version_chgset_size_by_branch(&self, br_name: BranchName) -> Result<usize> {
+        if let Some(i) = self.as_ref() {
+            return i.version_chgset_size_by_branch(br_name).c(d!());
+        }
+        Ok(0)
+    }
+
+    #[inline(always)]
+    fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<bool> {
+        if let Some(i) = self.as_ref() {
+            return i.version_has_conflicts_with(ver_a, ver_b).c(d!());
+        }
+        Ok(false)
+    }
+
+    #[inline(always)]
+    fn version_conflict_keys(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<Vec<RawKey>> {
+        if let Some(i) = self.as_ref() {
+            return i.version_conflict_keys(ver_a, ver_b).c(d!());
+        }
+        Ok(Default::default())
+    }
+
+    #[inline(always)]
+    fn version_blame(
+        &self,
+        br_name: BranchName,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>> {
+        if let Some(i) = self.as_ref() {
+            return i.version_blame(br_name).c(d!());
+        }
+        Ok(Default::default())
+    }
+
     #[inline(always)]
     fn version_clean_up_globally(&mut self) -> Result<()> {
         if let Some(i) = self.as_mut() {
@@ -1218,6 +2082,82 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(())
     }
 
+    #[inline(always)]
+    fn version_rollback_to(
+        &mut self,
+        br_name: BranchName,
+        last_ver: VersionName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_rollback_to(br_name, last_ver).c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn version_cherry_pick(
+        &mut self,
+        src_ver: VersionName,
+        src_br: BranchName,
+        dst_br: BranchName,
+        dst_ver: VersionName,
+        force: bool,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_cherry_pick(src_ver, src_br, dst_br, dst_ver, force)
+                .c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn version_apply_patch(
+        &mut self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        changes: &[(RawKey, Option<RawValue>)],
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_apply_patch(br_name, ver_name, changes).c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn version_squash(&mut self, br_name: BranchName, count: usize) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_squash(br_name, count).c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    unsafe fn version_squash_force(
+        &mut self,
+        br_name: BranchName,
+        count: usize,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_squash_force(br_name, count).c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn version_compact_between(
+        &mut self,
+        br_name: BranchName,
+        from_ver: VersionName,
+        to_ver: VersionName,
+        target_ver_name: VersionName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.version_compact_between(br_name, from_ver, to_ver, target_ver_name)
+                .c(d!())?;
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn version_chgset_trie_root(
         &self,
@@ -1388,6 +2328,14 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(())
     }
 
+    #[inline(always)]
+    fn branch_detach(&mut self, br_name: BranchName) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.branch_detach(br_name).c(d!())?;
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn branch_merge_to(
         &mut self,
@@ -1412,6 +2360,45 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(())
     }
 
+    #[inline(always)]
+    fn branch_merge_to_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.branch_merge_to_rebase(br_name, target_br_name).c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn branch_merge_to_base_with_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.branch_merge_to_base_with_rebase(br_name, target_br_name)
+                .c(d!())?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn branch_merge_squash(
+        &mut self,
+        src_br: BranchName,
+        dst_br: BranchName,
+        squash_ver_name: VersionName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.branch_merge_squash(src_br, dst_br, squash_ver_name)
+                .c(d!())?;
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn branch_set_default(&mut self, br_name: BranchName) -> Result<()> {
         if let Some(i) = self.as_mut() {
@@ -1441,6 +2428,61 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Default::default()
     }
 
+    fn branch_diff(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>> {
+        if let Some(i) = self.as_ref() {
+            return i.branch_diff(br_a, br_b).c(d!());
+        }
+        Ok(Default::default())
+    }
+
+    #[inline(always)]
+    fn branch_ancestors(&self, br_name: BranchName) -> Result<Vec<BranchNameOwned>> {
+        if let Some(i) = self.as_ref() {
+            return i.branch_ancestors(br_name).c(d!());
+        }
+        Ok(Default::default())
+    }
+
+    #[inline(always)]
+    fn branch_children(&self, br_name: BranchName) -> Vec<BranchNameOwned> {
+        if let Some(i) = self.as_ref() {
+            return i.branch_children(br_name);
+        }
+        Default::default()
+    }
+
+    #[inline(always)]
+    fn branch_is_leaf(&self, br_name: BranchName) -> bool {
+        if let Some(i) = self.as_ref() {
+            return i.branch_is_leaf(br_name);
+        }
+        true // always return true if nope
+    }
+
+    #[inline(always)]
+    fn branch_fork_point(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Option<VersionNameOwned>> {
+        if let Some(i) = self.as_ref() {
+            return i.branch_fork_point(br_a, br_b).c(d!());
+        }
+        Ok(None)
+    }
+
+    #[inline(always)]
+    fn branch_is_dirty(&self, br_name: BranchName) -> Result<bool> {
+        if let Some(i) = self.as_ref() {
+            return i.branch_is_dirty(br_name).c(d!());
+        }
+        Ok(false)
+    }
+
     unsafe fn branch_swap(
         &mut self,
         branch_1: BranchName,
@@ -1452,6 +2494,18 @@ impl<T: VsMgmt> VsMgmt for Option<T> {
         Ok(())
     }
 
+    #[inline(always)]
+    fn branch_rename(
+        &mut self,
+        old_name: BranchName,
+        new_name: BranchName,
+    ) -> Result<()> {
+        if let Some(i) = self.as_mut() {
+            i.branch_rename(old_name, new_name).c(d!())?;
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn prune(&mut self, reserved_ver_num: Option<usize>) -> Result<()> {
         if let Some(i) = self.as_mut() {
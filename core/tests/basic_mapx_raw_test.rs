@@ -1,5 +1,5 @@
 use ruc::*;
-use std::borrow::Cow;
+use std::{borrow::Cow, fs};
 use vsdb_core::{vsdb_set_base_dir, MapxRaw};
 
 #[test]
@@ -88,3 +88,28 @@ fn basic_cases() {
     assert_eq!(vec![80], reloaded.get_le(&[80]).unwrap().1);
     assert_eq!(vec![80], reloaded.get_le(&[100]).unwrap().1);
 }
+
+#[test]
+fn list_instances() {
+    info_omit!(vsdb_set_base_dir(&format!(
+        "/tmp/vsdb_testing/{}",
+        rand::random::<u64>()
+    )));
+
+    // open the engine
+    let _hdr = MapxRaw::new();
+
+    // unrelated directories and a symlink loop
+    let custom_dir = vsdb_core::vsdb_get_custom_dir().to_path_buf();
+    let unrelated = custom_dir.join("list_instances");
+    pnk!(fs::create_dir_all(unrelated.join("a/b/c")));
+    #[cfg(unix)]
+    pnk!(std::os::unix::fs::symlink(
+        &unrelated,
+        unrelated.join("a/loop")
+    ));
+
+    let instances = vsdb_core::vsdb_list_instances();
+    assert_eq!(instances[0], vsdb_core::vsdb_get_base_dir());
+    assert!(instances.iter().all(|p| !p.starts_with(&unrelated)));
+}
@@ -17,6 +17,7 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     let version_create_by_branch = gen_version_create_by_branch(&input.data);
     let version_exists = gen_version_exists(&input.data);
     let version_exists_on_branch = gen_version_exists_on_branch(&input.data);
+    let branch_contains_version_fast = gen_branch_contains_version_fast(&input.data);
     let version_pop = gen_version_pop(&input.data);
     let version_pop_by_branch = gen_version_pop_by_branch(&input.data);
     let version_rebase = gen_version_rebase(&input.data);
@@ -41,23 +42,50 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     let branch_truncate = gen_branch_truncate(&input.data);
     let branch_truncate_to = gen_branch_truncate_to(&input.data);
     let branch_pop_version = gen_branch_pop_version(&input.data);
+    let branch_detach = gen_branch_detach(&input.data);
     let branch_merge_to = gen_branch_merge_to(&input.data);
     let branch_merge_to_force = gen_branch_merge_to_force(&input.data);
+    let branch_merge_to_rebase = gen_branch_merge_to_rebase(&input.data);
+    let branch_merge_to_base_with_rebase =
+        gen_branch_merge_to_base_with_rebase(&input.data);
+    let branch_merge_squash = gen_branch_merge_squash(&input.data);
     let branch_set_default = gen_branch_set_default(&input.data);
     let prune = gen_prune(&input.data);
 
     let version_exists_globally = gen_version_exists_globally(&input.data);
     let version_list = gen_version_list(&input.data);
     let version_list_by_branch = gen_version_list_by_branch(&input.data);
+    let version_is_ancestor_of = gen_version_is_ancestor_of(&input.data);
+    let versions_between = gen_versions_between(&input.data);
     let version_list_globally = gen_version_list_globally(&input.data);
     let version_has_change_set = gen_version_has_change_set(&input.data);
+    let version_list_changed_keys = gen_version_list_changed_keys(&input.data);
+    let version_changed_key_count = gen_version_changed_key_count(&input.data);
+    let version_chgset_size = gen_version_chgset_size(&input.data);
+    let version_chgset_size_by_branch = gen_version_chgset_size_by_branch(&input.data);
+    let version_has_conflicts_with = gen_version_has_conflicts_with(&input.data);
+    let version_conflict_keys = gen_version_conflict_keys(&input.data);
+    let version_blame = gen_version_blame(&input.data);
     let version_clean_up_globally = gen_version_clean_up_globally(&input.data);
     let version_revert_globally = gen_version_revert_globally(&input.data);
+    let version_rollback_to = gen_version_rollback_to(&input.data);
+    let version_cherry_pick = gen_version_cherry_pick(&input.data);
+    let version_apply_patch = gen_version_apply_patch(&input.data);
+    let version_squash = gen_version_squash(&input.data);
+    let version_squash_force = gen_version_squash_force(&input.data);
+    let version_compact_between = gen_version_compact_between(&input.data);
     let version_chgset_trie_root = gen_version_chgset_trie_root(&input.data);
     let branch_is_empty = gen_branch_is_empty(&input.data);
     let branch_list = gen_branch_list(&input.data);
     let branch_get_default = gen_branch_get_default(&input.data);
+    let branch_diff = gen_branch_diff(&input.data);
+    let branch_ancestors = gen_branch_ancestors(&input.data);
+    let branch_children = gen_branch_children(&input.data);
+    let branch_is_leaf = gen_branch_is_leaf(&input.data);
+    let branch_fork_point = gen_branch_fork_point(&input.data);
+    let branch_is_dirty = gen_branch_is_dirty(&input.data);
     let branch_swap = gen_branch_swap(&input.data);
+    let branch_rename = gen_branch_rename(&input.data);
 
     let expanded = quote! {
         use ruc::*;
@@ -88,6 +116,14 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 #version_exists_on_branch
             }
 
+            fn branch_contains_version_fast(
+                &self,
+                br_name: vsdb::BranchName,
+                ver_name: vsdb::VersionName,
+                ) -> bool {
+                #branch_contains_version_fast
+            }
+
             fn version_pop(&mut self) -> ruc::Result<()> {
                 #version_pop
                 Ok(())
@@ -212,6 +248,11 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Ok(())
             }
 
+            fn branch_detach(&mut self, br_name: vsdb::BranchName) -> ruc::Result<()> {
+                #branch_detach
+                Ok(())
+            }
+
             fn branch_merge_to(
                 &mut self,
                 br_name: vsdb::BranchName,
@@ -230,6 +271,34 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Ok(())
             }
 
+            fn branch_merge_to_rebase(
+                &mut self,
+                br_name: vsdb::BranchName,
+                target_br_name: vsdb::BranchName
+            ) -> ruc::Result<()> {
+                #branch_merge_to_rebase
+                Ok(())
+            }
+
+            fn branch_merge_to_base_with_rebase(
+                &mut self,
+                br_name: vsdb::BranchName,
+                target_br_name: vsdb::BranchName
+            ) -> ruc::Result<()> {
+                #branch_merge_to_base_with_rebase
+                Ok(())
+            }
+
+            fn branch_merge_squash(
+                &mut self,
+                src_br: vsdb::BranchName,
+                dst_br: vsdb::BranchName,
+                squash_ver_name: vsdb::VersionName
+            ) -> ruc::Result<()> {
+                #branch_merge_squash
+                Ok(())
+            }
+
             fn branch_set_default(&mut self, br_name: vsdb::BranchName) -> ruc::Result<()> {
                 #branch_set_default
                 Ok(())
@@ -260,6 +329,28 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Ok(guard)
             }
 
+            fn version_is_ancestor_of(
+                &self,
+                ancestor: vsdb::VersionName,
+                descendant: vsdb::VersionName,
+                br_name: vsdb::BranchName,
+            ) -> ruc::Result<bool> {
+                #version_is_ancestor_of
+                Ok(false)
+            }
+
+            fn versions_between(
+                &self,
+                from: vsdb::VersionName,
+                to: vsdb::VersionName,
+                br_name: vsdb::BranchName,
+            ) -> ruc::Result<Vec<vsdb::VersionNameOwned>> {
+                let guard_default: Vec<vsdb::VersionNameOwned> = Default::default();
+                let mut guard: Vec<vsdb::VersionNameOwned> = Default::default();
+                #versions_between
+                Ok(guard)
+            }
+
             fn version_list_globally(&self) -> Vec<vsdb::VersionNameOwned> {
                 let guard_default: Vec<vsdb::VersionNameOwned> = Default::default();
                 let mut guard: Vec<vsdb::VersionNameOwned> = Default::default();
@@ -272,6 +363,58 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Ok(true)
             }
 
+            fn version_list_changed_keys(&self, ver_name: vsdb::VersionName) -> ruc::Result<Vec<vsdb::RawKey>> {
+                let mut ret: Vec<vsdb::RawKey> = Default::default();
+                #version_list_changed_keys
+                Ok(ret)
+            }
+
+            fn version_changed_key_count(&self, ver_name: vsdb::VersionName) -> ruc::Result<usize> {
+                let mut ret = 0;
+                #version_changed_key_count
+                Ok(ret)
+            }
+
+            fn version_chgset_size(&self, ver_name: vsdb::VersionName) -> ruc::Result<usize> {
+                let mut ret = 0;
+                #version_chgset_size
+                Ok(ret)
+            }
+
+            fn version_chgset_size_by_branch(&self, br_name: vsdb::BranchName) -> ruc::Result<usize> {
+                let mut ret = 0;
+                #version_chgset_size_by_branch
+                Ok(ret)
+            }
+
+            fn version_has_conflicts_with(
+                &self,
+                ver_a: vsdb::VersionName,
+                ver_b: vsdb::VersionName
+            ) -> ruc::Result<bool> {
+                #version_has_conflicts_with
+                Ok(false)
+            }
+
+            fn version_conflict_keys(
+                &self,
+                ver_a: vsdb::VersionName,
+                ver_b: vsdb::VersionName
+            ) -> ruc::Result<Vec<vsdb::RawKey>> {
+                let mut ret: Vec<vsdb::RawKey> = Default::default();
+                #version_conflict_keys
+                Ok(ret)
+            }
+
+            fn version_blame(
+                &self,
+                br_name: vsdb::BranchName,
+            ) -> ruc::Result<Vec<(vsdb::RawKey, vsdb::VersionNameOwned, Option<vsdb::RawValue>)>> {
+                let mut ret: Vec<(vsdb::RawKey, vsdb::VersionNameOwned, Option<vsdb::RawValue>)> = Default::default();
+                #version_blame
+                Ok(ret)
+            }
+
             fn version_clean_up_globally(&mut self) -> ruc::Result<()> {
                 #version_clean_up_globally
                 Ok(())
@@ -282,6 +425,62 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 Ok(())
             }
 
+            fn version_rollback_to(&mut self, br_name: vsdb::BranchName, last_ver: vsdb::VersionName) -> ruc::Result<()> {
+                #version_rollback_to
+                Ok(())
+            }
+
+            fn version_cherry_pick(
+                &mut self,
+                src_ver: vsdb::VersionName,
+                src_br: vsdb::BranchName,
+                dst_br: vsdb::BranchName,
+                dst_ver: vsdb::VersionName,
+                force: bool
+            ) -> ruc::Result<()> {
+                #version_cherry_pick
+                Ok(())
+            }
+
+            fn version_apply_patch(
+                &mut self,
+                br_name: vsdb::BranchName,
+                ver_name: vsdb::VersionName,
+                changes: &[(vsdb::RawKey, Option<vsdb::RawValue>)]
+            ) -> ruc::Result<()> {
+                #version_apply_patch
+                Ok(())
+            }
+
+            fn version_squash(
+                &mut self,
+                br_name: vsdb::BranchName,
+                count: usize
+            ) -> ruc::Result<()> {
+                #version_squash
+                Ok(())
+            }
+
+            unsafe fn version_squash_force(
+                &mut self,
+                br_name: vsdb::BranchName,
+                count: usize
+            ) -> ruc::Result<()> {
+                #version_squash_force
+                Ok(())
+            }
+
+            fn version_compact_between(
+                &mut self,
+                br_name: vsdb::BranchName,
+                from_ver: vsdb::VersionName,
+                to_ver: vsdb::VersionName,
+                target_ver_name: vsdb::VersionName
+            ) -> ruc::Result<()> {
+                #version_compact_between
+                Ok(())
+            }
+
             fn version_chgset_trie_root(
                 &self,
                 br_name: Option<vsdb::BranchName>,
@@ -311,6 +510,52 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 guard
             }
 
+            fn branch_diff(
+                &self,
+                br_a: vsdb::BranchName,
+                br_b: vsdb::BranchName
+            ) -> ruc::Result<Vec<(vsdb::RawKey, Option<vsdb::RawValue>, Option<vsdb::RawValue>)>> {
+                let mut res = vec![];
+                #branch_diff
+                Ok(res)
+            }
+
+            fn branch_ancestors(&self, br_name: vsdb::BranchName)
+                -> ruc::Result<Vec<vsdb::BranchNameOwned>> {
+
+                let guard_default: Vec<vsdb::BranchNameOwned> = Default::default();
+                let mut guard: Vec<vsdb::BranchNameOwned> = Default::default();
+                #branch_ancestors
+                Ok(guard)
+            }
+
+            fn branch_children(&self, br_name: vsdb::BranchName) -> Vec<vsdb::BranchNameOwned> {
+                let guard_default: Vec<vsdb::BranchNameOwned> = Default::default();
+                let mut guard: Vec<vsdb::BranchNameOwned> = Default::default();
+                #branch_children
+                guard
+            }
+
+            fn branch_is_leaf(&self, br_name: vsdb::BranchName) -> bool {
+                #branch_is_leaf
+            }
+
+            fn branch_fork_point(
+                &self,
+                br_a: vsdb::BranchName,
+                br_b: vsdb::BranchName
+            ) -> ruc::Result<Option<vsdb::VersionNameOwned>> {
+                let guard_default: Option<vsdb::VersionNameOwned> = None;
+                let mut guard: Option<vsdb::VersionNameOwned> = None;
+                #branch_fork_point
+                Ok(guard)
+            }
+
+            fn branch_is_dirty(&self, br_name: vsdb::BranchName) -> ruc::Result<bool> {
+                #branch_is_dirty
+                Ok(false)
+            }
+
             unsafe fn branch_swap(
                 &mut self,
                 br1: vsdb::BranchName,
@@ -319,6 +564,15 @@ pub fn derive_vsmgmt(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 #branch_swap
                 Ok(())
             }
+
+            fn branch_rename(
+                &mut self,
+                old_name: vsdb::BranchName,
+                new_name: vsdb::BranchName
+            ) -> ruc::Result<()> {
+                #branch_rename
+                Ok(())
+            }
         }
     };
 
@@ -452,6 +706,37 @@ fn gen_version_exists_on_branch(data: &Data) -> TokenStream {
     }
 }
 
+fn gen_branch_contains_version_fast(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                        let id = &f.ident;
+                        quote_spanned! {f.span()=>
+                            vsdb::VsMgmt::branch_contains_version_fast(&self.#id, br_name, ver_name) &&
+                        }
+                    });
+                quote! {
+                    #(#recurse)* true
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                        let id = Index::from(i);
+                        quote_spanned! {f.span()=>
+                            vsdb::VsMgmt::branch_contains_version_fast(&self.#id, br_name, ver_name) &&
+                        }
+                    });
+                quote! {
+                    #(#recurse)* true
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
 fn gen_version_pop(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
@@ -1041,14 +1326,14 @@ fn gen_branch_pop_version(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_merge_to(data: &Data) -> TokenStream {
+fn gen_branch_detach(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_merge_to(&mut self.#id, br_name, target_br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_detach(&mut self.#id, br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1059,7 +1344,7 @@ fn gen_branch_merge_to(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_merge_to(&mut self.#id, br_name, target_br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_detach(&mut self.#id, br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1072,14 +1357,14 @@ fn gen_branch_merge_to(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_merge_to_force(data: &Data) -> TokenStream {
+fn gen_branch_merge_to(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_merge_to_force(&mut self.#id, br_name, target_br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1090,7 +1375,7 @@ fn gen_branch_merge_to_force(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_merge_to_force(&mut self.#id, br_name, target_br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1103,14 +1388,14 @@ fn gen_branch_merge_to_force(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_set_default(data: &Data) -> TokenStream {
+fn gen_branch_merge_to_force(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_set_default(&mut self.#id, br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to_force(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1121,7 +1406,7 @@ fn gen_branch_set_default(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::branch_set_default(&mut self.#id, br_name).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to_force(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1134,14 +1419,14 @@ fn gen_branch_set_default(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_prune(data: &Data) -> TokenStream {
+fn gen_branch_merge_to_rebase(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::prune(&mut self.#id, reserved_ver_num).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to_rebase(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1152,7 +1437,7 @@ fn gen_prune(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::prune(&mut self.#id, reserved_ver_num).c(d!())?;
+                        vsdb::VsMgmt::branch_merge_to_rebase(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1165,29 +1450,29 @@ fn gen_prune(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_exists_globally(data: &Data) -> TokenStream {
+fn gen_branch_merge_to_base_with_rebase(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_exists_globally(&self.#id, ver_name) &&
+                        vsdb::VsMgmt::branch_merge_to_base_with_rebase(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
-                    #(#recurse)* true
+                    #(#recurse)*
                 }
             }
             Fields::Unnamed(ref fields) => {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_exists_globally(&self.#id, ver_name) &&
+                        vsdb::VsMgmt::branch_merge_to_base_with_rebase(&mut self.#id, br_name, target_br_name).c(d!())?;
                     }
                 });
                 quote! {
-                    #(#recurse)* true
+                    #(#recurse)*
                 }
             }
             Fields::Unit => todo!(),
@@ -1196,16 +1481,14 @@ fn gen_version_exists_globally(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_list(data: &Data) -> TokenStream {
+fn gen_branch_merge_squash(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list(&self.#id).c(d!())?;
-                        }
+                        vsdb::VsMgmt::branch_merge_squash(&mut self.#id, src_br, dst_br, squash_ver_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1216,9 +1499,7 @@ fn gen_version_list(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list(&self.#id).c(d!())?;
-                        }
+                        vsdb::VsMgmt::branch_merge_squash(&mut self.#id, src_br, dst_br, squash_ver_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1231,16 +1512,14 @@ fn gen_version_list(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_list_by_branch(data: &Data) -> TokenStream {
+fn gen_branch_set_default(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list_by_branch(&self.#id, br_name).c(d!())?;
-                        }
+                        vsdb::VsMgmt::branch_set_default(&mut self.#id, br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1251,9 +1530,7 @@ fn gen_version_list_by_branch(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list_by_branch(&self.#id, br_name).c(d!())?;
-                        }
+                        vsdb::VsMgmt::branch_set_default(&mut self.#id, br_name).c(d!())?;
                     }
                 });
                 quote! {
@@ -1266,16 +1543,14 @@ fn gen_version_list_by_branch(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_list_globally(data: &Data) -> TokenStream {
+fn gen_prune(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list_globally(&self.#id);
-                        }
+                        vsdb::VsMgmt::prune(&mut self.#id, reserved_ver_num).c(d!())?;
                     }
                 });
                 quote! {
@@ -1286,9 +1561,7 @@ fn gen_version_list_globally(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        if guard == guard_default {
-                            guard = vsdb::VsMgmt::version_list_globally(&self.#id);
-                        }
+                        vsdb::VsMgmt::prune(&mut self.#id, reserved_ver_num).c(d!())?;
                     }
                 });
                 quote! {
@@ -1301,29 +1574,29 @@ fn gen_version_list_globally(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_has_change_set(data: &Data) -> TokenStream {
+fn gen_version_exists_globally(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        if !vsdb::VsMgmt::version_has_change_set(&self.#id, ver_name)? { return Ok(false); }
+                        vsdb::VsMgmt::version_exists_globally(&self.#id, ver_name) &&
                     }
                 });
                 quote! {
-                    #(#recurse)*
+                    #(#recurse)* true
                 }
             }
             Fields::Unnamed(ref fields) => {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        if !vsdb::VsMgmt::version_has_change_set(&self.#id, ver_name)? { return Ok(false); }
+                        vsdb::VsMgmt::version_exists_globally(&self.#id, ver_name) &&
                     }
                 });
                 quote! {
-                    #(#recurse)*
+                    #(#recurse)* true
                 }
             }
             Fields::Unit => todo!(),
@@ -1332,14 +1605,16 @@ fn gen_version_has_change_set(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_clean_up_globally(data: &Data) -> TokenStream {
+fn gen_version_list(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_clean_up_globally(&mut self.#id).c(d!())?;
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::version_list(&self.#id).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1350,7 +1625,9 @@ fn gen_version_clean_up_globally(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_clean_up_globally(&mut self.#id).c(d!())?;
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::version_list(&self.#id).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1363,14 +1640,16 @@ fn gen_version_clean_up_globally(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_revert_globally(data: &Data) -> TokenStream {
+fn gen_version_list_by_branch(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_revert_globally(&mut self.#id, ver_name).c(d!())?;
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::version_list_by_branch(&self.#id, br_name).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1381,7 +1660,9 @@ fn gen_version_revert_globally(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        vsdb::VsMgmt::version_revert_globally(&mut self.#id, ver_name).c(d!())?;
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::version_list_by_branch(&self.#id, br_name).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1394,14 +1675,14 @@ fn gen_version_revert_globally(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_version_chgset_trie_root(data: &Data) -> TokenStream {
+fn gen_version_is_ancestor_of(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        res.append(&mut vsdb::VsMgmt::version_chgset_trie_root(&self.#id, br_name, ver_name).c(d!())?);
+                        if vsdb::VsMgmt::version_is_ancestor_of(&self.#id, ancestor, descendant, br_name).c(d!())? { return Ok(true); }
                     }
                 });
                 quote! {
@@ -1412,7 +1693,7 @@ fn gen_version_chgset_trie_root(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        res.append(vsdb::VsMgmt::version_chgset_trie_root(&self.#id, br_name, ver_name).c(d!())?);
+                        if vsdb::VsMgmt::version_is_ancestor_of(&self.#id, ancestor, descendant, br_name).c(d!())? { return Ok(true); }
                     }
                 });
                 quote! {
@@ -1425,14 +1706,16 @@ fn gen_version_chgset_trie_root(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_is_empty(data: &Data) -> TokenStream {
+fn gen_versions_between(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        if !vsdb::VsMgmt::branch_is_empty(&self.#id, br_name).c(d!())? { return Ok(false); }
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::versions_between(&self.#id, from, to, br_name).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1443,7 +1726,9 @@ fn gen_branch_is_empty(data: &Data) -> TokenStream {
                 let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
-                        if !vsdb::VsMgmt::branch_is_empty(&self.#id, br_name).c(d!())? { return Ok(false); }
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::versions_between(&self.#id, from, to, br_name).c(d!())?;
+                        }
                     }
                 });
                 quote! {
@@ -1456,7 +1741,7 @@ fn gen_branch_is_empty(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_list(data: &Data) -> TokenStream {
+fn gen_version_list_globally(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
@@ -1464,7 +1749,7 @@ fn gen_branch_list(data: &Data) -> TokenStream {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
                         if guard == guard_default {
-                            guard = vsdb::VsMgmt::branch_list(&self.#id);
+                            guard = vsdb::VsMgmt::version_list_globally(&self.#id);
                         }
                     }
                 });
@@ -1477,7 +1762,7 @@ fn gen_branch_list(data: &Data) -> TokenStream {
                     let id = Index::from(i);
                     quote_spanned! {f.span()=>
                         if guard == guard_default {
-                            guard = vsdb::VsMgmt::branch_list(&self.#id);
+                            guard = vsdb::VsMgmt::version_list_globally(&self.#id);
                         }
                     }
                 });
@@ -1491,21 +1776,650 @@ fn gen_branch_list(data: &Data) -> TokenStream {
     }
 }
 
-fn gen_branch_get_default(data: &Data) -> TokenStream {
+fn gen_version_has_change_set(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let recurse = fields.named.iter().map(|f| {
                     let id = &f.ident;
                     quote_spanned! {f.span()=>
-                        let new = vsdb::VsMgmt::branch_get_default(&self.#id);
-                        if guard_default != new {
-                            if guard_default == guard {
-                                guard = new;
-                            } else {
-                                assert_eq!(guard, new);
-                            }
-                        }
+                        if !vsdb::VsMgmt::version_has_change_set(&self.#id, ver_name)? { return Ok(false); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if !vsdb::VsMgmt::version_has_change_set(&self.#id, ver_name)? { return Ok(false); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_list_changed_keys(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_list_changed_keys(&self.#id, ver_name)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_list_changed_keys(&self.#id, ver_name)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_blame(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_blame(&self.#id, br_name)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_blame(&self.#id, br_name)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_changed_key_count(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_changed_key_count(&self.#id, ver_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_changed_key_count(&self.#id, ver_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_chgset_size(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_chgset_size(&self.#id, ver_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_chgset_size(&self.#id, ver_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_chgset_size_by_branch(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_chgset_size_by_branch(&self.#id, br_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret += vsdb::VsMgmt::version_chgset_size_by_branch(&self.#id, br_name)?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_has_conflicts_with(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if vsdb::VsMgmt::version_has_conflicts_with(&self.#id, ver_a, ver_b).c(d!())? { return Ok(true); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if vsdb::VsMgmt::version_has_conflicts_with(&self.#id, ver_a, ver_b).c(d!())? { return Ok(true); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_conflict_keys(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_conflict_keys(&self.#id, ver_a, ver_b)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        ret.extend(vsdb::VsMgmt::version_conflict_keys(&self.#id, ver_a, ver_b)?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_clean_up_globally(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_clean_up_globally(&mut self.#id).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_clean_up_globally(&mut self.#id).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_revert_globally(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_revert_globally(&mut self.#id, ver_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_revert_globally(&mut self.#id, ver_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_rollback_to(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_rollback_to(&mut self.#id, br_name, last_ver).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_rollback_to(&mut self.#id, br_name, last_ver).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_cherry_pick(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_cherry_pick(&mut self.#id, src_ver, src_br, dst_br, dst_ver, force).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_cherry_pick(&mut self.#id, src_ver, src_br, dst_br, dst_ver, force).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+// Route each change to its field by the `<field name>.` prefix of the key,
+// all keys are checked before any field is changed.
+fn gen_version_apply_patch(data: &Data) -> TokenStream {
+    let route = |names: Vec<String>, recurse: Vec<TokenStream>| {
+        quote! {
+            let names: &[&str] = &[#(#names),*];
+            let mut routed = vec![vec![]; names.len()];
+            for (k, v) in changes.iter() {
+                let pos = k
+                    .iter()
+                    .position(|b| b'.' == *b)
+                    .c(d!("no field name in the key"))?;
+                let idx = names
+                    .iter()
+                    .position(|n| k[..pos] == *n.as_bytes())
+                    .c(d!("field not found"))?;
+                routed[idx].push((k[1 + pos..].to_vec(), v.clone()));
+            }
+            #(#recurse)*
+        }
+    };
+
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let names = fields
+                    .named
+                    .iter()
+                    .map(|f| {
+                        let id = f.ident.as_ref().unwrap().to_string();
+                        id.trim_start_matches("r#").to_owned()
+                    })
+                    .collect();
+                let recurse = fields
+                    .named
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let id = &f.ident;
+                        quote_spanned! {f.span()=>
+                            vsdb::VsMgmt::version_apply_patch(&mut self.#id, br_name, ver_name, &routed[#i]).c(d!())?;
+                        }
+                    })
+                    .collect();
+                route(names, recurse)
+            }
+            Fields::Unnamed(ref fields) => {
+                let names = (0..fields.unnamed.len()).map(|i| i.to_string()).collect();
+                let recurse = fields
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let id = Index::from(i);
+                        quote_spanned! {f.span()=>
+                            vsdb::VsMgmt::version_apply_patch(&mut self.#id, br_name, ver_name, &routed[#i]).c(d!())?;
+                        }
+                    })
+                    .collect();
+                route(names, recurse)
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_squash(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_squash(&mut self.#id, br_name, count).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_squash(&mut self.#id, br_name, count).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_squash_force(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_squash_force(&mut self.#id, br_name, count).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_squash_force(&mut self.#id, br_name, count).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_compact_between(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_compact_between(&mut self.#id, br_name, from_ver, to_ver, target_ver_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::version_compact_between(&mut self.#id, br_name, from_ver, to_ver, target_ver_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_version_chgset_trie_root(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        res.append(&mut vsdb::VsMgmt::version_chgset_trie_root(&self.#id, br_name, ver_name).c(d!())?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        res.append(vsdb::VsMgmt::version_chgset_trie_root(&self.#id, br_name, ver_name).c(d!())?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_is_empty(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if !vsdb::VsMgmt::branch_is_empty(&self.#id, br_name).c(d!())? { return Ok(false); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if !vsdb::VsMgmt::branch_is_empty(&self.#id, br_name).c(d!())? { return Ok(false); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_list(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_list(&self.#id);
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_list(&self.#id);
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_get_default(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        let new = vsdb::VsMgmt::branch_get_default(&self.#id);
+                        if guard_default != new {
+                            if guard_default == guard {
+                                guard = new;
+                            } else {
+                                assert_eq!(guard, new);
+                            }
+                        }
                     }
                 });
                 quote! {
@@ -1536,6 +2450,204 @@ fn gen_branch_get_default(data: &Data) -> TokenStream {
     }
 }
 
+fn gen_branch_diff(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        res.append(&mut vsdb::VsMgmt::branch_diff(&self.#id, br_a, br_b).c(d!())?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        res.append(&mut vsdb::VsMgmt::branch_diff(&self.#id, br_a, br_b).c(d!())?);
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_ancestors(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_ancestors(&self.#id, br_name).c(d!())?;
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_ancestors(&self.#id, br_name).c(d!())?;
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_children(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_children(&self.#id, br_name);
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_children(&self.#id, br_name);
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_is_leaf(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::branch_is_leaf(&self.#id, br_name) &&
+                    }
+                });
+                quote! {
+                    #(#recurse)* true
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::branch_is_leaf(&self.#id, br_name) &&
+                    }
+                });
+                quote! {
+                    #(#recurse)* true
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_fork_point(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_fork_point(&self.#id, br_a, br_b).c(d!())?;
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if guard == guard_default {
+                            guard = vsdb::VsMgmt::branch_fork_point(&self.#id, br_a, br_b).c(d!())?;
+                        }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
+fn gen_branch_is_dirty(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        if vsdb::VsMgmt::branch_is_dirty(&self.#id, br_name).c(d!())? { return Ok(true); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        if vsdb::VsMgmt::branch_is_dirty(&self.#id, br_name).c(d!())? { return Ok(true); }
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
+
 fn gen_branch_swap(data: &Data) -> TokenStream {
     match *data {
         Data::Struct(ref data) => match data.fields {
@@ -1566,3 +2678,34 @@ fn gen_branch_swap(data: &Data) -> TokenStream {
         Data::Enum(_) | Data::Union(_) => todo!(),
     }
 }
+
+fn gen_branch_rename(data: &Data) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let recurse = fields.named.iter().map(|f| {
+                    let id = &f.ident;
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::branch_rename(&mut self.#id, old_name, new_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let recurse = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let id = Index::from(i);
+                    quote_spanned! {f.span()=>
+                        vsdb::VsMgmt::branch_rename(&mut self.#id, old_name, new_name).c(d!())?;
+                    }
+                });
+                quote! {
+                    #(#recurse)*
+                }
+            }
+            Fields::Unit => todo!(),
+        },
+        Data::Enum(_) | Data::Union(_) => todo!(),
+    }
+}
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use vsdb_slot_db::SlotDB;
+
+const DATA_SIZE: u32 = 10_0000;
+const SLOT_NUM: u32 = 1000;
+
+type V = Vec<u8>;
+
+fn insert(small_threshold: usize) {
+    let mut db = SlotDB::<V>::new(8, small_threshold, false);
+
+    (0..DATA_SIZE).for_each(|i| {
+        db.insert((i % SLOT_NUM) as u64, i.to_be_bytes().to_vec())
+            .unwrap();
+    });
+
+    db.clear();
+}
+
+fn small_threshold(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert 100k entries into 1k slots");
+    group.sample_size(10);
+
+    [4, 8, 16, 64].into_iter().for_each(|n| {
+        group.bench_function(format!("small threshold: {}", n), |b| {
+            b.iter(|| insert(black_box(n)))
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, small_threshold);
+criterion_main!(benches);
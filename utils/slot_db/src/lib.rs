@@ -4,9 +4,11 @@
 use ruc::*;
 use serde::{de, Deserialize, Serialize};
 use std::{
-    collections::{btree_set::Iter as SmallIter, BTreeSet},
+    collections::{btree_set::Iter as SmallIter, BTreeMap, BTreeSet},
     mem,
     ops::Bound,
+    result::Result as StdResult,
+    vec::IntoIter,
 };
 use vsdb::{
     basic::mapx_ord::MapxOrdIter as LargeIter, KeyEnDeOrdered, MapxOrd,
@@ -27,9 +29,26 @@ type Distance = i128;
 type PageSize = u16;
 type PageIndex = u32;
 
+// Bump this if the serialized layout of `SlotDB` changes,
+// and add the corresponding step to `upgrade_format`.
+//
+// - 0: the legacy layout, ends with the `swap_order` field
+// - 1: same as 0, with `small_threshold` and `format_version` appended
+//
+// The appended fields must have a serde default,
+// so that the instances in an older layout can still be decoded.
+const FORMAT_VERSION: u8 = 1;
+
+// The hardcoded threshold before it became configurable
+const DEFAULT_SMALL_THRESHOLD: usize = 8;
+
+fn default_small_threshold() -> usize {
+    DEFAULT_SMALL_THRESHOLD
+}
+
 /// A `Skip List` like structure,
 /// designed to support fast paged queries and indexes
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Serialize)]
 #[serde(
     bound = "T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned"
 )]
@@ -54,12 +73,114 @@ where
     // if most scenes are under the reverse mode,
     // then swap the low-level logic
     swap_order: bool,
+
+    // A `DataCtner` will be converted from `Small` to `Large`
+    // if the number of its entries exceeds this threshold
+    small_threshold: usize,
+
+    // The version of the serialized layout, see `FORMAT_VERSION`
+    format_version: u8,
+}
+
+impl<'de, T> Deserialize<'de> for SlotDB<T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut m =
+            <SlotDBWithoutDerivedFields<T> as Deserialize>::deserialize(
+                deserializer,
+            )?;
+
+        if m.format_version > FORMAT_VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported format version: {}, the latest one is {}",
+                m.format_version, FORMAT_VERSION
+            )));
+        }
+        m.upgrade_format();
+
+        Ok(Self::from(m))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    bound = "T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned"
+)]
+struct SlotDBWithoutDerivedFields<T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    data: MapxOrd<Slot, DataCtner<T>>,
+    total: EntryCnt,
+    #[serde(default)]
+    levels: Vec<Level>,
+    multiple_step: u64,
+    swap_order: bool,
+    // Missing in the legacy layout
+    #[serde(default = "default_small_threshold")]
+    small_threshold: usize,
+    // Missing in the legacy layout
+    #[serde(default)]
+    format_version: u8,
+}
+
+impl<T> SlotDBWithoutDerivedFields<T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    // Upgrade step by step from the serialized version to the latest one
+    fn upgrade_format(&mut self) {
+        while self.format_version < FORMAT_VERSION {
+            match self.format_version {
+                // Nothing to do, the appended fields
+                // have been filled with their defaults
+                0 => {}
+                _ => unreachable!(),
+            }
+            self.format_version += 1;
+        }
+    }
+}
+
+impl<T> From<SlotDBWithoutDerivedFields<T>> for SlotDB<T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    fn from(m: SlotDBWithoutDerivedFields<T>) -> Self {
+        let mut ret = Self {
+            data: m.data,
+            total: m.total,
+            levels: m.levels,
+            multiple_step: m.multiple_step,
+            small_threshold: m.small_threshold,
+            swap_order: m.swap_order,
+            format_version: m.format_version,
+        };
+
+        // The levels are derived from `data`,
+        // all paged queries will be wrong if they are missing
+        if ret.levels.is_empty() && !ret.data.is_empty() {
+            ret.rebuild_levels();
+        }
+
+        ret
+    }
 }
 
 impl<T> SlotDB<T>
 where
     T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
 {
+    ///
+    /// @param: `small_threshold`:
+    ///
+    /// The entries of a slot will be moved from memory to disk
+    /// if the number of them exceeds this threshold, default to 8
     ///
     /// @param: `swap_order`:
     ///
@@ -69,13 +190,19 @@ where
     ///
     /// Positive query usually get better performance,
     /// swap order if most cases run in the reverse mode
-    pub fn new(multiple_step: u64, swap_order: bool) -> Self {
+    pub fn new(
+        multiple_step: u64,
+        small_threshold: usize,
+        swap_order: bool,
+    ) -> Self {
         Self {
             data: MapxOrd::new(),
             total: 0,
             levels: vec![],
             multiple_step,
+            small_threshold,
             swap_order,
+            format_version: FORMAT_VERSION,
         }
     }
 
@@ -113,7 +240,7 @@ where
             .data
             .entry(&slot)
             .or_insert(DataCtner::default())
-            .insert(t)
+            .insert(t, self.small_threshold)
         {
             self.levels.iter_mut().for_each(|l| {
                 let slot_floor = slot / l.floor_base * l.floor_base;
@@ -125,6 +252,205 @@ where
         Ok(())
     }
 
+    /// Insert lots of entries at once, the levels will be rebuilt only
+    /// once at the end, much faster than calling `insert` one by one.
+    pub fn batch_insert(
+        &mut self,
+        items: impl IntoIterator<Item = (Slot, T)>,
+    ) -> Result<()> {
+        let mut items = items
+            .into_iter()
+            .map(|(slot, t)| {
+                (alt!(self.swap_order, swap_order(slot), slot), t)
+            })
+            .collect::<Vec<_>>();
+        items.sort_by_key(|(slot, _)| *slot);
+
+        let mut items = items.into_iter().peekable();
+        while let Some((slot, t)) = items.next() {
+            let mut d = self.data.get(&slot).unwrap_or_default();
+            d.insert(t, self.small_threshold);
+            while let Some((_, t)) = items.next_if(|(s, _)| *s == slot) {
+                d.insert(t, self.small_threshold);
+            }
+            self.data.insert(&slot, &d);
+        }
+
+        self.rebuild_levels();
+
+        Ok(())
+    }
+
+    /// Insert all entries of another instance into this one,
+    /// the duplicate entries will not be counted twice,
+    /// and the levels will be rebuilt only once at the end.
+    pub fn merge_from(&mut self, other: &SlotDB<T>) -> Result<()> {
+        if self.swap_order != other.swap_order {
+            return Err(eg!("incompatible `swap_order`"));
+        }
+
+        // The slots of both sides have been converted in the same way,
+        // so they can be used directly.
+        for (slot, entries) in other.data.iter() {
+            let mut d = self.data.get(&slot).unwrap_or_default();
+            entries.iter().for_each(|t| {
+                d.insert(t, self.small_threshold);
+            });
+            self.data.insert(&slot, &d);
+        }
+
+        self.rebuild_levels();
+
+        Ok(())
+    }
+
+    /// Split this instance into two ones at the `boundary`:
+    /// slots `< boundary` go to the left one,
+    /// slots `>= boundary` go to the right one,
+    /// both of them keep the `multiple_step` and the `swap_order` of this one.
+    pub fn split_at_slot(self, boundary: Slot) -> (SlotDB<T>, SlotDB<T>) {
+        let mut left = Self::new(
+            self.multiple_step,
+            self.small_threshold,
+            self.swap_order,
+        );
+        let mut right = Self::new(
+            self.multiple_step,
+            self.small_threshold,
+            self.swap_order,
+        );
+
+        // The containers are moved as a whole,
+        // the `Large` ones will not be copied entry by entry.
+        for (slot, entries) in self.data.iter() {
+            let original = alt!(self.swap_order, swap_order(slot), slot);
+            if original < boundary {
+                left.data.insert(&slot, &entries);
+            } else {
+                right.data.insert(&slot, &entries);
+            }
+        }
+
+        left.rebuild_levels();
+        right.rebuild_levels();
+
+        (left, right)
+    }
+
+    /// Recompute all levels and the total number of entries from `self.data`,
+    /// useful for repairing the index after `verify_levels` failed.
+    pub fn rebuild_levels(&mut self) {
+        self.levels.iter_mut().for_each(|l| {
+            l.data.clear();
+        });
+        self.levels.clear();
+
+        self.total = self
+            .data
+            .iter()
+            .map(|(_, entries)| entries.len() as EntryCnt)
+            .sum();
+
+        if self.data.is_empty() {
+            return;
+        }
+
+        let bottom = self.data.iter().fold(
+            Level::new(0, self.multiple_step),
+            |mut l, (slot, entries)| {
+                let slot_floor = slot / l.floor_base * l.floor_base;
+                *l.data.entry(&slot_floor).or_insert(0) +=
+                    entries.len() as EntryCnt;
+                l
+            },
+        );
+        self.levels.push(bottom);
+
+        while let Some(top) = self
+            .levels
+            .last()
+            .filter(|top| top.data.len() as u64 > self.multiple_step)
+        {
+            let newtop = top.data.iter().fold(
+                Level::new(self.levels.len() as u32, self.multiple_step),
+                |mut l, (slot, cnt)| {
+                    let slot_floor = slot / l.floor_base * l.floor_base;
+                    *l.data.entry(&slot_floor).or_insert(0) += cnt;
+                    l
+                },
+            );
+
+            // avoid endless loops when the `multiple_step` is too small
+            if newtop.data.len() >= top.data.len() {
+                break;
+            }
+
+            self.levels.push(newtop);
+        }
+    }
+
+    /// Check the total number of entries and the per-slot counts of all levels
+    /// against `self.data`(or the level below), an error describing the first
+    /// discrepancy will be returned.
+    pub fn verify_levels(&self) -> Result<()> {
+        let total = self
+            .data
+            .iter()
+            .map(|(_, entries)| entries.len() as EntryCnt)
+            .sum::<EntryCnt>();
+        if total != self.total {
+            return Err(eg!(
+                "total mismatch: recorded {}, actual {}",
+                self.total,
+                total
+            ));
+        }
+
+        let mut below = self
+            .data
+            .iter()
+            .map(|(slot, entries)| (slot, entries.len() as EntryCnt))
+            .collect::<Vec<_>>();
+
+        for (idx, l) in self.levels.iter().enumerate() {
+            let mut expected = BTreeMap::new();
+            for (slot, cnt) in below.iter().filter(|(_, cnt)| 0 < *cnt) {
+                let slot_floor = slot / l.floor_base * l.floor_base;
+                *expected.entry(slot_floor).or_insert(0) += cnt;
+            }
+
+            for (slot_floor, cnt) in expected.iter() {
+                let recorded = l.data.get(slot_floor).unwrap_or(0);
+                if recorded != *cnt {
+                    return Err(eg!(
+                        "level {}, slot floor {}: recorded {}, actual {}",
+                        idx,
+                        slot_floor,
+                        recorded,
+                        cnt
+                    ));
+                }
+            }
+
+            if let Some((slot_floor, recorded)) =
+                l.data.iter().find(|(slot_floor, cnt)| {
+                    0 < *cnt && !expected.contains_key(slot_floor)
+                })
+            {
+                return Err(eg!(
+                    "level {}, slot floor {}: recorded {}, actual 0",
+                    idx,
+                    slot_floor,
+                    recorded
+                ));
+            }
+
+            below = expected.into_iter().collect();
+        }
+
+        Ok(())
+    }
+
     pub fn remove(&mut self, mut slot: Slot, t: &T) {
         if self.swap_order {
             slot = swap_order(slot);
@@ -166,6 +492,64 @@ where
         }
     }
 
+    /// Remove all entries within `[slot_itv[0], slot_itv[1]]`(both included),
+    /// the levels will be rebuilt only once at the end,
+    /// return the number of the removed entries.
+    pub fn remove_range_by_slot(&mut self, slot_itv: [Slot; 2]) -> EntryCnt {
+        let [mut slot_min, mut slot_max] = slot_itv;
+
+        if self.swap_order {
+            (slot_min, slot_max) =
+                (swap_order(slot_max), swap_order(slot_min));
+        }
+
+        if slot_min > slot_max {
+            return 0;
+        }
+
+        let slots = self
+            .data
+            .range((Bound::Included(slot_min), Bound::Included(slot_max)))
+            .map(|(slot, entries)| (slot, entries.len() as EntryCnt))
+            .collect::<Vec<_>>();
+
+        if slots.is_empty() {
+            return 0;
+        }
+
+        let cnt = slots.iter().map(|(_, n)| n).sum();
+
+        slots.into_iter().for_each(|(slot, _)| {
+            if let Some(DataCtner::Large(mut i)) = self.data.remove(&slot) {
+                i.clear();
+            }
+        });
+
+        self.rebuild_levels();
+
+        cnt
+    }
+
+    /// Drop the empty slot containers left behind by removals,
+    /// and then rebuild all levels from `self.data`,
+    /// useful after lots of scattered removals.
+    pub fn compact(&mut self) {
+        let empty_slots = self
+            .data
+            .iter()
+            .filter(|(_, entries)| entries.is_empty())
+            .map(|(slot, _)| slot)
+            .collect::<Vec<_>>();
+
+        empty_slots.into_iter().for_each(|slot| {
+            if let Some(DataCtner::Large(mut i)) = self.data.remove(&slot) {
+                i.clear();
+            }
+        });
+
+        self.rebuild_levels();
+    }
+
     pub fn clear(&mut self) {
         self.total = 0;
         self.data.clear();
@@ -228,6 +612,254 @@ where
         )
     }
 
+    /// Cursor-based paging, no need to re-scan the leading pages.
+    ///
+    /// - `cursor == None` means starting from the first page,
+    ///   in the order specified by `reverse_order`
+    /// - otherwise the order recorded in the cursor is used,
+    ///   and `reverse_order` is ignored
+    ///
+    /// The returned cursor is `None` if the last page has been reached.
+    pub fn get_entries_by_cursor(
+        &self,
+        page_size: PageSize,
+        cursor: Option<CursorState>,
+        reverse_order: bool,
+    ) -> (Vec<T>, Option<CursorState>) {
+        if 0 == page_size {
+            return (vec![], None);
+        }
+
+        let reverse_order =
+            cursor.map(|c| c.reverse_order).unwrap_or(reverse_order);
+        let inner_reverse = reverse_order ^ self.swap_order;
+
+        // The inner slot of the last returned entry,
+        // and the number of entries to skip in it
+        let start = cursor.map(|c| {
+            let slot =
+                alt!(self.swap_order, swap_order(c.last_slot), c.last_slot);
+            (slot, c.last_entry_idx.saturating_add(1))
+        });
+
+        // Seek to the slot of the cursor directly
+        let slots: Box<dyn Iterator<Item = (Slot, DataCtner<T>)>> =
+            match (start, inner_reverse) {
+                (None, false) => Box::new(self.data.iter()),
+                (None, true) => Box::new(self.data.iter().rev()),
+                (Some((s, _)), false) => Box::new(self.data.range(s..)),
+                (Some((s, _)), true) => Box::new(self.data.range(..=s).rev()),
+            };
+
+        // One more entry is loaded to know if there are more pages
+        let limit = 1 + page_size as usize;
+        let mut entries = Vec::with_capacity(limit);
+        'x: for (slot, ctner) in slots {
+            let skip = match start {
+                Some((s, n)) if s == slot => n,
+                _ => 0,
+            };
+            let iter: Box<dyn Iterator<Item = T>> = alt!(
+                inner_reverse,
+                Box::new(ctner.iter().rev()),
+                Box::new(ctner.iter())
+            );
+            for (idx, t) in iter.enumerate().skip(skip) {
+                entries.push((slot, idx, t));
+                if limit == entries.len() {
+                    break 'x;
+                }
+            }
+        }
+
+        let has_more = limit == entries.len();
+        if has_more {
+            entries.pop();
+        }
+
+        let next =
+            entries.last().filter(|_| has_more).map(|(slot, idx, _)| {
+                CursorState {
+                    last_slot: alt!(self.swap_order, swap_order(*slot), *slot),
+                    last_entry_idx: *idx,
+                    reverse_order,
+                }
+            });
+
+        (entries.into_iter().map(|(_, _, t)| t).collect(), next)
+    }
+
+    /// Get the entry at the global `rank`(start from 0),
+    /// the result is the same as `get_entries_by_page(1, rank, reverse_order)`,
+    /// but the level index is used directly and no `Vec` is constructed.
+    pub fn get_entry_at_rank(
+        &self,
+        rank: EntryCnt,
+        reverse_order: bool,
+    ) -> Option<T> {
+        self.get_entry_at_rank_in_slot_range(
+            [Slot::MIN, Slot::MAX],
+            rank,
+            reverse_order,
+        )
+    }
+
+    /// Same as `get_entry_at_rank`, but the rank is counted within
+    /// `[slot_itv[0], slot_itv[1]]`, both bounds are included.
+    pub fn get_entry_at_rank_in_slot_range(
+        &self,
+        slot_itv: [Slot; 2],
+        rank: EntryCnt,
+        mut reverse_order: bool,
+    ) -> Option<T> {
+        let [mut slot_min, mut slot_max] = slot_itv;
+
+        if self.swap_order {
+            (slot_min, slot_max) =
+                (swap_order(slot_max), swap_order(slot_min));
+            reverse_order = !reverse_order;
+        }
+
+        if slot_max < slot_min {
+            return None;
+        }
+
+        let offset = self.distance_to_the_leftmost_slot(slot_min);
+        let cnt = self.distance_to_the_leftmost_slot(slot_max)
+            + self.slot_entry_cnt(slot_max) as Distance
+            - offset;
+        if rank as Distance >= cnt {
+            return None;
+        }
+
+        let idx =
+            alt!(reverse_order, cnt - 1 - rank as Distance, rank as Distance);
+        let (slot_start_actual, local_idx) =
+            self.get_local_skip_num((offset + idx) as EntryCnt);
+
+        self.data
+            .range((slot_start_actual, Bound::Included(slot_max)))
+            .next()
+            .and_then(|(_, entries)| entries.iter().nth(local_idx as usize))
+    }
+
+    /// Iterate over all entries in the positive slot order,
+    /// use `rev()` to get the reverse order.
+    pub fn iter(&self) -> SlotDbIter<'_, T> {
+        self.iter_by_slot_range([Slot::MIN, Slot::MAX])
+    }
+
+    /// Iterate over the entries within `[slot_itv[0], slot_itv[1]]`,
+    /// both bounds are included, the order is the same as the results of
+    /// `get_entries_by_page_slot`.
+    pub fn iter_by_slot_range(
+        &self,
+        slot_itv: [Slot; 2],
+    ) -> SlotDbIter<'_, T> {
+        let [mut slot_min, mut slot_max] = slot_itv;
+
+        if self.swap_order {
+            (slot_min, slot_max) =
+                (swap_order(slot_max), swap_order(slot_min));
+        }
+
+        SlotDbIter {
+            db: self,
+            slot_itv: alt!(
+                slot_min > slot_max,
+                None,
+                Some([slot_min, slot_max])
+            ),
+            front: None,
+            back: None,
+        }
+    }
+
+    /// Get all the non-empty slots within `[slot_itv[0], slot_itv[1]]`
+    /// (both included) in the positive order, no entries will be materialized.
+    pub fn get_slots_in_range(
+        &self,
+        slot_itv: Option<[Slot; 2]>,
+    ) -> Vec<Slot> {
+        let mut ret = self.slots_in_range(slot_itv).collect::<Vec<_>>();
+        if self.swap_order {
+            ret.reverse();
+        }
+        ret
+    }
+
+    /// Count the non-empty slots within `[slot_itv[0], slot_itv[1]]`
+    /// (both included), no entries will be materialized.
+    pub fn get_slot_count_in_range(&self, slot_itv: Option<[Slot; 2]>) -> u64 {
+        self.slots_in_range(slot_itv).count() as u64
+    }
+
+    /// Get the number of entries within a single slot,
+    /// no entries will be materialized.
+    pub fn get_slot_entry_count(&self, mut slot: Slot) -> usize {
+        if self.swap_order {
+            slot = swap_order(slot);
+        }
+        self.slot_entry_cnt(slot) as usize
+    }
+
+    /// Check if there are any entries within the slot.
+    pub fn slot_exists(&self, mut slot: Slot) -> bool {
+        if self.swap_order {
+            slot = swap_order(slot);
+        }
+        self.data.contains_key(&slot)
+    }
+
+    /// Get the minimum non-empty slot, `None` if the DB is empty.
+    pub fn earliest_slot(&self) -> Option<Slot> {
+        if self.swap_order {
+            self.data
+                .iter()
+                .next_back()
+                .map(|(slot, _)| swap_order(slot))
+        } else {
+            self.data.iter().next().map(|(slot, _)| slot)
+        }
+    }
+
+    /// Get the maximum non-empty slot, `None` if the DB is empty.
+    pub fn latest_slot(&self) -> Option<Slot> {
+        if self.swap_order {
+            self.data.iter().next().map(|(slot, _)| swap_order(slot))
+        } else {
+            self.data.iter().next_back().map(|(slot, _)| slot)
+        }
+    }
+
+    // The original slot values are returned,
+    // the order is reversed if the `swap_order` is true.
+    fn slots_in_range(
+        &self,
+        slot_itv: Option<[Slot; 2]>,
+    ) -> impl Iterator<Item = Slot> + '_ {
+        let [mut slot_min, mut slot_max] =
+            slot_itv.unwrap_or([Slot::MIN, Slot::MAX]);
+
+        if self.swap_order {
+            (slot_min, slot_max) =
+                (swap_order(slot_max), swap_order(slot_min));
+        }
+
+        let swap = self.swap_order;
+        (slot_min <= slot_max)
+            .then(|| {
+                self.data
+                    .range((
+                        Bound::Included(slot_min),
+                        Bound::Included(slot_max),
+                    ))
+                    .map(move |(slot, _)| alt!(swap, swap_order(slot), slot))
+            })
+            .into_iter()
+            .flatten()
+    }
+
     fn slot_entry_cnt(&self, slot: Slot) -> EntryCnt {
         self.data
             .get(&slot)
@@ -419,6 +1051,76 @@ where
         }
     }
 
+    /// Count the entries within `[slot_itv[0], slot_itv[1]]`(both included),
+    /// the level index is used, no entries will be materialized.
+    pub fn count_in_slot_range(&self, slot_itv: [Slot; 2]) -> EntryCnt {
+        let [slot_start, slot_end] = slot_itv;
+        if 0 == self.total || slot_start > slot_end {
+            return 0;
+        }
+        self.entry_cnt_within_two_slots(slot_start, slot_end)
+    }
+
+    /// Estimate the number of entries within `[slot_itv[0], slot_itv[1]]`
+    /// by only reading the coarse levels of the index.
+    ///
+    /// `confidence` should be within `[0, 1]`, the relative error of the
+    /// result is guaranteed to be no more than `1 - confidence`,
+    /// e.g. `0.95` means the result is within 5% of the exact count;
+    /// the exact count is returned if no level can meet the requirement.
+    ///
+    /// NOTE: entries are assumed to be evenly distributed
+    /// within the partially covered floors at the edges.
+    pub fn estimate_count_in_slot_range(
+        &self,
+        slot_itv: [Slot; 2],
+        confidence: f64,
+    ) -> EntryCnt {
+        let [mut slot_start, mut slot_end] = slot_itv;
+        if 0 == self.total || slot_start > slot_end {
+            return 0;
+        }
+
+        if self.swap_order {
+            (slot_start, slot_end) =
+                (swap_order(slot_end), swap_order(slot_start));
+        }
+
+        let tolerance = 1.0 - confidence.clamp(0.0, 1.0);
+
+        for l in self.levels.iter().rev() {
+            let fb = l.floor_base;
+            let floor_start = slot_start / fb * fb;
+            let floor_end = slot_end / fb * fb;
+            if floor_start == floor_end {
+                continue;
+            }
+
+            let inner = l
+                .data
+                .range((
+                    Bound::Excluded(floor_start),
+                    Bound::Excluded(floor_end),
+                ))
+                .map(|(_, cnt)| cnt)
+                .sum::<EntryCnt>();
+            let cnt_start = l.data.get(&floor_start).unwrap_or(0);
+            let cnt_end = l.data.get(&floor_end).unwrap_or(0);
+
+            // The exact count is within `[inner, inner + cnt_start + cnt_end]`
+            if ((cnt_start + cnt_end) as f64) <= tolerance * inner as f64 {
+                let part = |cnt: EntryCnt, covered: Slot| {
+                    (cnt as u128 * covered as u128 / fb as u128) as EntryCnt
+                };
+                return inner
+                    + part(cnt_start, floor_start + fb - slot_start)
+                    + part(cnt_end, slot_end - floor_end + 1);
+            }
+        }
+
+        self.count_in_slot_range(slot_itv)
+    }
+
     pub fn total_by_slot(
         &self,
         slot_start: Option<Slot>,
@@ -437,6 +1139,33 @@ where
     pub fn total(&self) -> EntryCnt {
         self.total_by_slot(None, None)
     }
+
+    /// The number of pages needed to hold all entries,
+    /// a `page_size` of zero always gets zero pages.
+    pub fn page_count(&self, page_size: PageSize) -> PageIndex {
+        Self::entry_cnt_to_page_cnt(self.total(), page_size)
+    }
+
+    /// The number of pages needed to hold the entries within `slot_itv`,
+    /// a `page_size` of zero always gets zero pages.
+    pub fn page_count_in_slot_range(
+        &self,
+        slot_itv: [Slot; 2],
+        page_size: PageSize,
+    ) -> PageIndex {
+        Self::entry_cnt_to_page_cnt(
+            self.count_in_slot_range(slot_itv),
+            page_size,
+        )
+    }
+
+    fn entry_cnt_to_page_cnt(cnt: EntryCnt, page_size: PageSize) -> PageIndex {
+        if 0 == page_size {
+            return 0;
+        }
+        let n = cnt.div_ceil(page_size as EntryCnt);
+        PageIndex::try_from(n).unwrap_or(PageIndex::MAX)
+    }
 }
 
 impl<T> Default for SlotDB<T>
@@ -444,7 +1173,7 @@ where
     T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
 {
     fn default() -> Self {
-        Self::new(8, false)
+        Self::new(8, DEFAULT_SMALL_THRESHOLD, false)
     }
 }
 
@@ -475,9 +1204,9 @@ where
         0 == self.len()
     }
 
-    fn insert(&mut self, t: T) -> bool {
+    fn insert(&mut self, t: T, small_threshold: usize) -> bool {
         if let Self::Small(i) = self {
-            if i.len() > 8 {
+            if i.len() > small_threshold {
                 *self = Self::Large(i.iter().fold(
                     MapxOrd::new(),
                     |mut acc, t| {
@@ -551,6 +1280,116 @@ where
     }
 }
 
+/// The position of the last entry returned by
+/// `SlotDB::get_entries_by_cursor`, used to load the next page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CursorState {
+    /// The slot of the last returned entry
+    pub last_slot: Slot,
+    /// The index of the last returned entry within its slot
+    pub last_entry_idx: usize,
+    /// The order of the paging
+    pub reverse_order: bool,
+}
+
+/// Created by `SlotDB::iter` or `SlotDB::iter_by_slot_range`,
+/// entries are loaded slot by slot.
+pub struct SlotDbIter<'a, T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    db: &'a SlotDB<T>,
+
+    // The inner slots that have not been loaded, both included,
+    // `None` means all slots have been loaded
+    slot_itv: Option<[Slot; 2]>,
+
+    front: Option<IntoIter<T>>,
+    back: Option<IntoIter<T>>,
+}
+
+impl<'a, T> SlotDbIter<'a, T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    // Load the entries of the leftmost(or rightmost) unloaded slot
+    fn load_slot(&mut self, from_back: bool) -> Option<IntoIter<T>> {
+        let [slot_min, slot_max] = self.slot_itv?;
+
+        let mut hdr = self.db.data.range(slot_min..=slot_max);
+        let (slot, entries) =
+            if let Some(i) = alt!(from_back, hdr.next_back(), hdr.next()) {
+                i
+            } else {
+                self.slot_itv = None;
+                return None;
+            };
+
+        self.slot_itv = if from_back {
+            alt!(slot == slot_min, None, Some([slot_min, slot - 1]))
+        } else {
+            alt!(slot == slot_max, None, Some([slot + 1, slot_max]))
+        };
+
+        Some(entries.iter().collect::<Vec<_>>().into_iter())
+    }
+
+    // Get entries in the inner(not swapped) positive order
+    fn inner_next(&mut self) -> Option<T> {
+        loop {
+            if let Some(t) = self.front.as_mut().and_then(|i| i.next()) {
+                return Some(t);
+            }
+            if let Some(i) = self.load_slot(false) {
+                self.front = Some(i);
+            } else {
+                return self.back.as_mut().and_then(|i| i.next());
+            }
+        }
+    }
+
+    // Get entries in the inner(not swapped) reverse order
+    fn inner_next_back(&mut self) -> Option<T> {
+        loop {
+            if let Some(t) = self.back.as_mut().and_then(|i| i.next_back()) {
+                return Some(t);
+            }
+            if let Some(i) = self.load_slot(true) {
+                self.back = Some(i);
+            } else {
+                return self.front.as_mut().and_then(|i| i.next_back());
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for SlotDbIter<'a, T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.db.swap_order {
+            self.inner_next_back()
+        } else {
+            self.inner_next()
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for SlotDbIter<'a, T>
+where
+    T: Clone + Ord + KeyEnDeOrdered + Serialize + de::DeserializeOwned,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.db.swap_order {
+            self.inner_next()
+        } else {
+            self.inner_next_back()
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct Level {
     floor_base: u64,
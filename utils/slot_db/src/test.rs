@@ -1,5 +1,7 @@
 use super::*;
 use rand::random;
+use std::{env, fs};
+use vsdb::ValueEnDe;
 
 #[test]
 fn workflow_normal() {
@@ -16,7 +18,7 @@ fn workflow_swap_order() {
 }
 
 fn slot_db(mn: u64, swap_order: bool) {
-    let mut db = SlotDB::new(mn, swap_order);
+    let mut db = SlotDB::new(mn, 8, swap_order);
     let mut test_db = testdb::TestDB::default();
 
     let mut slot_min = Slot::MAX;
@@ -139,7 +141,7 @@ const fn siz() -> u64 {
 
 #[test]
 fn data_container() {
-    let mut db = SlotDB::new(16, false);
+    let mut db = SlotDB::new(16, 8, false);
 
     db.insert(0, 0).unwrap();
 
@@ -164,6 +166,816 @@ fn data_container() {
     db.clear();
 }
 
+#[test]
+fn data_container_threshold() {
+    let mut db = SlotDB::new(16, 4, false);
+
+    (0..5u32).for_each(|i| {
+        db.insert(0, i).unwrap();
+    });
+    assert!(matches!(
+        db.data.iter().next().unwrap().1,
+        DataCtner::Small(_)
+    ));
+
+    db.insert(0, 5).unwrap();
+    assert!(matches!(
+        db.data.iter().next().unwrap().1,
+        DataCtner::Large(_)
+    ));
+    assert_eq!(db.data.first().unwrap().1.len(), 6);
+
+    db.clear();
+}
+
+#[test]
+fn count_in_slot_range() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        assert_eq!(0, db.count_in_slot_range([0, Slot::MAX]));
+
+        (0..2000u64).for_each(|i| {
+            db.insert(random::<u64>() % 500, i).unwrap();
+        });
+
+        for _ in 0..100 {
+            let smin = random::<u64>() % 600;
+            let smax = smin + random::<u64>() % 600;
+
+            let cnt = db.count_in_slot_range([smin, smax]);
+            let entries = db.get_entries_by_page_slot(
+                Some(smin),
+                Some(smax),
+                PageSize::MAX,
+                0,
+                false,
+            );
+            assert_eq!(cnt as usize, entries.len());
+
+            if smin < smax {
+                assert_eq!(0, db.count_in_slot_range([smax, smin]));
+            }
+        }
+
+        assert_eq!(db.total(), db.count_in_slot_range([0, Slot::MAX]));
+
+        db.clear();
+    });
+}
+
+#[test]
+fn estimate_count_in_slot_range() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        assert_eq!(0, db.estimate_count_in_slot_range([0, Slot::MAX], 0.95));
+
+        db.batch_insert((0..siz()).map(|i| (random::<u64>() % siz(), i)))
+            .unwrap();
+
+        for _ in 0..100 {
+            let smin = random::<u64>() % siz();
+            let smax = smin + random::<u64>() % siz();
+
+            let exact = db.count_in_slot_range([smin, smax]);
+            let est = db.estimate_count_in_slot_range([smin, smax], 0.95);
+            assert!(exact.abs_diff(est) as f64 <= 0.05 * exact as f64);
+
+            assert_eq!(
+                exact,
+                db.estimate_count_in_slot_range([smin, smax], 1.0)
+            );
+
+            if smin < smax {
+                assert_eq!(
+                    0,
+                    db.estimate_count_in_slot_range([smax, smin], 0.95)
+                );
+            }
+        }
+
+        let est = db.estimate_count_in_slot_range([0, Slot::MAX], 0.95);
+        assert!(db.total().abs_diff(est) as f64 <= 0.05 * db.total() as f64);
+
+        db.clear();
+    });
+}
+
+#[test]
+fn page_count() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        assert_eq!(0, db.page_count(10));
+        assert_eq!(0, db.page_count_in_slot_range([0, Slot::MAX], 10));
+
+        (0..95u64).for_each(|i| {
+            db.insert(i, i).unwrap();
+        });
+        assert_eq!(10, db.page_count(10));
+        assert_eq!(0, db.page_count(0));
+
+        (95..100u64).for_each(|i| {
+            db.insert(i, i).unwrap();
+        });
+        assert_eq!(10, db.page_count(10));
+
+        db.insert(100, 100).unwrap();
+        assert_eq!(11, db.page_count(10));
+        assert_eq!(1, db.page_count(PageSize::MAX));
+
+        assert_eq!(3, db.page_count_in_slot_range([0, 20], 10));
+        assert_eq!(2, db.page_count_in_slot_range([10, 29], 10));
+        assert_eq!(0, db.page_count_in_slot_range([29, 10], 10));
+        assert_eq!(0, db.page_count_in_slot_range([0, 20], 0));
+
+        db.clear();
+    });
+}
+
+#[test]
+fn iter() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        assert!(db.iter().next().is_none());
+
+        (0..2000u64).for_each(|i| {
+            db.insert(random::<u64>() % 500, i).unwrap();
+        });
+
+        assert_eq!(
+            db.get_entries_by_page(PageSize::MAX, 0, false),
+            db.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            db.get_entries_by_page(PageSize::MAX, 0, true),
+            db.iter().rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            db.get_entries_by_page(10, 3, false),
+            db.iter().skip(30).take(10).collect::<Vec<_>>()
+        );
+
+        for _ in 0..100 {
+            let smin = random::<u64>() % 600;
+            let smax = smin + random::<u64>() % 600;
+
+            let entries = db.get_entries_by_page_slot(
+                Some(smin),
+                Some(smax),
+                PageSize::MAX,
+                0,
+                false,
+            );
+            assert_eq!(
+                entries,
+                db.iter_by_slot_range([smin, smax]).collect::<Vec<_>>()
+            );
+
+            // Consume from both ends alternately
+            let mut hdr = db.iter_by_slot_range([smin, smax]);
+            let mut head = vec![];
+            let mut tail = vec![];
+            loop {
+                if let Some(i) = hdr.next() {
+                    head.push(i);
+                } else {
+                    break;
+                }
+                if let Some(i) = hdr.next_back() {
+                    tail.push(i);
+                } else {
+                    break;
+                }
+            }
+            head.extend(tail.into_iter().rev());
+            assert_eq!(entries, head);
+
+            if smin < smax {
+                assert!(db.iter_by_slot_range([smax, smin]).next().is_none());
+            }
+        }
+
+        db.clear();
+    });
+}
+
+#[test]
+fn batch_insert() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+        let mut db_batch = SlotDB::new(8, 8, swap_order);
+
+        let items = (0..10_000u64)
+            .map(|i| (random::<u64>() % 1000, i))
+            .collect::<Vec<_>>();
+
+        items.iter().for_each(|(slot, i)| {
+            db.insert(*slot, *i).unwrap();
+        });
+
+        // duplicated items should be counted only once
+        db_batch.batch_insert(items[..100].iter().copied()).unwrap();
+        db_batch.batch_insert(items.iter().copied()).unwrap();
+
+        assert_eq!(db.total(), db_batch.total());
+
+        [1, 7, 64, 1000].into_iter().for_each(|page_size| {
+            [0, 1, 9, 100].into_iter().for_each(|page_index| {
+                [false, true].into_iter().for_each(|reverse| {
+                    assert_eq!(
+                        db.get_entries_by_page(page_size, page_index, reverse),
+                        db_batch.get_entries_by_page(
+                            page_size, page_index, reverse
+                        )
+                    );
+                    assert_eq!(
+                        db.get_entries_by_page_slot(
+                            Some(100),
+                            Some(300),
+                            page_size,
+                            page_index,
+                            reverse
+                        ),
+                        db_batch.get_entries_by_page_slot(
+                            Some(100),
+                            Some(300),
+                            page_size,
+                            page_index,
+                            reverse
+                        )
+                    );
+                });
+            });
+        });
+
+        // normal operations still work after the rebuilding
+        db_batch.insert(2000, 0).unwrap();
+        db_batch.remove(2000, &0);
+        assert_eq!(db.total(), db_batch.total());
+
+        db.clear();
+        db_batch.clear();
+    });
+}
+
+#[test]
+fn remove_range_by_slot() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        (0..1000u64).for_each(|slot| {
+            (0..3u64).for_each(|i| {
+                db.insert(slot, slot * 10 + i).unwrap();
+            });
+        });
+        assert_eq!(3000, db.total());
+
+        assert_eq!(0, db.remove_range_by_slot([200, 100]));
+        assert_eq!(0, db.remove_range_by_slot([2000, 3000]));
+        assert_eq!(3 * 301, db.remove_range_by_slot([100, 400]));
+        assert_eq!(3000 - 3 * 301, db.total());
+        assert_eq!(0, db.remove_range_by_slot([100, 400]));
+
+        (0..3).for_each(|page_index| {
+            [false, true].into_iter().for_each(|reverse| {
+                assert!(db
+                    .get_entries_by_page_slot(
+                        Some(100),
+                        Some(400),
+                        64,
+                        page_index,
+                        reverse
+                    )
+                    .is_empty());
+            });
+        });
+
+        // entries outside the range are intact,
+        // NOTE: the order within a slot depends on the `swap_order`
+        let mut entries =
+            db.get_entries_by_page_slot(None, Some(399), 1000, 0, false);
+        entries.sort_unstable();
+        assert_eq!(
+            (0..100u64)
+                .flat_map(|slot| (0..3u64).map(move |i| slot * 10 + i))
+                .collect::<Vec<_>>(),
+            entries
+        );
+        let mut entries = db.get_entries_by_page(99, 0, true);
+        entries.sort_unstable();
+        assert_eq!(
+            (967..1000u64)
+                .flat_map(|slot| (0..3u64).map(move |i| slot * 10 + i))
+                .collect::<Vec<_>>(),
+            entries
+        );
+        assert_eq!(3 * 599, db.total_by_slot(Some(401), None));
+
+        // the levels still work after the rebuilding
+        db.insert(200, 2000).unwrap();
+        assert_eq!(
+            vec![2000],
+            db.get_entries_by_page_slot(Some(100), Some(400), 10, 0, false)
+        );
+        assert_eq!(3000 - 3 * 301 + 1, db.total());
+
+        assert_eq!(3000 - 3 * 301 + 1, db.remove_range_by_slot([0, u64::MAX]));
+        assert_eq!(0, db.total());
+        assert!(db.get_entries_by_page(10, 0, false).is_empty());
+
+        db.clear();
+    });
+}
+
+#[test]
+fn get_slots_in_range() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+
+        [10, 20, 30, 40, 50].into_iter().for_each(|slot| {
+            db.insert(slot, slot).unwrap();
+            db.insert(slot, slot + 1).unwrap();
+        });
+        assert_eq!(10, db.total());
+
+        assert_eq!(vec![10, 20, 30, 40, 50], db.get_slots_in_range(None));
+        assert_eq!(5, db.get_slot_count_in_range(None));
+
+        assert_eq!(vec![20, 30, 40], db.get_slots_in_range(Some([20, 40])));
+        assert_eq!(3, db.get_slot_count_in_range(Some([20, 40])));
+
+        assert_eq!(vec![20, 30], db.get_slots_in_range(Some([11, 39])));
+        assert_eq!(vec![50], db.get_slots_in_range(Some([50, 50])));
+        assert!(db.get_slots_in_range(Some([51, 100])).is_empty());
+        assert!(db.get_slots_in_range(Some([40, 20])).is_empty());
+        assert_eq!(0, db.get_slot_count_in_range(Some([40, 20])));
+
+        db.remove(30, &30);
+        db.remove(30, &31);
+        assert_eq!(vec![10, 20, 40, 50], db.get_slots_in_range(None));
+        assert_eq!(4, db.get_slot_count_in_range(None));
+
+        db.clear();
+    });
+}
+
+#[test]
+fn get_slot_entry_count() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+
+        // slot `i` holds `i` entries, both small and large containers
+        (1..20u64).for_each(|slot| {
+            (0..slot).for_each(|i| {
+                db.insert(slot, slot * 100 + i).unwrap();
+            });
+        });
+
+        (1..20u64).for_each(|slot| {
+            assert_eq!(slot as usize, db.get_slot_entry_count(slot));
+            assert!(db.slot_exists(slot));
+        });
+        [0, 20, 100, Slot::MAX].into_iter().for_each(|slot| {
+            assert_eq!(0, db.get_slot_entry_count(slot));
+            assert!(!db.slot_exists(slot));
+        });
+
+        db.remove(3, &300);
+        assert_eq!(2, db.get_slot_entry_count(3));
+        db.remove(1, &100);
+        assert_eq!(0, db.get_slot_entry_count(1));
+        assert!(!db.slot_exists(1));
+
+        db.clear();
+        assert!(!db.slot_exists(19));
+    });
+}
+
+#[test]
+fn earliest_and_latest_slot() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+        assert!(db.earliest_slot().is_none());
+        assert!(db.latest_slot().is_none());
+
+        db.insert(10, 1000).unwrap();
+        assert_eq!(Some(10), db.earliest_slot());
+        assert_eq!(Some(10), db.latest_slot());
+
+        (20..30u64).chain(3..8).for_each(|slot| {
+            db.insert(slot, slot * 100).unwrap();
+        });
+        assert_eq!(Some(3), db.earliest_slot());
+        assert_eq!(Some(29), db.latest_slot());
+
+        db.remove(3, &300);
+        db.remove(29, &2900);
+        assert_eq!(Some(4), db.earliest_slot());
+        assert_eq!(Some(28), db.latest_slot());
+
+        db.clear();
+        assert!(db.earliest_slot().is_none());
+        assert!(db.latest_slot().is_none());
+    });
+}
+
+#[test]
+fn split_at_slot() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+        (0..1000u64).for_each(|i| {
+            db.insert(i / 10, i).unwrap();
+        });
+        let total = db.total();
+
+        let (left, right) = db.split_at_slot(40);
+        assert_eq!(total, left.total() + right.total());
+        assert_eq!(400, left.total());
+        assert_eq!(600, right.total());
+
+        assert_eq!(Some(0), left.earliest_slot());
+        assert_eq!(Some(39), left.latest_slot());
+        assert_eq!(Some(40), right.earliest_slot());
+        assert_eq!(Some(99), right.latest_slot());
+
+        let expected = (0..400u64).collect::<Vec<_>>();
+        assert_eq!(expected, left.get_entries_by_page(400, 0, false));
+        assert_eq!(
+            (0..10u64).collect::<Vec<_>>(),
+            left.get_entries_by_page(10, 0, false)
+        );
+        assert_eq!(
+            (390..400u64).rev().collect::<Vec<_>>(),
+            left.get_entries_by_page(10, 0, true)
+        );
+
+        let expected = (400..1000u64).collect::<Vec<_>>();
+        assert_eq!(expected, right.get_entries_by_page(600, 0, false));
+        assert_eq!(
+            (410..420u64).collect::<Vec<_>>(),
+            right.get_entries_by_page(10, 1, false)
+        );
+        assert_eq!(
+            (980..990u64).rev().collect::<Vec<_>>(),
+            right.get_entries_by_page(10, 1, true)
+        );
+    });
+}
+
+#[test]
+fn verify_and_rebuild_levels() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(4, 4, swap_order);
+        (0..1000u64).for_each(|i| {
+            db.insert(i / 3, i).unwrap();
+        });
+        (0..1000u64).filter(|i| 0 == i % 7).for_each(|i| {
+            db.remove(i / 3, &i);
+        });
+        assert!(1 < db.levels.len());
+        db.verify_levels().unwrap();
+
+        let expected = db.get_entries_by_page(100, 2, false);
+
+        // corrupt a counter of the bottom level
+        let (slot_floor, cnt) = db.levels[0].data.iter().next().unwrap();
+        db.levels[0].data.insert(&slot_floor, &(cnt + 1));
+        assert!(db.verify_levels().is_err());
+        db.rebuild_levels();
+        db.verify_levels().unwrap();
+        assert_eq!(expected, db.get_entries_by_page(100, 2, false));
+
+        // a redundant counter on the top level
+        let top = db.levels.len() - 1;
+        let floor_base = db.levels[top].floor_base;
+        let slot_floor = if swap_order {
+            db.levels[top].data.iter().next().unwrap().0 - floor_base
+        } else {
+            db.levels[top].data.iter().last().unwrap().0 + floor_base
+        };
+        db.levels[top].data.insert(&slot_floor, &1);
+        assert!(db.verify_levels().is_err());
+        db.rebuild_levels();
+        db.verify_levels().unwrap();
+
+        // a wrong total
+        db.total += 1;
+        assert!(db.verify_levels().is_err());
+        db.rebuild_levels();
+        db.verify_levels().unwrap();
+        assert_eq!(expected, db.get_entries_by_page(100, 2, false));
+    });
+}
+
+#[test]
+fn compact() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+
+        (0..10_000u64).for_each(|i| {
+            db.insert(i / 5, i).unwrap();
+        });
+        (0..10_000u64).filter(|i| 0 != i % 10).for_each(|i| {
+            db.remove(i / 5, &i);
+        });
+
+        db.compact();
+        assert_eq!(1000, db.total());
+
+        let expected = (0..1000u64).map(|i| i * 10).collect::<Vec<_>>();
+        assert_eq!(expected, db.get_entries_by_page(1000, 0, false));
+        assert!(db.get_entries_by_page(1000, 1, false).is_empty());
+        assert_eq!(
+            expected.iter().rev().copied().collect::<Vec<_>>(),
+            db.get_entries_by_page(1000, 0, true)
+        );
+        assert_eq!(
+            expected[100..200].to_vec(),
+            db.get_entries_by_page(100, 1, false)
+        );
+
+        // compacting a compacted instance changes nothing
+        db.compact();
+        assert_eq!(expected, db.get_entries_by_page(1000, 0, false));
+    });
+}
+
+#[test]
+fn merge_from() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 8, swap_order);
+        let mut other = SlotDB::new(8, 8, swap_order);
+        let mut test_db = testdb::TestDB::default();
+
+        // non-overlapping
+        (0..500).for_each(|i| {
+            db.insert(i, i).unwrap();
+            test_db.insert(i, i);
+        });
+        (500..1000).for_each(|i| {
+            other.insert(i, i).unwrap();
+            test_db.insert(i, i);
+        });
+
+        db.merge_from(&other).unwrap();
+        assert_eq!(1000, db.total());
+        assert_eq!(500, other.total());
+        assert_queryable(&db, &test_db, 0, 999);
+
+        // partially overlapping, the duplicate entries are ignored
+        let mut other = SlotDB::new(8, 8, swap_order);
+        (900..1200).for_each(|i| {
+            other.insert(i, i).unwrap();
+            if i >= 1000 {
+                test_db.insert(i, i);
+            }
+        });
+
+        db.merge_from(&other).unwrap();
+        assert_eq!(1200, db.total());
+        assert_queryable(&db, &test_db, 0, 1199);
+
+        // same slots with different entries
+        let mut other = SlotDB::new(8, 8, swap_order);
+        (0..20).for_each(|i| {
+            other.insert(i, i + 10000).unwrap();
+        });
+        db.merge_from(&other).unwrap();
+        assert_eq!(1220, db.total());
+        assert_eq!(1220, db.iter().count());
+        assert_eq!(
+            2,
+            db.get_entries_by_page_slot(Some(0), Some(0), 10, 0, false)
+                .len()
+        );
+
+        let other = SlotDB::<u64>::new(8, 8, !swap_order);
+        assert!(db.merge_from(&other).is_err());
+
+        db.clear();
+    });
+}
+
+#[test]
+fn get_entries_by_cursor() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+
+        // several entries in one slot, both small and large containers
+        (0..1000u64).for_each(|i| {
+            db.insert(i % 97, i).unwrap();
+        });
+
+        [false, true].into_iter().for_each(|reverse_order| {
+            [1, 7, 10, 1000, 2000].into_iter().for_each(|page_size| {
+                let mut page_index = 0;
+                let mut cursor = None;
+                loop {
+                    let (entries, next) = db.get_entries_by_cursor(
+                        page_size,
+                        cursor,
+                        reverse_order,
+                    );
+                    let expected = db.get_entries_by_page(
+                        page_size,
+                        page_index,
+                        reverse_order,
+                    );
+                    assert_eq!(expected, entries);
+
+                    page_index += 1;
+                    if next.is_none() {
+                        break;
+                    }
+                    cursor = next;
+                }
+
+                assert!(db
+                    .get_entries_by_page(page_size, page_index, reverse_order)
+                    .is_empty());
+                assert_eq!(
+                    (1000 + page_size as u32 - 1) / page_size as u32,
+                    page_index
+                );
+            });
+        });
+
+        assert_eq!((vec![], None), db.get_entries_by_cursor(0, None, false));
+
+        db.clear();
+        assert_eq!((vec![], None), db.get_entries_by_cursor(10, None, false));
+    });
+}
+
+#[test]
+fn get_entry_at_rank() {
+    [false, true].into_iter().for_each(|swap_order| {
+        let mut db = SlotDB::new(8, 4, swap_order);
+        assert!(db.get_entry_at_rank(0, false).is_none());
+
+        (0..1000u64).for_each(|i| {
+            db.insert(i % 97, i).unwrap();
+        });
+        let total = db.total();
+
+        assert_eq!(db.iter().next(), db.get_entry_at_rank(0, false));
+        assert_eq!(
+            db.iter().next_back(),
+            db.get_entry_at_rank(total - 1, false)
+        );
+        assert!(db.get_entry_at_rank(total, false).is_none());
+
+        [false, true].into_iter().for_each(|reverse_order| {
+            (0..total).step_by(7).for_each(|rank| {
+                assert_eq!(
+                    db.get_entries_by_page(1, rank as u32, reverse_order),
+                    db.get_entry_at_rank(rank, reverse_order)
+                        .into_iter()
+                        .collect::<Vec<_>>()
+                );
+            });
+
+            let itv = [10, 20];
+            let cnt = db.count_in_slot_range(itv);
+            (0..=cnt).for_each(|rank| {
+                assert_eq!(
+                    db.get_entries_by_page_slot(
+                        Some(itv[0]),
+                        Some(itv[1]),
+                        1,
+                        rank as u32,
+                        reverse_order
+                    ),
+                    db.get_entry_at_rank_in_slot_range(
+                        itv,
+                        rank,
+                        reverse_order
+                    )
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                );
+            });
+        });
+
+        assert!(db
+            .get_entry_at_rank_in_slot_range([20, 10], 0, false)
+            .is_none());
+    });
+}
+
+#[test]
+fn serde_reload() {
+    [false, true].into_iter().for_each(|swap_order| {
+        serde_reload_inner(16, swap_order, false);
+        serde_reload_inner(16, swap_order, true);
+    });
+}
+
+fn serde_reload_inner(mn: u64, swap_order: bool, drop_levels: bool) {
+    let mut db = SlotDB::new(mn, 8, swap_order);
+    let mut test_db = testdb::TestDB::default();
+
+    (0..siz()).for_each(|i| {
+        db.insert(i, i).unwrap();
+        test_db.insert(i, i);
+    });
+
+    // Simulate an instance whose levels are lost
+    if drop_levels {
+        mem::take(&mut db.levels).into_iter().for_each(|mut l| {
+            l.data.clear();
+        });
+    }
+
+    let path = env::temp_dir().join(format!("slot_db_{}", random::<u128>()));
+    fs::write(&path, ValueEnDe::encode(&db)).unwrap();
+    drop(db);
+
+    let mut db =
+        <SlotDB<u64> as ValueEnDe>::decode(&fs::read(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(!db.levels.is_empty());
+    assert_eq!(siz(), db.total());
+    assert_queryable(&db, &test_db, 0, siz() - 1);
+
+    db.clear();
+}
+
+// `bcs` can not skip the missing fields
+#[cfg(not(feature = "bcs_codec"))]
+#[test]
+fn serde_legacy_layout() {
+    let mut db = SlotDB::new(8, 4, false);
+    (0..100u64).for_each(|i| {
+        db.insert(i % 10, i).unwrap();
+    });
+
+    let mut db =
+        <SlotDB<u64> as ValueEnDe>::decode(&encode_in_legacy_layout(&mut db))
+            .unwrap();
+    assert_eq!(DEFAULT_SMALL_THRESHOLD, db.small_threshold);
+    assert_eq!(100, db.total());
+    assert_eq!(50, db.count_in_slot_range([0, 4]));
+
+    db.insert(0, 100).unwrap();
+    assert_eq!(11, db.get_slot_entry_count(0));
+
+    db.clear();
+}
+
+// Encode in the layout that has no `small_threshold` and `format_version`
+#[cfg(not(feature = "bcs_codec"))]
+fn encode_in_legacy_layout(db: &mut SlotDB<u64>) -> Vec<u8> {
+    #[derive(Serialize)]
+    struct Legacy {
+        data: MapxOrd<Slot, DataCtner<u64>>,
+        total: EntryCnt,
+        levels: Vec<Level>,
+        multiple_step: u64,
+        swap_order: bool,
+    }
+
+    Legacy {
+        data: unsafe { db.data.shadow() },
+        total: db.total,
+        levels: mem::take(&mut db.levels),
+        multiple_step: db.multiple_step,
+        swap_order: db.swap_order,
+    }
+    .encode()
+}
+
+#[test]
+fn serde_format_version() {
+    let mut db = SlotDB::new(8, 8, false);
+    (0..100u64).for_each(|i| {
+        db.insert(i, i).unwrap();
+    });
+    assert_eq!(FORMAT_VERSION, db.format_version);
+
+    // Written in the legacy layout, without the version field
+    #[cfg(not(feature = "bcs_codec"))]
+    {
+        db = <SlotDB<u64> as ValueEnDe>::decode(&encode_in_legacy_layout(
+            &mut db,
+        ))
+        .unwrap();
+        assert_eq!(FORMAT_VERSION, db.format_version);
+        assert_eq!(100, db.total());
+        assert_eq!(50, db.count_in_slot_range([0, 49]));
+    }
+
+    // Created by a newer release
+    db.format_version = FORMAT_VERSION + 1;
+    assert!(<SlotDB<u64> as ValueEnDe>::decode(&db.encode()).is_err());
+
+    db.clear();
+}
+
 mod testdb {
     use super::*;
     use std::{
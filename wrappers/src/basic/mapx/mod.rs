@@ -115,11 +115,16 @@ where
         self.inner.contains_key(key.encode())
     }
 
+    /// NOTE: no iterating happens here, the length is a counter
+    /// maintained by the underlying engine, it is increased when a new key is inserted
+    /// and decreased when an existing key is removed.
+    /// The counter is stored along with the data, so it survives reloading.
     #[inline(always)]
     pub fn len(&self) -> usize {
         self.inner.len()
     }
 
+    /// Same cost as [`len`](Self::len).
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
@@ -40,6 +40,27 @@ fn test_len() {
     assert_eq!(0, hdr.len());
 }
 
+#[test]
+fn test_len_reloaded() {
+    let cnt = 10_000;
+    let mut hdr: Mapx<usize, usize> = Mapx::new();
+    (0..cnt).for_each(|i| {
+        assert!(hdr.insert(&i, &i).is_none());
+    });
+    (0..cnt).step_by(2).for_each(|i| {
+        assert!(hdr.remove(&i).is_some());
+    });
+    // removing absent keys changes nothing
+    (0..cnt).step_by(2).for_each(|i| {
+        assert!(hdr.remove(&i).is_none());
+    });
+    assert_eq!(cnt / 2, hdr.len());
+
+    let reloaded = pnk!(<Mapx<usize, usize> as ValueEnDe>::decode(&hdr.encode()));
+    assert_eq!(cnt / 2, reloaded.len());
+    assert!(!reloaded.is_empty());
+}
+
 #[test]
 fn xx_test_valueende() {
     let cnt = 100;
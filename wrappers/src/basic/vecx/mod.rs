@@ -40,7 +40,12 @@ use crate::{
 };
 use ruc::*;
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, cmp::Ordering};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+    result::Result as StdResult,
+};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 #[serde(bound = "")]
@@ -182,6 +187,61 @@ impl<T: ValueEnDe> Vecx<T> {
         panic!("out of index");
     }
 
+    /// Remove the specified range from the vector in bulk,
+    /// and return all removed elements as an iterator.
+    ///
+    /// Panics like `Vec::drain` if the range is out of index.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> VecxDrainIter<T> {
+        let len = self.len();
+        let (start, end) = self.resolve_range(range);
+
+        let drained = (start as u64..end as u64)
+            .map(|i| self.inner.remove(i.to_be_bytes()).unwrap())
+            .collect::<Vec<_>>();
+
+        if !drained.is_empty() {
+            let cnt = drained.len() as u64;
+            let shadow = unsafe { self.inner.shadow() };
+            shadow
+                .range(Cow::Borrowed(&(end as u64).to_be_bytes()[..])..)
+                .for_each(|(i, v)| {
+                    self.inner
+                        .insert((crate::parse_int!(i, u64) - cnt).to_be_bytes(), &v);
+                });
+            (len as u64 - cnt..len as u64).for_each(|i| {
+                self.inner.remove(i.to_be_bytes());
+            });
+        }
+
+        VecxDrainIter(drained.into_iter())
+    }
+
+    // Convert the `range` to `[start, end)`,
+    // panics like `Vec::drain` if the range is out of index.
+    fn resolve_range(&self, range: impl RangeBounds<usize>) -> (usize, usize) {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.checked_add(1).expect("out of index"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.checked_add(1).expect("out of index"),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => len,
+        };
+        if start > end {
+            panic!(
+                "out of index: range starts at {} but ends at {}",
+                start, end
+            );
+        }
+        if end > len {
+            panic!("out of index: range end {} exceeds the length {}", end, len);
+        }
+        (start, end)
+    }
+
     #[inline(always)]
     pub fn update(&mut self, idx: usize, v: &T) -> Option<T> {
         if idx < self.len() {
@@ -190,22 +250,427 @@ impl<T: ValueEnDe> Vecx<T> {
         panic!("out of index");
     }
 
+    /// Same as `slice::fill`, overwrite all the elements with `value`,
+    /// the length keeps unchanged.
+    #[inline(always)]
+    pub fn fill(&mut self, value: T) {
+        (0..self.len()).for_each(|i| {
+            self.update(i, &value);
+        });
+    }
+
+    /// Same as `slice::fill_with`, `f` is called once for each position,
+    /// from the first one to the last one.
+    #[inline(always)]
+    pub fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        (0..self.len()).for_each(|i| {
+            self.update(i, &f());
+        });
+    }
+
+    /// Same as `Vec::retain`, only the elements for which `f` returns `true`
+    /// are kept, and their original order is preserved.
+    #[inline(always)]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|v| f(v))
+    }
+
+    /// Same as `Vec::retain_mut`, the changes made by `f` are written back
+    /// to the kept elements.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        let len = self.len() as u64;
+        let mut cnt = 0u64;
+
+        let shadow = unsafe { self.inner.shadow() };
+        shadow.iter().for_each(|(_, mut v)| {
+            if f(&mut v) {
+                self.inner.insert(cnt.to_be_bytes(), &v);
+                cnt += 1;
+            }
+        });
+
+        (cnt..len).for_each(|i| {
+            self.inner.remove(i.to_be_bytes());
+        });
+    }
+
+    /// Same as `Vec::dedup`, consecutive equal elements are removed.
+    #[inline(always)]
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Same as `Vec::dedup_by_key`, consecutive elements
+    /// that resolve to the same key are removed.
+    #[inline(always)]
+    pub fn dedup_by_key<B: PartialEq, F: FnMut(&T) -> B>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    // Every kept element is compared with the previous kept one,
+    // and then shifted down to the tail of the kept part.
+    fn dedup_by<F: FnMut(&T, &T) -> bool>(&mut self, mut same_bucket: F) {
+        let len = self.len() as u64;
+        let mut cnt = 0u64;
+        let mut prev: Option<T> = None;
+
+        let shadow = unsafe { self.inner.shadow() };
+        shadow.iter().for_each(|(_, v)| {
+            if prev.as_ref().map(|p| same_bucket(p, &v)).unwrap_or(false) {
+                return;
+            }
+            self.inner.insert(cnt.to_be_bytes(), &v);
+            cnt += 1;
+            prev = Some(v);
+        });
+
+        (cnt..len).for_each(|i| {
+            self.inner.remove(i.to_be_bytes());
+        });
+    }
+
+    /// Same as `slice::sort_by`, the sort is stable.
+    ///
+    /// NOTE: all the elements will be loaded into memory for comparison,
+    /// so this is O(n) in memory.
+    #[inline(always)]
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.sort_with(compare, true)
+    }
+
+    /// Same as `slice::sort_unstable_by`, the sort is not stable.
+    ///
+    /// NOTE: all the elements will be loaded into memory for comparison,
+    /// so this is O(n) in memory.
+    #[inline(always)]
+    pub fn sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.sort_with(compare, false)
+    }
+
+    // The indices are sorted in memory,
+    // and only the elements that have been moved are written back.
+    fn sort_with<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F, stable: bool) {
+        let values = self.iter().collect::<Vec<_>>();
+
+        let mut idxs = (0..values.len()).collect::<Vec<_>>();
+        let f = |a: &usize, b: &usize| compare(&values[*a], &values[*b]);
+        alt!(stable, idxs.sort_by(f), idxs.sort_unstable_by(f));
+
+        idxs.into_iter().enumerate().for_each(|(pos, idx)| {
+            if pos != idx {
+                self.update(pos, &values[idx]);
+            }
+        });
+    }
+
+    /// Same as `slice::rotate_left`.
+    ///
+    /// NOTE: all the elements will be loaded into memory,
+    /// so this is O(n) in memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the length.
+    #[inline(always)]
+    pub fn rotate_left(&mut self, mid: usize) {
+        self.rotate_with(mid, |values| values.rotate_left(mid))
+    }
+
+    /// Same as `slice::rotate_right`.
+    ///
+    /// NOTE: all the elements will be loaded into memory,
+    /// so this is O(n) in memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is greater than the length.
+    #[inline(always)]
+    pub fn rotate_right(&mut self, k: usize) {
+        self.rotate_with(k, |values| values.rotate_right(k))
+    }
+
+    // Every element is moved unless the rotation is a no-op,
+    // so all of them are written back.
+    fn rotate_with<F: FnOnce(&mut [T])>(&mut self, n: usize, rotate: F) {
+        let len = self.len();
+        assert!(n <= len, "out of index");
+        alt!(0 == n || len == n, return);
+
+        let mut values = self.iter().collect::<Vec<_>>();
+        rotate(&mut values);
+        values.iter().enumerate().for_each(|(i, v)| {
+            self.update(i, v);
+        });
+    }
+
+    /// Merge two sorted `Vecx` into a new sorted one in O(n + m) time,
+    /// elements are read one by one, neither side will be fully loaded into memory.
+    ///
+    /// NOTE: the caller is responsible for keeping both inputs sorted.
+    #[inline(always)]
+    pub fn merge_sorted(a: Vecx<T>, b: Vecx<T>) -> Vecx<T>
+    where
+        T: Ord,
+    {
+        Self::merge_sorted_by(a, b, |x, y| x.cmp(y))
+    }
+
+    /// Same as `merge_sorted`, but with a custom comparator,
+    /// equal elements from `a` are placed before those from `b`.
+    pub fn merge_sorted_by<F>(a: Vecx<T>, b: Vecx<T>, mut f: F) -> Vecx<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut ret = Vecx::new();
+
+        let (mut i, mut j) = (0, 0);
+        let (mut x, mut y) = (a.get(0), b.get(0));
+        loop {
+            match (x.as_ref(), y.as_ref()) {
+                (Some(l), Some(r)) => {
+                    if Ordering::Greater == f(l, r) {
+                        ret.push(r);
+                        j += 1;
+                        y = b.get(j);
+                    } else {
+                        ret.push(l);
+                        i += 1;
+                        x = a.get(i);
+                    }
+                }
+                (Some(l), None) => {
+                    ret.push(l);
+                    i += 1;
+                    x = a.get(i);
+                }
+                (None, Some(r)) => {
+                    ret.push(r);
+                    j += 1;
+                    y = b.get(j);
+                }
+                (None, None) => break,
+            }
+        }
+
+        ret
+    }
+
+    /// Same as `Iterator::partition`, split the elements into two new `Vecx`,
+    /// the first one holds those for which `f` returns `true`,
+    /// and the second one holds the others, the original order is preserved.
+    ///
+    /// NOTE: elements are moved one by one, nothing is fully loaded into memory.
+    pub fn partition<F: FnMut(&T) -> bool>(self, mut f: F) -> (Vecx<T>, Vecx<T>) {
+        let mut yes = Vecx::new();
+        let mut no = Vecx::new();
+
+        self.iter().for_each(|v| {
+            alt!(f(&v), yes.push(&v), no.push(&v));
+        });
+
+        (yes, no)
+    }
+
+    /// Zip two `Vecx` of the same length into a new one,
+    /// the `i`th element of the result is `(a[i], b[i])`.
+    ///
+    /// NOTE: elements are read one by one, nothing is fully loaded into memory.
+    pub fn zip_with<U: ValueEnDe>(a: &Vecx<T>, b: &Vecx<U>) -> Vecx<(T, U)>
+    where
+        (T, U): ValueEnDe,
+    {
+        assert_eq!(a.len(), b.len(), "length mismatch");
+
+        let mut ret = Vecx::new();
+        a.iter().zip(b.iter()).for_each(|v| {
+            ret.push(&v);
+        });
+
+        ret
+    }
+
+    /// Same as `partition`, but `self` is kept unchanged,
+    /// and the results are collected into memory.
+    #[inline(always)]
+    pub fn partition_ref<F: FnMut(&T) -> bool>(&self, mut f: F) -> (Vec<T>, Vec<T>) {
+        self.iter().partition(|v| f(v))
+    }
+
+    /// Same as `slice::binary_search`, only O(log n) elements will be read.
+    ///
+    /// NOTE: the caller is responsible for keeping the `Vecx` sorted.
+    #[inline(always)]
+    pub fn binary_search(&self, target: &T) -> StdResult<usize, usize>
+    where
+        T: Ord,
+    {
+        self.binary_search_by(|v| v.cmp(target))
+    }
+
+    /// Same as `slice::binary_search_by`, only O(log n) elements will be read.
+    ///
+    /// NOTE: the caller is responsible for keeping the `Vecx` sorted.
+    pub fn binary_search_by<F>(&self, mut f: F) -> StdResult<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let mut lo = 0;
+        let mut hi = self.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match f(&self.get(mid).unwrap()) {
+                Ordering::Less => lo = mid + 1,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(lo)
+    }
+
+    /// Same as `slice::binary_search_by_key`, only O(log n) elements will be read.
+    ///
+    /// NOTE: the caller is responsible for keeping the `Vecx` sorted.
+    #[inline(always)]
+    pub fn binary_search_by_key<B, F>(
+        &self,
+        key: &B,
+        mut f: F,
+    ) -> StdResult<usize, usize>
+    where
+        B: Ord,
+        F: FnMut(&T) -> B,
+    {
+        self.binary_search_by(|v| f(v).cmp(key))
+    }
+
+    /// Same as `Iterator::position`, return the index of the first element
+    /// for which the `predicate` returns `true`.
+    ///
+    /// NOTE: this is a linear scan, O(n).
+    #[inline(always)]
+    pub fn position<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> Option<usize> {
+        self.iter().position(|v| predicate(&v))
+    }
+
+    /// Same as `position`, but search from the end,
+    /// return the index of the last matched element.
+    #[inline(always)]
+    pub fn rposition<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> Option<usize> {
+        self.iter()
+            .rev()
+            .position(|v| predicate(&v))
+            .map(|n| self.len() - 1 - n)
+    }
+
+    /// Same as `Iterator::find`, return the first element
+    /// for which the `predicate` returns `true`.
+    #[inline(always)]
+    pub fn find<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> Option<T> {
+        self.iter().find(|v| predicate(v))
+    }
+
     #[inline(always)]
     pub fn iter(&self) -> VecxIter<T> {
         VecxIter(self.inner.iter())
     }
 
+    /// Mutate the elements in place, the changes on an element
+    /// will be written back when its `ValueIterMut` is dropped,
+    /// the elements that are not visited keep unchanged.
     #[inline(always)]
     pub fn iter_mut(&mut self) -> VecxIterMut<T> {
         VecxIterMut(self.inner.iter_mut())
     }
 
+    /// Consume the vector, and iterate over its elements
+    /// from the last one to the first one.
+    ///
+    /// NOTE: elements are loaded one by one, so it is cheap
+    /// to take only a few elements from the tail.
+    #[inline(always)]
+    pub fn into_iter_rev(self) -> VecxIntoIterRev<T> {
+        VecxIntoIterRev(self.into_iter_range(..))
+    }
+
+    /// Consume the vector, and iterate over the elements within the `range`.
+    ///
+    /// Panics like `Vec::drain` if the range is out of index.
+    #[inline(always)]
+    pub fn into_iter_range(
+        self,
+        range: impl RangeBounds<usize>,
+    ) -> VecxIntoIterRange<T> {
+        let (start, end) = self.resolve_range(range);
+        VecxIntoIterRange {
+            hdr: self,
+            start,
+            end,
+        }
+    }
+
+    /// Same as `slice::chunks`, but the chunks are owned `Vec`s,
+    /// the last one may be shorter than `size`.
+    ///
+    /// NOTE: `size` elements will be loaded into memory in each iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    #[inline(always)]
+    pub fn chunks(&self, size: usize) -> VecxChunks<'_, T> {
+        VecxChunks::new(self, size, size, false)
+    }
+
+    /// Same as `chunks`, but the remainder shorter than `size` is skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    #[inline(always)]
+    pub fn chunks_exact(&self, size: usize) -> VecxChunks<'_, T> {
+        VecxChunks::new(self, size, size, true)
+    }
+
+    /// Same as `slice::windows`, overlapping windows of length `size`,
+    /// nothing will be returned if the `Vecx` is shorter than `size`.
+    ///
+    /// NOTE: `size` elements will be loaded into memory in each iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    #[inline(always)]
+    pub fn windows(&self, size: usize) -> VecxChunks<'_, T> {
+        VecxChunks::new(self, size, 1, true)
+    }
+
     #[inline(always)]
     pub fn clear(&mut self) {
         self.inner.clear();
     }
 }
 
+impl<A: ValueEnDe, B: ValueEnDe> Vecx<(A, B)>
+where
+    (A, B): ValueEnDe,
+{
+    /// The reverse of `zip_with`, split the pairs into two new `Vecx`.
+    pub fn unzip(zipped: Vecx<(A, B)>) -> (Vecx<A>, Vecx<B>) {
+        let mut l = Vecx::new();
+        let mut r = Vecx::new();
+
+        zipped.iter().for_each(|(x, y)| {
+            l.push(&x);
+            r.push(&y);
+        });
+
+        (l, r)
+    }
+}
+
 impl<T> Clone for Vecx<T> {
     fn clone(&self) -> Self {
         Self {
@@ -220,6 +685,14 @@ impl<T: ValueEnDe> Default for Vecx<T> {
     }
 }
 
+impl<T: ValueEnDe> IntoIterator for Vecx<T> {
+    type Item = T;
+    type IntoIter = VecxIntoIterRange<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_iter_range(..)
+    }
+}
+
 /////////////////////////////////////////////////////////////////////////////
 /////////////////////////////////////////////////////////////////////////////
 
@@ -265,6 +738,117 @@ where
     }
 }
 
+pub struct VecxDrainIter<T>(std::vec::IntoIter<T>);
+
+/// Created by `chunks`, `chunks_exact` and `windows`.
+pub struct VecxChunks<'a, T> {
+    hdr: &'a Vecx<T>,
+    size: usize,
+    // the distance between the beginnings of two adjacent chunks
+    step: usize,
+    // skip the remainder shorter than `size`
+    exact: bool,
+    idx: usize,
+}
+
+impl<'a, T: ValueEnDe> VecxChunks<'a, T> {
+    fn new(hdr: &'a Vecx<T>, size: usize, step: usize, exact: bool) -> Self {
+        assert!(0 < size, "size must be non-zero");
+        Self {
+            hdr,
+            size,
+            step,
+            exact,
+            idx: 0,
+        }
+    }
+}
+
+impl<'a, T: ValueEnDe> Iterator for VecxChunks<'a, T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.hdr.len().min(self.idx + self.size);
+        if self.idx >= end || (self.exact && end - self.idx < self.size) {
+            return None;
+        }
+
+        let ret = (self.idx..end)
+            .map(|i| self.hdr.get(i).unwrap())
+            .collect::<Vec<_>>();
+        self.idx += self.step;
+
+        Some(ret)
+    }
+}
+
+/// Created by `into_iter` and `into_iter_range`,
+/// elements within `[start, end)` are yielded.
+pub struct VecxIntoIterRange<T> {
+    hdr: Vecx<T>,
+    start: usize,
+    end: usize,
+}
+
+impl<T: ValueEnDe> Iterator for VecxIntoIterRange<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        alt!(self.start >= self.end, return None);
+        let ret = self.hdr.get(self.start);
+        self.start += 1;
+        ret
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end.saturating_sub(self.start);
+        (n, Some(n))
+    }
+}
+
+impl<T: ValueEnDe> DoubleEndedIterator for VecxIntoIterRange<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        alt!(self.start >= self.end, return None);
+        self.end -= 1;
+        self.hdr.get(self.end)
+    }
+}
+
+impl<T: ValueEnDe> ExactSizeIterator for VecxIntoIterRange<T> {}
+
+/// Created by `into_iter_rev`.
+pub struct VecxIntoIterRev<T>(VecxIntoIterRange<T>);
+
+impl<T: ValueEnDe> Iterator for VecxIntoIterRev<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T: ValueEnDe> DoubleEndedIterator for VecxIntoIterRev<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<T: ValueEnDe> ExactSizeIterator for VecxIntoIterRev<T> {}
+
+impl<T> Iterator for VecxDrainIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<T> DoubleEndedIterator for VecxDrainIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
 ////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////
 
@@ -1,5 +1,6 @@
 use super::*;
 use ruc::*;
+use std::collections::BTreeSet;
 
 #[test]
 fn test_insert() {
@@ -80,6 +81,41 @@ fn test_iter_next() {
     assert_eq!(max - 1, value);
 }
 
+#[test]
+fn test_iter_mut() {
+    let mut hdr: Vecx<u32> = Vecx::new();
+    (0..100u32).for_each(|i| {
+        hdr.push(&(i * 2));
+    });
+
+    hdr.iter_mut().for_each(|mut v| {
+        *v += 1;
+    });
+    (0..100usize).for_each(|i| {
+        assert_eq!(i as u32 * 2 + 1, pnk!(hdr.get(i)));
+    });
+
+    // only the visited elements are changed
+    {
+        let mut it = hdr.iter_mut();
+        for _ in 0..10 {
+            let mut v = pnk!(it.next());
+            *v += 1;
+        }
+        let mut v = pnk!(it.next_back());
+        *v = 0;
+    }
+    (0..100usize).for_each(|i| {
+        let expected = match i {
+            0..=9 => i as u32 * 2 + 2,
+            99 => 0,
+            _ => i as u32 * 2 + 1,
+        };
+        assert_eq!(expected, pnk!(hdr.get(i)));
+    });
+    assert_eq!(100, hdr.len());
+}
+
 #[test]
 fn test_push_pop() {
     let mut hdr = Vecx::new();
@@ -180,3 +216,403 @@ fn write_out_of_index_7() {
     hdr.insert(0, &0);
     hdr.swap_remove(100);
 }
+
+#[test]
+fn test_drain() {
+    let mut hdr = Vecx::new();
+    let max = 100;
+    (0..max).map(|i: usize| i).for_each(|value| {
+        hdr.push(&value);
+    });
+
+    assert_eq!(0, hdr.drain(10..10).count());
+    assert_eq!(max, hdr.len());
+
+    assert_eq!(
+        (10..20).collect::<Vec<_>>(),
+        hdr.drain(10..20).collect::<Vec<_>>()
+    );
+    assert_eq!(max - 10, hdr.len());
+    assert_eq!(
+        (0..10).chain(20..max).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        (90..max).collect::<Vec<_>>(),
+        hdr.drain(80..).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        (0..10).chain(20..90).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        (0..10).chain(20..90).collect::<Vec<_>>(),
+        hdr.drain(..).collect::<Vec<_>>()
+    );
+    assert!(hdr.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn drain_out_of_index() {
+    let mut hdr = Vecx::new();
+    hdr.push(&0);
+    hdr.drain(0..2);
+}
+
+#[test]
+fn test_binary_search() {
+    let mut hdr = Vecx::new();
+    let mut vec = vec![];
+    for i in 0..10_000u64 {
+        hdr.push(&(i * 2));
+        vec.push(i * 2);
+    }
+
+    for target in [0, 1, 2, 9_999, 10_000, 19_998, 19_999, 20_000, 99_999] {
+        assert_eq!(vec.binary_search(&target), hdr.binary_search(&target));
+    }
+    for target in (0..20_002).step_by(7) {
+        assert_eq!(vec.binary_search(&target), hdr.binary_search(&target));
+        assert_eq!(
+            vec.binary_search_by(|v| v.cmp(&target)),
+            hdr.binary_search_by(|v| v.cmp(&target))
+        );
+        assert_eq!(
+            vec.binary_search_by_key(&(target / 2), |v| v / 2),
+            hdr.binary_search_by_key(&(target / 2), |v| v / 2)
+        );
+    }
+
+    assert_eq!(Err(0), Vecx::<u64>::new().binary_search(&0));
+}
+
+#[test]
+fn test_position() {
+    let mut hdr = Vecx::new();
+    (0..1000u64).for_each(|i| {
+        hdr.push(&(i % 300));
+    });
+
+    assert_eq!(Some(150), hdr.position(|v| *v == 150));
+    assert_eq!(Some(750), hdr.rposition(|v| *v == 150));
+    assert_eq!(Some(999), hdr.rposition(|v| *v == 99));
+    assert_eq!(Some(0), hdr.position(|v| *v < 10));
+    assert_eq!(Some(909), hdr.rposition(|v| *v < 10));
+
+    assert_eq!(Some(299), hdr.find(|v| 298 < *v));
+    assert!(hdr.find(|v| 300 <= *v).is_none());
+    assert!(hdr.position(|v| 300 <= *v).is_none());
+    assert!(hdr.rposition(|v| 300 <= *v).is_none());
+
+    assert!(Vecx::<u64>::new().rposition(|_| true).is_none());
+}
+
+#[test]
+fn test_into_iter_rev() {
+    let mut hdr = Vecx::new();
+    (0..100u64).for_each(|i| hdr.push(&i));
+
+    let fwd = hdr.clone().into_iter().collect::<Vec<_>>();
+    let mut rev = hdr.clone().into_iter_rev().collect::<Vec<_>>();
+    assert_eq!(100, fwd.len());
+    rev.reverse();
+    assert_eq!(fwd, rev);
+
+    assert_eq!(
+        (90..100u64).rev().collect::<Vec<_>>(),
+        hdr.clone().into_iter_rev().take(10).collect::<Vec<_>>()
+    );
+    assert_eq!(100, hdr.clone().into_iter_rev().len());
+
+    assert_eq!(
+        (10..20u64).collect::<Vec<_>>(),
+        hdr.clone().into_iter_range(10..20).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        (95..=99u64).rev().collect::<Vec<_>>(),
+        hdr.clone().into_iter_range(95..).rev().collect::<Vec<_>>()
+    );
+    assert_eq!(0, hdr.clone().into_iter_range(50..50).count());
+
+    let mut it = hdr.into_iter_range(..=3);
+    assert_eq!(Some(0), it.next());
+    assert_eq!(Some(3), it.next_back());
+    assert_eq!(vec![1, 2], it.collect::<Vec<_>>());
+
+    assert!(Vecx::<u64>::new().into_iter_rev().next().is_none());
+}
+
+#[test]
+#[should_panic]
+fn test_into_iter_range_out_of_index() {
+    let mut hdr = Vecx::new();
+    (0..10u64).for_each(|i| hdr.push(&i));
+    hdr.into_iter_range(5..11);
+}
+
+#[test]
+fn test_fill() {
+    let mut hdr: Vecx<String> = Vecx::new();
+    hdr.fill("nothing".to_owned());
+    assert!(hdr.is_empty());
+
+    (0..100).for_each(|i| hdr.push(&i.to_string()));
+
+    hdr.fill("hello".to_owned());
+    assert_eq!(100, hdr.len());
+    assert!(hdr.iter().all(|v| "hello" == v));
+
+    let mut cnt = 0;
+    hdr.fill_with(|| {
+        cnt += 1;
+        format!("s{}", cnt)
+    });
+    assert_eq!(100, hdr.len());
+    assert_eq!(
+        (1..=100).map(|i| format!("s{}", i)).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_retain() {
+    let mut hdr = Vecx::new();
+    (0..100u64).for_each(|i| hdr.push(&i));
+
+    hdr.retain(|_| true);
+    assert_eq!(
+        (0..100).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<u64>>()
+    );
+
+    hdr.retain(|v| 0 == v % 2);
+    assert_eq!(50, hdr.len());
+    assert_eq!(
+        (0..100).step_by(2).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<u64>>()
+    );
+    assert!(hdr.get(50).is_none());
+
+    hdr.retain_mut(|v| {
+        *v += 1;
+        1 == *v % 4
+    });
+    assert_eq!(25, hdr.len());
+    assert_eq!(
+        (1..100).step_by(4).collect::<Vec<_>>(),
+        hdr.iter().collect::<Vec<u64>>()
+    );
+
+    hdr.retain(|_| false);
+    assert!(hdr.is_empty());
+    assert!(hdr.get(0).is_none());
+}
+
+#[test]
+fn test_partition() {
+    let mut hdr = Vecx::new();
+    (0..200i32).for_each(|i| hdr.push(&i));
+
+    let (l, r) = hdr.partition_ref(|v| 0 == v % 2);
+    assert_eq!((0..200).step_by(2).collect::<Vec<_>>(), l);
+    assert_eq!((1..200).step_by(2).collect::<Vec<_>>(), r);
+    assert_eq!(200, hdr.len());
+
+    let (even, odd) = hdr.partition(|v| 0 == v % 2);
+    assert_eq!(200, even.len() + odd.len());
+    assert!(even.iter().all(|v| 0 == v % 2));
+    assert!(odd.iter().all(|v| 1 == v % 2));
+    assert_eq!(l, even.iter().collect::<Vec<_>>());
+    assert_eq!(r, odd.iter().collect::<Vec<_>>());
+
+    let (all, none) = even.partition(|_| true);
+    assert_eq!(100, all.len());
+    assert!(none.is_empty());
+}
+
+#[test]
+fn test_zip_with() {
+    let mut a = Vecx::new();
+    let mut b = Vecx::new();
+    (0..100u32).for_each(|i| {
+        a.push(&i);
+        b.push(&format!("{}", i * 2));
+    });
+
+    let zipped = Vecx::zip_with(&a, &b);
+    assert_eq!(100, zipped.len());
+    assert_eq!(Some((7, "14".to_owned())), zipped.get(7));
+
+    let (l, r) = Vecx::unzip(zipped);
+    assert_eq!(a.iter().collect::<Vec<_>>(), l.iter().collect::<Vec<_>>());
+    assert_eq!(b.iter().collect::<Vec<_>>(), r.iter().collect::<Vec<_>>());
+
+    let (l, r) = Vecx::unzip(Vecx::zip_with(&Vecx::<u8>::new(), &Vecx::<u8>::new()));
+    assert!(l.is_empty() && r.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn zip_with_length_mismatch() {
+    let mut a = Vecx::new();
+    a.push(&1u8);
+    Vecx::zip_with(&a, &Vecx::<u8>::new());
+}
+
+#[test]
+fn test_rotate() {
+    let mut hdr = Vecx::new();
+    (1..=5u32).for_each(|i| hdr.push(&i));
+
+    hdr.rotate_left(2);
+    assert_eq!(vec![3, 4, 5, 1, 2], hdr.iter().collect::<Vec<_>>());
+
+    hdr.rotate_right(1);
+    assert_eq!(vec![2, 3, 4, 5, 1], hdr.iter().collect::<Vec<_>>());
+
+    hdr.rotate_left(0);
+    assert_eq!(vec![2, 3, 4, 5, 1], hdr.iter().collect::<Vec<_>>());
+
+    hdr.rotate_right(5);
+    assert_eq!(vec![2, 3, 4, 5, 1], hdr.iter().collect::<Vec<_>>());
+    assert_eq!(5, hdr.len());
+}
+
+#[test]
+#[should_panic]
+fn rotate_out_of_index() {
+    let mut hdr = Vecx::new();
+    (1..=5u32).for_each(|i| hdr.push(&i));
+    hdr.rotate_left(6);
+}
+
+#[test]
+fn test_merge_sorted() {
+    let check = |a: Vec<u32>, b: Vec<u32>| {
+        let mut va = Vecx::new();
+        let mut vb = Vecx::new();
+        a.iter().for_each(|i| va.push(i));
+        b.iter().for_each(|i| vb.push(i));
+
+        let merged = Vecx::merge_sorted(va, vb).iter().collect::<Vec<_>>();
+
+        let mut expected = [a.clone(), b.clone()].concat();
+        expected.sort();
+        assert_eq!(expected, merged);
+
+        let mut deduped = merged.clone();
+        deduped.dedup();
+        let set = a.into_iter().chain(b).collect::<BTreeSet<_>>();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), deduped);
+    };
+
+    check(vec![], vec![]);
+    check((0..10).collect(), vec![]);
+    check(vec![], (0..10).collect());
+    check((0..100).step_by(2).collect(), (1..100).step_by(2).collect());
+    check((0..10).collect(), (5..100).collect());
+    check(vec![1, 1, 2, 3, 3, 3], vec![0, 1, 3, 3, 4]);
+
+    // descending order with a custom comparator
+    let mut va = Vecx::new();
+    let mut vb = Vecx::new();
+    (0..10u32).rev().for_each(|i| va.push(&i));
+    (5..20u32).rev().for_each(|i| vb.push(&i));
+    let merged = Vecx::merge_sorted_by(va, vb, |x, y| y.cmp(x))
+        .iter()
+        .collect::<Vec<_>>();
+    let mut expected = (0..10).chain(5..20).collect::<Vec<u32>>();
+    expected.sort_by(|x, y| y.cmp(x));
+    assert_eq!(expected, merged);
+}
+
+#[test]
+fn test_dedup() {
+    let mut hdr = Vecx::new();
+    let mut v = (0..1000u32).map(|i| i / 7).collect::<Vec<_>>();
+    v.iter().for_each(|i| hdr.push(i));
+
+    hdr.dedup();
+    v.dedup();
+    assert_eq!(v.len(), hdr.len());
+    assert_eq!(v, hdr.iter().collect::<Vec<u32>>());
+    assert!(hdr.get(v.len()).is_none());
+
+    // a fully unique vector is unchanged
+    hdr.dedup();
+    assert_eq!(v, hdr.iter().collect::<Vec<u32>>());
+
+    hdr.dedup_by_key(|i| i / 10);
+    v.dedup_by_key(|i| *i / 10);
+    assert_eq!(v, hdr.iter().collect::<Vec<u32>>());
+
+    let mut hdr = Vecx::new();
+    hdr.push(&9u32);
+    hdr.dedup();
+    assert_eq!(vec![9], hdr.iter().collect::<Vec<u32>>());
+
+    let mut hdr = Vecx::<u32>::new();
+    hdr.dedup();
+    assert!(hdr.is_empty());
+}
+
+#[test]
+fn test_sort() {
+    let n = 1000;
+    let mut hdr = Vecx::new();
+    (0..n as u32).for_each(|i| hdr.push(&((i * 7919) % 1009)));
+
+    hdr.sort_unstable_by(|a, b| b.cmp(a));
+    assert_eq!(n, hdr.len());
+    (1..n).for_each(|i| {
+        assert!(pnk!(hdr.get(i - 1)) >= pnk!(hdr.get(i)));
+    });
+
+    // stable, elements with the same key keep their relative order
+    let mut hdr = Vecx::new();
+    let mut v = (0..n as u32).map(|i| (i % 10, i)).collect::<Vec<_>>();
+    v.iter().for_each(|i| hdr.push(i));
+    hdr.sort_by(|a, b| b.0.cmp(&a.0));
+    v.sort_by(|a, b| b.0.cmp(&a.0));
+    assert_eq!(v, hdr.iter().collect::<Vec<_>>());
+
+    let mut hdr = Vecx::<u32>::new();
+    hdr.sort_by(|a, b| a.cmp(b));
+    assert!(hdr.is_empty());
+}
+
+#[test]
+fn test_chunks() {
+    let mut hdr = Vecx::new();
+    (0..100u32).for_each(|i| hdr.push(&i));
+
+    let chunks = hdr.chunks(7).collect::<Vec<_>>();
+    assert_eq!(15, chunks.len());
+    assert!(chunks[..14].iter().all(|c| 7 == c.len()));
+    assert_eq!(vec![98, 99], chunks[14]);
+    assert_eq!(
+        (0..100).collect::<Vec<u32>>(),
+        chunks.into_iter().flatten().collect::<Vec<_>>()
+    );
+
+    let chunks = hdr.chunks_exact(7).collect::<Vec<_>>();
+    assert_eq!(14, chunks.len());
+    assert_eq!(vec![91, 92, 93, 94, 95, 96, 97], chunks[13]);
+    assert_eq!(10, hdr.chunks_exact(10).count());
+
+    let windows = hdr.windows(3).collect::<Vec<_>>();
+    assert_eq!(98, windows.len());
+    windows.iter().enumerate().for_each(|(i, w)| {
+        let i = i as u32;
+        assert_eq!(vec![i, i + 1, i + 2], *w);
+    });
+    assert_eq!(1, hdr.windows(100).count());
+    assert_eq!(0, hdr.windows(101).count());
+
+    let hdr = Vecx::<u32>::new();
+    assert_eq!(0, hdr.chunks(7).count());
+    assert_eq!(0, hdr.windows(1).count());
+}
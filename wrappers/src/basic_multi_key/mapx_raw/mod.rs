@@ -9,12 +9,18 @@
 mod test;
 
 use crate::{
-    common::{ende::ValueEnDe, RawKey, RawValue},
+    basic::orphan::Orphan,
+    common::{bounds_is_empty, ende::ValueEnDe, RawKey, RawValue},
     MapxRaw,
 };
 use ruc::*;
 use serde::{Deserialize, Serialize};
-use std::ops::{Deref, DerefMut};
+use std::{
+    borrow::Cow,
+    marker::PhantomData,
+    ops::{Bound, Deref, DerefMut, RangeBounds},
+    vec::IntoIter,
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(bound = "")]
@@ -24,6 +30,11 @@ pub struct MapxRawMk {
     // A nested map-structure, looks like:
     // map { key => map { key => map { key => value } } }
     inner: MapxRaw,
+    // The number of entries, maintained by `insert` and `remove`,
+    // missing in the instances created by older versions,
+    // in which case it will be re-counted at the first time being used
+    #[serde(default)]
+    total: Orphan<Option<u64>>,
 }
 
 impl MapxRawMk {
@@ -36,6 +47,7 @@ impl MapxRawMk {
         Self {
             key_size: self.key_size,
             inner: self.inner.shadow(),
+            total: self.total.shadow(),
         }
     }
 
@@ -47,29 +59,13 @@ impl MapxRawMk {
         Self {
             key_size,
             inner: MapxRaw::new(),
+            total: Orphan::new(Some(0)),
         }
     }
 
     #[inline(always)]
     pub fn get(&self, key: &[&[u8]]) -> Option<RawValue> {
-        if key.len() != self.key_size as usize {
-            return None;
-        }
-
-        let mut hdr = unsafe { self.inner.shadow() };
-        for (idx, k) in key.iter().enumerate() {
-            if let Some(v) = hdr.get(k) {
-                if 1 + idx == self.key_size as usize {
-                    return Some(v);
-                } else {
-                    hdr = pnk!(ValueEnDe::decode(&v));
-                }
-            } else {
-                return None;
-            }
-        }
-
-        None // empty key
+        get_from_level(&self.inner, self.key_size, key)
     }
 
     #[inline(always)]
@@ -91,17 +87,40 @@ impl MapxRawMk {
         self.get(key).is_some()
     }
 
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.total() as usize
+    }
+
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        0 == self.total()
+    }
+
+    // Re-count the entries if the counter is missing,
+    // should be called before any changes of the inner map.
+    fn total(&self) -> u64 {
+        if let Some(n) = self.total.get_value() {
+            return n;
+        }
+        let n = count_entries(&self.inner, self.key_size);
+        unsafe { self.total.shadow() }.set_value(&Some(n));
+        n
+    }
+
+    #[inline(always)]
+    fn set_total(&mut self, n: u64) {
+        self.total.set_value(&Some(n));
     }
 
     #[inline(always)]
     pub fn entry<'a>(&'a mut self, key: &'a [&'a [u8]]) -> Result<Entry<'a>> {
         if key.len() != self.key_size() as usize {
             Err(eg!())
+        } else if self.contains_key(key) {
+            Ok(Entry::Occupied(OccupiedEntry { key, hdr: self }))
         } else {
-            Ok(Entry { key, hdr: self })
+            Ok(Entry::Vacant(VacantEntry { key, hdr: self }))
         }
     }
 
@@ -112,11 +131,15 @@ impl MapxRawMk {
         }
 
         let mut ret = None;
+        let total = self.total();
 
         let mut hdr = unsafe { self.inner.shadow() };
         for (idx, k) in key.iter().enumerate() {
             if 1 + idx == self.key_size as usize {
                 ret = hdr.insert(k, value);
+                if ret.is_none() {
+                    self.set_total(total + 1);
+                }
                 break;
             } else {
                 let mut new_hdr = None;
@@ -146,16 +169,26 @@ impl MapxRawMk {
             return Err(eg!("Incorrect key size"));
         }
 
+        let total = self.total();
+
         let mut hdr = unsafe { self.inner.shadow() };
         for (idx, k) in key.iter().enumerate() {
             if let Some(v) = hdr.get(k) {
                 // NOTE: use `key.len()` instead of `self.key_size`
                 if 1 + idx == key.len() {
-                    let ret = hdr.remove(k);
                     // NOTE: use `self.key_size` instead of `key.len()`
                     if 1 + idx == self.key_size as usize {
+                        let ret = hdr.remove(k);
+                        if ret.is_some() {
+                            self.set_total(total - 1);
+                        }
                         return Ok(ret);
                     } else {
+                        // All entries under the sub-tree will be removed
+                        let depth = self.key_size - 1 - idx as u32;
+                        let cnt = count_entries(&pnk!(ValueEnDe::decode(&v)), depth);
+                        hdr.remove(k);
+                        self.set_total(total - cnt);
                         return Ok(None);
                     }
                 } else {
@@ -172,6 +205,7 @@ impl MapxRawMk {
     #[inline(always)]
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.set_total(0);
     }
 
     #[inline(always)]
@@ -179,6 +213,35 @@ impl MapxRawMk {
         self.key_size
     }
 
+    /// Insert all entries of `other` into this one,
+    /// the existing values will be overwritten by those of `other`,
+    /// return the number of new(non-overwriting) insertions.
+    ///
+    /// Fail if the key sizes are different.
+    pub fn merge_from(&mut self, other: &MapxRawMk) -> Result<u64> {
+        if self.key_size != other.key_size {
+            return Err(eg!("Incorrect key size"));
+        }
+
+        let mut cnt = 0;
+        other
+            .iter_op(&mut |k: &[&[u8]], v: &[u8]| {
+                if self.insert(k, v).c(d!())?.is_none() {
+                    cnt += 1;
+                }
+                Ok(())
+            })
+            .c(d!())?;
+
+        Ok(cnt)
+    }
+
+    /// Same as `merge_from`, but consume both instances.
+    #[inline(always)]
+    pub fn merge(mut self, other: MapxRawMk) -> Result<MapxRawMk> {
+        self.merge_from(&other).c(d!()).map(|_| self)
+    }
+
     #[inline(always)]
     pub fn iter_op<F>(&self, op: &mut F) -> Result<()>
     where
@@ -196,67 +259,29 @@ impl MapxRawMk {
     where
         F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
     {
-        let key_size = self.key_size() as usize;
-        let mut key_buf = vec![RawKey::default(); key_size];
-        let mut hdr = unsafe { self.inner.shadow() };
-        let mut depth = key_size;
-
-        if key_size < key_prefix.len() {
-            return Err(eg!("Invalid key size"));
-        } else {
-            for (idx, k) in key_prefix.iter().enumerate() {
-                if let Some(v) = hdr.get(k) {
-                    key_buf[idx] = k.to_vec();
-                    if 1 + idx == key_size {
-                        let key = key_buf
-                            .iter()
-                            .map(|sub_k| sub_k.as_ref())
-                            .collect::<Vec<_>>();
-                        return op(key.as_slice(), &v).c(d!());
-                    } else {
-                        hdr = pnk!(ValueEnDe::decode(&v));
-                        depth -= 1;
-                    }
-                } else {
-                    // key-prefix does not exist
-                    return Ok(());
-                }
-            }
-        };
+        walk_with_key_prefix(&self.inner, self.key_size, op, key_prefix, false).c(d!())
+    }
 
-        self.recursive_walk(hdr, key_buf.as_mut_slice(), depth as u32, op)
-            .c(d!())
+    /// Same as `iter_op`, but in the reverse order.
+    #[inline(always)]
+    pub fn iter_op_rev<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+    {
+        self.iter_op_rev_with_key_prefix(op, &[]).c(d!())
     }
 
-    fn recursive_walk<F>(
+    /// Same as `iter_op_with_key_prefix`, but in the reverse order.
+    #[inline(always)]
+    pub fn iter_op_rev_with_key_prefix<F>(
         &self,
-        hdr: MapxRaw,
-        key_buf: &mut [RawKey],
-        depth: u32,
         op: &mut F,
+        key_prefix: &[&[u8]],
     ) -> Result<()>
     where
         F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
     {
-        let idx = (self.key_size() - depth) as usize;
-        if 1 == depth {
-            for (k, v) in hdr.iter() {
-                key_buf[idx] = k;
-                let key = key_buf
-                    .iter()
-                    .map(|sub_k| sub_k.as_ref())
-                    .collect::<Vec<_>>();
-                op(key.as_slice(), &v[..]).c(d!())?;
-            }
-        } else {
-            for (k, v) in hdr.iter() {
-                key_buf[idx] = k;
-                let hdr = pnk!(ValueEnDe::decode(&v));
-                self.recursive_walk(hdr, key_buf, depth - 1, op).c(d!())?;
-            }
-        }
-
-        Ok(())
+        walk_with_key_prefix(&self.inner, self.key_size, op, key_prefix, true).c(d!())
     }
 
     #[inline(always)]
@@ -343,6 +368,81 @@ impl MapxRawMk {
         Ok(())
     }
 
+    /// Iterate over all entries under the `key_prefix`,
+    /// the `bounds` are applied to the key level right behind the prefix.
+    ///
+    /// NOTE: nothing will be returned if the prefix is not shorter than the key size.
+    pub fn range_by_prefix<'a, R: RangeBounds<&'a [u8]>>(
+        &self,
+        key_prefix: &[&[u8]],
+        bounds: R,
+    ) -> MapxRawMkRangeIter {
+        let level = if key_prefix.len() < self.key_size as usize {
+            // `None` if the key-prefix does not exist
+            key_prefix
+                .iter()
+                .try_fold(unsafe { self.inner.shadow() }, |hdr, k| {
+                    hdr.get(k).map(|v| pnk!(ValueEnDe::decode(&v)))
+                })
+        } else {
+            None
+        };
+
+        let to_owned = |b: Bound<&&[u8]>| match b {
+            Bound::Included(k) => Bound::Included(k.to_vec()),
+            Bound::Excluded(k) => Bound::Excluded(k.to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        MapxRawMkRangeIter {
+            hdr: self,
+            level,
+            key_prefix: key_prefix.iter().map(|k| k.to_vec()).collect(),
+            lo: to_owned(bounds.start_bound()),
+            hi: to_owned(bounds.end_bound()),
+            front: None,
+            back: None,
+        }
+    }
+
+    /// Check if any entry exists under the `key_prefix`,
+    /// at most one entry will be read.
+    ///
+    /// NOTE: a full-length prefix is the same as `contains_key`.
+    #[inline(always)]
+    pub fn contains_prefix(&self, key_prefix: &[&[u8]]) -> bool {
+        if key_prefix.len() < self.key_size as usize {
+            self.range_by_prefix(key_prefix, ..).next().is_some()
+        } else {
+            self.contains_key(key_prefix)
+        }
+    }
+
+    /// Get the sub-map under the `first_key` of a 2-key map,
+    /// no data will be copied.
+    ///
+    /// NOTE:
+    /// - `None` if the key size is not 2, or no entry exists under the `first_key`
+    /// - the returned map shares the same storage with `self`,
+    ///   writing through it will not update the entry count of `self`
+    pub fn sub_map(&self, first_key: &[u8]) -> Option<MapxRaw> {
+        if 2 != self.key_size {
+            return None;
+        }
+        sub_level(&self.inner, first_key)
+    }
+
+    /// Same as `sub_map`, but for maps with more than 2 keys,
+    /// returns a read-only view whose key size is `self.key_size() - 1`.
+    ///
+    /// NOTE: the entries of the sub-map will be counted once.
+    pub fn sub_map_mk(&self, first_key: &[u8]) -> Option<MapxRawMkView<'_>> {
+        if 2 >= self.key_size {
+            return None;
+        }
+        MapxRawMkView::new(sub_level(&self.inner, first_key)?, self.key_size - 1)
+    }
+
     // TODO
     // pub fn iter_mut_op
     // pub fn iter_mut_op_with_key_prefix
@@ -350,6 +450,213 @@ impl MapxRawMk {
     // pub fn iter_mut_op_typed_value_with_key_prefix
 }
 
+/// A read-only view of a sub-map of `MapxRawMk`,
+/// created by `MapxRawMk::sub_map_mk`.
+///
+/// The view borrows the original map, so it can not be changed
+/// while the view is alive.
+#[derive(Debug)]
+pub struct MapxRawMkView<'a> {
+    key_size: u32,
+    inner: MapxRaw,
+    total: u64,
+    _p: PhantomData<&'a MapxRawMk>,
+}
+
+impl<'a> MapxRawMkView<'a> {
+    // `None` if no entry exists, empty sub-trees may be left behind by `remove`
+    fn new(inner: MapxRaw, key_size: u32) -> Option<Self> {
+        let total = count_entries(&inner, key_size);
+        alt!(0 == total, return None);
+        Some(Self {
+            key_size,
+            inner,
+            total,
+            _p: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    pub fn get(&self, key: &[&[u8]]) -> Option<RawValue> {
+        get_from_level(&self.inner, self.key_size, key)
+    }
+
+    #[inline(always)]
+    pub fn contains_key(&self, key: &[&[u8]]) -> bool {
+        self.get(key).is_some()
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.total as usize
+    }
+
+    /// Always `false`, an empty view will not be created.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        0 == self.total
+    }
+
+    #[inline(always)]
+    pub fn key_size(&self) -> u32 {
+        self.key_size
+    }
+
+    #[inline(always)]
+    pub fn iter_op<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+    {
+        self.iter_op_with_key_prefix(op, &[]).c(d!())
+    }
+
+    #[inline(always)]
+    pub fn iter_op_with_key_prefix<F>(
+        &self,
+        op: &mut F,
+        key_prefix: &[&[u8]],
+    ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+    {
+        walk_with_key_prefix(&self.inner, self.key_size, op, key_prefix, false).c(d!())
+    }
+
+    /// Same as `iter_op`, but in the reverse order.
+    #[inline(always)]
+    pub fn iter_op_rev<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+    {
+        self.iter_op_rev_with_key_prefix(op, &[]).c(d!())
+    }
+
+    /// Same as `iter_op_with_key_prefix`, but in the reverse order.
+    #[inline(always)]
+    pub fn iter_op_rev_with_key_prefix<F>(
+        &self,
+        op: &mut F,
+        key_prefix: &[&[u8]],
+    ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+    {
+        walk_with_key_prefix(&self.inner, self.key_size, op, key_prefix, true).c(d!())
+    }
+
+    /// Same as `MapxRawMk::sub_map`.
+    pub fn sub_map(&self, first_key: &[u8]) -> Option<MapxRaw> {
+        if 2 != self.key_size {
+            return None;
+        }
+        sub_level(&self.inner, first_key)
+    }
+
+    /// Same as `MapxRawMk::sub_map_mk`.
+    pub fn sub_map_mk(&self, first_key: &[u8]) -> Option<MapxRawMkView<'a>> {
+        if 2 >= self.key_size {
+            return None;
+        }
+        MapxRawMkView::new(sub_level(&self.inner, first_key)?, self.key_size - 1)
+    }
+}
+
+pub struct MapxRawMkRangeIter<'a> {
+    hdr: &'a MapxRawMk,
+
+    // The map right behind the key prefix,
+    // `None` means the key prefix does not exist
+    level: Option<MapxRaw>,
+    key_prefix: Vec<RawKey>,
+
+    // The bounds of the keys that have not been loaded
+    lo: Bound<RawKey>,
+    hi: Bound<RawKey>,
+
+    front: Option<IntoIter<(Vec<RawKey>, RawValue)>>,
+    back: Option<IntoIter<(Vec<RawKey>, RawValue)>>,
+}
+
+impl<'a> MapxRawMkRangeIter<'a> {
+    // Load all entries under the leftmost(or rightmost) unloaded key
+    fn load(&mut self, from_back: bool) -> Option<IntoIter<(Vec<RawKey>, RawValue)>> {
+        if bounds_is_empty(&self.lo, &self.hi) {
+            return None;
+        }
+
+        let (k, v) = {
+            let mut hdr = self
+                .level
+                .as_ref()?
+                .range((to_cow_bound(&self.lo), to_cow_bound(&self.hi)));
+            alt!(from_back, hdr.next_back(), hdr.next())?
+        };
+
+        let key_size = self.hdr.key_size() as usize;
+        let mut key_buf = self.key_prefix.clone();
+        key_buf.push(k.clone());
+        if from_back {
+            self.hi = Bound::Excluded(k);
+        } else {
+            self.lo = Bound::Excluded(k);
+        }
+
+        let mut entries = vec![];
+        if key_size == key_buf.len() {
+            entries.push((key_buf, v));
+        } else {
+            let depth = key_size - key_buf.len();
+            key_buf.resize(key_size, RawKey::default());
+            let mut op = |k: &[&[u8]], v: &[u8]| {
+                entries.push((k.iter().map(|k| k.to_vec()).collect(), v.to_vec()));
+                Ok(())
+            };
+            pnk!(recursive_walk(
+                pnk!(ValueEnDe::decode(&v)),
+                key_size as u32,
+                key_buf.as_mut_slice(),
+                depth as u32,
+                &mut op,
+                false
+            ));
+        }
+
+        Some(entries.into_iter())
+    }
+}
+
+impl<'a> Iterator for MapxRawMkRangeIter<'a> {
+    type Item = (Vec<RawKey>, RawValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(i) = self.front.as_mut().and_then(|i| i.next()) {
+                return Some(i);
+            }
+            if let Some(i) = self.load(false) {
+                self.front = Some(i);
+            } else {
+                return self.back.as_mut().and_then(|i| i.next());
+            }
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for MapxRawMkRangeIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(i) = self.back.as_mut().and_then(|i| i.next_back()) {
+                return Some(i);
+            }
+            if let Some(i) = self.load(true) {
+                self.back = Some(i);
+            } else {
+                return self.front.as_mut().and_then(|i| i.next_back());
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ValueMut<'a> {
     hdr: &'a mut MapxRawMk,
@@ -382,20 +689,108 @@ impl<'a> DerefMut for ValueMut<'a> {
     }
 }
 
-pub struct Entry<'a> {
-    key: &'a [&'a [u8]],
-    hdr: &'a mut MapxRawMk,
+pub enum Entry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>),
 }
 
 impl<'a> Entry<'a> {
-    pub fn or_insert(self, default: &'a [u8]) -> ValueMut<'a> {
-        let hdr = self.hdr as *mut MapxRawMk;
-        if let Some(v) = unsafe { &mut *hdr }.get_mut(self.key) {
-            v
-        } else {
-            unsafe { &mut *hdr }.gen_mut(self.key, default.to_vec())
+    pub fn key(&self) -> &[&[u8]] {
+        match self {
+            Self::Occupied(e) => e.key(),
+            Self::Vacant(e) => e.key(),
         }
     }
+
+    pub fn or_insert(self, default: &[u8]) -> ValueMut<'a> {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, f: F) -> ValueMut<'a>
+    where
+        F: FnOnce() -> RawValue,
+    {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.insert(&f()),
+        }
+    }
+
+    pub fn or_default(self) -> ValueMut<'a> {
+        match self {
+            Self::Occupied(e) => e.into_mut(),
+            Self::Vacant(e) => e.or_default(),
+        }
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut RawValue),
+    {
+        if let Self::Occupied(e) = &mut self {
+            f(&mut *e.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'a> {
+    key: &'a [&'a [u8]],
+    hdr: &'a mut MapxRawMk,
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &[&[u8]] {
+        self.key
+    }
+
+    pub fn get(&self) -> RawValue {
+        pnk!(self.hdr.get(self.key))
+    }
+
+    /// The change will be written back when the returned `ValueMut` is dropped.
+    pub fn get_mut(&mut self) -> ValueMut<'_> {
+        let v = self.get();
+        self.hdr.gen_mut(self.key, v)
+    }
+
+    pub fn into_mut(self) -> ValueMut<'a> {
+        let v = self.get();
+        let Self { key, hdr } = self;
+        hdr.gen_mut(key, v)
+    }
+
+    /// Return the old value.
+    pub fn insert(&mut self, value: &[u8]) -> RawValue {
+        pnk!(pnk!(self.hdr.insert(self.key, value)))
+    }
+
+    pub fn remove(self) -> RawValue {
+        pnk!(pnk!(self.hdr.remove(self.key)))
+    }
+}
+
+pub struct VacantEntry<'a> {
+    key: &'a [&'a [u8]],
+    hdr: &'a mut MapxRawMk,
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &[&[u8]] {
+        self.key
+    }
+
+    pub fn insert(self, value: &[u8]) -> ValueMut<'a> {
+        let Self { key, hdr } = self;
+        hdr.gen_mut(key, value.to_vec())
+    }
+
+    pub fn or_default(self) -> ValueMut<'a> {
+        self.insert(&[])
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////
@@ -405,3 +800,137 @@ impl<'a> Entry<'a> {
 impl vsdb_core::VsMgmt for MapxRawMk {
     vsdb_core::impl_vs_methods_nope! {}
 }
+
+/////////////////////////////////////////////////////////////////////////////
+/////////////////////////////////////////////////////////////////////////////
+
+#[inline(always)]
+fn to_cow_bound(b: &Bound<RawKey>) -> Bound<Cow<[u8]>> {
+    match b {
+        Bound::Included(k) => Bound::Included(Cow::Borrowed(&k[..])),
+        Bound::Excluded(k) => Bound::Excluded(Cow::Borrowed(&k[..])),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn get_from_level(hdr: &MapxRaw, key_size: u32, key: &[&[u8]]) -> Option<RawValue> {
+    if key.len() != key_size as usize {
+        return None;
+    }
+
+    let mut hdr = unsafe { hdr.shadow() };
+    for (idx, k) in key.iter().enumerate() {
+        if let Some(v) = hdr.get(k) {
+            if 1 + idx == key_size as usize {
+                return Some(v);
+            } else {
+                hdr = pnk!(ValueEnDe::decode(&v));
+            }
+        } else {
+            return None;
+        }
+    }
+
+    None // empty key
+}
+
+fn walk_with_key_prefix<F>(
+    hdr: &MapxRaw,
+    key_size: u32,
+    op: &mut F,
+    key_prefix: &[&[u8]],
+    rev: bool,
+) -> Result<()>
+where
+    F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+{
+    let key_size = key_size as usize;
+    let mut key_buf = vec![RawKey::default(); key_size];
+    let mut hdr = unsafe { hdr.shadow() };
+    let mut depth = key_size;
+
+    if key_size < key_prefix.len() {
+        return Err(eg!("Invalid key size"));
+    } else {
+        for (idx, k) in key_prefix.iter().enumerate() {
+            if let Some(v) = hdr.get(k) {
+                key_buf[idx] = k.to_vec();
+                if 1 + idx == key_size {
+                    let key = key_buf
+                        .iter()
+                        .map(|sub_k| sub_k.as_ref())
+                        .collect::<Vec<_>>();
+                    return op(key.as_slice(), &v).c(d!());
+                } else {
+                    hdr = pnk!(ValueEnDe::decode(&v));
+                    depth -= 1;
+                }
+            } else {
+                // key-prefix does not exist
+                return Ok(());
+            }
+        }
+    };
+
+    recursive_walk(
+        hdr,
+        key_size as u32,
+        key_buf.as_mut_slice(),
+        depth as u32,
+        op,
+        rev,
+    )
+    .c(d!())
+}
+
+// The inner iterators are double-ended,
+// so the reverse order is got by calling `next_back` on each level.
+fn recursive_walk<F>(
+    hdr: MapxRaw,
+    key_size: u32,
+    key_buf: &mut [RawKey],
+    depth: u32,
+    op: &mut F,
+    rev: bool,
+) -> Result<()>
+where
+    F: FnMut(&[&[u8]], &[u8]) -> Result<()>,
+{
+    let idx = (key_size - depth) as usize;
+    let mut iter = hdr.iter();
+    if 1 == depth {
+        while let Some((k, v)) = alt!(rev, iter.next_back(), iter.next()) {
+            key_buf[idx] = k;
+            let key = key_buf
+                .iter()
+                .map(|sub_k| sub_k.as_ref())
+                .collect::<Vec<_>>();
+            op(key.as_slice(), &v[..]).c(d!())?;
+        }
+    } else {
+        while let Some((k, v)) = alt!(rev, iter.next_back(), iter.next()) {
+            key_buf[idx] = k;
+            let hdr = pnk!(ValueEnDe::decode(&v));
+            recursive_walk(hdr, key_size, key_buf, depth - 1, op, rev).c(d!())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sub_level(hdr: &MapxRaw, first_key: &[u8]) -> Option<MapxRaw> {
+    hdr.get(first_key)
+        .map(|v| pnk!(<MapxRaw as ValueEnDe>::decode(&v)))
+        .filter(|hdr| !hdr.is_empty())
+}
+
+// Count all entries of a sub-tree, `depth` is the number of its levels
+fn count_entries(hdr: &MapxRaw, depth: u32) -> u64 {
+    if 1 == depth {
+        hdr.len() as u64
+    } else {
+        hdr.iter()
+            .map(|(_, v)| count_entries(&pnk!(ValueEnDe::decode(&v)), depth - 1))
+            .sum()
+    }
+}
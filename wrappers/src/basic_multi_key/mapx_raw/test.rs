@@ -30,6 +30,99 @@ fn test_insert() {
     assert!(hdr.is_empty());
 }
 
+#[test]
+fn test_len() {
+    let mut hdr = MapxRawMk::new(3);
+    let mut cnt = 0;
+    assert_eq!(0, hdr.len());
+    assert!(hdr.is_empty());
+
+    for i in 0..4u8 {
+        for j in 0..4u8 {
+            for k in 0..4u8 {
+                assert!(pnk!(hdr.insert(&[&[i], &[j], &[k]], &[i, j, k])).is_none());
+                cnt += 1;
+                assert_eq!(cnt, hdr.len());
+            }
+        }
+    }
+
+    // overwrite an existing entry
+    assert!(pnk!(hdr.insert(&[&[0], &[0], &[0]], &[9])).is_some());
+    assert_eq!(cnt, hdr.len());
+
+    assert!(pnk!(hdr.remove(&[&[0], &[0], &[0]])).is_some());
+    cnt -= 1;
+    assert_eq!(cnt, hdr.len());
+
+    // remove a non-existing entry
+    assert!(pnk!(hdr.remove(&[&[0], &[0], &[0]])).is_none());
+    assert!(pnk!(hdr.remove(&[&[9]])).is_none());
+    assert_eq!(cnt, hdr.len());
+
+    // batch removal by key prefixes
+    pnk!(hdr.remove(&[&[0], &[0]]));
+    cnt -= 3;
+    assert_eq!(cnt, hdr.len());
+    pnk!(hdr.remove(&[&[1]]));
+    cnt -= 16;
+    assert_eq!(cnt, hdr.len());
+
+    hdr.entry(&[&[1], &[1], &[1]]).unwrap().or_insert(&[1]);
+    cnt += 1;
+    assert_eq!(cnt, hdr.len());
+
+    let mut manual = 0;
+    pnk!(hdr.iter_op(&mut |_: &[&[u8]], _: &[u8]| {
+        manual += 1;
+        Ok(())
+    }));
+    assert_eq!(manual, hdr.len());
+
+    // the counter is shared between shadows
+    let shadow = unsafe { hdr.shadow() };
+    pnk!(hdr.insert(&[&[5], &[5], &[5]], &[5]));
+    assert_eq!(cnt + 1, shadow.len());
+
+    hdr.clear();
+    assert_eq!(0, hdr.len());
+    assert!(hdr.is_empty());
+}
+
+// `bcs` can not skip the missing fields
+#[cfg(not(feature = "bcs_codec"))]
+#[test]
+fn test_len_of_legacy_data() {
+    // the layout without the entry counter
+    #[derive(serde::Serialize)]
+    struct Legacy {
+        key_size: u32,
+        inner: MapxRaw,
+    }
+
+    let hdr = MapxRawMk::new(2);
+    for i in 0..4u8 {
+        for j in 0..4u8 {
+            pnk!(unsafe { hdr.shadow() }.insert(&[&[i], &[j]], &[i, j]));
+        }
+    }
+
+    let legacy = Legacy {
+        key_size: 2,
+        inner: unsafe { hdr.inner.shadow() },
+    };
+    let mut hdr = pnk!(<MapxRawMk as ValueEnDe>::decode(&legacy.encode()));
+    assert!(hdr.total.get_value().is_none());
+
+    assert_eq!(16, hdr.len());
+    assert_eq!(Some(16), hdr.total.get_value());
+
+    pnk!(hdr.insert(&[&[9], &[9]], &[9]));
+    assert_eq!(17, hdr.len());
+    pnk!(hdr.remove(&[&[0]]));
+    assert_eq!(13, hdr.len());
+}
+
 #[test]
 fn test_valueende() {
     let cnt = 100;
@@ -124,3 +217,297 @@ fn test_iter_op_with_key_prefix() {
 
     assert_eq!(cnt, 10);
 }
+
+#[test]
+fn test_iter_op_rev() {
+    let mut map = MapxRawMk::new(4);
+    (0u8..200).for_each(|i| {
+        pnk!(map.insert(&[&[i % 3], &[i % 5], &[i % 7], &[i]], &[i]));
+    });
+    assert_eq!(200, map.len());
+
+    let collect = |rev: bool, key_prefix: &[&[u8]]| {
+        let mut res = vec![];
+        let mut op = |k: &[&[u8]], v: &[u8]| {
+            res.push((k.iter().map(|k| k.to_vec()).collect::<Vec<_>>(), v.to_vec()));
+            Ok(())
+        };
+        if rev {
+            pnk!(map.iter_op_rev_with_key_prefix(&mut op, key_prefix));
+        } else {
+            pnk!(map.iter_op_with_key_prefix(&mut op, key_prefix));
+        }
+        res
+    };
+
+    let mut fwd = collect(false, &[]);
+    let rev = collect(true, &[]);
+    assert_eq!(200, rev.len());
+    assert!(rev.windows(2).all(|w| w[0].0 > w[1].0));
+    fwd.reverse();
+    assert_eq!(fwd, rev);
+
+    let mut fwd = collect(false, &[&[1], &[2]]);
+    let rev = collect(true, &[&[1], &[2]]);
+    assert!(!rev.is_empty());
+    fwd.reverse();
+    assert_eq!(fwd, rev);
+
+    assert!(collect(true, &[&[9]]).is_empty());
+
+    let mut all = vec![];
+    pnk!(map.iter_op_rev(&mut |k: &[&[u8]], _: &[u8]| {
+        all.push(k[3].to_vec());
+        Ok(())
+    }));
+    assert_eq!(200, all.len());
+}
+
+#[test]
+fn test_range_by_prefix() {
+    let mut hdr = MapxRawMk::new(3);
+    for i in 0..5u8 {
+        for j in 0..5u8 {
+            pnk!(hdr.insert(&[&[i], &[j], &[0]], &[i, j]));
+            pnk!(hdr.insert(&[&[i], &[j], &[1]], &[i, j]));
+        }
+    }
+
+    let entries = hdr
+        .range_by_prefix(&[&[1]], &[2][..]..&[4][..])
+        .collect::<Vec<_>>();
+    assert_eq!(4, entries.len());
+    entries.iter().for_each(|(k, v)| {
+        assert_eq!(vec![1], k[0]);
+        assert!([vec![2], vec![3]].contains(&k[1]));
+        assert_eq!(v, &[k[0][0], k[1][0]]);
+    });
+    assert_eq!(
+        Some((vec![vec![1], vec![3], vec![1]], vec![1, 3])),
+        hdr.range_by_prefix(&[&[1]], &[2][..]..&[4][..]).next_back()
+    );
+
+    let entries = hdr
+        .range_by_prefix(
+            &[&[1]],
+            (Bound::Excluded(&[2][..]), Bound::Included(&[4][..])),
+        )
+        .rev()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (vec![vec![1], vec![4], vec![1]], vec![1, 4]),
+            (vec![vec![1], vec![4], vec![0]], vec![1, 4]),
+            (vec![vec![1], vec![3], vec![1]], vec![1, 3]),
+            (vec![vec![1], vec![3], vec![0]], vec![1, 3]),
+        ],
+        entries
+    );
+
+    // the front and the back meet in the middle
+    let mut it = hdr.range_by_prefix(&[&[1], &[2]], ..);
+    assert_eq!(
+        Some((vec![vec![1], vec![2], vec![0]], vec![1, 2])),
+        it.next()
+    );
+    assert_eq!(
+        Some((vec![vec![1], vec![2], vec![1]], vec![1, 2])),
+        it.next_back()
+    );
+    assert!(it.next().is_none());
+    assert!(it.next_back().is_none());
+
+    assert_eq!(50, hdr.range_by_prefix(&[], ..).count());
+    assert_eq!(20, hdr.range_by_prefix(&[], &[3][..]..).count());
+    assert_eq!(0, hdr.range_by_prefix(&[&[9]], ..).count());
+    assert_eq!(0, hdr.range_by_prefix(&[&[1], &[2], &[0]], ..).count());
+    assert_eq!(0, hdr.range_by_prefix(&[&[1]], &[4][..]..&[2][..]).count());
+}
+
+#[test]
+fn test_entry() {
+    let mut hdr = MapxRawMk::new(2);
+    let key: &[&[u8]] = &[&[1], &[1]];
+    assert!(hdr.entry(&[&[1]]).is_err());
+
+    let mut computed = 0;
+    let mut modified = 0;
+
+    // vacant: `and_modify` is skipped, `or_insert_with` is called
+    let v = pnk!(hdr.entry(key))
+        .and_modify(|_| modified += 1)
+        .or_insert_with(|| {
+            computed += 1;
+            vec![0]
+        });
+    assert_eq!(&[0], &v[..]);
+    drop(v);
+    assert_eq!((1, 0), (computed, modified));
+    assert_eq!(Some(vec![0]), hdr.get(key));
+    assert_eq!(1, hdr.len());
+
+    // occupied: `and_modify` is called, `or_insert_with` is skipped
+    pnk!(hdr.entry(key))
+        .and_modify(|v| {
+            modified += 1;
+            v[0] += 1;
+        })
+        .or_insert_with(|| {
+            computed += 1;
+            vec![9]
+        });
+    assert_eq!((1, 1), (computed, modified));
+    assert_eq!(Some(vec![1]), hdr.get(key));
+    assert_eq!(1, hdr.len());
+
+    match pnk!(hdr.entry(key)) {
+        Entry::Occupied(mut e) => {
+            assert_eq!(key, e.key());
+            assert_eq!(vec![1], e.get());
+            e.get_mut().push(2);
+            assert_eq!(vec![1, 2], e.get());
+            assert_eq!(vec![1, 2], e.insert(&[3]));
+            assert_eq!(vec![3], e.remove());
+        }
+        Entry::Vacant(_) => panic!(),
+    }
+    assert!(hdr.get(key).is_none());
+    assert!(hdr.is_empty());
+
+    match pnk!(hdr.entry(key)) {
+        Entry::Vacant(e) => {
+            assert_eq!(key, e.key());
+            assert!(e.or_default().is_empty());
+        }
+        Entry::Occupied(_) => panic!(),
+    }
+    assert_eq!(Some(vec![]), hdr.get(key));
+
+    let key2: &[&[u8]] = &[&[2], &[2]];
+    pnk!(hdr.entry(key2)).or_insert(&[2]);
+    pnk!(hdr.entry(key2)).or_insert(&[22]);
+    assert_eq!(Some(vec![2]), hdr.get(key2));
+    assert_eq!(2, hdr.len());
+}
+
+#[test]
+fn test_contains_prefix() {
+    let mut hdr = MapxRawMk::new(3);
+    assert!(!hdr.contains_prefix(&[]));
+
+    pnk!(hdr.insert(&[&[1], &[1], &[1]], &[1]));
+    pnk!(hdr.insert(&[&[1], &[2], &[0]], &[2]));
+    assert!(hdr.contains_prefix(&[]));
+
+    assert!(hdr.contains_prefix(&[&[1]]));
+    assert!(!hdr.contains_prefix(&[&[2]]));
+    assert!(hdr.contains_prefix(&[&[1], &[2]]));
+    assert!(!hdr.contains_prefix(&[&[1], &[3]]));
+
+    // full-length prefixes
+    assert!(hdr.contains_prefix(&[&[1], &[1], &[1]]));
+    assert!(!hdr.contains_prefix(&[&[1], &[1], &[0]]));
+    assert!(!hdr.contains_prefix(&[&[1], &[1], &[1], &[1]]));
+
+    // an empty sub-tree is left behind
+    pnk!(hdr.remove(&[&[1], &[2], &[0]]));
+    assert!(!hdr.contains_prefix(&[&[1], &[2]]));
+    assert!(hdr.contains_prefix(&[&[1]]));
+}
+
+#[test]
+fn test_sub_map() {
+    let mut hdr = MapxRawMk::new(3);
+    (0u8..10).for_each(|i| {
+        (0u8..10).for_each(|j| {
+            pnk!(hdr.insert(&[&[i % 3], &[i], &[j]], &[i, j]));
+        });
+    });
+    assert!(hdr.sub_map(&[0]).is_none());
+
+    let sub = pnk!(hdr.sub_map_mk(&[1]));
+    assert_eq!(2, sub.key_size());
+    assert_eq!(30, sub.len());
+
+    let mut expected = vec![];
+    pnk!(hdr.iter_op_with_key_prefix(
+        &mut |k: &[&[u8]], v: &[u8]| {
+            expected.push((k[1..].concat(), v.to_vec()));
+            Ok(())
+        },
+        &[&[1]]
+    ));
+    let mut got = vec![];
+    pnk!(sub.iter_op(&mut |k: &[&[u8]], v: &[u8]| {
+        got.push((k.concat(), v.to_vec()));
+        Ok(())
+    }));
+    assert_eq!(30, got.len());
+    assert_eq!(expected, got);
+
+    got.clear();
+    pnk!(sub.iter_op_rev(&mut |k: &[&[u8]], v: &[u8]| {
+        got.push((k.concat(), v.to_vec()));
+        Ok(())
+    }));
+    expected.reverse();
+    assert_eq!(expected, got);
+
+    assert_eq!(Some(vec![4, 5]), sub.get(&[&[4], &[5]]));
+    assert!(sub.contains_key(&[&[7], &[0]]));
+    assert!(!sub.contains_key(&[&[5], &[0]]));
+    assert!(sub.get(&[&[4]]).is_none());
+
+    // a 2-key view of the 2-key sub-map
+    let raw = pnk!(sub.sub_map(&[4]));
+    assert_eq!(
+        (0u8..10).map(|j| (vec![j], vec![4, j])).collect::<Vec<_>>(),
+        raw.iter().collect::<Vec<_>>()
+    );
+    assert!(sub.sub_map(&[5]).is_none());
+    assert!(sub.sub_map_mk(&[4]).is_none());
+
+    // the views do not touch the entry count of the original map
+    assert_eq!(100, hdr.len());
+
+    assert!(hdr.sub_map_mk(&[3]).is_none());
+    pnk!(hdr.remove(&[&[2], &[2]]));
+    pnk!(hdr.remove(&[&[2], &[5]]));
+    pnk!(hdr.remove(&[&[2], &[8]]));
+    assert!(hdr.sub_map_mk(&[2]).is_none());
+}
+
+#[test]
+fn test_merge() {
+    let fill = |range: std::ops::Range<u8>, v: u8| {
+        let mut hdr = MapxRawMk::new(2);
+        range.for_each(|i| {
+            pnk!(hdr.insert(&[&[i / 10], &[i % 10]], &[v]));
+        });
+        hdr
+    };
+
+    let mut a = fill(0..100, 0);
+    let b = fill(50..150, 1);
+    assert_eq!(100, a.len());
+    assert_eq!(100, b.len());
+
+    assert_eq!(50, pnk!(a.merge_from(&b)));
+    assert_eq!(150, a.len());
+    assert_eq!(100, b.len());
+    (0..150u8).for_each(|i| {
+        let v = pnk!(a.get(&[&[i / 10], &[i % 10]]));
+        assert_eq!(vec![(i >= 50) as u8], v);
+    });
+
+    // nothing new
+    assert_eq!(0, pnk!(a.merge_from(&b)));
+    assert_eq!(150, a.len());
+
+    let c = pnk!(fill(0..10, 0).merge(fill(5..20, 1)));
+    assert_eq!(20, c.len());
+    assert_eq!(Some(vec![0]), c.get(&[&[0], &[4]]));
+    assert_eq!(Some(vec![1]), c.get(&[&[0], &[5]]));
+
+    assert!(a.merge_from(&MapxRawMk::new(3)).is_err());
+}
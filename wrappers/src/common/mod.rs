@@ -5,3 +5,20 @@
 pub mod ende;
 
 pub use vsdb_core::common::*;
+
+use std::ops::Bound;
+
+/// Check if a pair of bounds can not contain anything.
+///
+/// NOTE: `BTreeMap::range` will panic if the start is greater than the end,
+/// or if they are equal and both are excluded.
+#[inline(always)]
+pub(crate) fn bounds_is_empty<T: Ord>(lo: &Bound<T>, hi: &Bound<T>) -> bool {
+    match (lo, hi) {
+        (Bound::Included(l), Bound::Included(h)) => l > h,
+        (Bound::Included(l), Bound::Excluded(h))
+        | (Bound::Excluded(l), Bound::Included(h))
+        | (Bound::Excluded(l), Bound::Excluded(h)) => l >= h,
+        _ => false,
+    }
+}
@@ -30,7 +30,8 @@ pub use versioned::{mapx::MapxVs, mapx_ord::MapxOrdVs, orphan::OrphanVs, vecx::V
 
 #[cfg(feature = "vs")]
 pub use versioned_multi_key::{
-    mapx_double_key::MapxDkVs, mapx_raw::MapxRawMkVs, mapx_triple_key::MapxTkVs,
+    mapx_double_key::MapxDkVs, mapx_n_key::MapxNkVs, mapx_raw::MapxRawMkVs,
+    mapx_triple_key::MapxTkVs,
 };
 
 pub use vsdb_core::{self, *};
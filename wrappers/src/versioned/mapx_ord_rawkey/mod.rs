@@ -6,15 +6,16 @@
 mod test;
 
 use crate::{
-    common::{ende::ValueEnDe, BranchName, RawKey, VersionName},
+    common::{ende::ValueEnDe, BranchName, RawKey, RawValue, VersionName},
     VsMgmt,
 };
 use ruc::*;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     marker::PhantomData,
-    ops::{Deref, DerefMut, RangeBounds},
+    ops::{Bound, Deref, DerefMut, RangeBounds},
 };
 use vsdb_core::versioned::mapx_raw::{self, MapxRawVs, MapxRawVsIter, MapxRawVsIterMut};
 
@@ -68,8 +69,8 @@ where
     }
 
     #[inline(always)]
-    pub fn entry<'a>(&'a mut self, key: &'a [u8]) -> Entry<'a, V> {
-        Entry { key, hdr: self }
+    pub fn entry<'a>(&'a mut self, key: &'a [u8]) -> EntryOrkVs<'a, V> {
+        EntryOrkVs { key, hdr: self }
     }
 
     #[inline(always)]
@@ -246,6 +247,37 @@ where
         }
     }
 
+    /// Iterate over the keys of the default branch,
+    /// values will not be decoded.
+    #[inline(always)]
+    pub fn keys(&self) -> impl Iterator<Item = RawKey> + '_ {
+        self.inner.iter().map(|(k, _)| k)
+    }
+
+    #[inline(always)]
+    pub fn keys_by_branch(
+        &self,
+        br_name: BranchName,
+    ) -> impl Iterator<Item = RawKey> + '_ {
+        self.inner.iter_by_branch(br_name).map(|(k, _)| k)
+    }
+
+    #[inline(always)]
+    pub fn keys_by_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> impl Iterator<Item = RawKey> + '_ {
+        self.inner
+            .iter_by_branch_version(br_name, ver_name)
+            .map(|(k, _)| k)
+    }
+
+    #[inline(always)]
+    pub fn values(&self) -> impl Iterator<Item = V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
     #[inline(always)]
     pub fn range<'a, R: RangeBounds<Cow<'a, [u8]>>>(
         &'a self,
@@ -295,6 +327,46 @@ where
         }
     }
 
+    /// Iterate all keys starting with the `prefix`.
+    ///
+    /// NOTE: if the `prefix` is empty or consists of `0xFF` bytes only,
+    /// there is no upper bound, the iteration will go to the end.
+    #[inline(always)]
+    pub fn range_with_prefix<'a>(&'a self, prefix: &[u8]) -> MapxOrdRawKeyVsIter<'a, V> {
+        self.range(prefix_bounds(prefix))
+    }
+
+    /// The same as `range_with_prefix`, but on the head of a specified branch.
+    #[inline(always)]
+    pub fn range_with_prefix_by_branch<'a>(
+        &'a self,
+        br_name: BranchName,
+        prefix: &[u8],
+    ) -> MapxOrdRawKeyVsIter<'a, V> {
+        self.range_by_branch(br_name, prefix_bounds(prefix))
+    }
+
+    /// The same as `range_with_prefix`,
+    /// but on a specified version of a specified branch.
+    #[inline(always)]
+    pub fn range_with_prefix_by_branch_version<'a>(
+        &'a self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        prefix: &[u8],
+    ) -> MapxOrdRawKeyVsIter<'a, V> {
+        self.range_by_branch_version(br_name, ver_name, prefix_bounds(prefix))
+    }
+
+    /// Create a cursor over the default branch, it is invalid until the first move.
+    #[inline(always)]
+    pub fn cursor(&self) -> MapxOrdRawKeyVsCursor<'_, V> {
+        MapxOrdRawKeyVsCursor {
+            hdr: self,
+            current: None,
+        }
+    }
+
     #[inline(always)]
     pub fn first(&self) -> Option<(RawKey, V)> {
         self.iter().next()
@@ -374,6 +446,120 @@ where
             .map(|v| v.map(|v| <V as ValueEnDe>::decode(&v).unwrap()))
     }
 
+    /// Remove all the keys on the default branch,
+    /// the previous values are returned in the same order as `keys`.
+    pub fn batch_remove(&mut self, keys: &[impl AsRef<[u8]>]) -> Result<Vec<Option<V>>> {
+        keys.iter()
+            .map(|k| self.remove(k.as_ref()).c(d!()))
+            .collect()
+    }
+
+    pub fn batch_remove_by_branch(
+        &mut self,
+        keys: &[impl AsRef<[u8]>],
+        br_name: BranchName,
+    ) -> Result<Vec<Option<V>>> {
+        keys.iter()
+            .map(|k| self.remove_by_branch(k.as_ref(), br_name).c(d!()))
+            .collect()
+    }
+
+    /// Remove the first entry on the default branch and return it.
+    #[inline(always)]
+    pub fn pop_first(&mut self) -> Result<Option<(RawKey, V)>> {
+        match self.first() {
+            Some((k, v)) => self.inner.remove(&k).c(d!()).map(|_| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the first entry on a specified branch and return it.
+    #[inline(always)]
+    pub fn pop_first_by_branch(
+        &mut self,
+        br_name: BranchName,
+    ) -> Result<Option<(RawKey, V)>> {
+        match self.first_by_branch(br_name) {
+            Some((k, v)) => self
+                .inner
+                .remove_by_branch(&k, br_name)
+                .c(d!())
+                .map(|_| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the last entry on the default branch and return it.
+    #[inline(always)]
+    pub fn pop_last(&mut self) -> Result<Option<(RawKey, V)>> {
+        match self.last() {
+            Some((k, v)) => self.inner.remove(&k).c(d!()).map(|_| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the last entry on a specified branch and return it.
+    #[inline(always)]
+    pub fn pop_last_by_branch(
+        &mut self,
+        br_name: BranchName,
+    ) -> Result<Option<(RawKey, V)>> {
+        match self.last_by_branch(br_name) {
+            Some((k, v)) => self
+                .inner
+                .remove_by_branch(&k, br_name)
+                .c(d!())
+                .map(|_| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove all keys within the `bounds`,
+    /// return the number of the removed entries.
+    pub fn remove_range<R: RangeBounds<RawKey>>(&mut self, bounds: R) -> Result<u64> {
+        let keys = self
+            .range(raw_bounds(&bounds))
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>();
+
+        let mut cnt = 0;
+        for k in keys.iter() {
+            if self.inner.remove(k).c(d!())?.is_some() {
+                cnt += 1;
+            }
+        }
+
+        Ok(cnt)
+    }
+
+    pub fn remove_range_by_branch<R: RangeBounds<RawKey>>(
+        &mut self,
+        br_name: BranchName,
+        bounds: R,
+    ) -> Result<u64> {
+        let keys = self
+            .range_by_branch(br_name, raw_bounds(&bounds))
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>();
+
+        let mut cnt = 0;
+        for k in keys.iter() {
+            if self.inner.remove_by_branch(k, br_name).c(d!())?.is_some() {
+                cnt += 1;
+            }
+        }
+
+        Ok(cnt)
+    }
+
+    /// Collect all entries of the default branch into a `BTreeMap`.
+    ///
+    /// NOTE: all entries will be loaded into memory.
+    #[inline(always)]
+    pub fn collect_into_btreemap(&self) -> BTreeMap<RawKey, V> {
+        self.iter().collect()
+    }
+
     #[inline(always)]
     pub fn clear(&mut self) {
         self.inner.clear();
@@ -401,6 +587,21 @@ where
     }
 }
 
+impl<V> From<BTreeMap<RawKey, V>> for MapxOrdRawKeyVs<V>
+where
+    V: ValueEnDe,
+{
+    /// All entries will be inserted into an initial version named `INITIAL`.
+    fn from(m: BTreeMap<RawKey, V>) -> Self {
+        let mut hdr = Self::new();
+        pnk!(hdr.version_create(VersionName(b"INITIAL")));
+        m.iter().for_each(|(k, v)| {
+            pnk!(hdr.insert(k, v));
+        });
+        hdr
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
@@ -448,6 +649,79 @@ where
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
+/// A bidirectional cursor, every move is a range query
+/// starting from the key of the current position.
+///
+/// Moving from an invalid position, `next` goes to the first entry,
+/// and `prev` goes to the last entry.
+pub struct MapxOrdRawKeyVsCursor<'a, V>
+where
+    V: ValueEnDe,
+{
+    hdr: &'a MapxOrdRawKeyVs<V>,
+    current: Option<(RawKey, V)>,
+}
+
+impl<'a, V> MapxOrdRawKeyVsCursor<'a, V>
+where
+    V: ValueEnDe,
+{
+    /// Move to the first entry whose key is greater than or equal to `key`.
+    #[inline(always)]
+    pub fn seek(&mut self, key: &[u8]) {
+        let kv = self.hdr.inner.get_ge(key);
+        self.set(kv);
+    }
+
+    /// Move to the next entry and return it.
+    pub fn next(&mut self) -> Option<(RawKey, V)> {
+        let kv = if let Some((k, _)) = self.current.as_ref() {
+            self.hdr
+                .inner
+                .range((Bound::Excluded(Cow::Borrowed(&k[..])), Bound::Unbounded))
+                .next()
+        } else {
+            self.hdr.inner.first()
+        };
+        self.set(kv)
+    }
+
+    /// Move to the previous entry and return it.
+    pub fn prev(&mut self) -> Option<(RawKey, V)> {
+        let kv = if let Some((k, _)) = self.current.as_ref() {
+            self.hdr
+                .inner
+                .range((Bound::Unbounded, Bound::Excluded(Cow::Borrowed(&k[..]))))
+                .next_back()
+        } else {
+            self.hdr.inner.last()
+        };
+        self.set(kv)
+    }
+
+    #[inline(always)]
+    pub fn current(&self) -> Option<(&[u8], &V)> {
+        self.current.as_ref().map(|(k, v)| (&k[..], v))
+    }
+
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    // `V` is not required to be `Clone`,
+    // so the value is decoded twice, one for the cursor itself.
+    fn set(&mut self, kv: Option<(RawKey, RawValue)>) -> Option<(RawKey, V)> {
+        self.current = kv
+            .as_ref()
+            .map(|(k, v)| (k.clone(), <V as ValueEnDe>::decode(v).unwrap()));
+        kv.map(|(k, v)| (k, <V as ValueEnDe>::decode(&v).unwrap()))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug)]
 pub struct ValueMut<'a, V: ValueEnDe> {
     hdr: &'a mut MapxOrdRawKeyVs<V>,
@@ -495,25 +769,44 @@ where
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
-pub struct Entry<'a, V: ValueEnDe> {
+pub type Entry<'a, V> = EntryOrkVs<'a, V>;
+
+pub struct EntryOrkVs<'a, V: ValueEnDe> {
     hdr: &'a mut MapxOrdRawKeyVs<V>,
     key: &'a [u8],
 }
 
-impl<'a, V> Entry<'a, V>
+impl<'a, V> EntryOrkVs<'a, V>
 where
     V: ValueEnDe,
 {
     pub fn or_insert(self, default: V) -> ValueMut<'a, V> {
+        self.or_insert_with(|| default)
+    }
+
+    /// The closure will not be called if the key exists.
+    pub fn or_insert_with<F>(self, f: F) -> ValueMut<'a, V>
+    where
+        F: FnOnce() -> V,
+    {
         let hdr = self.hdr as *mut MapxOrdRawKeyVs<V>;
         if let Some(v) = unsafe { &mut *hdr }.get_mut(self.key) {
             v
         } else {
-            unsafe { &mut *hdr }.gen_mut(self.key, default)
+            unsafe { &mut *hdr }.gen_mut(self.key, f())
         }
     }
 }
 
+impl<'a, V> EntryOrkVs<'a, V>
+where
+    V: ValueEnDe + Default,
+{
+    pub fn or_default(self) -> ValueMut<'a, V> {
+        self.or_insert_with(V::default)
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
@@ -602,3 +895,32 @@ where
 
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
+
+// The upper bound is got by incrementing the last non-`0xFF` byte,
+// and dropping all bytes behind it.
+fn prefix_bounds<'a>(prefix: &[u8]) -> (Bound<Cow<'a, [u8]>>, Bound<Cow<'a, [u8]>>) {
+    let lo = Bound::Included(Cow::Owned(prefix.to_vec()));
+
+    let mut hi = prefix.to_vec();
+    while let Some(last) = hi.pop() {
+        if u8::MAX > last {
+            hi.push(last + 1);
+            return (lo, Bound::Excluded(Cow::Owned(hi)));
+        }
+    }
+
+    (lo, Bound::Unbounded)
+}
+
+fn raw_bounds<R: RangeBounds<RawKey>>(
+    bounds: &R,
+) -> (Bound<Cow<'_, [u8]>>, Bound<Cow<'_, [u8]>>) {
+    fn conv(b: Bound<&RawKey>) -> Bound<Cow<'_, [u8]>> {
+        match b {
+            Bound::Included(k) => Bound::Included(Cow::Borrowed(&k[..])),
+            Bound::Excluded(k) => Bound::Excluded(Cow::Borrowed(&k[..])),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+    (conv(bounds.start_bound()), conv(bounds.end_bound()))
+}
@@ -443,6 +443,49 @@ fn test_iter() {
     assert_eq!(0, hdr.len());
 }
 
+#[test]
+fn test_iter_mut_rev() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..100usize).for_each(|i| {
+        assert!(pnk!(hdr.insert(&i.to_bytes(), &i)).is_none());
+    });
+
+    hdr.iter_mut().rev().for_each(|(_, mut v)| {
+        *v *= 2;
+    });
+    (0..100usize).for_each(|i| {
+        assert_eq!(i * 2, pnk!(hdr.get(&i.to_bytes())));
+    });
+
+    let lo = 10usize.to_bytes();
+    let hi = 20usize.to_bytes();
+    hdr.range_mut(Cow::Borrowed(&lo[..])..Cow::Borrowed(&hi[..]))
+        .rev()
+        .for_each(|(_, mut v)| {
+            *v += 1;
+        });
+    (0..100usize).for_each(|i| {
+        let expected = alt!((10..20).contains(&i), i * 2 + 1, i * 2);
+        assert_eq!(expected, pnk!(hdr.get(&i.to_bytes())));
+    });
+
+    // the changes are made on the head version only
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    hdr.iter_mut().for_each(|(_, mut v)| {
+        *v = 0;
+    });
+    assert!(hdr.iter().all(|(_, v)| 0 == v));
+    assert_eq!(
+        2,
+        pnk!(hdr.get_by_branch_version(
+            &1usize.to_bytes(),
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v0")
+        ))
+    );
+}
+
 #[test]
 fn test_first_last() {
     let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
@@ -480,3 +523,390 @@ fn test_by_branch() {
     assert!(!hdr.is_empty_by_branch(INITIAL_BRANCH_NAME));
     assert_eq!(max, hdr.len_by_branch(INITIAL_BRANCH_NAME));
 }
+
+#[test]
+fn test_branch_diff() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[0], &0));
+    pnk!(hdr.insert(&[1], &1));
+
+    let bn = BranchName(b"test");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert!(pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn)).is_empty());
+
+    pnk!(hdr.remove_by_branch(&[0], bn));
+    pnk!(hdr.insert_by_branch(&[2], &2, bn));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.insert(&[1], &9));
+
+    assert_eq!(
+        vec![
+            (vec![0], Some(0usize.encode()), None),
+            (vec![1], Some(9usize.encode()), Some(1usize.encode())),
+            (vec![2], None, Some(2usize.encode())),
+        ],
+        pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn))
+    );
+}
+
+#[test]
+fn test_entry() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    assert_eq!(1, *hdr.entry(&[1]).or_insert(1));
+    assert_eq!(1, *hdr.entry(&[1]).or_insert(9));
+    assert_eq!(1, pnk!(hdr.get(&[1])));
+
+    assert_eq!(1, *hdr.entry(&[1]).or_insert_with(|| panic!()));
+    assert_eq!(2, *hdr.entry(&[2]).or_insert_with(|| 2));
+    assert_eq!(2, pnk!(hdr.get(&[2])));
+
+    assert_eq!(0, *hdr.entry(&[3]).or_default());
+    assert_eq!(0, pnk!(hdr.get(&[3])));
+
+    *hdr.entry(&[1]).or_default() += 10;
+    assert_eq!(11, pnk!(hdr.get(&[1])));
+    *hdr.entry(&[4]).or_insert(4) *= 10;
+    assert_eq!(40, pnk!(hdr.get(&[4])));
+}
+
+#[test]
+fn test_range_with_prefix() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    let vn = VersionName(b"v0");
+    pnk!(hdr.version_create(vn));
+
+    let prefixes: [&[u8]; 3] = [b"user:", b"usf", &[0xFF, 0xFF]];
+    prefixes.iter().enumerate().for_each(|(i, p)| {
+        (0..10usize).for_each(|j| {
+            let key = [*p, &[j as u8][..]].concat();
+            pnk!(hdr.insert(&key, &(10 * i + j)));
+        });
+    });
+    // shorter than the prefix, must be excluded
+    pnk!(hdr.insert(b"user", &999));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch(b"user:x", &100, bn));
+
+    prefixes.iter().enumerate().for_each(|(i, p)| {
+        let expected = (0..10).map(|j| 10 * i + j).collect::<Vec<_>>();
+
+        let keys = hdr
+            .range_with_prefix(p)
+            .map(|(k, v)| {
+                assert!(k.starts_with(p));
+                v
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, keys);
+
+        let keys = hdr
+            .range_with_prefix_by_branch_version(INITIAL_BRANCH_NAME, vn, p)
+            .map(|(_, v)| v)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, keys);
+    });
+
+    assert_eq!(11, hdr.range_with_prefix_by_branch(bn, b"user:").count());
+    assert_eq!(10, hdr.range_with_prefix(b"user:").rev().count());
+    assert_eq!(21, hdr.range_with_prefix(b"us").count());
+    assert_eq!(31, hdr.range_with_prefix(&[]).count());
+    assert_eq!(0, hdr.range_with_prefix(b"x").count());
+}
+
+#[test]
+fn test_remove_range() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..50u8).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+
+    assert_eq!(0, pnk!(hdr.remove_range(vec![5]..vec![5])));
+    assert_eq!(0, pnk!(hdr.remove_range(vec![100]..)));
+    assert_eq!(50, hdr.len());
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert_eq!(10, pnk!(hdr.remove_range_by_branch(bn, vec![10]..vec![20])));
+    assert_eq!(40, hdr.len_by_branch(bn));
+    assert!(hdr.get_by_branch(&[9], bn).is_some());
+    assert!(hdr.get_by_branch(&[15], bn).is_none());
+    assert!(hdr.get_by_branch(&[20], bn).is_some());
+    assert_eq!(50, hdr.len());
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    assert_eq!(10, pnk!(hdr.remove_range(vec![0]..=vec![9])));
+    assert_eq!(40, hdr.len());
+    assert!(hdr.get(&[9]).is_none());
+    assert_eq!(Some(10), hdr.get(&[10]));
+    assert_eq!(Some(49), hdr.get(&[49]));
+
+    let n = hdr.len() as u64;
+    assert_eq!(n, pnk!(hdr.remove_range(..)));
+    assert!(hdr.is_empty());
+    assert_eq!(40, hdr.len_by_branch(bn));
+}
+
+#[test]
+fn test_batch_remove() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..100u8).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+
+    // half of them are not inserted
+    let keys = (50..150u8).map(|i| vec![i]).collect::<Vec<_>>();
+    let ret = pnk!(hdr.batch_remove(&keys));
+    assert_eq!(100, ret.len());
+    assert_eq!((50..100).map(Some).collect::<Vec<_>>(), ret[..50].to_vec());
+    assert!(ret[50..].iter().all(|v| v.is_none()));
+    assert_eq!(50, hdr.len());
+    assert!(hdr.get(&[49]).is_some());
+    assert!(hdr.get(&[50]).is_none());
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    let ret = pnk!(hdr.batch_remove_by_branch(&[[0u8], [0], [99]], bn));
+    assert_eq!(vec![Some(0), None, None], ret);
+    assert_eq!(49, hdr.len_by_branch(bn));
+    assert_eq!(50, hdr.len());
+
+    assert!(
+        hdr.batch_remove_by_branch(&[[1u8]], BranchName(b"nope"))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_pop_first_last() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    assert!(pnk!(hdr.pop_first()).is_none());
+    assert!(pnk!(hdr.pop_last()).is_none());
+
+    // LIFO, as a stack
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+    (0..10u8).rev().for_each(|i| {
+        let n = hdr.len();
+        assert_eq!(Some((vec![i], i as usize)), pnk!(hdr.pop_last()));
+        assert_eq!(n - 1, hdr.len());
+    });
+    assert!(pnk!(hdr.pop_last()).is_none());
+
+    // FIFO, as a queue
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+    (0..10u8).for_each(|i| {
+        let n = hdr.len();
+        assert_eq!(Some((vec![i], i as usize)), pnk!(hdr.pop_first()));
+        assert_eq!(n - 1, hdr.len());
+    });
+    assert!(pnk!(hdr.pop_first()).is_none());
+
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert_eq!(Some((vec![0], 0)), pnk!(hdr.pop_first_by_branch(bn)));
+    assert_eq!(Some((vec![9], 9)), pnk!(hdr.pop_last_by_branch(bn)));
+    assert_eq!(8, hdr.len_by_branch(bn));
+    assert_eq!(10, hdr.len());
+    assert!(pnk!(hdr.pop_first_by_branch(BranchName(b"nope"))).is_none());
+}
+
+#[test]
+fn test_cursor() {
+    let mut hdr: MapxOrdRawKeyVs<usize> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..20u8).map(|i| i * 2).for_each(|i| {
+        pnk!(hdr.insert(&[i], &(i as usize)));
+    });
+    pnk!(hdr.remove(&[10]));
+
+    let mut cursor = hdr.cursor();
+    assert!(!cursor.is_valid());
+    assert!(cursor.current().is_none());
+
+    // seek to an absent key, stop at the next one
+    cursor.seek(&[5]);
+    assert_eq!(Some((&[6][..], &6)), cursor.current());
+
+    // the removed `10` is skipped
+    for _ in 0..5 {
+        assert!(cursor.next().is_some());
+    }
+    assert_eq!(Some((&[18][..], &18)), cursor.current());
+
+    for _ in 0..3 {
+        assert!(cursor.prev().is_some());
+    }
+    assert_eq!(Some((&[12][..], &12)), cursor.current());
+    assert_eq!(Some((vec![8], 8)), cursor.prev());
+
+    // off the ends
+    cursor.seek(&[38]);
+    assert!(cursor.next().is_none());
+    assert!(!cursor.is_valid());
+    assert_eq!(Some((vec![0], 0)), cursor.next());
+    assert!(cursor.prev().is_none());
+    assert_eq!(Some((vec![38], 38)), cursor.prev());
+
+    cursor.seek(&[39]);
+    assert!(!cursor.is_valid());
+}
+
+#[test]
+fn test_btreemap_conversion() {
+    let m = (0..500u64)
+        .map(|i| ((i * 7919 % 500).to_be_bytes().to_vec(), i))
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(500, m.len());
+
+    let hdr = MapxOrdRawKeyVs::from(m.clone());
+    assert_eq!(500, hdr.len());
+    assert!(hdr.version_exists(VersionName(b"INITIAL")));
+    m.iter().for_each(|(k, v)| {
+        assert_eq!(*v, pnk!(hdr.get(k)));
+    });
+
+    assert_eq!(m, hdr.collect_into_btreemap());
+
+    let empty: MapxOrdRawKeyVs<u64> = MapxOrdRawKeyVs::new();
+    assert!(empty.collect_into_btreemap().is_empty());
+}
+
+#[test]
+fn test_keys_values() {
+    let mut hdr: MapxOrdRawKeyVs<u64> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..100u64).for_each(|i| {
+        pnk!(hdr.insert(&i.to_be_bytes(), &i));
+    });
+
+    let keys = hdr.keys().collect::<Vec<_>>();
+    assert_eq!(100, keys.len());
+    assert_eq!(keys, hdr.iter().map(|(k, _)| k).collect::<Vec<_>>());
+    assert_eq!(
+        (0..100u64).collect::<Vec<_>>(),
+        hdr.values().collect::<Vec<_>>()
+    );
+
+    pnk!(hdr.branch_create(BranchName(b"b"), VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch(&100u64.to_be_bytes(), &100, BranchName(b"b")));
+    assert_eq!(
+        hdr.keys_by_branch(BranchName(b"b")).collect::<Vec<_>>(),
+        hdr.iter_by_branch(BranchName(b"b"))
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(101, hdr.keys_by_branch(BranchName(b"b")).count());
+    assert_eq!(100, hdr.keys().count());
+    assert_eq!(
+        keys,
+        hdr.keys_by_branch_version(BranchName(b"b"), VersionName(b"v0"))
+            .collect::<Vec<_>>()
+    );
+}
+
+// The patch is forwarded to the inner raw map as it is,
+// so the values must be in the encoded form.
+#[test]
+fn test_version_apply_patch() {
+    let mut hdr: MapxOrdRawKeyVs<u64> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[1], &1));
+    pnk!(hdr.insert(&[2], &2));
+
+    let vn = VersionName(b"p0");
+    let changes = vec![(vec![1], None), (vec![3], Some(3u64.encode()))];
+    pnk!(hdr.version_apply_patch(INITIAL_BRANCH_NAME, vn, &changes));
+
+    assert!(hdr.version_exists(vn));
+    assert!(hdr.get(&[1]).is_none());
+    assert_eq!(Some(2), hdr.get(&[2]));
+    assert_eq!(Some(3), hdr.get(&[3]));
+    assert!(
+        hdr.version_apply_patch(INITIAL_BRANCH_NAME, vn, &changes)
+            .is_err()
+    );
+}
+
+// `bcs` can not skip the missing fields,
+// and the values of the legacy layout are not compressed
+#[cfg(not(any(feature = "bcs_codec", feature = "compressed-values")))]
+#[test]
+fn test_decode_legacy_layout() {
+    use crate::common::{BranchID, BranchNameOwned, VersionNameOwned};
+    use vsdb_core::MapxRaw;
+
+    // the persisted fields of the inner `MapxRawVs`
+    #[derive(Deserialize)]
+    struct Current {
+        default_branch: BranchID,
+        br_name_to_br_id: MapxRaw,
+        ver_name_to_ver_id: MapxRaw,
+        br_to_its_vers: MapxRaw,
+        layered_kv: MapxRaw,
+        #[allow(dead_code)]
+        br_to_parent: MapxRaw,
+        #[allow(dead_code)]
+        compressed: bool,
+    }
+
+    // the layout without `br_to_parent` and `compressed`
+    #[derive(Serialize)]
+    struct Legacy {
+        default_branch: BranchID,
+        br_name_to_br_id: MapxRaw,
+        ver_name_to_ver_id: MapxRaw,
+        br_to_its_vers: MapxRaw,
+        layered_kv: MapxRaw,
+    }
+
+    let bn = BranchName(b"b");
+    let mut hdr: MapxOrdRawKeyVs<u64> = MapxOrdRawKeyVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[1], &1));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch(&[2], &2, bn));
+
+    let c = pnk!(<Current as ValueEnDe>::decode(&hdr.inner.encode()));
+    let legacy = Legacy {
+        default_branch: c.default_branch,
+        br_name_to_br_id: c.br_name_to_br_id,
+        ver_name_to_ver_id: c.ver_name_to_ver_id,
+        br_to_its_vers: c.br_to_its_vers,
+        layered_kv: c.layered_kv,
+    };
+    let mut hdr = MapxOrdRawKeyVs::<u64> {
+        inner: pnk!(<MapxRawVs as ValueEnDe>::decode(&legacy.encode())),
+        p: PhantomData,
+    };
+
+    assert_eq!(Some(1), hdr.get(&[1]));
+    assert!(hdr.get(&[2]).is_none());
+    assert_eq!(Some(2), hdr.get_by_branch(&[2], bn));
+    assert_eq!(
+        vec![VersionNameOwned(b"v0".to_vec())],
+        pnk!(hdr.version_list())
+    );
+
+    // the parents of the old branches are unknown
+    assert!(pnk!(hdr.branch_ancestors(bn)).is_empty());
+    pnk!(hdr.branch_create(BranchName(b"c"), VersionName(b"c0"), false));
+    assert_eq!(
+        vec![BranchNameOwned(INITIAL_BRANCH_NAME.0.to_vec())],
+        pnk!(hdr.branch_ancestors(BranchName(b"c")))
+    );
+}
@@ -6,7 +6,7 @@ use crate::{
         ende::{KeyEnDe, ValueEnDe},
         RawValue,
     },
-    versioned_multi_key::mapx_raw::MapxRawMkVs,
+    versioned_multi_key::mapx_raw::{MapxRawMkVs, MapxRawMkVsIter},
     BranchName, VersionName, VsMgmt,
 };
 use ruc::*;
@@ -334,6 +334,22 @@ where
             )
             .c(d!())
     }
+
+    /// Iterate over the entries with the specified `k1` on the default branch,
+    /// the iterator is bound to the head version at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1(&self, k1: &K1) -> MapxDkVsIter<'_, K1, K2, V> {
+        let k1 = k1.encode();
+        MapxDkVsIter::new(self.inner.iter_with_key_prefix(&[&k1[..]]))
+    }
+
+    /// Iterate over the entry with the specified full key on the default branch,
+    /// at most one entry will be yielded.
+    #[inline(always)]
+    pub fn iter_by_k1_k2(&self, k1: &K1, k2: &K2) -> MapxDkVsIter<'_, K1, K2, V> {
+        let key = Self::encode_key(&(k1, k2));
+        MapxDkVsIter::new(self.inner.iter_with_key_prefix(&keyref(&key)))
+    }
 }
 
 impl<K1, K2, V> Clone for MapxDkVs<K1, K2, V> {
@@ -365,6 +381,57 @@ where
     crate::impl_vs_methods!();
 }
 
+pub struct MapxDkVsIter<'a, K1, K2, V> {
+    iter: MapxRawMkVsIter<'a>,
+    p: PhantomData<(K1, K2, V)>,
+}
+
+impl<'a, K1, K2, V> MapxDkVsIter<'a, K1, K2, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    V: ValueEnDe,
+{
+    fn new(iter: MapxRawMkVsIter<'a>) -> Self {
+        Self {
+            iter,
+            p: PhantomData,
+        }
+    }
+
+    fn decode(k: Vec<RawValue>, v: RawValue) -> (K1, K2, V) {
+        assert_eq!(KEY_SIZE, k.len(), "key size mismatch");
+        (
+            pnk!(KeyEnDe::decode(&k[0])),
+            pnk!(KeyEnDe::decode(&k[1])),
+            pnk!(ValueEnDe::decode(&v)),
+        )
+    }
+}
+
+impl<'a, K1, K2, V> Iterator for MapxDkVsIter<'a, K1, K2, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    V: ValueEnDe,
+{
+    type Item = (K1, K2, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| Self::decode(k, v))
+    }
+}
+
+impl<'a, K1, K2, V> DoubleEndedIterator for MapxDkVsIter<'a, K1, K2, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    V: ValueEnDe,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, v)| Self::decode(k, v))
+    }
+}
+
 #[derive(Debug)]
 pub struct ValueMut<'a, K1, K2, V>
 where
@@ -375,3 +375,44 @@ fn test_prune() {
     assert!(!hdr.version_exists(VersionName(b"b")));
     assert!(hdr.version_exists(VersionName(b"c")));
 }
+
+#[test]
+fn test_iter_by_prefix() {
+    let mut hdr: MapxDkVs<usize, usize, usize> = MapxDkVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    for k1 in 0..4 {
+        for k2 in 0..4 {
+            pnk!(hdr.insert(&(&k1, &k2), &(k1 * 10 + k2)));
+        }
+    }
+    pnk!(hdr.remove(&(&1, Some(&1))));
+
+    let entries = hdr.iter_by_k1(&1).collect::<Vec<_>>();
+    assert_eq!(3, entries.len());
+    assert!(entries.iter().all(|(k1, _, _)| 1 == *k1));
+    assert!(!entries.contains(&(1, 1, 11)));
+    assert_eq!(Some((1, 0, 10)), hdr.iter_by_k1(&1).next());
+    assert_eq!(Some((1, 3, 13)), hdr.iter_by_k1(&1).next_back());
+
+    let mut iter = hdr.iter_by_k1(&2);
+    assert_eq!(Some((2, 0, 20)), iter.next());
+    assert_eq!(Some((2, 3, 23)), iter.next_back());
+    assert_eq!(Some((2, 1, 21)), iter.next());
+    assert_eq!(Some((2, 2, 22)), iter.next_back());
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+
+    assert_eq!(
+        vec![(3, 2, 32)],
+        hdr.iter_by_k1_k2(&3, &2).collect::<Vec<_>>()
+    );
+    assert!(hdr.iter_by_k1_k2(&1, &1).next().is_none());
+    assert!(hdr.iter_by_k1(&9).next().is_none());
+
+    // remove all entries with the same `k1`
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.remove(&(&3, None)));
+    assert!(hdr.iter_by_k1(&3).next().is_none());
+    assert_eq!(4, hdr.iter_by_k1(&2).count());
+}
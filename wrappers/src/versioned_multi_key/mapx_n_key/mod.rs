@@ -0,0 +1,328 @@
+#[cfg(test)]
+mod test;
+
+use crate::{
+    common::{
+        ende::{KeyEnDe, ValueEnDe},
+        RawValue,
+    },
+    versioned_multi_key::mapx_raw::MapxRawMkVs,
+    BranchName, VersionName, VsMgmt,
+};
+use ruc::*;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// A versioned map structure with `N`-level keys.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct MapxNkVs<const N: usize, K, V> {
+    inner: MapxRawMkVs,
+    p: PhantomData<(K, V)>,
+}
+
+impl<const N: usize, K, V> MapxNkVs<N, K, V>
+where
+    K: KeyEnDe,
+    V: ValueEnDe,
+{
+    /// # Safety
+    ///
+    /// This API breaks the semantic safety guarantees,
+    /// but it is safe to use in a race-free environment.
+    #[inline(always)]
+    pub unsafe fn shadow(&self) -> Self {
+        Self {
+            inner: self.inner.shadow(),
+            p: PhantomData,
+        }
+    }
+
+    /// Panic if `N` is zero.
+    #[inline(always)]
+    pub fn new() -> Self {
+        assert!(0 < N, "the number of keys must be greater than zero");
+        MapxNkVs {
+            inner: MapxRawMkVs::new(N as u32),
+            p: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, key: &[&K; N]) -> Option<V> {
+        let key = encode_key(key);
+        self.inner
+            .get(&keyref(&key))
+            .map(|v| pnk!(ValueEnDe::decode(&v)))
+    }
+
+    #[inline(always)]
+    pub fn insert(&mut self, key: &[&K; N], value: &V) -> Result<Option<V>> {
+        let key = encode_key(key);
+        self.inner
+            .insert(&keyref(&key), &value.encode())
+            .c(d!())
+            .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
+    }
+
+    #[inline(always)]
+    pub fn contains_key(&self, key: &[&K; N]) -> bool {
+        let key = encode_key(key);
+        self.inner.contains_key(&keyref(&key))
+    }
+
+    /// Support batch removal,
+    /// all entries prefixed by a partial key will be removed.
+    #[inline(always)]
+    pub fn remove(&mut self, key: &[&K]) -> Result<Option<V>> {
+        let key = encode_key_prefix::<N, K>(key).c(d!())?;
+        self.inner
+            .remove(&keyref(&key))
+            .c(d!())
+            .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
+    }
+
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    #[inline(always)]
+    pub fn get_by_branch(&self, key: &[&K; N], br_name: BranchName) -> Option<V> {
+        let key = encode_key(key);
+        self.inner
+            .get_by_branch(&keyref(&key), br_name)
+            .map(|v| pnk!(ValueEnDe::decode(&v)))
+    }
+
+    #[inline(always)]
+    pub fn insert_by_branch(
+        &mut self,
+        key: &[&K; N],
+        value: &V,
+        br_name: BranchName,
+    ) -> Result<Option<V>> {
+        let key = encode_key(key);
+        self.inner
+            .insert_by_branch(&keyref(&key), &value.encode(), br_name)
+            .c(d!())
+            .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
+    }
+
+    #[inline(always)]
+    pub fn contains_key_by_branch(&self, key: &[&K; N], br_name: BranchName) -> bool {
+        let key = encode_key(key);
+        self.inner.contains_key_by_branch(&keyref(&key), br_name)
+    }
+
+    /// Support batch removal,
+    /// all entries prefixed by a partial key will be removed.
+    #[inline(always)]
+    pub fn remove_by_branch(
+        &mut self,
+        key: &[&K],
+        br_name: BranchName,
+    ) -> Result<Option<V>> {
+        let key = encode_key_prefix::<N, K>(key).c(d!())?;
+        self.inner
+            .remove_by_branch(&keyref(&key), br_name)
+            .c(d!())
+            .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
+    }
+
+    #[inline(always)]
+    pub fn get_by_branch_version(
+        &self,
+        key: &[&K; N],
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Option<V> {
+        let key = encode_key(key);
+        self.inner
+            .get_by_branch_version(&keyref(&key), br_name, ver_name)
+            .map(|v| pnk!(ValueEnDe::decode(&v)))
+    }
+
+    #[inline(always)]
+    pub fn contains_key_by_branch_version(
+        &self,
+        key: &[&K; N],
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> bool {
+        let key = encode_key(key);
+        self.inner
+            .contains_key_by_branch_version(&keyref(&key), br_name, ver_name)
+    }
+
+    #[inline(always)]
+    pub fn iter_op<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner.iter_op(&mut cb).c(d!())
+    }
+
+    pub fn iter_op_by_branch<F>(&self, br_name: BranchName, op: &mut F) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner.iter_op_by_branch(br_name, &mut cb).c(d!())
+    }
+
+    pub fn iter_op_by_branch_version<F>(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        op: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner
+            .iter_op_by_branch_version(br_name, ver_name, &mut cb)
+            .c(d!())
+    }
+
+    pub fn iter_op_with_key_prefix<F>(&self, op: &mut F, key_prefix: &[&K]) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let key_prefix = encode_key_prefix::<N, K>(key_prefix).c(d!())?;
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner
+            .iter_op_with_key_prefix(&mut cb, &keyref(&key_prefix))
+            .c(d!())
+    }
+
+    pub fn iter_op_with_key_prefix_by_branch<F>(
+        &self,
+        br_name: BranchName,
+        op: &mut F,
+        key_prefix: &[&K],
+    ) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let key_prefix = encode_key_prefix::<N, K>(key_prefix).c(d!())?;
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner
+            .iter_op_with_key_prefix_by_branch(br_name, &mut cb, &keyref(&key_prefix))
+            .c(d!())
+    }
+
+    pub fn iter_op_with_key_prefix_by_branch_version<F>(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        op: &mut F,
+        key_prefix: &[&K],
+    ) -> Result<()>
+    where
+        F: FnMut([K; N], V) -> Result<()>,
+    {
+        let key_prefix = encode_key_prefix::<N, K>(key_prefix).c(d!())?;
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            let (k, v) = decode_kv::<N, K, V>(k, v).c(d!())?;
+            op(k, v).c(d!())
+        };
+
+        self.inner
+            .iter_op_with_key_prefix_by_branch_version(
+                br_name,
+                ver_name,
+                &mut cb,
+                &keyref(&key_prefix),
+            )
+            .c(d!())
+    }
+}
+
+impl<const N: usize, K, V> Clone for MapxNkVs<N, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            p: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, K, V> Default for MapxNkVs<N, K, V>
+where
+    K: KeyEnDe,
+    V: ValueEnDe,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, K, V> VsMgmt for MapxNkVs<N, K, V>
+where
+    K: KeyEnDe,
+    V: ValueEnDe,
+{
+    crate::impl_vs_methods!();
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////////
+
+#[inline(always)]
+fn encode_key<K: KeyEnDe>(key: &[&K]) -> Vec<RawValue> {
+    key.iter().map(|k| k.encode()).collect()
+}
+
+// A partial key must not be empty or longer than `N`
+#[inline(always)]
+fn encode_key_prefix<const N: usize, K: KeyEnDe>(key: &[&K]) -> Result<Vec<RawValue>> {
+    if key.is_empty() || key.len() > N {
+        return Err(eg!("invalid key size"));
+    }
+    Ok(encode_key(key))
+}
+
+#[inline(always)]
+fn keyref(key: &[RawValue]) -> Vec<&[u8]> {
+    key.iter().map(|k| &k[..]).collect()
+}
+
+fn decode_kv<const N: usize, K, V>(k: &[&[u8]], v: RawValue) -> Result<([K; N], V)>
+where
+    K: KeyEnDe,
+    V: ValueEnDe,
+{
+    if N != k.len() {
+        return Err(eg!("key size mismatch"));
+    }
+    let k = k
+        .iter()
+        .map(|k| KeyEnDe::decode(k).c(d!()))
+        .collect::<Result<Vec<K>>>()?;
+    let k = <[K; N]>::try_from(k).ok().c(d!())?;
+    let v = ValueEnDe::decode(&v).c(d!())?;
+    Ok((k, v))
+}
@@ -0,0 +1,126 @@
+use super::*;
+use crate::{
+    common::{BranchName, INITIAL_BRANCH_NAME},
+    ValueEnDe, VsMgmt,
+};
+
+// Generate all keys with `N` levels, each level has `width` kinds of values
+fn all_keys<const N: usize>(width: usize) -> Vec<[usize; N]> {
+    (0..width.pow(N as u32))
+        .map(|mut i| {
+            let mut k = [0; N];
+            k.iter_mut().rev().for_each(|k| {
+                *k = i % width;
+                i /= width;
+            });
+            k
+        })
+        .collect()
+}
+
+fn value_of<const N: usize>(key: &[usize; N]) -> usize {
+    key.iter().fold(0, |acc, k| acc * 10 + k)
+}
+
+fn keyref<const N: usize>(key: &[usize; N]) -> [&usize; N] {
+    let mut ret = [&key[0]; N];
+    ret.iter_mut().zip(key.iter()).for_each(|(r, k)| *r = k);
+    ret
+}
+
+fn round_trip<const N: usize>() {
+    let mut hdr: MapxNkVs<N, usize, usize> = MapxNkVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let keys = all_keys::<N>(3);
+    keys.iter().for_each(|k| {
+        assert!(pnk!(hdr.insert(&keyref(k), &value_of(k))).is_none());
+    });
+    keys.iter().for_each(|k| {
+        assert!(hdr.contains_key(&keyref(k)));
+        assert_eq!(value_of(k), pnk!(hdr.get(&keyref(k))));
+    });
+
+    let mut cnt = 0;
+    pnk!(hdr.iter_op(&mut |k: [usize; N], v: usize| {
+        assert_eq!(value_of(&k), v);
+        cnt += 1;
+        Ok(())
+    }));
+    assert_eq!(keys.len(), cnt);
+
+    let mut cnt = 0;
+    pnk!(hdr.iter_op_with_key_prefix(
+        &mut |k: [usize; N], _| {
+            assert_eq!(1, k[0]);
+            cnt += 1;
+            Ok(())
+        },
+        &[&1]
+    ));
+    assert_eq!(keys.len() / 3, cnt);
+
+    // version-aware changes on a new branch
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    let k0 = keyref(&keys[0]);
+    assert_eq!(Some(0), pnk!(hdr.insert_by_branch(&k0, &999, bn)));
+    pnk!(hdr.remove_by_branch(&[&2], bn));
+
+    assert_eq!(999, pnk!(hdr.get_by_branch(&k0, bn)));
+    assert_eq!(0, pnk!(hdr.get_by_branch(&k0, INITIAL_BRANCH_NAME)));
+    assert_eq!(
+        0,
+        pnk!(hdr.get_by_branch_version(&k0, bn, VersionName(b"v0")))
+    );
+
+    let mut cnt = 0;
+    pnk!(hdr.iter_op_by_branch(bn, &mut |k: [usize; N], _| {
+        assert_ne!(2, k[0]);
+        cnt += 1;
+        Ok(())
+    }));
+    assert_eq!(keys.len() / 3 * 2, cnt);
+
+    let mut cnt = 0;
+    pnk!(hdr.iter_op_with_key_prefix_by_branch_version(
+        bn,
+        VersionName(b"v0"),
+        &mut |_: [usize; N], _| {
+            cnt += 1;
+            Ok(())
+        },
+        &[&2]
+    ));
+    assert_eq!(keys.len() / 3, cnt);
+
+    // partial keys
+    pnk!(hdr.remove(&[&0, &0]));
+    assert!(!hdr.contains_key(&k0));
+    assert!(hdr.contains_key_by_branch(&k0, bn));
+    assert!(hdr.remove(&[]).is_err());
+    assert!(hdr.remove(&[&0; 6][..N + 1]).is_err());
+
+    pnk!(hdr.branch_remove(bn));
+    assert!(hdr.get_by_branch(&k0, bn).is_none());
+}
+
+#[test]
+fn test_round_trip() {
+    round_trip::<2>();
+    round_trip::<3>();
+    round_trip::<4>();
+    round_trip::<5>();
+}
+
+#[test]
+fn test_valueende() {
+    let dehdr = {
+        let mut hdr: MapxNkVs<4, usize, usize> = MapxNkVs::new();
+        pnk!(hdr.version_create(VersionName(b"v0")));
+        pnk!(hdr.insert(&[&1, &2, &3, &4], &1234));
+        <MapxNkVs<4, usize, usize> as ValueEnDe>::encode(&hdr)
+    };
+    let reloaded = pnk!(<MapxNkVs<4, usize, usize> as ValueEnDe>::decode(&dehdr));
+    assert_eq!(1234, pnk!(reloaded.get(&[&1, &2, &3, &4])));
+}
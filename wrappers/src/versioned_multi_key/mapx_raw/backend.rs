@@ -2,10 +2,10 @@ use crate::{
     basic::{mapx_ord::MapxOrd, mapx_ord_rawkey::MapxOrdRawKey},
     basic_multi_key::mapx_raw::MapxRawMk,
     common::{
-        trie_root, BranchID, BranchIDBase, BranchName, BranchNameOwned, RawKey,
-        RawValue, VersionID, VersionIDBase, VersionName, VersionNameOwned,
-        INITIAL_BRANCH_ID, INITIAL_BRANCH_NAME, NULL, RESERVED_VERSION_NUM_DEFAULT,
-        TRASH_CLEANER, VER_ID_MAX, VSDB,
+        bounds_is_empty, ende::ValueEnDe, trie_root, BranchID, BranchIDBase, BranchName,
+        BranchNameOwned, RawKey, RawValue, VersionID, VersionIDBase, VersionName,
+        VersionNameOwned, INITIAL_BRANCH_ID, INITIAL_BRANCH_NAME, NULL, NULL_ID,
+        RESERVED_VERSION_NUM_DEFAULT, TRASH_CLEANER, VER_ID_MAX, VSDB,
     },
 };
 use parking_lot::RwLock;
@@ -13,7 +13,8 @@ use ruc::*;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::{Bound, RangeBounds},
     result::Result as StdResult,
     sync::Arc,
 };
@@ -31,6 +32,8 @@ pub(super) struct MapxRawMkVs {
     ver_name_to_ver_id: MapxOrdRawKey<VersionID>,
     br_to_its_vers: MapxOrd<BranchID, MapxOrd<VersionID, ()>>,
     ver_to_change_set: MapxOrd<VersionID, MapxRawMk>,
+    // the base branch of each branch, the initial branch has no parent
+    br_to_parent: MapxOrd<BranchID, BranchID>,
 
     br_id_to_br_name: Arc<RwLock<HashMap<BranchID, RawValue>>>,
     ver_id_to_ver_name: Arc<RwLock<HashMap<VersionID, RawValue>>>,
@@ -47,6 +50,7 @@ impl Clone for MapxRawMkVs {
             ver_name_to_ver_id: self.ver_name_to_ver_id.clone(),
             br_to_its_vers: self.br_to_its_vers.clone(),
             ver_to_change_set: self.ver_to_change_set.clone(),
+            br_to_parent: self.br_to_parent.clone(),
 
             br_id_to_br_name: self.br_id_to_br_name.clone(),
             ver_id_to_ver_name: self.ver_id_to_ver_name.clone(),
@@ -83,6 +87,9 @@ pub(super) struct MapxRawMkVsWithoutDerivedFields {
     ver_name_to_ver_id: MapxOrdRawKey<VersionID>,
     br_to_its_vers: MapxOrd<BranchID, MapxOrd<VersionID, ()>>,
     ver_to_change_set: MapxOrd<VersionID, MapxRawMk>,
+    // appended to the end to keep the data of old versions decodable
+    #[serde(default)]
+    br_to_parent: MapxOrd<BranchID, BranchID>,
 }
 
 impl From<MapxRawMkVsWithoutDerivedFields> for MapxRawMkVs {
@@ -119,6 +126,7 @@ impl From<MapxRawMkVsWithoutDerivedFields> for MapxRawMkVs {
             ver_name_to_ver_id: m.ver_name_to_ver_id,
             br_to_its_vers: m.br_to_its_vers,
             ver_to_change_set: m.ver_to_change_set,
+            br_to_parent: m.br_to_parent,
 
             br_id_to_br_name: Arc::new(RwLock::new(br_id_to_br_name)),
             ver_id_to_ver_name: Arc::new(RwLock::new(ver_id_to_ver_name)),
@@ -138,6 +146,7 @@ impl From<&MapxRawMkVs> for MapxRawMkVsWithoutDerivedFields {
                 ver_name_to_ver_id: m.ver_name_to_ver_id.shadow(),
                 br_to_its_vers: m.br_to_its_vers.shadow(),
                 ver_to_change_set: m.ver_to_change_set.shadow(),
+                br_to_parent: m.br_to_parent.shadow(),
             }
         }
     }
@@ -157,6 +166,7 @@ impl MapxRawMkVs {
             ver_name_to_ver_id: self.ver_name_to_ver_id.shadow(),
             br_to_its_vers: self.br_to_its_vers.shadow(),
             ver_to_change_set: self.ver_to_change_set.shadow(),
+            br_to_parent: self.br_to_parent.shadow(),
 
             br_id_to_br_name: Arc::clone(&self.br_id_to_br_name),
             ver_id_to_ver_name: Arc::clone(&self.ver_id_to_ver_name),
@@ -174,6 +184,7 @@ impl MapxRawMkVs {
             ver_name_to_ver_id: MapxOrdRawKey::new(),
             br_to_its_vers: MapxOrd::new(),
             ver_to_change_set: MapxOrd::new(),
+            br_to_parent: MapxOrd::new(),
 
             br_id_to_br_name: Arc::new(RwLock::new(Default::default())),
             ver_id_to_ver_name: Arc::new(RwLock::new(Default::default())),
@@ -185,6 +196,11 @@ impl MapxRawMkVs {
         ret
     }
 
+    #[inline(always)]
+    pub(super) fn key_size(&self) -> u32 {
+        self.key_size
+    }
+
     #[inline(always)]
     fn init(&mut self) {
         let initial_brid = INITIAL_BRANCH_ID.to_be_bytes();
@@ -228,6 +244,34 @@ impl MapxRawMkVs {
             })
     }
 
+    // The head version of the branch is resolved only once for all entries,
+    // return the number of new(non-overwriting) insertions.
+    pub(super) fn batch_insert_by_branch(
+        &mut self,
+        items: &[(&[&[u8]], &[u8])],
+        br_id: BranchID,
+    ) -> Result<usize> {
+        let (ver_id, _) = self
+            .br_to_its_vers
+            .get(&br_id)
+            .c(d!("branch not found"))?
+            .last()
+            .c(d!("no version on this branch, create a version first"))?;
+
+        let mut cnt = 0;
+        for (key, value) in items.iter() {
+            if self
+                .insert_by_branch_version(key, value, br_id, ver_id)
+                .c(d!())?
+                .is_none()
+            {
+                cnt += 1;
+            }
+        }
+
+        Ok(cnt)
+    }
+
     #[inline(always)]
     fn insert_by_branch_version(
         &mut self,
@@ -437,17 +481,76 @@ impl MapxRawMkVs {
         op: &mut F,
         key_prefix: &[&[u8]],
     ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        self.iter_op_with_key_prefix_by_branch_version_x(
+            br_id, ver_id, op, key_prefix, false,
+        )
+    }
+
+    #[inline(always)]
+    pub(super) fn iter_op_rev<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        self.iter_op_rev_by_branch(self.branch_get_default(), op)
+            .c(d!())
+    }
+
+    #[inline(always)]
+    pub(super) fn iter_op_rev_by_branch<F>(
+        &self,
+        br_id: BranchID,
+        op: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        self.br_to_its_vers
+            .get(&br_id)
+            .and_then(|vers| vers.last().map(|(id, _)| id))
+            .c(d!("no versions found"))
+            .and_then(|ver_id| {
+                self.iter_op_rev_by_branch_version(br_id, ver_id, op)
+                    .c(d!())
+            })
+    }
+
+    #[inline(always)]
+    pub(super) fn iter_op_rev_by_branch_version<F>(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+        op: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        self.iter_op_with_key_prefix_by_branch_version_x(br_id, ver_id, op, &[], true)
+    }
+
+    fn iter_op_with_key_prefix_by_branch_version_x<F>(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+        op: &mut F,
+        key_prefix: &[&[u8]],
+        reverse: bool,
+    ) -> Result<()>
     where
         F: FnMut(&[&[u8]], RawValue) -> Result<()>,
     {
         let vers = self.br_to_its_vers.get(&br_id).c(d!())?;
         let key_prefix = to_owned_key(key_prefix);
-        for (k, v) in self
-            .layered_kv
-            .read()
+        let layered_kv = self.layered_kv.read();
+        let hdr = layered_kv
             .range(key_prefix.clone()..)
-            .filter(|(k, _)| k.starts_with(&key_prefix))
-        {
+            .filter(|(k, _)| k.starts_with(&key_prefix));
+        let hdr: Box<dyn Iterator<Item = _>> =
+            alt!(reverse, Box::new(hdr.rev()), Box::new(hdr));
+
+        for (k, v) in hdr {
             if let Some((_, v)) = v
                 .range(..=ver_id)
                 .rev()
@@ -460,11 +563,148 @@ impl MapxRawMkVs {
         Ok(())
     }
 
+    #[inline(always)]
+    pub(super) fn iter_with_key_prefix(&self, key_prefix: &[&[u8]]) -> MapxRawMkVsIter {
+        self.iter_with_key_prefix_by_branch(self.branch_get_default(), key_prefix)
+    }
+
+    // The version is fixed to the head of the branch at the creation time.
+    #[inline(always)]
+    pub(super) fn iter_with_key_prefix_by_branch(
+        &self,
+        br_id: BranchID,
+        key_prefix: &[&[u8]],
+    ) -> MapxRawMkVsIter {
+        let ver_id = self
+            .br_to_its_vers
+            .get(&br_id)
+            .and_then(|vers| vers.last().map(|(id, _)| id))
+            .unwrap_or(NULL_ID);
+        self.iter_with_key_prefix_by_branch_version(br_id, ver_id, key_prefix)
+    }
+
+    pub(super) fn iter_with_key_prefix_by_branch_version(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+        key_prefix: &[&[u8]],
+    ) -> MapxRawMkVsIter {
+        let (lo, hi) = if key_prefix.is_empty() {
+            (Bound::Unbounded, Bound::Unbounded)
+        } else {
+            // All keys with the same prefix are continuous,
+            // `[.., last_prefix + [0]]` is the first one behind them.
+            let lo = to_owned_key(key_prefix);
+            let mut hi = lo.clone();
+            hi.last_mut().unwrap().push(0);
+            (Bound::Included(lo), Bound::Excluded(hi))
+        };
+
+        MapxRawMkVsIter {
+            hdr: self,
+            br_id,
+            ver_id,
+            lo,
+            hi,
+        }
+    }
+
+    // The `bounds` are applied to the key level right behind the `key_prefix`.
+    pub(super) fn range_by_prefix_on_branch_version<'a, R: RangeBounds<&'a [u8]>>(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+        key_prefix: &[&[u8]],
+        bounds: R,
+    ) -> MapxRawMkVsIter {
+        // nothing can be found
+        if key_prefix.len() >= self.key_size as usize {
+            return MapxRawMkVsIter {
+                hdr: self,
+                br_id: NULL_ID,
+                ver_id: NULL_ID,
+                lo: Bound::Unbounded,
+                hi: Bound::Unbounded,
+            };
+        }
+
+        let prefix = to_owned_key(key_prefix);
+        let at = |k: &[u8]| {
+            let mut key = prefix.clone();
+            key.push(k.to_vec());
+            key
+        };
+        // `[.., k + [0]]` is the first one behind all keys starting with `[.., k]`
+        let behind = |k: &[u8]| {
+            let mut key = at(k);
+            key.last_mut().unwrap().push(0);
+            key
+        };
+
+        let lo = match bounds.start_bound() {
+            Bound::Included(k) => Bound::Included(at(*k)),
+            Bound::Excluded(k) => Bound::Included(behind(*k)),
+            Bound::Unbounded if prefix.is_empty() => Bound::Unbounded,
+            Bound::Unbounded => Bound::Included(prefix.clone()),
+        };
+        let hi = match bounds.end_bound() {
+            Bound::Included(k) => Bound::Excluded(behind(*k)),
+            Bound::Excluded(k) => Bound::Excluded(at(*k)),
+            Bound::Unbounded if prefix.is_empty() => Bound::Unbounded,
+            Bound::Unbounded => {
+                let mut hi = prefix.clone();
+                hi.last_mut().unwrap().push(0);
+                Bound::Excluded(hi)
+            }
+        };
+
+        MapxRawMkVsIter {
+            hdr: self,
+            br_id,
+            ver_id,
+            lo,
+            hi,
+        }
+    }
+
+    // Find the first(or the last) visible entry within the bounds.
+    fn seek_by_branch_version(
+        &self,
+        br_id: BranchID,
+        ver_id: VersionID,
+        bounds: (&Bound<Vec<RawKey>>, &Bound<Vec<RawKey>>),
+        from_back: bool,
+    ) -> Option<(Vec<RawKey>, RawValue)> {
+        if bounds_is_empty(bounds.0, bounds.1) {
+            return None;
+        }
+
+        let vers = self.br_to_its_vers.get(&br_id)?;
+        let lkv_hdr = self.layered_kv.read();
+        let mut hdr =
+            lkv_hdr.range::<Vec<RawKey>, _>((bounds.0.as_ref(), bounds.1.as_ref()));
+
+        loop {
+            let (k, v) = alt!(from_back, hdr.next_back(), hdr.next())?;
+            if let Some((_, value)) = v
+                .range(..=ver_id)
+                .rev()
+                .find(|(ver, _)| vers.contains_key(ver))
+            {
+                // A 'NULL' value means 'not exist'.
+                if !value.is_empty() {
+                    return Some((k.clone(), value.clone()));
+                }
+            }
+        }
+    }
+
     #[inline(always)]
     pub(super) fn clear(&mut self) {
         self.br_name_to_br_id.clear();
         self.ver_name_to_ver_id.clear();
         self.br_to_its_vers.clear();
+        self.br_to_parent.clear();
         self.ver_to_change_set.clear();
 
         self.br_id_to_br_name.write().clear();
@@ -507,6 +747,24 @@ impl MapxRawMkVs {
         Ok(())
     }
 
+    // Give a new name to an existing version,
+    // the new name must not be used by any other version.
+    fn version_rename(&mut self, ver_id: VersionID, new_name: &[u8]) -> Result<()> {
+        if self.ver_name_to_ver_id.contains_key(new_name) {
+            return Err(eg!("version already exists"));
+        }
+
+        let old_name = self
+            .ver_id_to_ver_name
+            .write()
+            .insert(ver_id, new_name.to_vec())
+            .c(d!("version not found"))?;
+        self.ver_name_to_ver_id.remove(&old_name);
+        self.ver_name_to_ver_id.insert(new_name, &ver_id);
+
+        Ok(())
+    }
+
     // Check if a verison exists in the global scope
     #[inline(always)]
     pub(super) fn version_exists_globally(&self, ver_id: BranchID) -> bool {
@@ -560,8 +818,7 @@ impl MapxRawMkVs {
         base_version: VersionID,
         br_id: BranchID,
     ) -> Result<()> {
-        let mut brvers_hdr =
-            self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let brvers_hdr = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
         let mut brvers = brvers_hdr.range(base_version..).map(|(ver, _)| ver);
 
         if let Some(ver) = brvers.next() {
@@ -572,15 +829,33 @@ impl MapxRawMkVs {
             return Err(eg!("base version is not on this branch"));
         };
 
-        let mut base_ver_chgset = self.ver_to_change_set.get(&base_version).c(d!())?;
         let vers_to_be_merged = brvers.collect::<Vec<_>>();
 
+        self.merge_versions_into(br_id, base_version, &vers_to_be_merged)
+            .c(d!())
+    }
+
+    // Move the changes of `vers` into `base_version`,
+    // and then remove `vers` from the branch and the global version list.
+    //
+    // The `vers` should be newer than the `base_version`,
+    // the newer ones are applied later, so their values take precedence.
+    fn merge_versions_into(
+        &mut self,
+        br_id: BranchID,
+        base_version: VersionID,
+        vers: &[VersionID],
+    ) -> Result<()> {
+        let mut brvers_hdr =
+            self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let mut base_ver_chgset = self.ver_to_change_set.get(&base_version).c(d!())?;
+
         let mut trash = vec![];
 
         {
             let mut lkv_hdr = self.layered_kv.write();
             let mut ver_hdr = self.ver_id_to_ver_name.write();
-            for verid in vers_to_be_merged.iter() {
+            for verid in vers.iter() {
                 let chgset = self.ver_to_change_set.remove(verid).c(d!())?;
                 let mut chgset_ops = |k: &[&[u8]], v: &[u8]| {
                     let key = to_owned_key(k);
@@ -642,6 +917,44 @@ impl MapxRawMkVs {
         })
     }
 
+    // Version IDs are allocated incrementally,
+    // so the versions of a branch are ordered by their IDs.
+    pub(super) fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionID,
+        descendant: VersionID,
+        br_id: BranchID,
+    ) -> Result<bool> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        if !vers.contains_key(&ancestor) || !vers.contains_key(&descendant) {
+            return Err(eg!("version is not on this branch"));
+        }
+        Ok(ancestor < descendant)
+    }
+
+    pub(super) fn versions_between(
+        &self,
+        from: VersionID,
+        to: VersionID,
+        br_id: BranchID,
+    ) -> Result<Vec<VersionNameOwned>> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        if !vers.contains_key(&from) || !vers.contains_key(&to) {
+            return Err(eg!("version is not on this branch"));
+        }
+        if from > to {
+            return Err(eg!("`from` is newer than `to`"));
+        }
+
+        let ver_hdr = self.ver_id_to_ver_name.read();
+        let ret = vers
+            .range(from..to)
+            .map(|(ver, _)| ver_hdr.get(&ver).unwrap().to_vec())
+            .map(VersionNameOwned)
+            .collect();
+        Ok(ret)
+    }
+
     #[inline(always)]
     pub(super) fn version_list_globally(&self) -> Vec<VersionNameOwned> {
         self.ver_to_change_set
@@ -659,6 +972,120 @@ impl MapxRawMkVs {
             .map(|chgset| !chgset.is_empty())
     }
 
+    pub(super) fn version_list_changed_keys(
+        &self,
+        ver_id: VersionID,
+    ) -> Result<Vec<RawKey>> {
+        let chgset = self.ver_to_change_set.get(&ver_id).c(d!())?;
+        let mut ret = vec![];
+        let mut op = |k: &[&[u8]], _: &[u8]| {
+            ret.push(encode_key(k));
+            Ok(())
+        };
+        chgset.iter_op(&mut op).c(d!())?;
+        Ok(ret)
+    }
+
+    pub(super) fn version_changed_key_count(&self, ver_id: VersionID) -> Result<usize> {
+        let chgset = self.ver_to_change_set.get(&ver_id).c(d!())?;
+        let mut cnt = 0;
+        let mut op = |_: &[&[u8]], _: &[u8]| {
+            cnt += 1;
+            Ok(())
+        };
+        chgset.iter_op(&mut op).c(d!())?;
+        Ok(cnt)
+    }
+
+    // The number of entries is maintained by the change set itself,
+    // so there is no need to iterate it.
+    #[inline(always)]
+    pub(super) fn version_chgset_size(&self, ver_id: VersionID) -> Result<usize> {
+        self.ver_to_change_set
+            .get(&ver_id)
+            .c(d!())
+            .map(|chgset| chgset.len())
+    }
+
+    #[inline(always)]
+    pub(super) fn version_chgset_size_by_branch(
+        &self,
+        br_id: BranchID,
+    ) -> Result<usize> {
+        self.br_to_its_vers
+            .get(&br_id)
+            .c(d!("branch not found"))?
+            .last()
+            .c(d!("no version on this branch"))
+            .and_then(|(ver_id, _)| self.version_chgset_size(ver_id).c(d!()))
+    }
+
+    #[inline(always)]
+    pub(super) fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionID,
+        ver_b: VersionID,
+    ) -> Result<bool> {
+        self.version_conflict_keys(ver_a, ver_b)
+            .c(d!())
+            .map(|keys| !keys.is_empty())
+    }
+
+    pub(super) fn version_conflict_keys(
+        &self,
+        ver_a: VersionID,
+        ver_b: VersionID,
+    ) -> Result<Vec<RawKey>> {
+        let a = self.ver_to_change_set.get(&ver_a).c(d!())?;
+        let b = self.ver_to_change_set.get(&ver_b).c(d!())?;
+        let mut ret = vec![];
+        let mut op = |k: &[&[u8]], _: &[u8]| {
+            if b.contains_key(k) {
+                ret.push(encode_key(k));
+            }
+            Ok(())
+        };
+        a.iter_op(&mut op).c(d!())?;
+        Ok(ret)
+    }
+
+    // Walk backward from the head version for each key,
+    // the cost is O(total_keys * avg_version_depth).
+    pub(super) fn version_blame(
+        &self,
+        br_id: BranchID,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let head = if let Some((ver, _)) = vers.last() {
+            ver
+        } else {
+            return Ok(vec![]);
+        };
+
+        let ver_hdr = self.ver_id_to_ver_name.read();
+        let mut ret = vec![];
+        for (k, kvers) in self.layered_kv.read().iter() {
+            let last_write = kvers
+                .range(..=head)
+                .rev()
+                .find(|(ver, _)| vers.contains_key(ver));
+            if let Some((ver, value)) = last_write {
+                // removed on this branch
+                if value.is_empty() {
+                    continue;
+                }
+                let name = ver_hdr.get(ver).cloned().c(d!("version not found"))?;
+                ret.push((
+                    encode_key(&k.iter().map(|k| &k[..]).collect::<Vec<_>>()),
+                    VersionNameOwned(name),
+                    Some(value.clone()),
+                ));
+            }
+        }
+
+        Ok(ret)
+    }
+
     // clean up all orphaned versions in the global scope
     #[inline(always)]
     pub(super) fn version_clean_up_globally(&mut self) -> Result<()> {
@@ -746,6 +1173,203 @@ impl MapxRawMkVs {
             .map(|_| ())
     }
 
+    // Apply all changes of the source version onto the head of the target branch,
+    // a new version named `dst_ver_name` will be created to hold them.
+    pub(super) fn version_cherry_pick(
+        &mut self,
+        src_ver_id: VersionID,
+        src_br_id: BranchID,
+        dst_br_id: BranchID,
+        dst_ver_name: &[u8],
+        force: bool,
+    ) -> Result<()> {
+        let src_vers = self
+            .br_to_its_vers
+            .get(&src_br_id)
+            .c(d!("source branch not found"))?;
+        if !src_vers.contains_key(&src_ver_id) {
+            return Err(eg!("version not found on the source branch"));
+        }
+        if !self.branch_exists(dst_br_id) {
+            return Err(eg!("target branch not found"));
+        }
+
+        let mut changes = vec![];
+        let mut chgset_ops = |key: &[&[u8]], value: &[u8]| {
+            // an empty value means the key was removed in the source version
+            let new = alt!(value.is_empty(), None, Some(value.to_vec()));
+            changes.push((to_owned_key(key), new));
+            Ok(())
+        };
+        self.ver_to_change_set
+            .get(&src_ver_id)
+            .c(d!("source version not found"))?
+            .iter_op(&mut chgset_ops)
+            .c(d!())?;
+
+        if !force {
+            for (k, new) in changes.iter() {
+                let base = self
+                    .layered_kv
+                    .read()
+                    .get(k)
+                    .c(d!())?
+                    .range(..src_ver_id)
+                    .rev()
+                    .find(|(ver, _)| src_vers.contains_key(ver))
+                    .and_then(|(_, v)| alt!(v.is_empty(), None, Some(v.clone())));
+                let key = k.iter().map(|k| &k[..]).collect::<Vec<_>>();
+                let cur = self.get_by_branch(&key, dst_br_id);
+                if cur != base && cur != *new {
+                    return Err(eg!(format!(
+                        "conflict on key: {:?}, use `force` to overwrite it",
+                        k
+                    )));
+                }
+            }
+        }
+
+        self.version_create_by_branch(dst_ver_name, dst_br_id)
+            .c(d!())?;
+
+        for (k, v) in changes.iter() {
+            let key = k.iter().map(|k| &k[..]).collect::<Vec<_>>();
+            if let Some(v) = v {
+                self.insert_by_branch(&key, v, dst_br_id).c(d!())?;
+            } else {
+                self.remove_by_branch(&key, dst_br_id).c(d!())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Merge the newest `count` versions of a branch into the oldest one of them,
+    // and then rename the merged version to the name of the head version.
+    //
+    // # Safety
+    //
+    // If `force` is true and some of the versions are shared with other branches,
+    // the data records of other branches may be corrupted.
+    pub(super) unsafe fn version_squash(
+        &mut self,
+        br_id: BranchID,
+        count: usize,
+        force: bool,
+    ) -> Result<()> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+
+        if 2 > count {
+            return Ok(());
+        }
+
+        let squashed = vers
+            .iter()
+            .rev()
+            .take(count)
+            .map(|(ver, _)| ver)
+            .collect::<Vec<_>>();
+        if squashed.len() < count {
+            return Err(eg!("not enough versions on this branch"));
+        }
+
+        if !force {
+            let shared = self
+                .br_to_its_vers
+                .iter()
+                .filter(|(id, _)| *id != br_id)
+                .any(|(_, other_vers)| {
+                    squashed.iter().any(|ver| other_vers.contains_key(ver))
+                });
+            if shared {
+                return Err(eg!(
+                    "some versions are shared with other branches, use `force` to squash them"
+                ));
+            }
+        }
+
+        let head = squashed[0];
+        let base = squashed[count - 1];
+        let head_name = self
+            .ver_id_to_ver_name
+            .read()
+            .get(&head)
+            .c(d!("version not found"))?
+            .clone();
+
+        self.version_rebase_by_branch(base, br_id).c(d!())?;
+
+        let base_name = self
+            .ver_id_to_ver_name
+            .write()
+            .insert(base, head_name.clone())
+            .c(d!("version not found"))?;
+        self.ver_name_to_ver_id.remove(&base_name);
+        self.ver_name_to_ver_id.insert(&head_name, &base);
+
+        Ok(())
+    }
+
+    // Merge all versions in `[from_ver, to_ver]` of the branch into `from_ver`,
+    // and then rename it to `target_ver_name`.
+    //
+    // The newer versions are applied later, so the data visible
+    // at `to_ver` and all its descendants will keep unchanged.
+    pub(super) fn version_compact_between(
+        &mut self,
+        br_id: BranchID,
+        from_ver: VersionID,
+        to_ver: VersionID,
+        target_ver_name: &[u8],
+    ) -> Result<()> {
+        let brvers_hdr = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        if !brvers_hdr.contains_key(&from_ver) || !brvers_hdr.contains_key(&to_ver) {
+            return Err(eg!("version not found on the branch"));
+        }
+        if from_ver > to_ver {
+            return Err(eg!("`from_ver` is newer than `to_ver`"));
+        }
+
+        let vers = brvers_hdr
+            .range(from_ver..=to_ver)
+            .map(|(ver, _)| ver)
+            .collect::<Vec<_>>();
+
+        let shared = self
+            .br_to_its_vers
+            .iter()
+            .filter(|(id, _)| *id != br_id)
+            .any(|(_, other_vers)| vers.iter().any(|ver| other_vers.contains_key(ver)));
+        if shared {
+            return Err(eg!("some versions are shared with other branches"));
+        }
+
+        let from_ver_name = self
+            .ver_id_to_ver_name
+            .read()
+            .get(&from_ver)
+            .c(d!("version not found"))?
+            .clone();
+
+        // the names of the merged versions can be reused
+        if let Some(id) = self.ver_name_to_ver_id.get(target_ver_name) {
+            if !vers.contains(&id) {
+                return Err(eg!("version already exists"));
+            }
+        }
+
+        self.merge_versions_into(br_id, from_ver, &vers[1..])
+            .c(d!())?;
+
+        self.ver_name_to_ver_id.remove(&from_ver_name);
+        self.ver_name_to_ver_id.insert(target_ver_name, &from_ver);
+        self.ver_id_to_ver_name
+            .write()
+            .insert(from_ver, target_ver_name.to_vec());
+
+        Ok(())
+    }
+
     pub(super) fn version_chgset_trie_root(
         &self,
         br_id: Option<BranchID>,
@@ -950,6 +1574,7 @@ impl MapxRawMkVs {
             .write()
             .insert(br_id, br_name.to_vec());
         self.br_to_its_vers.insert(&br_id, &vers_copied);
+        self.br_to_parent.insert(&br_id, &base_br_id);
 
         if let Some(vername) = ver_name {
             self.version_create_by_branch(vername, br_id).c(d!())?;
@@ -988,6 +1613,22 @@ impl MapxRawMkVs {
 
         let mut vers = self.br_to_its_vers.remove(&br_id).c(d!())?;
 
+        // the children of this branch are attached to its parent
+        let parent = self.br_to_parent.remove(&br_id);
+        let children = self
+            .br_to_parent
+            .iter()
+            .filter(|(_, p)| *p == br_id)
+            .map(|(c, _)| c)
+            .collect::<Vec<_>>();
+        for c in children.iter() {
+            if let Some(p) = parent.as_ref() {
+                self.br_to_parent.insert(c, p);
+            } else {
+                self.br_to_parent.remove(c);
+            }
+        }
+
         TRASH_CLEANER.lock().execute(move || {
             vers.clear();
         });
@@ -1050,6 +1691,40 @@ impl MapxRawMkVs {
         self.version_pop_by_branch(br_id).c(d!())
     }
 
+    // Copy the head state of the branch into a new version,
+    // and replace all versions of the branch with it.
+    //
+    // The replaced versions are only dereferenced here,
+    // the child branches still hold their own references,
+    // and the data will be dropped by `version_clean_up_globally`.
+    pub(super) fn branch_detach(&mut self, br_id: BranchID) -> Result<()> {
+        let mut vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let ver_name = detached_ver_name(
+            &self.ver_name_to_ver_id,
+            self.br_id_to_br_name.read().get(&br_id).c(d!())?,
+        );
+
+        let mut entries = vec![];
+        self.iter_op_by_branch(br_id, &mut |k, v| {
+            entries.push((to_owned_key(k), v));
+            Ok(())
+        })
+        .c(d!())?;
+
+        vers.clear();
+        self.br_to_parent.remove(&br_id);
+
+        self.version_create_by_branch(&ver_name, br_id).c(d!())?;
+        let (ver_id, _) = vers.last().c(d!())?;
+        for (k, v) in entries.iter() {
+            let k = k.iter().map(|k| k.as_slice()).collect::<Vec<_>>();
+            self.insert_by_branch_version(&k, v, br_id, ver_id)
+                .c(d!())?;
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub(super) fn branch_merge_to(
         &mut self,
@@ -1059,6 +1734,196 @@ impl MapxRawMkVs {
         unsafe { self.do_branch_merge_to(br_id, target_br_id, false) }
     }
 
+    // Apply the net changes of `br_id` since its fork point with `target_br_id`
+    // onto the head of the target branch, as one new version.
+    //
+    // NOTE: the source branch wins if a key has also been changed on the target branch.
+    pub(super) fn branch_merge_squash(
+        &mut self,
+        br_id: BranchID,
+        target_br_id: BranchID,
+        squash_ver_name: &[u8],
+    ) -> Result<()> {
+        let fork_point = self
+            .branch_fork_point(br_id, target_br_id)
+            .c(d!())?
+            .map(|ver_name| {
+                self.version_get_id_by_name(VersionName(&ver_name.0))
+                    .c(d!("version not found"))
+            })
+            .transpose()?;
+
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let mut keys = BTreeSet::new();
+        let mut op = |k: &[&[u8]], _: &[u8]| {
+            keys.insert(to_owned_key(k));
+            Ok(())
+        };
+        for (ver, _) in vers
+            .iter()
+            .filter(|(ver, _)| fork_point.map(|fp| *ver > fp).unwrap_or(true))
+        {
+            if let Some(chgset) = self.ver_to_change_set.get(&ver) {
+                chgset.iter_op(&mut op).c(d!())?;
+            }
+        }
+
+        let changes = keys
+            .into_iter()
+            .map(|k| {
+                let key = k.iter().map(|k| &k[..]).collect::<Vec<_>>();
+                let v = self.get_by_branch(&key, br_id);
+                let base = fork_point
+                    .and_then(|fp| self.get_by_branch_version(&key, br_id, fp));
+                (k, v, base)
+            })
+            .filter(|(_, v, base)| v != base)
+            .map(|(k, v, _)| (k, v))
+            .collect::<Vec<_>>();
+
+        self.version_create_by_branch(squash_ver_name, target_br_id)
+            .c(d!())?;
+        for (k, v) in changes.iter() {
+            let key = k.iter().map(|k| &k[..]).collect::<Vec<_>>();
+            if let Some(v) = v {
+                self.insert_by_branch(&key, v, target_br_id).c(d!())?;
+            } else {
+                self.remove_by_branch(&key, target_br_id).c(d!())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Replay the versions of `br_id` since the fork point on the head of the target
+    // branch by `version_cherry_pick`, and then fast-forward both branches to the new tip.
+    //
+    // The replay is done on a temporary branch forked from the head of the target,
+    // if it fails, the temporary branch and versions are dropped,
+    // and the two branches are left unchanged.
+    // After a successful replay, the original versions of `br_id` are dropped,
+    // and the replayed ones take over their names.
+    pub(super) fn branch_merge_to_rebase(
+        &mut self,
+        br_id: BranchID,
+        target_br_id: BranchID,
+    ) -> Result<()> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        let target_vers = self
+            .br_to_its_vers
+            .get(&target_br_id)
+            .c(d!("target branch not found"))?;
+
+        // No new versions on the target branch, a normal merge is enough
+        if target_vers
+            .last()
+            .map(|(ver, _)| vers.contains_key(&ver))
+            .unwrap_or(true)
+        {
+            return self.branch_merge_to(br_id, target_br_id).c(d!());
+        }
+
+        let fork_point = vers
+            .iter()
+            .rev()
+            .find(|(ver, _)| target_vers.contains_key(ver))
+            .map(|(ver, _)| ver);
+        let replay_vers = vers
+            .iter()
+            .map(|(ver, _)| ver)
+            .filter(|ver| fork_point.map(|fp| *ver > fp).unwrap_or(true))
+            .collect::<Vec<_>>();
+
+        let shared = self
+            .br_to_its_vers
+            .iter()
+            .filter(|(br, _)| *br != br_id)
+            .any(|(_, vs)| replay_vers.iter().any(|ver| vs.contains_key(ver)));
+        if shared {
+            return Err(eg!(
+                "some versions to be replayed are used by other branches"
+            ));
+        }
+
+        let names = replay_vers
+            .iter()
+            .map(|ver| {
+                self.ver_id_to_ver_name
+                    .read()
+                    .get(ver)
+                    .cloned()
+                    .c(d!("version not found"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let tmp_br_name = unused_name(&self.br_name_to_br_id, b"rebase");
+        unsafe {
+            self.branch_create_by_base_branch_without_new_version(
+                &tmp_br_name,
+                target_br_id,
+                false,
+            )
+            .c(d!())?;
+        }
+        let tmp_br_id = self.br_name_to_br_id.get(&tmp_br_name).c(d!())?;
+
+        let mut replayed = Vec::with_capacity(replay_vers.len());
+        let mut failed = None;
+        for (ver, name) in replay_vers.iter().zip(names.iter()) {
+            let tmp_ver_name = unused_name(&self.ver_name_to_ver_id, name);
+            match self
+                .version_cherry_pick(*ver, br_id, tmp_br_id, &tmp_ver_name, false)
+                .and_then(|_| {
+                    self.br_to_its_vers
+                        .get(&tmp_br_id)
+                        .and_then(|vs| vs.last())
+                        .map(|(ver, _)| ver)
+                        .c(d!())
+                }) {
+                Ok(new_ver) => replayed.push(new_ver),
+                Err(e) => {
+                    failed = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(e) = failed {
+            // a failed cherry-pick may have created its version
+            let created = self
+                .br_to_its_vers
+                .get(&tmp_br_id)
+                .c(d!())?
+                .iter()
+                .map(|(ver, _)| ver)
+                .filter(|ver| !target_vers.contains_key(ver))
+                .collect::<Vec<_>>();
+            for ver in created.into_iter() {
+                unsafe { self.version_revert_globally(ver).c(d!())? };
+            }
+            self.branch_remove(tmp_br_id).c(d!())?;
+            return Err(e).c(d!());
+        }
+
+        // fast-forward the target branch to the new tip
+        self.branch_merge_to(tmp_br_id, target_br_id).c(d!())?;
+        self.branch_remove(tmp_br_id).c(d!())?;
+
+        // the original versions are only used by `br_id`, as checked above
+        if let Some(fp) = fork_point {
+            self.branch_truncate_to(br_id, fp).c(d!())?;
+        } else {
+            self.branch_truncate(br_id).c(d!())?;
+        }
+        for ((old, name), new) in replay_vers.iter().zip(names.iter()).zip(replayed) {
+            unsafe { self.version_revert_globally(*old).c(d!())? };
+            self.version_rename(new, name).c(d!())?;
+        }
+
+        // fast-forward the source branch to the new tip
+        self.branch_merge_to(target_br_id, br_id).c(d!())
+    }
+
     #[inline(always)]
     pub(super) unsafe fn branch_merge_to_force(
         &mut self,
@@ -1163,6 +2028,97 @@ impl MapxRawMkVs {
             .collect()
     }
 
+    // Compare the heads of two branches, keys with identical values are skipped,
+    // multiple keys are encoded into one by `encode_key`.
+    pub(super) fn branch_diff(
+        &self,
+        br_a: BranchID,
+        br_b: BranchID,
+    ) -> Vec<(RawKey, Option<RawValue>, Option<RawValue>)> {
+        let keys = self.layered_kv.read().keys().cloned().collect::<Vec<_>>();
+        keys.into_iter()
+            .filter_map(|k| {
+                let k = k.iter().map(|k| &k[..]).collect::<Vec<_>>();
+                let a = self.get_by_branch(&k, br_a);
+                let b = self.get_by_branch(&k, br_b);
+                alt!(a == b, None, Some((encode_key(&k), a, b)))
+            })
+            .collect()
+    }
+
+    // Ordered from the immediate parent to the initial branch.
+    pub(super) fn branch_ancestors(&self, br_id: BranchID) -> Vec<BranchNameOwned> {
+        let br_hdr = self.br_id_to_br_name.read();
+
+        let mut ret = vec![];
+        let mut id = br_id;
+        while let Some(p) = self.br_to_parent.get(&id) {
+            id = p;
+            if let Some(name) = br_hdr.get(&id) {
+                ret.push(BranchNameOwned(name.clone()));
+            } else {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    // Ordered by the creation time.
+    pub(super) fn branch_children(&self, br_id: BranchID) -> Vec<BranchNameOwned> {
+        let br_hdr = self.br_id_to_br_name.read();
+        self.br_to_parent
+            .iter()
+            .filter(|(_, p)| *p == br_id)
+            .filter_map(|(c, _)| br_hdr.get(&c).cloned())
+            .map(BranchNameOwned)
+            .collect()
+    }
+
+    #[inline(always)]
+    pub(super) fn branch_is_leaf(&self, br_id: BranchID) -> bool {
+        !self.br_to_parent.iter().any(|(_, p)| p == br_id)
+    }
+
+    pub(super) fn branch_fork_point(
+        &self,
+        br_a: BranchID,
+        br_b: BranchID,
+    ) -> Result<Option<VersionNameOwned>> {
+        let vers_a = self.br_to_its_vers.get(&br_a).c(d!("branch not found"))?;
+        let vers_b = self.br_to_its_vers.get(&br_b).c(d!("branch not found"))?;
+
+        vers_a
+            .iter()
+            .rev()
+            .find(|(ver, _)| vers_b.contains_key(ver))
+            .map(|(ver, _)| {
+                self.ver_id_to_ver_name
+                    .read()
+                    .get(&ver)
+                    .cloned()
+                    .map(VersionNameOwned)
+                    .c(d!("version not found"))
+            })
+            .transpose()
+    }
+
+    // Compare with the version list of the parent branch,
+    // all children are re-attached when their parent is removed,
+    // so the parent recorded in `br_to_parent` always exists.
+    pub(super) fn branch_is_dirty(&self, br_id: BranchID) -> Result<bool> {
+        let vers = self.br_to_its_vers.get(&br_id).c(d!("branch not found"))?;
+        if let Some(p) = self.br_to_parent.get(&br_id) {
+            let parent_vers = self
+                .br_to_its_vers
+                .get(&p)
+                .c(d!("parent branch not found"))?;
+            Ok(vers.iter().any(|(ver, _)| !parent_vers.contains_key(&ver)))
+        } else {
+            Ok(!vers.is_empty())
+        }
+    }
+
     // Logically similar to `std::ptr::swap`
     //
     // For example: If you have a master branch and a test branch, the data is always trial-run on the test branch, and then periodically merged back into the master branch. Rather than merging the test branch into the master branch, and then recreating the new test branch, it is more efficient to just swap the two branches, and then recreating the new test branch.
@@ -1188,6 +2144,24 @@ impl MapxRawMkVs {
             hdr.insert(brid_2, branch_1.to_vec()).c(d!())?;
         }
 
+        // keep the relationships between the branch names unchanged
+        let swap_id = |id: BranchID| match id {
+            _ if id == brid_1 => brid_2,
+            _ if id == brid_2 => brid_1,
+            _ => id,
+        };
+        let relations = self
+            .br_to_parent
+            .iter()
+            .filter(|(c, p)| [brid_1, brid_2].iter().any(|id| id == c || id == p))
+            .collect::<Vec<_>>();
+        relations.iter().for_each(|(c, _)| {
+            self.br_to_parent.remove(c);
+        });
+        relations.into_iter().for_each(|(c, p)| {
+            self.br_to_parent.insert(&swap_id(c), &swap_id(p));
+        });
+
         if self.default_branch == brid_1 {
             self.default_branch = brid_2;
         } else if self.default_branch == brid_2 {
@@ -1197,6 +2171,26 @@ impl MapxRawMkVs {
         Ok(())
     }
 
+    // The default branch is recorded by ID, so it needs not to be changed.
+    pub(super) fn branch_rename(
+        &mut self,
+        br_id: BranchID,
+        new_name: &[u8],
+    ) -> Result<()> {
+        if self.br_name_to_br_id.contains_key(new_name) {
+            return Err(eg!("branch already exists"));
+        }
+
+        let mut br_hdr = self.br_id_to_br_name.write();
+        let old_name = br_hdr.get(&br_id).c(d!("branch not found"))?.clone();
+
+        self.br_name_to_br_id.remove(&old_name);
+        self.br_name_to_br_id.insert(new_name, &br_id);
+        br_hdr.insert(br_id, new_name.to_vec());
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub(super) fn branch_get_id_by_name(&self, br_name: BranchName) -> Option<BranchID> {
         self.br_name_to_br_id.get(br_name.0)
@@ -1303,6 +2297,53 @@ impl MapxRawMkVs {
 ////////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////////
 
+pub struct MapxRawMkVsIter<'a> {
+    hdr: &'a MapxRawMkVs,
+    br_id: BranchID,
+    ver_id: VersionID,
+    lo: Bound<Vec<RawKey>>,
+    hi: Bound<Vec<RawKey>>,
+}
+
+impl<'a> Iterator for MapxRawMkVsIter<'a> {
+    type Item = (Vec<RawKey>, RawValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if NULL_ID == self.br_id || NULL_ID == self.ver_id {
+            return None;
+        }
+
+        let (k, v) = self.hdr.seek_by_branch_version(
+            self.br_id,
+            self.ver_id,
+            (&self.lo, &self.hi),
+            false,
+        )?;
+        self.lo = Bound::Excluded(k.clone());
+        Some((k, v))
+    }
+}
+
+impl DoubleEndedIterator for MapxRawMkVsIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if NULL_ID == self.br_id || NULL_ID == self.ver_id {
+            return None;
+        }
+
+        let (k, v) = self.hdr.seek_by_branch_version(
+            self.br_id,
+            self.ver_id,
+            (&self.lo, &self.hi),
+            true,
+        )?;
+        self.hi = Bound::Excluded(k.clone());
+        Some((k, v))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////////
+
 #[inline(always)]
 fn ver_add_1(ver: VersionID) -> VersionID {
     (VersionIDBase::from_be_bytes(ver) + 1).to_be_bytes()
@@ -1312,3 +2353,49 @@ fn ver_add_1(ver: VersionID) -> VersionID {
 fn to_owned_key(k: &[&[u8]]) -> Vec<RawKey> {
     k.iter().map(|k| k.to_vec()).collect()
 }
+
+// Each level is prefixed by its length(a big-endian `u32`),
+// so the boundaries between the levels can not be lost.
+pub(super) fn encode_key(k: &[&[u8]]) -> RawKey {
+    let mut ret = Vec::with_capacity(k.iter().map(|k| 4 + k.len()).sum());
+    for k in k.iter() {
+        ret.extend_from_slice(&(k.len() as u32).to_be_bytes());
+        ret.extend_from_slice(k);
+    }
+    ret
+}
+
+pub(super) fn decode_key(mut k: &[u8]) -> Result<Vec<RawKey>> {
+    let mut ret = vec![];
+    while !k.is_empty() {
+        if 4 > k.len() {
+            return Err(eg!("invalid key"));
+        }
+        let len = u32::from_be_bytes(k[..4].try_into().unwrap()) as usize;
+        if 4 + len > k.len() {
+            return Err(eg!("invalid key"));
+        }
+        ret.push(k[4..4 + len].to_vec());
+        k = &k[4 + len..];
+    }
+    Ok(ret)
+}
+
+// A name derived from `base` that is not used in `names`,
+// for the temporary branches and versions.
+#[inline(always)]
+fn unused_name<V: ValueEnDe>(names: &MapxOrdRawKey<V>, base: &[u8]) -> RawValue {
+    (0u64..)
+        .map(|i| [base, &b"@tmp"[..], &i.to_be_bytes()[..]].concat())
+        .find(|n| !names.contains_key(n))
+        .unwrap()
+}
+
+// The name of the single version of a detached branch.
+#[inline(always)]
+fn detached_ver_name(names: &MapxOrdRawKey<VersionID>, br_name: &[u8]) -> RawValue {
+    (0u64..)
+        .map(|i| [br_name, &b"@detached"[..], &i.to_be_bytes()[..]].concat())
+        .find(|n| !names.contains_key(n))
+        .unwrap()
+}
@@ -8,16 +8,18 @@ mod backend;
 mod test;
 
 use crate::{
-    common::{BranchName, ParentBranchName, RawValue, VersionName},
+    common::{BranchName, ParentBranchName, RawKey, RawValue, VersionName, NULL_ID},
     BranchNameOwned, VersionNameOwned, VsMgmt,
 };
 use ruc::*;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeSet,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, RangeBounds},
 };
 
+pub use backend::MapxRawMkVsIter;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MapxRawMkVs {
     inner: backend::MapxRawMkVs,
@@ -43,6 +45,22 @@ impl MapxRawMkVs {
         }
     }
 
+    /// Encode a multi-key into one `RawKey`,
+    /// each level is prefixed by its length(a big-endian `u32`).
+    ///
+    /// The keys returned by the `VsMgmt` functions are in this form,
+    /// e.g. `version_list_changed_keys`.
+    #[inline(always)]
+    pub fn encode_key(key: &[&[u8]]) -> RawKey {
+        backend::encode_key(key)
+    }
+
+    /// The reverse of [`encode_key`](Self::encode_key).
+    #[inline(always)]
+    pub fn decode_key(key: &[u8]) -> Result<Vec<RawKey>> {
+        backend::decode_key(key).c(d!())
+    }
+
     #[inline(always)]
     pub fn insert(&mut self, key: &[&[u8]], value: &[u8]) -> Result<Option<RawValue>> {
         self.inner.insert(key, value).c(d!())
@@ -59,6 +77,25 @@ impl MapxRawMkVs {
         self.inner.insert_by_branch(key, value, br_id).c(d!())
     }
 
+    /// Insert lots of entries to the head version of the default branch,
+    /// return the number of new(non-overwriting) insertions.
+    #[inline(always)]
+    pub fn batch_insert(&mut self, items: &[(&[&[u8]], &[u8])]) -> Result<usize> {
+        let br_id = self.inner.branch_get_default();
+        self.inner.batch_insert_by_branch(items, br_id).c(d!())
+    }
+
+    /// The head version of the branch is resolved only once for all entries.
+    #[inline(always)]
+    pub fn batch_insert_by_branch(
+        &mut self,
+        items: &[(&[&[u8]], &[u8])],
+        br_name: BranchName,
+    ) -> Result<usize> {
+        let br_id = self.inner.branch_get_id_by_name(br_name).c(d!())?;
+        self.inner.batch_insert_by_branch(items, br_id).c(d!())
+    }
+
     #[inline(always)]
     pub fn remove(&mut self, key: &[&[u8]]) -> Result<Option<RawValue>> {
         self.inner.remove(key).c(d!())
@@ -145,6 +182,40 @@ impl MapxRawMkVs {
         self.inner.iter_op(op).c(d!())
     }
 
+    #[inline(always)]
+    pub fn iter_op_rev<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        self.inner.iter_op_rev(op).c(d!())
+    }
+
+    #[inline(always)]
+    pub fn iter_op_rev_by_branch<F>(&self, br_name: BranchName, op: &mut F) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        let br_id = self.inner.branch_get_id_by_name(br_name).c(d!())?;
+        self.inner.iter_op_rev_by_branch(br_id, op).c(d!())
+    }
+
+    #[inline(always)]
+    pub fn iter_op_rev_by_branch_version<F>(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        op: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[&[u8]], RawValue) -> Result<()>,
+    {
+        let br_id = self.inner.branch_get_id_by_name(br_name).c(d!())?;
+        let ver_id = self.inner.version_get_id_by_name(ver_name).c(d!())?;
+        self.inner
+            .iter_op_rev_by_branch_version(br_id, ver_id, op)
+            .c(d!())
+    }
+
     #[inline(always)]
     pub fn iter_op_with_key_prefix<F>(
         &self,
@@ -216,6 +287,74 @@ impl MapxRawMkVs {
             .iter_op_with_key_prefix_by_branch_version(br_id, ver_id, op, key_prefix)
             .c(d!())
     }
+
+    /// Create a lazy iterator over the entries with the specified key prefix
+    /// on the default branch, an empty prefix means all entries.
+    #[inline(always)]
+    pub fn iter_with_key_prefix(&self, key_prefix: &[&[u8]]) -> MapxRawMkVsIter {
+        self.inner.iter_with_key_prefix(key_prefix)
+    }
+
+    /// The iterator is bound to the head version of the branch
+    /// at the time of creation.
+    #[inline(always)]
+    pub fn iter_with_key_prefix_by_branch(
+        &self,
+        br_name: BranchName,
+        key_prefix: &[&[u8]],
+    ) -> MapxRawMkVsIter {
+        let br_id = self.inner.branch_get_id_by_name(br_name).unwrap_or(NULL_ID);
+        self.inner.iter_with_key_prefix_by_branch(br_id, key_prefix)
+    }
+
+    #[inline(always)]
+    pub fn iter_with_key_prefix_by_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        key_prefix: &[&[u8]],
+    ) -> MapxRawMkVsIter {
+        let br_id = self.inner.branch_get_id_by_name(br_name).unwrap_or(NULL_ID);
+        let ver_id = self
+            .inner
+            .version_get_id_by_name(ver_name)
+            .unwrap_or(NULL_ID);
+        self.inner
+            .iter_with_key_prefix_by_branch_version(br_id, ver_id, key_prefix)
+    }
+
+    /// The `bounds` are applied to the key level right behind the `key_prefix`.
+    #[inline(always)]
+    pub fn range_by_prefix_on_branch_version<'a, R: RangeBounds<&'a [u8]>>(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        key_prefix: &[&[u8]],
+        bounds: R,
+    ) -> MapxRawMkVsIter {
+        let br_id = self.inner.branch_get_id_by_name(br_name).unwrap_or(NULL_ID);
+        let ver_id = self
+            .inner
+            .version_get_id_by_name(ver_name)
+            .unwrap_or(NULL_ID);
+        self.inner
+            .range_by_prefix_on_branch_version(br_id, ver_id, key_prefix, bounds)
+    }
+
+    /// Check if any entry exists under the `key_prefix`
+    /// on a specified version of a specified branch,
+    /// at most one entry will be read.
+    #[inline(always)]
+    pub fn contains_prefix_on_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        key_prefix: &[&[u8]],
+    ) -> bool {
+        self.iter_with_key_prefix_by_branch_version(br_name, ver_name, key_prefix)
+            .next()
+            .is_some()
+    }
 }
 
 impl VsMgmt for MapxRawMkVs {
@@ -264,6 +403,15 @@ impl VsMgmt for MapxRawMkVs {
             .unwrap_or(false)
     }
 
+    #[inline(always)]
+    fn branch_contains_version_fast(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> bool {
+        self.version_exists_on_branch(ver_name, br_name)
+    }
+
     #[inline(always)]
     fn version_pop(&mut self) -> Result<()> {
         self.inner.version_pop().c(d!())
@@ -320,6 +468,52 @@ impl VsMgmt for MapxRawMkVs {
             .and_then(|brid| self.inner.version_list_by_branch(brid).c(d!()))
     }
 
+    #[inline(always)]
+    fn version_is_ancestor_of(
+        &self,
+        ancestor: VersionName,
+        descendant: VersionName,
+        br_name: BranchName,
+    ) -> Result<bool> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let ancestor = self
+            .inner
+            .version_get_id_by_name(ancestor)
+            .c(d!("version not found"))?;
+        let descendant = self
+            .inner
+            .version_get_id_by_name(descendant)
+            .c(d!("version not found"))?;
+        self.inner
+            .version_is_ancestor_of(ancestor, descendant, br_id)
+            .c(d!())
+    }
+
+    #[inline(always)]
+    fn versions_between(
+        &self,
+        from: VersionName,
+        to: VersionName,
+        br_name: BranchName,
+    ) -> Result<Vec<VersionNameOwned>> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let from = self
+            .inner
+            .version_get_id_by_name(from)
+            .c(d!("version not found"))?;
+        let to = self
+            .inner
+            .version_get_id_by_name(to)
+            .c(d!("version not found"))?;
+        self.inner.versions_between(from, to, br_id).c(d!())
+    }
+
     #[inline(always)]
     fn version_list_globally(&self) -> Vec<VersionNameOwned> {
         self.inner.version_list_globally()
@@ -333,6 +527,86 @@ impl VsMgmt for MapxRawMkVs {
             .and_then(|verid| self.inner.version_has_change_set(verid).c(d!()))
     }
 
+    /// NOTE: each key is encoded by [`encode_key`](Self::encode_key).
+    #[inline(always)]
+    fn version_list_changed_keys(&self, ver_name: VersionName) -> Result<Vec<RawKey>> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_list_changed_keys(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_changed_key_count(&self, ver_name: VersionName) -> Result<usize> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_changed_key_count(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_chgset_size(&self, ver_name: VersionName) -> Result<usize> {
+        self.inner
+            .version_get_id_by_name(ver_name)
+            .c(d!("version not found"))
+            .and_then(|verid| self.inner.version_chgset_size(verid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_chgset_size_by_branch(&self, br_name: BranchName) -> Result<usize> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_chgset_size_by_branch(brid).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_has_conflicts_with(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<bool> {
+        let ver_a = self
+            .inner
+            .version_get_id_by_name(ver_a)
+            .c(d!("version not found"))?;
+        let ver_b = self
+            .inner
+            .version_get_id_by_name(ver_b)
+            .c(d!("version not found"))?;
+        self.inner.version_has_conflicts_with(ver_a, ver_b).c(d!())
+    }
+
+    /// NOTE: each key is encoded by [`encode_key`](Self::encode_key).
+    #[inline(always)]
+    fn version_conflict_keys(
+        &self,
+        ver_a: VersionName,
+        ver_b: VersionName,
+    ) -> Result<Vec<RawKey>> {
+        let ver_a = self
+            .inner
+            .version_get_id_by_name(ver_a)
+            .c(d!("version not found"))?;
+        let ver_b = self
+            .inner
+            .version_get_id_by_name(ver_b)
+            .c(d!("version not found"))?;
+        self.inner.version_conflict_keys(ver_a, ver_b).c(d!())
+    }
+
+    /// NOTE: each key is encoded by [`encode_key`](Self::encode_key).
+    #[inline(always)]
+    fn version_blame(
+        &self,
+        br_name: BranchName,
+    ) -> Result<Vec<(RawKey, VersionNameOwned, Option<RawValue>)>> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_blame(brid).c(d!()))
+    }
+
     #[inline(always)]
     fn version_clean_up_globally(&mut self) -> Result<()> {
         self.inner.version_clean_up_globally().c(d!())
@@ -346,6 +620,132 @@ impl VsMgmt for MapxRawMkVs {
             .and_then(|verid| self.inner.version_revert_globally(verid).c(d!()))
     }
 
+    #[inline(always)]
+    fn version_rollback_to(
+        &mut self,
+        br_name: BranchName,
+        last_ver: VersionName,
+    ) -> Result<()> {
+        if !self.version_exists_on_branch(last_ver, br_name) {
+            return Err(eg!("version not found on the branch"));
+        }
+        self.branch_truncate_to(br_name, last_ver).c(d!())?;
+        self.version_clean_up_globally().c(d!())
+    }
+
+    #[inline(always)]
+    fn version_cherry_pick(
+        &mut self,
+        src_ver: VersionName,
+        src_br: BranchName,
+        dst_br: BranchName,
+        dst_ver: VersionName,
+        force: bool,
+    ) -> Result<()> {
+        let src_verid = self
+            .inner
+            .version_get_id_by_name(src_ver)
+            .c(d!("version not found"))?;
+        let src_brid = self
+            .inner
+            .branch_get_id_by_name(src_br)
+            .c(d!("branch not found"))?;
+        let dst_brid = self
+            .inner
+            .branch_get_id_by_name(dst_br)
+            .c(d!("branch not found"))?;
+        self.inner
+            .version_cherry_pick(src_verid, src_brid, dst_brid, dst_ver.0, force)
+            .c(d!())
+    }
+
+    /// The keys must be in the form of [`encode_key`](Self::encode_key),
+    /// they are all checked before the version is created,
+    /// so a malformed key will not leave an empty version behind.
+    fn version_apply_patch(
+        &mut self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        changes: &[(RawKey, Option<RawValue>)],
+    ) -> Result<()> {
+        let key_size = self.inner.key_size() as usize;
+        let changes = changes
+            .iter()
+            .map(|(k, v)| {
+                let k = backend::decode_key(k).c(d!())?;
+                if k.len() != key_size {
+                    return Err(eg!("key size mismatch"));
+                }
+                Ok((k, v))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        self.inner
+            .version_create_by_branch(ver_name.0, br_id)
+            .c(d!())?;
+
+        for (k, v) in changes.iter() {
+            let k = k.iter().map(|i| &i[..]).collect::<Vec<_>>();
+            if let Some(v) = v {
+                self.inner.insert_by_branch(&k, v, br_id).c(d!())?;
+            } else {
+                self.inner.remove_by_branch(&k, br_id).c(d!())?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn version_squash(&mut self, br_name: BranchName, count: usize) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| unsafe {
+                self.inner.version_squash(brid, count, false).c(d!())
+            })
+    }
+
+    #[inline(always)]
+    unsafe fn version_squash_force(
+        &mut self,
+        br_name: BranchName,
+        count: usize,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.version_squash(brid, count, true).c(d!()))
+    }
+
+    #[inline(always)]
+    fn version_compact_between(
+        &mut self,
+        br_name: BranchName,
+        from_ver: VersionName,
+        to_ver: VersionName,
+        target_ver_name: VersionName,
+    ) -> Result<()> {
+        let br_id = self
+            .inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))?;
+        let from_ver_id = self
+            .inner
+            .version_get_id_by_name(from_ver)
+            .c(d!("version not found"))?;
+        let to_ver_id = self
+            .inner
+            .version_get_id_by_name(to_ver)
+            .c(d!("version not found"))?;
+        self.inner
+            .version_compact_between(br_id, from_ver_id, to_ver_id, target_ver_name.0)
+            .c(d!())
+    }
+
     #[inline(always)]
     fn version_chgset_trie_root(
         &self,
@@ -568,6 +968,15 @@ impl VsMgmt for MapxRawMkVs {
             .and_then(|id| self.inner.branch_pop_version(id).c(d!()))
     }
 
+    /// Make a branch independent of the history of its parent.
+    #[inline(always)]
+    fn branch_detach(&mut self, br_name: BranchName) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|id| self.inner.branch_detach(id).c(d!()))
+    }
+
     #[inline(always)]
     fn branch_merge_to(
         &mut self,
@@ -604,6 +1013,55 @@ impl VsMgmt for MapxRawMkVs {
             })
     }
 
+    #[inline(always)]
+    fn branch_merge_to_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| {
+                let target_brid = self
+                    .inner
+                    .branch_get_id_by_name(target_br_name)
+                    .c(d!("target branch not found"))?;
+                self.inner.branch_merge_to_rebase(brid, target_brid).c(d!())
+            })
+    }
+
+    // `branch_merge_to_rebase` falls back to a fast-forward merge
+    // if the target branch has not advanced
+    #[inline(always)]
+    fn branch_merge_to_base_with_rebase(
+        &mut self,
+        br_name: BranchName,
+        target_br_name: BranchName,
+    ) -> Result<()> {
+        self.branch_merge_to_rebase(br_name, target_br_name).c(d!())
+    }
+
+    #[inline(always)]
+    fn branch_merge_squash(
+        &mut self,
+        src_br: BranchName,
+        dst_br: BranchName,
+        squash_ver_name: VersionName,
+    ) -> Result<()> {
+        let src_brid = self
+            .inner
+            .branch_get_id_by_name(src_br)
+            .c(d!("branch not found"))?;
+        let dst_brid = self
+            .inner
+            .branch_get_id_by_name(dst_br)
+            .c(d!("target branch not found"))?;
+        self.inner
+            .branch_merge_squash(src_brid, dst_brid, squash_ver_name.0)
+            .c(d!())
+    }
+
     #[inline(always)]
     fn branch_set_default(&mut self, br_name: BranchName) -> Result<()> {
         self.inner
@@ -630,6 +1088,73 @@ impl VsMgmt for MapxRawMkVs {
         self.inner.branch_get_default_name()
     }
 
+    /// NOTE: each key is encoded by [`encode_key`](Self::encode_key).
+    #[inline(always)]
+    fn branch_diff(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Vec<(RawKey, Option<RawValue>, Option<RawValue>)>> {
+        let br_a = self
+            .inner
+            .branch_get_id_by_name(br_a)
+            .c(d!("branch not found"))?;
+        let br_b = self
+            .inner
+            .branch_get_id_by_name(br_b)
+            .c(d!("branch not found"))?;
+        Ok(self.inner.branch_diff(br_a, br_b))
+    }
+
+    #[inline(always)]
+    fn branch_ancestors(&self, br_name: BranchName) -> Result<Vec<BranchNameOwned>> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .map(|br_id| self.inner.branch_ancestors(br_id))
+    }
+
+    #[inline(always)]
+    fn branch_children(&self, br_name: BranchName) -> Vec<BranchNameOwned> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .map(|br_id| self.inner.branch_children(br_id))
+            .unwrap_or_default()
+    }
+
+    #[inline(always)]
+    fn branch_is_leaf(&self, br_name: BranchName) -> bool {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .map(|br_id| self.inner.branch_is_leaf(br_id))
+            .unwrap_or(false)
+    }
+
+    #[inline(always)]
+    fn branch_fork_point(
+        &self,
+        br_a: BranchName,
+        br_b: BranchName,
+    ) -> Result<Option<VersionNameOwned>> {
+        let br_a = self
+            .inner
+            .branch_get_id_by_name(br_a)
+            .c(d!("branch not found"))?;
+        let br_b = self
+            .inner
+            .branch_get_id_by_name(br_b)
+            .c(d!("branch not found"))?;
+        self.inner.branch_fork_point(br_a, br_b).c(d!())
+    }
+
+    #[inline(always)]
+    fn branch_is_dirty(&self, br_name: BranchName) -> Result<bool> {
+        self.inner
+            .branch_get_id_by_name(br_name)
+            .c(d!("branch not found"))
+            .and_then(|brid| self.inner.branch_is_dirty(brid).c(d!()))
+    }
+
     #[inline(always)]
     unsafe fn branch_swap(
         &mut self,
@@ -639,6 +1164,18 @@ impl VsMgmt for MapxRawMkVs {
         self.inner.branch_swap(branch_1.0, branch_2.0).c(d!())
     }
 
+    #[inline(always)]
+    fn branch_rename(
+        &mut self,
+        old_name: BranchName,
+        new_name: BranchName,
+    ) -> Result<()> {
+        self.inner
+            .branch_get_id_by_name(old_name)
+            .c(d!("branch not found"))
+            .and_then(|br_id| self.inner.branch_rename(br_id, new_name.0).c(d!()))
+    }
+
     #[inline(always)]
     fn prune(&mut self, reserved_ver_num: Option<usize>) -> Result<()> {
         self.inner.prune(reserved_ver_num).c(d!())
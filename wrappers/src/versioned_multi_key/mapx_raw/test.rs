@@ -3,6 +3,7 @@ use crate::{
     common::{BranchName, ParentBranchName, INITIAL_BRANCH_NAME},
     ValueEnDe, VsMgmt, NULL,
 };
+use std::ops::Bound;
 
 #[test]
 fn test_master_branch_exists() {
@@ -375,3 +376,968 @@ fn test_prune() {
     assert!(!hdr.version_exists(VersionName(b"b")));
     assert!(hdr.version_exists(VersionName(b"c")));
 }
+
+#[test]
+fn test_iter_with_key_prefix() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+    pnk!(hdr.insert(&[&[1], &[2]], &[12]));
+    pnk!(hdr.insert(&[&[1, 0], &[1]], &[101]));
+    pnk!(hdr.insert(&[&[2], &[1]], &[21]));
+
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.remove(&[&[1], &[1]]));
+
+    assert_eq!(
+        vec![(vec![vec![1], vec![2]], vec![12])],
+        hdr.iter_with_key_prefix(&[&[1]]).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![
+            (vec![vec![1], vec![1]], vec![11]),
+            (vec![vec![1], vec![2]], vec![12]),
+        ],
+        hdr.iter_with_key_prefix_by_branch_version(INITIAL_BRANCH_NAME, vn0, &[&[1]])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(3, hdr.iter_with_key_prefix(&[]).count());
+    assert_eq!(
+        Some((vec![vec![2], vec![1]], vec![21])),
+        hdr.iter_with_key_prefix(&[]).next_back()
+    );
+    assert!(
+        hdr.iter_with_key_prefix_by_branch(BranchName(b"nope"), &[])
+            .next()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_version_cherry_pick() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+    pnk!(hdr.insert(&[&[1], &[2]], &[12]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch(&[&[1], &[2]], &[0], bn));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.remove(&[&[1], &[1]]));
+    pnk!(hdr.insert(&[&[1], &[2]], &[22]));
+
+    assert!(
+        hdr.version_cherry_pick(
+            VersionName(b"v2"),
+            INITIAL_BRANCH_NAME,
+            bn,
+            VersionName(b"v3"),
+            false
+        )
+        .is_err()
+    );
+    pnk!(hdr.version_cherry_pick(
+        VersionName(b"v2"),
+        INITIAL_BRANCH_NAME,
+        bn,
+        VersionName(b"v3"),
+        true
+    ));
+    assert!(hdr.get_by_branch(&[&[1], &[1]], bn).is_none());
+    assert_eq!(vec![22], pnk!(hdr.get_by_branch(&[&[1], &[2]], bn)));
+}
+
+#[test]
+fn test_range_by_prefix_on_branch_version() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    for i in 0..5u8 {
+        pnk!(hdr.insert(&[&[1], &[i]], &[i]));
+        pnk!(hdr.insert(&[&[1, 0], &[i]], &[i]));
+        pnk!(hdr.insert(&[&[2], &[i]], &[i]));
+    }
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.remove(&[&[1], &[3]]));
+
+    assert_eq!(
+        vec![
+            (vec![vec![1], vec![2]], vec![2]),
+            (vec![vec![1], vec![3]], vec![3]),
+        ],
+        hdr.range_by_prefix_on_branch_version(
+            INITIAL_BRANCH_NAME,
+            vn0,
+            &[&[1]],
+            &[2][..]..&[4][..]
+        )
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![
+            (vec![vec![1], vec![4]], vec![4]),
+            (vec![vec![1], vec![2]], vec![2]),
+        ],
+        hdr.range_by_prefix_on_branch_version(
+            INITIAL_BRANCH_NAME,
+            vn1,
+            &[&[1]],
+            &[2][..]..=&[4][..]
+        )
+        .rev()
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![(vec![vec![2], vec![4]], vec![4])],
+        hdr.range_by_prefix_on_branch_version(
+            INITIAL_BRANCH_NAME,
+            vn1,
+            &[&[2]],
+            (Bound::Excluded(&[3][..]), Bound::Unbounded)
+        )
+        .collect::<Vec<_>>()
+    );
+
+    // `[1, 0]` is out of the range of `[1]`
+    assert_eq!(
+        5,
+        hdr.range_by_prefix_on_branch_version(INITIAL_BRANCH_NAME, vn0, &[&[1]], ..)
+            .count()
+    );
+    assert_eq!(
+        5,
+        hdr.range_by_prefix_on_branch_version(
+            INITIAL_BRANCH_NAME,
+            vn0,
+            &[],
+            &[1, 0][..]..&[2][..]
+        )
+        .count()
+    );
+    assert_eq!(
+        15,
+        hdr.range_by_prefix_on_branch_version(INITIAL_BRANCH_NAME, vn0, &[], ..)
+            .count()
+    );
+    assert_eq!(
+        14,
+        hdr.range_by_prefix_on_branch_version(INITIAL_BRANCH_NAME, vn1, &[], ..)
+            .count()
+    );
+    assert_eq!(
+        0,
+        hdr.range_by_prefix_on_branch_version(
+            INITIAL_BRANCH_NAME,
+            vn1,
+            &[&[1], &[1]],
+            ..
+        )
+        .count()
+    );
+}
+
+#[test]
+fn test_version_squash() {
+    let mut hdr = MapxRawMkVs::new(2);
+    (0u8..5).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&[i])));
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+        pnk!(hdr.insert(&[&[9], &[9]], &[i]));
+    });
+
+    pnk!(hdr.version_squash(INITIAL_BRANCH_NAME, 4));
+    assert_eq!(2, pnk!(hdr.version_list()).len());
+    assert!(hdr.version_exists(VersionName(&[0])));
+    assert!(hdr.version_exists(VersionName(&[4])));
+    assert!(!hdr.version_exists(VersionName(&[1])));
+    (0u8..5).for_each(|i| {
+        assert_eq!(vec![i], pnk!(hdr.get(&[&[i], &[i]])));
+    });
+    assert_eq!(vec![4], pnk!(hdr.get(&[&[9], &[9]])));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert!(hdr.version_squash(INITIAL_BRANCH_NAME, 2).is_err());
+    unsafe {
+        pnk!(hdr.version_squash_force(INITIAL_BRANCH_NAME, 2));
+    }
+    assert_eq!(1, pnk!(hdr.version_list()).len());
+    assert!(hdr.version_exists(VersionName(&[4])));
+}
+
+#[test]
+fn test_branch_is_leaf() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    let [ba, bb, bc, bd] = [
+        BranchName(b"a"),
+        BranchName(b"b"),
+        BranchName(b"c"),
+        BranchName(b"d"),
+    ];
+    pnk!(hdr.branch_create(ba, VersionName(b"va"), false));
+    pnk!(hdr.branch_create(bb, VersionName(b"vb"), false));
+    pnk!(hdr.branch_create_by_base_branch(
+        bc,
+        VersionName(b"vc"),
+        ParentBranchName(b"a"),
+        false
+    ));
+    pnk!(hdr.branch_create_by_base_branch(
+        bd,
+        VersionName(b"vd"),
+        ParentBranchName(b"a"),
+        false
+    ));
+
+    assert_eq!(
+        vec![BranchNameOwned::from(ba), bb.into()],
+        hdr.branch_children(INITIAL_BRANCH_NAME)
+    );
+    assert_eq!(
+        vec![BranchNameOwned::from(bc), bd.into()],
+        hdr.branch_children(ba)
+    );
+
+    assert!(!hdr.branch_is_leaf(INITIAL_BRANCH_NAME));
+    assert!(!hdr.branch_is_leaf(ba));
+    assert!(hdr.branch_is_leaf(bb));
+    assert!(hdr.branch_is_leaf(bc));
+    assert!(hdr.branch_is_leaf(bd));
+    assert!(!hdr.branch_is_leaf(BranchName(b"nope")));
+
+    pnk!(hdr.branch_remove(bc));
+    pnk!(hdr.branch_remove(bd));
+    assert!(hdr.branch_is_leaf(ba));
+}
+
+#[test]
+fn test_version_conflict_keys() {
+    let mut hdr = MapxRawMkVs::new(2);
+
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    (0u8..10).for_each(|i| {
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+    });
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    (5u8..15).for_each(|i| {
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+    });
+    assert!(pnk!(hdr.version_has_conflicts_with(vn0, vn1)));
+    assert_eq!(
+        (5u8..10)
+            .map(|i| MapxRawMkVs::encode_key(&[&[i], &[i]]))
+            .collect::<Vec<_>>(),
+        pnk!(hdr.version_conflict_keys(vn0, vn1))
+    );
+
+    let vn2 = VersionName(b"v2");
+    pnk!(hdr.version_create(vn2));
+    pnk!(hdr.insert(&[&[20], &[20]], &[20]));
+    assert!(!pnk!(hdr.version_has_conflicts_with(vn0, vn2)));
+    assert!(pnk!(hdr.version_conflict_keys(vn2, vn1)).is_empty());
+
+    let vn3 = VersionName(b"v3");
+    pnk!(hdr.version_create(vn3));
+    assert!(!pnk!(hdr.version_has_conflicts_with(vn3, vn0)));
+}
+
+#[test]
+fn test_version_compact_between() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vn = |i: u8| format!("v{}", i).into_bytes();
+
+    (1u8..=10).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(&vn(i))));
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+        pnk!(hdr.insert(&[&[0], &[0]], &[i]));
+        if 4 == i {
+            pnk!(hdr.remove(&[&[3], &[3]]));
+        }
+    });
+
+    let head = hdr.iter_with_key_prefix(&[]).collect::<Vec<_>>();
+    let at_v7 = hdr
+        .iter_with_key_prefix_by_branch_version(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v7"),
+            &[],
+        )
+        .collect::<Vec<_>>();
+
+    pnk!(hdr.version_compact_between(
+        INITIAL_BRANCH_NAME,
+        VersionName(b"v3"),
+        VersionName(b"v7"),
+        VersionName(b"v3_to_v7"),
+    ));
+
+    assert_eq!(
+        vec![vn(1), vn(2), b"v3_to_v7".to_vec(), vn(8), vn(9), vn(10)],
+        pnk!(hdr.version_list())
+            .into_iter()
+            .map(|v| v.0)
+            .collect::<Vec<_>>()
+    );
+    assert!(!hdr.version_exists(VersionName(b"v5")));
+
+    assert_eq!(head, hdr.iter_with_key_prefix(&[]).collect::<Vec<_>>());
+    assert_eq!(
+        at_v7,
+        hdr.iter_with_key_prefix_by_branch_version(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v3_to_v7"),
+            &[]
+        )
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![2],
+        pnk!(hdr.get_by_branch_version(
+            &[&[0], &[0]],
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v2")
+        ))
+    );
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert!(
+        hdr.version_compact_between(
+            INITIAL_BRANCH_NAME,
+            VersionName(b"v8"),
+            VersionName(b"v9"),
+            VersionName(b"x"),
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_branch_rename() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_rename(INITIAL_BRANCH_NAME, bn));
+    assert!(!hdr.branch_exists(INITIAL_BRANCH_NAME));
+    assert_eq!(bn.0, &hdr.branch_get_default().0[..]);
+
+    pnk!(hdr.insert(&[&[1], &[2]], &[12]));
+    assert_eq!(vec![11], pnk!(hdr.get_by_branch(&[&[1], &[1]], bn)));
+    assert_eq!(vec![12], pnk!(hdr.get_by_branch(&[&[1], &[2]], bn)));
+    assert!(hdr.get_by_branch(&[&[1], &[1]], INITIAL_BRANCH_NAME).is_none());
+
+    pnk!(hdr.branch_create(INITIAL_BRANCH_NAME, VersionName(b"v1"), false));
+    assert!(hdr.branch_rename(INITIAL_BRANCH_NAME, bn).is_err());
+    assert!(hdr.branch_rename(BranchName(b"nope"), bn).is_err());
+}
+
+#[test]
+fn test_version_list_changed_keys() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    assert!(pnk!(hdr.version_list_changed_keys(vn0)).is_empty());
+
+    (0..50u8).rev().for_each(|i| {
+        pnk!(hdr.insert(&[&[i / 10], &[i % 10]], &[i]));
+    });
+
+    let keys = (0..50u8)
+        .map(|i| MapxRawMkVs::encode_key(&[&[i / 10], &[i % 10]]))
+        .collect::<Vec<_>>();
+    assert_eq!(keys, pnk!(hdr.version_list_changed_keys(vn0)));
+    assert_eq!(50, pnk!(hdr.version_changed_key_count(vn0)));
+    assert!(hdr.version_changed_key_count(VersionName(b"v1")).is_err());
+
+    // the levels can be split back
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.insert(&[&[1, 2], &[3]], &[0]));
+    pnk!(hdr.insert(&[&[1], &[2, 3]], &[0]));
+    let keys = pnk!(hdr.version_list_changed_keys(vn1))
+        .into_iter()
+        .map(|k| pnk!(MapxRawMkVs::decode_key(&k)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![vec![vec![1], vec![2, 3]], vec![vec![1, 2], vec![3]]],
+        keys
+    );
+    assert!(MapxRawMkVs::decode_key(&[0, 0, 0, 2, 1]).is_err());
+}
+
+#[test]
+fn test_branch_fork_point() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.version_create(VersionName(b"v1")));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bn));
+
+    assert_eq!(
+        Some(VersionNameOwned(b"v1".to_vec())),
+        pnk!(hdr.branch_fork_point(INITIAL_BRANCH_NAME, bn))
+    );
+    assert!(hdr.branch_fork_point(bn, BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_version_apply_patch() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let bn = BranchName(b"b");
+    let key = |k1: &[u8], k2: &[u8]| MapxRawMkVs::encode_key(&[k1, k2]);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.insert(&[&[1], &[2, 3]], &[2]));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    let changes = vec![
+        (key(&[1], &[1]), None),
+        (key(&[1], &[2, 3]), Some(vec![22])),
+        (key(&[1, 2], &[3]), Some(vec![3])),
+    ];
+    pnk!(hdr.version_apply_patch(bn, VersionName(b"p0"), &changes));
+
+    assert!(hdr.version_exists_on_branch(VersionName(b"p0"), bn));
+    assert!(hdr.get_by_branch(&[&[1], &[1]], bn).is_none());
+    assert_eq!(Some(vec![22]), hdr.get_by_branch(&[&[1], &[2, 3]], bn));
+    assert_eq!(Some(vec![3]), hdr.get_by_branch(&[&[1, 2], &[3]], bn));
+    assert_eq!(
+        3,
+        pnk!(hdr.version_list_changed_keys(VersionName(b"p0"))).len()
+    );
+
+    // the base branch is untouched
+    assert_eq!(Some(vec![1]), hdr.get(&[&[1], &[1]]));
+    assert_eq!(Some(vec![2]), hdr.get(&[&[1], &[2, 3]]));
+    assert!(hdr.get(&[&[1, 2], &[3]]).is_none());
+
+    assert!(
+        hdr.version_apply_patch(bn, VersionName(b"p0"), &changes)
+            .is_err()
+    );
+
+    // malformed keys are rejected before the version is created
+    let mut truncated = key(&[1], &[2]);
+    truncated.pop();
+    let bad_keys = [vec![1, 1], truncated, MapxRawMkVs::encode_key(&[&[1]])];
+    for k in bad_keys {
+        assert!(
+            hdr.version_apply_patch(bn, VersionName(b"p1"), &[(k, None)])
+                .is_err()
+        );
+        assert!(!hdr.version_exists(VersionName(b"p1")));
+    }
+}
+
+#[test]
+fn test_branch_merge_squash() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..10u8).for_each(|i| {
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+    });
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[11], bn));
+    pnk!(hdr.remove_by_branch(&[&[2], &[2]], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bn));
+    pnk!(hdr.insert_by_branch(&[&[20], &[20]], &[20], bn));
+
+    let src_vers = pnk!(hdr.version_list_by_branch(bn));
+    let dst_ver_cnt = pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len();
+
+    let sv = VersionName(b"squashed");
+    pnk!(hdr.branch_merge_squash(bn, INITIAL_BRANCH_NAME, sv));
+
+    assert_eq!(
+        1 + dst_ver_cnt,
+        pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len()
+    );
+    assert_eq!(src_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(3, pnk!(hdr.version_changed_key_count(sv)));
+    assert_eq!(Some(vec![11]), hdr.get(&[&[1], &[1]]));
+    assert!(hdr.get(&[&[2], &[2]]).is_none());
+    assert_eq!(Some(vec![20]), hdr.get(&[&[20], &[20]]));
+    assert_eq!(Some(vec![3]), hdr.get(&[&[3], &[3]]));
+    assert!(
+        hdr.branch_merge_squash(bn, INITIAL_BRANCH_NAME, sv)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_version_blame() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.insert(&[&[2], &[2]], &[2]));
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.remove(&[&[2], &[2]]));
+    pnk!(hdr.insert(&[&[3], &[3]], &[3]));
+
+    let key = |i: u8| MapxRawMkVs::encode_key(&[&[i], &[i]]);
+    assert_eq!(
+        pnk!(hdr.version_blame(INITIAL_BRANCH_NAME)),
+        vec![
+            (key(1), VersionNameOwned(b"v1".to_vec()), Some(vec![11])),
+            (key(3), VersionNameOwned(b"v2".to_vec()), Some(vec![3])),
+        ]
+    );
+    assert!(hdr.version_blame(BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_contains_prefix_on_branch_version() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.insert(&[&[1], &[2]], &[2]));
+
+    let vn1 = VersionName(b"v1");
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.remove(&[&[1], &[1]]));
+    pnk!(hdr.remove(&[&[1], &[2]]));
+    pnk!(hdr.insert(&[&[2], &[1]], &[1]));
+
+    let br = INITIAL_BRANCH_NAME;
+    assert!(hdr.contains_prefix_on_branch_version(br, vn0, &[&[1]]));
+    assert!(!hdr.contains_prefix_on_branch_version(br, vn0, &[&[2]]));
+    assert!(!hdr.contains_prefix_on_branch_version(br, vn1, &[&[1]]));
+    assert!(hdr.contains_prefix_on_branch_version(br, vn1, &[&[2]]));
+
+    // full-length prefixes
+    assert!(hdr.contains_prefix_on_branch_version(br, vn0, &[&[1], &[2]]));
+    assert!(!hdr.contains_prefix_on_branch_version(br, vn1, &[&[1], &[2]]));
+
+    let nope = VersionName(b"nope");
+    assert!(!hdr.contains_prefix_on_branch_version(br, nope, &[&[2]]));
+    let nope = BranchName(b"nope");
+    assert!(!hdr.contains_prefix_on_branch_version(nope, vn1, &[&[2]]));
+}
+
+#[test]
+fn test_version_rollback_to() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let (vn0, vn1) = (VersionName(b"v0"), VersionName(b"v1"));
+    pnk!(hdr.version_create(vn0));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.version_create(vn1));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+    pnk!(hdr.insert(&[&[2], &[2]], &[2]));
+
+    assert!(
+        hdr.version_rollback_to(INITIAL_BRANCH_NAME, VersionName(b"v2"))
+            .is_err()
+    );
+
+    pnk!(hdr.version_rollback_to(INITIAL_BRANCH_NAME, vn0));
+    assert_eq!(Some(vec![1]), hdr.get(&[&[1], &[1]]));
+    assert!(hdr.get(&[&[2], &[2]]).is_none());
+    assert!(!hdr.version_exists_globally(vn1));
+}
+
+#[test]
+fn test_branch_is_dirty() {
+    let mut hdr = MapxRawMkVs::new(2);
+    assert!(!pnk!(hdr.branch_is_dirty(INITIAL_BRANCH_NAME)));
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    assert!(pnk!(hdr.branch_is_dirty(INITIAL_BRANCH_NAME)));
+
+    let bn = BranchName(b"b");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+    assert!(!pnk!(hdr.branch_is_dirty(bn)));
+
+    pnk!(hdr.version_create_by_branch(VersionName(b"b0"), bn));
+    pnk!(hdr.insert_by_branch(&[&[2], &[2]], &[2], bn));
+    assert!(pnk!(hdr.branch_is_dirty(bn)));
+
+    pnk!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME));
+    assert!(!pnk!(hdr.branch_is_dirty(bn)));
+
+    assert!(hdr.branch_is_dirty(BranchName(b"nope")).is_err());
+}
+
+#[test]
+fn test_branch_merge_to_rebase() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| VersionNameOwned(n.as_bytes().to_vec()))
+            .collect::<Vec<_>>()
+    };
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.insert(&[&[2], &[2]], &[2]));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[11], bn));
+
+    // nothing new on the target branch
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+    assert_eq!(Some(vec![11]), hdr.get(&[&[1], &[1]]));
+
+    // parallel edits on different keys
+    pnk!(hdr.version_create_by_branch(VersionName(b"b1"), bn));
+    pnk!(hdr.insert_by_branch(&[&[3], &[3]], &[3], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b2"), bn));
+    pnk!(hdr.remove_by_branch(&[&[2], &[2]], bn));
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.insert(&[&[4], &[4]], &[4]));
+
+    assert!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME).is_err());
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+
+    let vers = vns(&["v0", "b0", "v1", "b1", "b2"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    for br in [INITIAL_BRANCH_NAME, bn] {
+        assert_eq!(Some(vec![11]), hdr.get_by_branch(&[&[1], &[1]], br));
+        assert!(hdr.get_by_branch(&[&[2], &[2]], br).is_none());
+        assert_eq!(Some(vec![3]), hdr.get_by_branch(&[&[3], &[3]], br));
+        assert_eq!(Some(vec![4]), hdr.get_by_branch(&[&[4], &[4]], br));
+    }
+
+    // an orphan version, not used by any branch
+    let bc = BranchName(b"c");
+    pnk!(hdr.branch_create(bc, VersionName(b"c0"), false));
+    pnk!(hdr.branch_remove(bc));
+    assert!(hdr.version_exists_globally(VersionName(b"c0")));
+
+    // conflicts, the first version can be replayed, but the second one can not
+    pnk!(hdr.version_create_by_branch(VersionName(b"b3"), bn));
+    pnk!(hdr.insert_by_branch(&[&[5], &[5]], &[5], bn));
+    pnk!(hdr.version_create_by_branch(VersionName(b"b4"), bn));
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[111], bn));
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1111]));
+
+    let brs = hdr.branch_list();
+    let all_vers = hdr.version_list_globally();
+    let br_vers = pnk!(hdr.version_list_by_branch(bn));
+    let main_vers = pnk!(hdr.version_list());
+
+    assert!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME).is_err());
+    assert_eq!(Some(vec![111]), hdr.get_by_branch(&[&[1], &[1]], bn));
+    assert_eq!(Some(vec![5]), hdr.get_by_branch(&[&[5], &[5]], bn));
+    assert_eq!(Some(vec![1111]), hdr.get(&[&[1], &[1]]));
+    assert!(hdr.get(&[&[5], &[5]]).is_none());
+    assert!(!hdr.version_exists(VersionName(b"b3")));
+
+    // nothing is changed by the failed rebase
+    assert_eq!(brs, hdr.branch_list());
+    assert_eq!(all_vers, hdr.version_list_globally());
+    assert_eq!(br_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(main_vers, pnk!(hdr.version_list()));
+
+    // resolve the conflict on the branch, and try again
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[1111], bn));
+    pnk!(hdr.branch_merge_to_rebase(bn, INITIAL_BRANCH_NAME));
+    let vers = vns(&["v0", "b0", "v1", "b1", "b2", "v2", "b3", "b4"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![1111]), hdr.get(&[&[1], &[1]]));
+    assert_eq!(Some(vec![5]), hdr.get(&[&[5], &[5]]));
+    assert_eq!(brs, hdr.branch_list());
+
+    // versions of other branches are not cleaned up
+    assert!(hdr.version_exists_globally(VersionName(b"c0")));
+}
+
+#[test]
+fn test_branch_merge_to_base_with_rebase() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let vns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| VersionNameOwned(n.as_bytes().to_vec()))
+            .collect::<Vec<_>>()
+    };
+
+    (0..=5u8).for_each(|i| {
+        pnk!(hdr.version_create(VersionName(format!("v{i}").as_bytes())));
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+    });
+
+    // nothing new on the base branch, a fast-forward merge is enough
+    let bs = BranchName(b"staging");
+    pnk!(hdr.branch_create(bs, VersionName(b"s0"), false));
+    pnk!(hdr.insert_by_branch(&[&[0], &[0]], &[10], bs));
+    pnk!(hdr.branch_merge_to_base_with_rebase(bs, INITIAL_BRANCH_NAME));
+    assert_eq!(Some(vec![10]), hdr.get(&[&[0], &[0]]));
+    pnk!(hdr.branch_remove(bs));
+
+    // fork from the main branch at `v5`
+    let bn = BranchName(b"b");
+    unsafe {
+        pnk!(hdr.branch_create_without_new_version(bn, false));
+    }
+    pnk!(hdr.version_create(VersionName(b"v6")));
+    pnk!(hdr.insert(&[&[6], &[6]], &[6]));
+    pnk!(hdr.version_create_by_branch(VersionName(b"v6'"), bn));
+    pnk!(hdr.insert_by_branch(&[&[7], &[7]], &[7], bn));
+
+    assert!(hdr.branch_merge_to(bn, INITIAL_BRANCH_NAME).is_err());
+    pnk!(hdr.branch_merge_to_base_with_rebase(bn, INITIAL_BRANCH_NAME));
+
+    let vers = vns(&["v0", "v1", "v2", "v3", "v4", "v5", "s0", "v6", "v6'"]);
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)));
+    assert_eq!(vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![6]), hdr.get(&[&[6], &[6]]));
+    assert_eq!(Some(vec![7]), hdr.get(&[&[7], &[7]]));
+
+    // a conflict leaves both branches unchanged
+    pnk!(hdr.version_create(VersionName(b"v7")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[11]));
+    pnk!(hdr.version_create_by_branch(VersionName(b"v7'"), bn));
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[111], bn));
+
+    let brs = hdr.branch_list();
+    let main_vers = pnk!(hdr.version_list());
+    let br_vers = pnk!(hdr.version_list_by_branch(bn));
+    assert!(
+        hdr.branch_merge_to_base_with_rebase(bn, INITIAL_BRANCH_NAME)
+            .is_err()
+    );
+    assert_eq!(brs, hdr.branch_list());
+    assert_eq!(main_vers, pnk!(hdr.version_list()));
+    assert_eq!(br_vers, pnk!(hdr.version_list_by_branch(bn)));
+    assert_eq!(Some(vec![11]), hdr.get(&[&[1], &[1]]));
+    assert_eq!(Some(vec![111]), hdr.get_by_branch(&[&[1], &[1]], bn));
+}
+
+#[test]
+fn test_branch_detach() {
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0u8..10).for_each(|i| {
+        pnk!(hdr.insert(&[&[i], &[i]], &[i]));
+    });
+
+    let ba = BranchName(b"a");
+    pnk!(hdr.branch_create(ba, VersionName(b"va0"), false));
+    pnk!(hdr.insert_by_branch(&[&[10], &[10]], &[10], ba));
+    pnk!(hdr.remove_by_branch(&[&[0], &[0]], ba));
+    pnk!(hdr.version_create_by_branch(VersionName(b"va1"), ba));
+    pnk!(hdr.insert_by_branch(&[&[1], &[1]], &[111], ba));
+
+    let bc = BranchName(b"c");
+    pnk!(hdr.branch_create_by_base_branch(
+        bc,
+        VersionName(b"vc"),
+        ParentBranchName(b"a"),
+        false
+    ));
+
+    assert!(hdr.branch_detach(BranchName(b"nope")).is_err());
+
+    let collect = |hdr: &MapxRawMkVs, br_name| {
+        let mut entries = vec![];
+        pnk!(hdr.iter_op_by_branch(br_name, &mut |k: &[&[u8]], v: RawValue| {
+            entries.push((k.concat(), v));
+            Ok(())
+        }));
+        entries
+    };
+
+    let before = collect(&hdr, ba);
+    let child = collect(&hdr, bc);
+    assert_eq!(10, before.len());
+    assert_eq!(3, pnk!(hdr.version_list_by_branch(ba)).len());
+    assert_eq!(1, pnk!(hdr.branch_ancestors(ba)).len());
+
+    let detached =
+        |i: u64| VersionNameOwned([&b"a@detached"[..], &i.to_be_bytes()[..]].concat());
+
+    pnk!(hdr.branch_detach(ba));
+    assert_eq!(vec![detached(0)], pnk!(hdr.version_list_by_branch(ba)));
+    assert!(pnk!(hdr.branch_ancestors(ba)).is_empty());
+    assert!(hdr.branch_is_leaf(INITIAL_BRANCH_NAME));
+    assert_eq!(before, collect(&hdr, ba));
+    assert!(hdr.get_by_branch(&[&[0], &[0]], ba).is_none());
+    assert_eq!(vec![111], pnk!(hdr.get_by_branch(&[&[1], &[1]], ba)));
+
+    // the parent is not affected
+    assert_eq!(
+        1,
+        pnk!(hdr.version_list_by_branch(INITIAL_BRANCH_NAME)).len()
+    );
+    assert_eq!(vec![1], pnk!(hdr.get(&[&[1], &[1]])));
+
+    // the child is not affected
+    assert!(!hdr.branch_is_leaf(ba));
+    assert_eq!(4, pnk!(hdr.version_list_by_branch(bc)).len());
+    assert_eq!(child, collect(&hdr, bc));
+
+    // detach again
+    pnk!(hdr.branch_detach(ba));
+    assert_eq!(vec![detached(1)], pnk!(hdr.version_list_by_branch(ba)));
+    assert_eq!(before, collect(&hdr, ba));
+
+    pnk!(hdr.version_clean_up_globally());
+    assert_eq!(before, collect(&hdr, ba));
+    assert_eq!(child, collect(&hdr, bc));
+    assert!(!hdr.version_exists_globally(VersionName(&detached(0).0)));
+    assert!(hdr.version_exists_globally(VersionName(b"va1")));
+    assert!(hdr.version_exists_globally(VersionName(b"v0")));
+
+    pnk!(hdr.branch_remove(bc));
+    pnk!(hdr.version_clean_up_globally());
+    assert_eq!(before, collect(&hdr, ba));
+    assert!(!hdr.version_exists_globally(VersionName(b"va1")));
+    assert!(hdr.version_exists_globally(VersionName(b"v0")));
+}
+
+#[test]
+fn test_version_is_ancestor_of() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let (v0, v1, v2) = (VersionName(b"v0"), VersionName(b"v1"), VersionName(b"v2"));
+    pnk!(hdr.version_create(v0));
+    pnk!(hdr.version_create(v1));
+    pnk!(hdr.version_create(v2));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+
+    let m = INITIAL_BRANCH_NAME;
+    assert!(pnk!(hdr.version_is_ancestor_of(v0, v2, m)));
+    assert!(!pnk!(hdr.version_is_ancestor_of(v2, v2, m)));
+    assert!(!pnk!(hdr.version_is_ancestor_of(v2, v1, m)));
+    assert!(pnk!(hdr.version_is_ancestor_of(v2, VersionName(b"b0"), bn)));
+    assert!(
+        hdr.version_is_ancestor_of(v2, VersionName(b"b0"), m)
+            .is_err()
+    );
+
+    let vers = pnk!(hdr.versions_between(v0, VersionName(b"b0"), bn));
+    assert_eq!(
+        vec![b"v0".to_vec(), b"v1".to_vec(), b"v2".to_vec()],
+        vers.into_iter().map(|v| v.0).collect::<Vec<_>>()
+    );
+    assert!(pnk!(hdr.versions_between(v2, v2, m)).is_empty());
+    assert!(hdr.versions_between(v2, v1, m).is_err());
+}
+
+#[test]
+fn test_version_chgset_size() {
+    let mut hdr = MapxRawMkVs::new(2);
+    let m = INITIAL_BRANCH_NAME;
+    assert!(hdr.version_chgset_size_by_branch(m).is_err());
+
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    (0..500u64).for_each(|i| {
+        let k = i.to_be_bytes();
+        pnk!(hdr.insert(&[&k[..4], &k[4..]], &k));
+    });
+    assert_eq!(500, pnk!(hdr.version_chgset_size(VersionName(b"v0"))));
+    assert_eq!(500, pnk!(hdr.version_chgset_size_by_branch(m)));
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    assert_eq!(0, pnk!(hdr.version_chgset_size(VersionName(b"b0"))));
+    assert_eq!(0, pnk!(hdr.version_chgset_size_by_branch(bn)));
+    assert!(hdr.version_chgset_size(VersionName(b"x")).is_err());
+    assert!(hdr.version_chgset_size_by_branch(BranchName(b"x")).is_err());
+}
+
+// `bcs` can not skip the missing fields
+#[cfg(not(feature = "bcs_codec"))]
+#[test]
+fn test_decode_legacy_layout() {
+    use crate::{
+        basic::{mapx_ord::MapxOrd, mapx_ord_rawkey::MapxOrdRawKey},
+        basic_multi_key::mapx_raw::MapxRawMk,
+        common::{BranchID, VersionID},
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize)]
+    struct Current {
+        inner: CurrentInner,
+    }
+
+    #[derive(Deserialize)]
+    struct CurrentInner {
+        key_size: u32,
+        default_branch: BranchID,
+        br_name_to_br_id: MapxOrdRawKey<BranchID>,
+        ver_name_to_ver_id: MapxOrdRawKey<VersionID>,
+        br_to_its_vers: MapxOrd<BranchID, MapxOrd<VersionID, ()>>,
+        ver_to_change_set: MapxOrd<VersionID, MapxRawMk>,
+        #[allow(dead_code)]
+        br_to_parent: MapxOrd<BranchID, BranchID>,
+    }
+
+    // the layout without `br_to_parent`
+    #[derive(Serialize)]
+    struct Legacy {
+        inner: LegacyInner,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyInner {
+        key_size: u32,
+        default_branch: BranchID,
+        br_name_to_br_id: MapxOrdRawKey<BranchID>,
+        ver_name_to_ver_id: MapxOrdRawKey<VersionID>,
+        br_to_its_vers: MapxOrd<BranchID, MapxOrd<VersionID, ()>>,
+        ver_to_change_set: MapxOrd<VersionID, MapxRawMk>,
+    }
+
+    let bn = BranchName(b"b");
+    let mut hdr = MapxRawMkVs::new(2);
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&[&[1], &[1]], &[1]));
+    pnk!(hdr.branch_create(bn, VersionName(b"b0"), false));
+    pnk!(hdr.insert_by_branch(&[&[2], &[2]], &[2], bn));
+
+    let c = pnk!(<Current as ValueEnDe>::decode(&hdr.encode())).inner;
+    let legacy = Legacy {
+        inner: LegacyInner {
+            key_size: c.key_size,
+            default_branch: c.default_branch,
+            br_name_to_br_id: c.br_name_to_br_id,
+            ver_name_to_ver_id: c.ver_name_to_ver_id,
+            br_to_its_vers: c.br_to_its_vers,
+            ver_to_change_set: c.ver_to_change_set,
+        },
+    };
+    let mut hdr = pnk!(<MapxRawMkVs as ValueEnDe>::decode(&legacy.encode()));
+
+    assert_eq!(Some(vec![1]), hdr.get(&[&[1], &[1]]));
+    assert!(hdr.get(&[&[2], &[2]]).is_none());
+    assert_eq!(Some(vec![2]), hdr.get_by_branch(&[&[2], &[2]], bn));
+
+    // the parents of the old branches are unknown
+    assert!(pnk!(hdr.branch_ancestors(bn)).is_empty());
+    pnk!(hdr.branch_create(BranchName(b"c"), VersionName(b"c0"), false));
+    assert_eq!(
+        vec![BranchNameOwned(INITIAL_BRANCH_NAME.0.to_vec())],
+        pnk!(hdr.branch_ancestors(BranchName(b"c")))
+    );
+}
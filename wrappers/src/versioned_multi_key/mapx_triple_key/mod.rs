@@ -6,7 +6,7 @@ use crate::{
         ende::{KeyEnDe, ValueEnDe},
         RawValue,
     },
-    versioned_multi_key::mapx_raw::MapxRawMkVs,
+    versioned_multi_key::mapx_raw::{MapxRawMkVs, MapxRawMkVsIter},
     BranchName, VersionName, VsMgmt,
 };
 use ruc::*;
@@ -95,6 +95,41 @@ where
             .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
     }
 
+    /// Insert lots of entries to the head version of a branch,
+    /// the default branch will be used if `br_name` is `None`,
+    /// return the number of new(non-overwriting) insertions.
+    ///
+    /// NOTE: a version must have been created on the branch.
+    pub fn batch_insert<'a, I>(
+        &mut self,
+        items: I,
+        br_name: Option<BranchName>,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = (&'a K1, &'a K2, &'a K3, &'a V)>,
+        K1: 'a,
+        K2: 'a,
+        K3: 'a,
+        V: 'a,
+    {
+        let encoded = items
+            .into_iter()
+            .map(|(k1, k2, k3, v)| (Self::encode_key(&(k1, k2, k3)), v.encode()))
+            .collect::<Vec<_>>();
+        let keys = encoded.iter().map(|(k, _)| keyref(k)).collect::<Vec<_>>();
+        let items = keys
+            .iter()
+            .zip(encoded.iter())
+            .map(|(k, (_, v))| (&k[..], &v[..]))
+            .collect::<Vec<_>>();
+
+        if let Some(br_name) = br_name {
+            self.inner.batch_insert_by_branch(&items, br_name).c(d!())
+        } else {
+            self.inner.batch_insert(&items).c(d!())
+        }
+    }
+
     #[inline(always)]
     pub fn contains_key(&self, key: &(&K1, &K2, &K3)) -> bool {
         let key = Self::encode_key(key);
@@ -126,6 +161,68 @@ where
             .map(|v| v.map(|v| pnk!(ValueEnDe::decode(&v))))
     }
 
+    /// Remove all entries whose second-level key equals `k2`,
+    /// across all the first-level keys, return the number of removed entries.
+    ///
+    /// NOTE: this is a scan-based implementation, O(n).
+    pub fn remove_all_for_k2(&mut self, k2: &K2) -> Result<usize> {
+        let k2 = k2.encode();
+
+        let mut keys = vec![];
+        let mut op = |k: &[&[u8]], _: RawValue| -> Result<()> {
+            if KEY_SIZE != k.len() {
+                return Err(eg!("key size mismatch"));
+            }
+            if k2 == k[1] {
+                keys.push([k[0].to_vec(), k[1].to_vec(), k[2].to_vec()]);
+            }
+            Ok(())
+        };
+        self.inner.iter_op(&mut op).c(d!())?;
+
+        for k in keys.iter() {
+            self.inner.remove(&keyref(k)).c(d!())?;
+        }
+
+        Ok(keys.len())
+    }
+
+    /// Remove all entries whose first-level key equals `k1`,
+    /// return the number of removed entries.
+    pub fn remove_by_k1(&mut self, k1: &K1) -> Result<u64> {
+        let k1 = k1.encode();
+        self.remove_by_key_prefix(&[&k1[..]]).c(d!())
+    }
+
+    /// Remove all entries whose first two keys equal `k1` and `k2`,
+    /// return the number of removed entries.
+    pub fn remove_by_k1_k2(&mut self, k1: &K1, k2: &K2) -> Result<u64> {
+        let k1 = k1.encode();
+        let k2 = k2.encode();
+        self.remove_by_key_prefix(&[&k1[..], &k2[..]]).c(d!())
+    }
+
+    // Collect all the matched keys first, then remove them one by one
+    fn remove_by_key_prefix(&mut self, key_prefix: &[&[u8]]) -> Result<u64> {
+        let mut keys = vec![];
+        let mut op = |k: &[&[u8]], _: RawValue| -> Result<()> {
+            if KEY_SIZE != k.len() {
+                return Err(eg!("key size mismatch"));
+            }
+            keys.push([k[0].to_vec(), k[1].to_vec(), k[2].to_vec()]);
+            Ok(())
+        };
+        self.inner
+            .iter_op_with_key_prefix(&mut op, key_prefix)
+            .c(d!())?;
+
+        for k in keys.iter() {
+            self.inner.remove(&keyref(k)).c(d!())?;
+        }
+
+        Ok(keys.len() as u64)
+    }
+
     #[inline(always)]
     pub fn clear(&mut self) {
         self.inner.clear();
@@ -288,6 +385,67 @@ where
             .c(d!())
     }
 
+    pub fn iter_op_rev<F>(&self, op: &mut F) -> Result<()>
+    where
+        F: FnMut((K1, K2, K3), V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            if KEY_SIZE != k.len() {
+                return Err(eg!("key size mismatch"));
+            }
+            let k1 = KeyEnDe::decode(k[0]).c(d!())?;
+            let k2 = KeyEnDe::decode(k[1]).c(d!())?;
+            let k3 = KeyEnDe::decode(k[2]).c(d!())?;
+            let v = ValueEnDe::decode(&v).c(d!())?;
+            op((k1, k2, k3), v).c(d!())
+        };
+
+        self.inner.iter_op_rev(&mut cb).c(d!())
+    }
+
+    pub fn iter_op_rev_by_branch<F>(&self, br_name: BranchName, op: &mut F) -> Result<()>
+    where
+        F: FnMut((K1, K2, K3), V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            if KEY_SIZE != k.len() {
+                return Err(eg!("key size mismatch"));
+            }
+            let k1 = KeyEnDe::decode(k[0]).c(d!())?;
+            let k2 = KeyEnDe::decode(k[1]).c(d!())?;
+            let k3 = KeyEnDe::decode(k[2]).c(d!())?;
+            let v = ValueEnDe::decode(&v).c(d!())?;
+            op((k1, k2, k3), v).c(d!())
+        };
+
+        self.inner.iter_op_rev_by_branch(br_name, &mut cb).c(d!())
+    }
+
+    pub fn iter_op_rev_by_branch_version<F>(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+        op: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut((K1, K2, K3), V) -> Result<()>,
+    {
+        let mut cb = |k: &[&[u8]], v: RawValue| -> Result<()> {
+            if KEY_SIZE != k.len() {
+                return Err(eg!("key size mismatch"));
+            }
+            let k1 = KeyEnDe::decode(k[0]).c(d!())?;
+            let k2 = KeyEnDe::decode(k[1]).c(d!())?;
+            let k3 = KeyEnDe::decode(k[2]).c(d!())?;
+            let v = ValueEnDe::decode(&v).c(d!())?;
+            op((k1, k2, k3), v).c(d!())
+        };
+
+        self.inner
+            .iter_op_rev_by_branch_version(br_name, ver_name, &mut cb)
+            .c(d!())
+    }
+
     pub fn iter_op_with_key_prefix<F>(
         &self,
         op: &mut F,
@@ -393,6 +551,273 @@ where
             .c(d!())
     }
 
+    /// Iterate over all entries on the default branch,
+    /// the iterator is bound to the head version at the time of creation.
+    #[inline(always)]
+    pub fn iter(&self) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix(&[]))
+    }
+
+    /// The iterator is bound to the head version of the branch
+    /// at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_branch(
+        &self,
+        br_name: BranchName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix_by_branch(br_name, &[]))
+    }
+
+    #[inline(always)]
+    pub fn iter_by_branch_version(
+        &self,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix_by_branch_version(
+            br_name,
+            ver_name,
+            &[],
+        ))
+    }
+
+    /// Iterate over the entries with the specified `k1` on the default branch,
+    /// the iterator is bound to the head version at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1(&self, k1: &K1) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix(&[&k1[..]]))
+    }
+
+    /// The iterator is bound to the head version of the branch
+    /// at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1_by_branch(
+        &self,
+        k1: &K1,
+        br_name: BranchName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        MapxTkVsIter::new(
+            self.inner
+                .iter_with_key_prefix_by_branch(br_name, &[&k1[..]]),
+        )
+    }
+
+    #[inline(always)]
+    pub fn iter_by_k1_by_branch_version(
+        &self,
+        k1: &K1,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix_by_branch_version(
+            br_name,
+            ver_name,
+            &[&k1[..]],
+        ))
+    }
+
+    /// Iterate over the entries with the specified `k1` and `k2` on the default branch,
+    /// the iterator is bound to the head version at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1_k2(&self, k1: &K1, k2: &K2) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        let k2 = k2.encode();
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix(&[&k1[..], &k2[..]]))
+    }
+
+    /// The iterator is bound to the head version of the branch
+    /// at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1_k2_by_branch(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        br_name: BranchName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        let k2 = k2.encode();
+        MapxTkVsIter::new(
+            self.inner
+                .iter_with_key_prefix_by_branch(br_name, &[&k1[..], &k2[..]]),
+        )
+    }
+
+    #[inline(always)]
+    pub fn iter_by_k1_k2_by_branch_version(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let k1 = k1.encode();
+        let k2 = k2.encode();
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix_by_branch_version(
+            br_name,
+            ver_name,
+            &[&k1[..], &k2[..]],
+        ))
+    }
+
+    /// Iterate over the entry with the specified full key on the default branch,
+    /// at most one entry will be yielded.
+    #[inline(always)]
+    pub fn iter_by_k1_k2_k3(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        k3: &K3,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let key = Self::encode_key(&(k1, k2, k3));
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix(&keyref(&key)))
+    }
+
+    /// The iterator is bound to the head version of the branch
+    /// at the time of creation.
+    #[inline(always)]
+    pub fn iter_by_k1_k2_k3_by_branch(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        k3: &K3,
+        br_name: BranchName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let key = Self::encode_key(&(k1, k2, k3));
+        MapxTkVsIter::new(
+            self.inner
+                .iter_with_key_prefix_by_branch(br_name, &keyref(&key)),
+        )
+    }
+
+    #[inline(always)]
+    pub fn iter_by_k1_k2_k3_by_branch_version(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        k3: &K3,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> MapxTkVsIter<'_, K1, K2, K3, V> {
+        let key = Self::encode_key(&(k1, k2, k3));
+        MapxTkVsIter::new(self.inner.iter_with_key_prefix_by_branch_version(
+            br_name,
+            ver_name,
+            &keyref(&key),
+        ))
+    }
+
+    /// Collect all the entries with the specified `k1` on the default branch.
+    ///
+    /// NOTE: the results are collected into a `Vec`,
+    /// use `iter_by_k1` or `iter_op_with_key_prefix` for large result sets.
+    #[inline(always)]
+    pub fn get_all_by_k1(&self, k1: &K1) -> Vec<(K2, K3, V)> {
+        self.iter_by_k1(k1)
+            .map(|(_, k2, k3, v)| (k2, k3, v))
+            .collect()
+    }
+
+    /// Collect all the entries with the specified `k1` on the head of a branch,
+    /// an empty `Vec` is returned if the branch does not exist.
+    #[inline(always)]
+    pub fn get_all_by_k1_by_branch(
+        &self,
+        k1: &K1,
+        br_name: BranchName,
+    ) -> Vec<(K2, K3, V)> {
+        self.iter_by_k1_by_branch(k1, br_name)
+            .map(|(_, k2, k3, v)| (k2, k3, v))
+            .collect()
+    }
+
+    /// Collect all the entries with the specified `k1` on a version of a branch,
+    /// an empty `Vec` is returned if the branch or the version does not exist.
+    #[inline(always)]
+    pub fn get_all_by_k1_by_branch_version(
+        &self,
+        k1: &K1,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Vec<(K2, K3, V)> {
+        self.iter_by_k1_by_branch_version(k1, br_name, ver_name)
+            .map(|(_, k2, k3, v)| (k2, k3, v))
+            .collect()
+    }
+
+    /// Collect all the entries with the specified `k1` and `k2` on the default branch.
+    ///
+    /// NOTE: the results are collected into a `Vec`,
+    /// use `iter_by_k1_k2` or `iter_op_with_key_prefix` for large result sets.
+    #[inline(always)]
+    pub fn get_all_by_k1_k2(&self, k1: &K1, k2: &K2) -> Vec<(K3, V)> {
+        self.iter_by_k1_k2(k1, k2)
+            .map(|(_, _, k3, v)| (k3, v))
+            .collect()
+    }
+
+    /// Collect all the entries with the specified `k1` and `k2`
+    /// on the head of a branch,
+    /// an empty `Vec` is returned if the branch does not exist.
+    #[inline(always)]
+    pub fn get_all_by_k1_k2_by_branch(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        br_name: BranchName,
+    ) -> Vec<(K3, V)> {
+        self.iter_by_k1_k2_by_branch(k1, k2, br_name)
+            .map(|(_, _, k3, v)| (k3, v))
+            .collect()
+    }
+
+    /// Collect all the entries with the specified `k1` and `k2`
+    /// on a version of a branch,
+    /// an empty `Vec` is returned if the branch or the version does not exist.
+    #[inline(always)]
+    pub fn get_all_by_k1_k2_by_branch_version(
+        &self,
+        k1: &K1,
+        k2: &K2,
+        br_name: BranchName,
+        ver_name: VersionName,
+    ) -> Vec<(K3, V)> {
+        self.iter_by_k1_k2_by_branch_version(k1, k2, br_name, ver_name)
+            .map(|(_, _, k3, v)| (k3, v))
+            .collect()
+    }
+
+    /// Move the entries on the head of the default branch into a new map
+    /// with different key and value types, such as widening the key types,
+    /// the entries for which `transform` returns `None` are skipped.
+    ///
+    /// NOTE: the history is not migrated, the new map holds only one version,
+    /// which is named after the head version of the default branch.
+    pub fn migrate_keys<NK1, NK2, NK3, NV, F>(
+        self,
+        mut transform: F,
+    ) -> Result<MapxTkVs<NK1, NK2, NK3, NV>>
+    where
+        NK1: KeyEnDe,
+        NK2: KeyEnDe,
+        NK3: KeyEnDe,
+        NV: ValueEnDe,
+        F: FnMut((K1, K2, K3, V)) -> Option<(NK1, NK2, NK3, NV)>,
+    {
+        let mut ret = MapxTkVs::new();
+
+        if let Some(ver) = self.version_list().c(d!())?.pop() {
+            ret.version_create(VersionName(&ver.0)).c(d!())?;
+            for (k1, k2, k3, v) in self.iter().filter_map(&mut transform) {
+                ret.insert(&(&k1, &k2, &k3), &v).c(d!())?;
+            }
+        }
+
+        Ok(ret)
+    }
+
     /// NOTE: This is not a member of `VsMgmt`!
     #[inline(always)]
     pub fn version_chgset_trie_root(
@@ -437,6 +862,61 @@ where
     crate::impl_vs_methods!();
 }
 
+pub struct MapxTkVsIter<'a, K1, K2, K3, V> {
+    iter: MapxRawMkVsIter<'a>,
+    p: PhantomData<(K1, K2, K3, V)>,
+}
+
+impl<'a, K1, K2, K3, V> MapxTkVsIter<'a, K1, K2, K3, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    K3: KeyEnDe,
+    V: ValueEnDe,
+{
+    fn new(iter: MapxRawMkVsIter<'a>) -> Self {
+        Self {
+            iter,
+            p: PhantomData,
+        }
+    }
+
+    fn decode(k: Vec<RawValue>, v: RawValue) -> (K1, K2, K3, V) {
+        assert_eq!(KEY_SIZE, k.len(), "key size mismatch");
+        (
+            pnk!(KeyEnDe::decode(&k[0])),
+            pnk!(KeyEnDe::decode(&k[1])),
+            pnk!(KeyEnDe::decode(&k[2])),
+            pnk!(ValueEnDe::decode(&v)),
+        )
+    }
+}
+
+impl<'a, K1, K2, K3, V> Iterator for MapxTkVsIter<'a, K1, K2, K3, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    K3: KeyEnDe,
+    V: ValueEnDe,
+{
+    type Item = (K1, K2, K3, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| Self::decode(k, v))
+    }
+}
+
+impl<'a, K1, K2, K3, V> DoubleEndedIterator for MapxTkVsIter<'a, K1, K2, K3, V>
+where
+    K1: KeyEnDe,
+    K2: KeyEnDe,
+    K3: KeyEnDe,
+    V: ValueEnDe,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, v)| Self::decode(k, v))
+    }
+}
+
 #[derive(Debug)]
 pub struct ValueMut<'a, K1, K2, K3, V>
 where
@@ -382,3 +382,423 @@ fn test_prune() {
     assert!(!hdr.version_exists(VersionName(b"b")));
     assert!(hdr.version_exists(VersionName(b"c")));
 }
+
+#[test]
+fn test_remove_all_for_k2() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    pnk!(hdr.version_create(VersionName(b"manster0")));
+
+    for k1 in 0..10 {
+        for k2 in 0..3 {
+            pnk!(hdr.insert(&(&k1, &k2, &0), &k1));
+            pnk!(hdr.insert(&(&k1, &k2, &1), &k1));
+        }
+    }
+
+    assert_eq!(20, pnk!(hdr.remove_all_for_k2(&1)));
+    assert_eq!(0, pnk!(hdr.remove_all_for_k2(&1)));
+
+    for k1 in 0..10 {
+        assert!(hdr.get(&(&k1, &1, &0)).is_none());
+        assert!(hdr.get(&(&k1, &1, &1)).is_none());
+        assert_eq!(k1, pnk!(hdr.get(&(&k1, &0, &1))));
+        assert_eq!(k1, pnk!(hdr.get(&(&k1, &2, &0))));
+    }
+}
+
+#[test]
+fn test_remove_by_k1() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    pnk!(hdr.version_create(VersionName(b"manster0")));
+
+    for k1 in 0..5 {
+        for k2 in 0..4 {
+            for k3 in 0..5 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &(k1 + k2 + k3)));
+            }
+        }
+    }
+
+    assert_eq!(20, pnk!(hdr.remove_by_k1(&2)));
+    assert_eq!(0, pnk!(hdr.remove_by_k1(&2)));
+    assert!(hdr.get_all_by_k1(&2).is_empty());
+    for k2 in 0..4 {
+        for k3 in 0..5 {
+            assert!(hdr.get(&(&2, &k2, &k3)).is_none());
+            assert_eq!(1 + k2 + k3, pnk!(hdr.get(&(&1, &k2, &k3))));
+        }
+    }
+
+    assert_eq!(5, pnk!(hdr.remove_by_k1_k2(&3, &1)));
+    assert_eq!(0, pnk!(hdr.remove_by_k1_k2(&3, &1)));
+    assert!(hdr.get_all_by_k1_k2(&3, &1).is_empty());
+    for k3 in 0..5 {
+        assert!(hdr.get(&(&3, &1, &k3)).is_none());
+        assert_eq!(3 + k3, pnk!(hdr.get(&(&3, &0, &k3))));
+    }
+
+    let mut cnt = 0;
+    pnk!(hdr.iter_op(&mut |_, _| {
+        cnt += 1;
+        Ok(())
+    }));
+    assert_eq!(75, cnt);
+}
+
+#[test]
+fn test_branch_diff() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    pnk!(hdr.insert(&(&0, &0, &0), &0));
+    pnk!(hdr.insert(&(&1, &1, &1), &1));
+
+    let bn = BranchName(b"test");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    assert!(pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn)).is_empty());
+
+    pnk!(hdr.remove_by_branch(&(&0, Some((&0, Some(&0)))), bn));
+    pnk!(hdr.insert_by_branch(&(&2, &2, &2), &2, bn));
+
+    pnk!(hdr.version_create(VersionName(b"v2")));
+    pnk!(hdr.insert(&(&1, &1, &1), &9));
+
+    let key = |i: usize| {
+        let k = i.encode();
+        MapxRawMkVs::encode_key(&[&k, &k, &k])
+    };
+    assert_eq!(
+        vec![
+            (key(0), Some(0usize.encode()), None),
+            (key(1), Some(9usize.encode()), Some(1usize.encode())),
+            (key(2), None, Some(2usize.encode())),
+        ],
+        pnk!(hdr.branch_diff(INITIAL_BRANCH_NAME, bn))
+    );
+}
+
+#[test]
+fn test_iter_by_prefix() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+
+    for k1 in 0..4 {
+        for k2 in 0..4 {
+            for k3 in 0..4 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &(k1 * 100 + k2 * 10 + k3)));
+            }
+        }
+    }
+    pnk!(hdr.remove(&(&1, Some((&1, Some(&1))))));
+
+    let entries = hdr.iter_by_k1(&1).collect::<Vec<_>>();
+    assert_eq!(15, entries.len());
+    assert!(entries.iter().all(|(k1, _, _, _)| 1 == *k1));
+    assert!(!entries.contains(&(1, 1, 1, 111)));
+    assert_eq!(Some((1, 0, 0, 100)), hdr.iter_by_k1(&1).next());
+    assert_eq!(Some((1, 3, 3, 133)), hdr.iter_by_k1(&1).next_back());
+
+    let mut iter = hdr.iter_by_k1_k2(&2, &3);
+    assert_eq!(Some((2, 3, 0, 230)), iter.next());
+    assert_eq!(Some((2, 3, 3, 233)), iter.next_back());
+    assert_eq!(Some((2, 3, 1, 231)), iter.next());
+    assert_eq!(Some((2, 3, 2, 232)), iter.next_back());
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+
+    assert_eq!(
+        vec![(3, 2, 1, 321)],
+        hdr.iter_by_k1_k2_k3(&3, &2, &1).collect::<Vec<_>>()
+    );
+    assert!(hdr.iter_by_k1_k2_k3(&1, &1, &1).next().is_none());
+    assert!(hdr.iter_by_k1(&9).next().is_none());
+
+    // on other branches and versions
+    let bn = BranchName(b"b");
+    let vn0 = VersionName(b"v0");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.insert_by_branch(&(&1, &1, &1), &0, bn));
+    pnk!(hdr.remove_by_branch(&(&2, Some((&3, None))), bn));
+
+    assert_eq!(16, hdr.iter_by_k1_by_branch(&1, bn).count());
+    assert_eq!(Some((1, 1, 1, 0)), hdr.iter_by_k1_by_branch(&1, bn).nth(5));
+    assert_eq!(15, hdr.iter_by_k1_by_branch_version(&1, bn, vn0).count());
+    assert!(hdr.iter_by_k1_k2_by_branch(&2, &3, bn).next().is_none());
+    assert_eq!(
+        Some((2, 3, 3, 233)),
+        hdr.iter_by_k1_k2_by_branch_version(&2, &3, bn, vn0)
+            .next_back()
+    );
+    assert_eq!(
+        4,
+        hdr.iter_by_k1_k2_by_branch(&2, &3, INITIAL_BRANCH_NAME)
+            .count()
+    );
+    assert_eq!(
+        vec![(1, 1, 1, 0)],
+        hdr.iter_by_k1_k2_k3_by_branch(&1, &1, &1, bn)
+            .collect::<Vec<_>>()
+    );
+    assert!(hdr
+        .iter_by_k1_k2_k3_by_branch_version(&1, &1, &1, bn, vn0)
+        .next()
+        .is_none());
+
+    // the default branch is not affected
+    assert_eq!(15, hdr.iter_by_k1(&1).count());
+    assert_eq!(4, hdr.iter_by_k1_k2(&2, &3).count());
+
+    // absent branches and versions
+    assert!(hdr
+        .iter_by_k1_by_branch(&1, BranchName(b"x"))
+        .next()
+        .is_none());
+    assert!(hdr
+        .iter_by_k1_by_branch_version(&1, bn, VersionName(b"x"))
+        .next()
+        .is_none());
+}
+
+#[test]
+fn test_iter() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    let bn = BranchName(b"test");
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    for k1 in 0..3 {
+        for k2 in 0..3 {
+            for k3 in 0..3 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &(k1 * 100 + k2 * 10 + k3)));
+            }
+        }
+    }
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.remove_by_branch(&(&1, Some((&1, Some(&1)))), bn));
+    pnk!(hdr.insert_by_branch(&(&9, &9, &9), &999, bn));
+
+    let mut by_op = vec![];
+    pnk!(hdr.iter_op(&mut |(k1, k2, k3), v| {
+        by_op.push((k1, k2, k3, v));
+        Ok(())
+    }));
+    let mut by_iter = hdr.iter().collect::<Vec<_>>();
+    assert_eq!(27, by_iter.len());
+    by_op.sort();
+    by_iter.sort();
+    assert_eq!(by_op, by_iter);
+
+    let mut fwd = hdr.iter_by_branch(bn).collect::<Vec<_>>();
+    let mut rev = hdr.iter_by_branch(bn).rev().collect::<Vec<_>>();
+    assert_eq!(27, fwd.len());
+    assert!(fwd.contains(&(9, 9, 9, 999)));
+    assert!(!fwd.contains(&(1, 1, 1, 111)));
+    fwd.sort();
+    rev.sort();
+    assert_eq!(fwd, rev);
+
+    let mut old = hdr
+        .iter_by_branch_version(bn, VersionName(b"v0"))
+        .collect::<Vec<_>>();
+    old.sort();
+    assert_eq!(by_iter, old);
+
+    assert!(hdr.iter_by_branch(BranchName(b"nope")).next().is_none());
+}
+
+#[test]
+fn test_iter_op_rev() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    let bn = BranchName(b"test");
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    for k1 in 0..3 {
+        for k2 in 0..3 {
+            for k3 in 0..3 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &(k1 * 100 + k2 * 10 + k3)));
+            }
+        }
+    }
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.remove_by_branch(&(&1, Some((&1, Some(&1)))), bn));
+    pnk!(hdr.insert_by_branch(&(&9, &9, &9), &999, bn));
+
+    let collect = |rev: bool, br: Option<BranchName>, ver: Option<VersionName>| {
+        let mut res = vec![];
+        let mut op = |k: (usize, usize, usize), v: usize| {
+            res.push((k, v));
+            Ok(())
+        };
+        match (rev, br, ver) {
+            (false, None, _) => pnk!(hdr.iter_op(&mut op)),
+            (true, None, _) => pnk!(hdr.iter_op_rev(&mut op)),
+            (false, Some(br), None) => pnk!(hdr.iter_op_by_branch(br, &mut op)),
+            (true, Some(br), None) => pnk!(hdr.iter_op_rev_by_branch(br, &mut op)),
+            (false, Some(br), Some(ver)) => {
+                pnk!(hdr.iter_op_by_branch_version(br, ver, &mut op))
+            }
+            (true, Some(br), Some(ver)) => {
+                pnk!(hdr.iter_op_rev_by_branch_version(br, ver, &mut op))
+            }
+        }
+        res
+    };
+
+    for (br, ver, n) in [
+        (None, None, 27),
+        (Some(INITIAL_BRANCH_NAME), None, 27),
+        (Some(bn), None, 27),
+        (Some(bn), Some(VersionName(b"v0")), 27),
+    ] {
+        let fwd = collect(false, br, ver);
+        let mut rev = collect(true, br, ver);
+        assert_eq!(n, fwd.len());
+        rev.reverse();
+        assert_eq!(fwd, rev);
+    }
+
+    let entries = collect(true, Some(bn), None);
+    assert_eq!(((9, 9, 9), 999), entries[0]);
+    assert!(!entries.contains(&((1, 1, 1), 111)));
+    assert_eq!(((0, 0, 0), 0), entries[26]);
+}
+
+#[test]
+fn test_get_all_by_k1() {
+    let mut hdr: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    let vn = VersionName(b"v0");
+    assert!(hdr.get_all_by_k1(&1).is_empty());
+
+    pnk!(hdr.version_create(vn));
+    for k1 in 0..3 {
+        for k2 in 0..10 {
+            for k3 in 0..10 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &(k1 * 100 + k2 * 10 + k3)));
+            }
+        }
+    }
+
+    let bn = BranchName(b"b");
+    pnk!(hdr.branch_create(bn, VersionName(b"v1"), false));
+    pnk!(hdr.remove_by_branch(&(&1, Some((&1, None))), bn));
+
+    let expected = (0..10)
+        .flat_map(|k2| (0..10).map(move |k3| (k2, k3, 100 + k2 * 10 + k3)))
+        .collect::<Vec<_>>();
+    assert_eq!(100, expected.len());
+    assert_eq!(expected, hdr.get_all_by_k1(&1));
+    assert_eq!(
+        expected,
+        hdr.get_all_by_k1_by_branch(&1, INITIAL_BRANCH_NAME)
+    );
+    assert_eq!(expected, hdr.get_all_by_k1_by_branch_version(&1, bn, vn));
+    assert_eq!(90, hdr.get_all_by_k1_by_branch(&1, bn).len());
+
+    let expected = (0..10).map(|k3| (k3, 120 + k3)).collect::<Vec<_>>();
+    assert_eq!(expected, hdr.get_all_by_k1_k2(&1, &2));
+    assert_eq!(expected, hdr.get_all_by_k1_k2_by_branch(&1, &2, bn));
+    assert_eq!(
+        expected,
+        hdr.get_all_by_k1_k2_by_branch_version(&1, &2, bn, vn)
+    );
+    assert_eq!(10, hdr.get_all_by_k1_k2(&1, &1).len());
+    assert!(hdr.get_all_by_k1_k2_by_branch(&1, &1, bn).is_empty());
+
+    // absent keys
+    assert!(hdr.get_all_by_k1(&9).is_empty());
+    assert!(hdr.get_all_by_k1_by_branch(&9, bn).is_empty());
+    assert!(hdr.get_all_by_k1_k2(&1, &99).is_empty());
+    assert!(hdr
+        .get_all_by_k1_by_branch(&1, BranchName(b"nope"))
+        .is_empty());
+    assert!(hdr
+        .get_all_by_k1_k2_by_branch_version(&1, &2, bn, VersionName(b"nope"))
+        .is_empty());
+}
+
+#[test]
+fn test_batch_insert() {
+    let n = 50_000;
+    let keys = (0..n)
+        .map(|i| (i / 1000, (i / 10) % 100, i % 10, i))
+        .collect::<Vec<_>>();
+
+    let mut hdr1: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+    let mut hdr2: MapxTkVs<usize, usize, usize, usize> = MapxTkVs::new();
+
+    // no version on the branch
+    assert!(
+        hdr1.batch_insert(keys.iter().map(|(a, b, c, d)| (a, b, c, d)), None)
+            .is_err()
+    );
+
+    pnk!(hdr1.version_create(VersionName(b"v0")));
+    pnk!(hdr2.version_create(VersionName(b"v0")));
+
+    let cnt =
+        pnk!(hdr1.batch_insert(keys.iter().map(|(a, b, c, d)| (a, b, c, d)), None));
+    assert_eq!(n, cnt);
+    keys.iter().for_each(|(a, b, c, d)| {
+        pnk!(hdr2.insert(&(a, b, c), d));
+    });
+
+    // half of them are overwritten
+    let keys = (n / 2..n + n / 2)
+        .map(|i| (i / 1000, (i / 10) % 100, i % 10, i + 1))
+        .collect::<Vec<_>>();
+    let cnt = pnk!(hdr1.batch_insert(
+        keys.iter().map(|(a, b, c, d)| (a, b, c, d)),
+        Some(INITIAL_BRANCH_NAME)
+    ));
+    assert_eq!(n / 2, cnt);
+    keys.iter().for_each(|(a, b, c, d)| {
+        pnk!(hdr2.insert(&(a, b, c), d));
+    });
+
+    (0..n + n / 2).for_each(|i| {
+        let k = (&(i / 1000), &((i / 10) % 100), &(i % 10));
+        assert_eq!(hdr2.get(&k), hdr1.get(&k));
+        assert_eq!(i + (i >= n / 2) as usize, pnk!(hdr1.get(&k)));
+    });
+
+    assert!(
+        hdr1.batch_insert(vec![], Some(BranchName(b"nope")))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_migrate_keys() {
+    let widen = |(k1, k2, k3, v): (u32, u32, u32, String)| {
+        Some((k1 as u64, k2 as u64, k3 as u64, v))
+    };
+
+    let hdr: MapxTkVs<u32, u32, u32, String> = MapxTkVs::new();
+    let new = pnk!(hdr.migrate_keys(widen));
+    assert_eq!(0, new.iter().count());
+    assert!(pnk!(new.version_list()).is_empty());
+
+    let mut hdr: MapxTkVs<u32, u32, u32, String> = MapxTkVs::new();
+    pnk!(hdr.version_create(VersionName(b"v0")));
+    for k1 in 0..4u32 {
+        for k2 in 0..4u32 {
+            for k3 in 0..4u32 {
+                pnk!(hdr.insert(&(&k1, &k2, &k3), &format!("{}{}{}", k1, k2, k3)));
+            }
+        }
+    }
+    pnk!(hdr.version_create(VersionName(b"v1")));
+    pnk!(hdr.remove(&(&0, Some((&0, Some(&0))))));
+    pnk!(hdr.insert(&(&u32::MAX, &u32::MAX, &u32::MAX), &"max".to_owned()));
+
+    // the entries with `k3 == 3` are skipped
+    let new: MapxTkVs<u64, u64, u64, String> =
+        pnk!(hdr.migrate_keys(|entry| alt!(3 == entry.2, None, widen(entry))));
+
+    let vers = pnk!(new.version_list());
+    assert_eq!(1, vers.len());
+    assert_eq!(b"v1".to_vec(), vers[0].0);
+
+    assert_eq!(4 * 4 * 3, new.iter().count());
+    assert!(new.get(&(&0, &0, &0)).is_none());
+    assert!(new.get(&(&1, &2, &3)).is_none());
+    assert_eq!(Some("122".to_owned()), new.get(&(&1, &2, &2)));
+    let max = u32::MAX as u64;
+    assert_eq!(Some("max".to_owned()), new.get(&(&max, &max, &max)));
+}
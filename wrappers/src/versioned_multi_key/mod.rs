@@ -3,5 +3,6 @@
 //!
 
 pub mod mapx_double_key;
+pub mod mapx_n_key;
 pub mod mapx_raw;
 pub mod mapx_triple_key;